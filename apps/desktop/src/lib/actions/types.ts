@@ -11,7 +11,7 @@ export type Outcome = {
 	updatedBranches: UpdatedBranch[];
 };
 
-export type ActionHandler = 'handleChangesSimple';
+export type ActionHandler = 'handleChangesSimple' | 'policyViolation';
 
 type MCPSourceDefinition = {
 	name: string;
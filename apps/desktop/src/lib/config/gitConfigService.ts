@@ -68,4 +68,5 @@ export class GbConfig {
 	signingFormat?: string | undefined;
 	gpgProgram?: string | undefined;
 	gpgSshProgram?: string | undefined;
+	gerritMode?: boolean | undefined;
 }
@@ -43,7 +43,14 @@ export default class FeedFactory {
 }
 
 type DBEvent = {
-	kind: 'actions' | 'workflows' | 'hunk-assignments' | 'unknown';
+	kind:
+		| 'actions'
+		| 'workflows'
+		| 'hunk-assignments'
+		| 'workspace-rules'
+		| 'claude-code-sessions'
+		| 'file-write-locks'
+		| 'unknown';
 	item?: string;
 };
 
@@ -56,6 +63,19 @@ type ToolCallEvent = ToolCall & {
 	messageId: string;
 };
 
+/**
+ * A tool call which needs the approval of the user according to the agent policy of the project.
+ */
+export type ToolApprovalRequest = {
+	requestId: string;
+	name: string;
+	parameters: string;
+};
+
+type ToolApprovalEvent = ToolApprovalRequest & {
+	messageId: string;
+};
+
 type UserMessageId = `user-${string}`;
 
 export type UserMessage = {
@@ -102,7 +122,7 @@ export function isInProgressAssistantMessage(
 }
 
 interface BaseInProgressUpdate {
-	type: 'token' | 'tool-call';
+	type: 'token' | 'tool-call' | 'tool-approval';
 }
 
 export interface TokenUpdate extends BaseInProgressUpdate {
@@ -115,7 +135,12 @@ export interface ToolCallUpdate extends BaseInProgressUpdate {
 	toolCall: ToolCall;
 }
 
-export type InProgressUpdate = TokenUpdate | ToolCallUpdate;
+export interface ToolApprovalUpdate extends BaseInProgressUpdate {
+	type: 'tool-approval';
+	request: ToolApprovalRequest;
+}
+
+export type InProgressUpdate = TokenUpdate | ToolCallUpdate | ToolApprovalUpdate;
 
 type InProgressSubscribeCallback = (update: InProgressUpdate) => void;
 
@@ -125,6 +150,7 @@ class Feed {
 	private unlistenDB: () => void;
 	private unlistenTokens: () => void;
 	private unlistenToolCalls: () => void;
+	private unlistenToolApprovals: () => void;
 	private initialized;
 	private mutex = new Mutex();
 	private updateTimeout: ReturnType<typeof setTimeout> | null = null;
@@ -163,6 +189,13 @@ class Feed {
 				this.handleToolCallEvent(event.payload);
 			}
 		);
+
+		this.unlistenToolApprovals = this.tauri.listen<ToolApprovalEvent>(
+			`project://${projectId}/tool-approval`,
+			(event) => {
+				this.handleToolApprovalEvent(event.payload);
+			}
+		);
 	}
 
 	isProjectFeed(projectId: string): boolean {
@@ -201,6 +234,14 @@ class Feed {
 		subscribers.forEach((callback) => callback({ type: 'tool-call', toolCall }));
 	}
 
+	private notifySubscribersToolApproval(
+		messageId: InProgressAssistantMessageId,
+		request: ToolApprovalRequest
+	) {
+		const subscribers = this.messageSubscribers.get(messageId) ?? [];
+		subscribers.forEach((callback) => callback({ type: 'tool-approval', request }));
+	}
+
 	private handleDBEvent(event: DBEvent) {
 		switch (event.kind) {
 			case 'actions':
@@ -209,6 +250,9 @@ class Feed {
 				return;
 			}
 			case 'hunk-assignments':
+			case 'workspace-rules':
+			case 'claude-code-sessions':
+			case 'file-write-locks':
 			case 'unknown': {
 				// Do nothing for now, as we are not handling these events.
 				return;
@@ -238,6 +282,19 @@ class Feed {
 		});
 	}
 
+	private handleToolApprovalEvent(event: ToolApprovalEvent) {
+		const { messageId, requestId, name, parameters } = event;
+		const inProgressId: InProgressAssistantMessageId = `assistant-in-progress-${messageId}`;
+		this.notifySubscribersToolApproval(inProgressId, { requestId, name, parameters });
+	}
+
+	/**
+	 * Approve or deny a tool call which is waiting for the user.
+	 */
+	async respondToToolApproval(requestId: string, approved: boolean) {
+		await invoke('respond_to_tool_approval', { requestId, approved });
+	}
+
 	private async handleLastAdded(entry: FeedEntry) {
 		this.lastAddedId.set(entry.id);
 	}
@@ -496,5 +553,6 @@ class Feed {
 		this.unlistenDB();
 		this.unlistenTokens();
 		this.unlistenToolCalls();
+		this.unlistenToolApprovals();
 	}
 }
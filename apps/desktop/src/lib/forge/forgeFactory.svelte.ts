@@ -25,8 +25,11 @@ export type ForgeConfig = {
 	githubAuthenticated?: boolean;
 	gitlabAuthenticated?: boolean;
 	forgeOverride?: ForgeName;
+	forgeHosts?: Record<string, ForgeName>;
 };
 
+const SELF_HOSTED_FORGES: ForgeName[] = ['github', 'gitlab', 'bitbucket', 'azure'];
+
 export const DEFAULT_FORGE_FACTORY = new InjectionToken<DefaultForgeFactory>('DefaultForgeFactory');
 
 export class DefaultForgeFactory implements Reactive<Forge> {
@@ -59,17 +62,25 @@ export class DefaultForgeFactory implements Reactive<Forge> {
 			return;
 		}
 		this._config = config;
-		const { repo, pushRepo, baseBranch, githubAuthenticated, gitlabAuthenticated, forgeOverride } =
-			config;
+		const {
+			repo,
+			pushRepo,
+			baseBranch,
+			githubAuthenticated,
+			gitlabAuthenticated,
+			forgeOverride,
+			forgeHosts
+		} = config;
 		if (repo && baseBranch) {
-			this._determinedForgeType.next(this.determineForgeType(repo));
+			this._determinedForgeType.next(this.determineForgeType(repo, forgeHosts));
 			this._forge = this.build({
 				repo,
 				pushRepo,
 				baseBranch,
 				githubAuthenticated,
 				gitlabAuthenticated,
-				forgeOverride
+				forgeOverride,
+				forgeHosts
 			});
 		} else {
 			this._forge = this.default;
@@ -82,7 +93,8 @@ export class DefaultForgeFactory implements Reactive<Forge> {
 		baseBranch,
 		githubAuthenticated,
 		gitlabAuthenticated,
-		forgeOverride
+		forgeOverride,
+		forgeHosts
 	}: {
 		repo: RepoInfo;
 		pushRepo?: RepoInfo;
@@ -90,8 +102,9 @@ export class DefaultForgeFactory implements Reactive<Forge> {
 		githubAuthenticated?: boolean;
 		gitlabAuthenticated?: boolean;
 		forgeOverride: ForgeName | undefined;
+		forgeHosts?: Record<string, ForgeName>;
 	}): Forge {
-		let forgeType = this.determineForgeType(repo);
+		let forgeType = this.determineForgeType(repo, forgeHosts);
 		if (forgeType === 'default' && forgeOverride) {
 			forgeType = forgeOverride;
 		}
@@ -134,9 +147,19 @@ export class DefaultForgeFactory implements Reactive<Forge> {
 		return this.default;
 	}
 
-	private determineForgeType(repo: RepoInfo): ForgeName {
+	private determineForgeType(repo: RepoInfo, forgeHosts?: Record<string, ForgeName>): ForgeName {
 		const domain = repo.domain;
 
+		// Self-hosted forges take precedence, and their hosts may include a port.
+		const selfHosted = Object.entries(forgeHosts ?? {}).find(
+			([host, name]) =>
+				host.replace(/:\d+$/, '').toLowerCase() === domain.toLowerCase() &&
+				SELF_HOSTED_FORGES.includes(name)
+		);
+		if (selfHosted) {
+			return selfHosted[1];
+		}
+
 		if (domain.includes(GITHUB_DOMAIN)) {
 			return 'github';
 		}
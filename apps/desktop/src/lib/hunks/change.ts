@@ -144,4 +144,6 @@ type IgnoredChangeStatus =
 	/** A conflicting entry in the index. The worktree state of the entry is unclear.*/
 	| 'Conflict'
 	/** A change in the `.git/index` that was overruled by a change to the same path in the *worktree*.*/
-	| 'TreeIndex';
+	| 'TreeIndex'
+	/** A submodule has changes in its worktree that have to be committed in the submodule itself.*/
+	| 'SubmoduleDirty';
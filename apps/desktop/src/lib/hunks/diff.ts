@@ -8,10 +8,28 @@ import type { Patch } from '$lib/hunks/hunk';
 export type UnifiedDiff =
 	| { readonly type: 'Binary' } // A binary file that can't be diffed.
 	| { readonly type: 'TooLarge'; readonly subject: TooLarge }
-	| { readonly type: 'Patch'; readonly subject: Patch };
+	| { readonly type: 'Patch'; readonly subject: Patch }
+	| { readonly type: 'Lfs'; readonly subject: LfsSizes }
+	| { readonly type: 'Submodule'; readonly subject: SubmoduleRange };
 
 /** The file was too large and couldn't be diffed. */
 type TooLarge = {
 	/** The size of the file on disk that made it too large. */
 	readonly sizeInBytes: number;
 };
+
+/** A file tracked by Git LFS changed, with the sizes of its objects. */
+type LfsSizes = {
+	/** The size of the previous version of the object, or `null` if it was added. */
+	readonly previousSize: number | null;
+	/** The size of the current version of the object, or `null` if it was deleted. */
+	readonly size: number | null;
+};
+
+/** A submodule changed the commit it points to. */
+type SubmoduleRange = {
+	/** The commit the submodule pointed to previously, or `null` if it was added. */
+	readonly previousCommitId: string | null;
+	/** The commit the submodule points to now, or `null` if it was deleted. */
+	readonly commitId: string | null;
+};
@@ -61,12 +61,19 @@ export type HunkAssignment = {
 	readonly path: string;
 	/** The file path of the hunk in bytes. Used to correctly communicate to the backed when creating new assignments */
 	readonly pathBytes: number[];
+	/** The previous location of the file in bytes, the source of a rename if there was one. */
+	readonly previousPathBytes?: number[] | null;
 	/** The stack to which the hunk is assigned. If None, the hunk is not assigned to any stack (i.e. it belongs in the unassigned area */
 	readonly stackId: string | null;
 	/** The line numbers that were added in this hunk. The "after" or "new" line numbers.*/
 	readonly lineNumsAdded: number[] | null;
 	/** The line numbers that were removed in this hunk. The "before" or "old" line numbers.*/
 	readonly lineNumsRemoved: number[] | null;
+	/**
+	 * If set, the assignment only owns these lines of the hunk, expressed as sub-hunk selections,
+	 * i.e. `-5,2 +0,0` for removed lines and `-0,0 +7,1` for added lines. Otherwise it owns the whole hunk.
+	 */
+	readonly lineRanges?: HunkHeader[] | null;
 };
 
 /**
@@ -86,6 +93,8 @@ export type HunkAssignmentRequest = {
 	 * If a stack id is set, it must be one of the applied stacks.
 	 */
 	stackId: string | null;
+	/** If set, only these lines of the hunk are assigned, in the same format as `HunkAssignment.lineRanges`. */
+	lineRanges?: HunkHeader[] | null;
 };
 
 /** Indicates that the assignment request was rejected due to locking - the hunk depends on a commit in the stack it is currently in. */
@@ -7,6 +7,8 @@ export type LocalKey = {
 
 export type AuthKey = Exclude<KeyType, 'local'> | LocalKey;
 
+export type FileMonitorBackend = 'notify' | 'watchman' | 'gitFsmonitor';
+
 export type Project = {
 	id: string;
 	title: string;
@@ -25,6 +27,11 @@ export type Project = {
 	// Produced just for the frontend to determine if the project is open in any window.
 	is_open: boolean;
 	forge_override: ForgeName | undefined;
+	// Hosts of self-hosted forges, mapped to the forge they run.
+	forge_hosts: Record<string, ForgeName> | undefined;
+	file_monitor_backend: FileMonitorBackend | undefined;
+	// Whether branch metadata is shared through a Git ref that is pushed and fetched along with branches.
+	share_metadata: boolean;
 };
 
 export function vscodePath(path: string) {
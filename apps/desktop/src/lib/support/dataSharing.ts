@@ -7,3 +7,7 @@ export async function logs() {
 export async function projectData(params: { projectId: string }) {
 	return await invoke<string>('get_project_archive_path', params);
 }
+
+export async function diagnostics(params: { projectId: string; includePaths: string[] }) {
+	return await invoke<string>('get_diagnostics_archive_path', params);
+}
@@ -10,9 +10,29 @@ type NameAndBranchStatus = {
 	status: BranchStatus;
 };
 
+export type IntegrationMethod =
+	| 'commitId'
+	| 'changeId'
+	| 'authorAndMessage'
+	| 'changesetId'
+	| 'ancestor'
+	| 'patchId'
+	| 'squash'
+	| 'treeMerge';
+
+/** How a commit was integrated upstream, and by which upstream commits if known. */
+export type Integration = {
+	method: IntegrationMethod;
+	upstreamCommits: string[];
+};
+
 export type BranchStatus =
 	| {
-			type: 'empty' | 'integrated' | 'saflyUpdatable';
+			type: 'empty' | 'saflyUpdatable';
+	  }
+	| {
+			type: 'integrated';
+			subject: Integration;
 	  }
 	| {
 			type: 'conflicted';
@@ -7,6 +7,7 @@ use std::{
 };
 
 use but_core::TreeChange;
+use but_tools::{emit::Emitter, policy::Approver};
 use but_workspace::{StackId, ui::StackEntry};
 use gitbutler_branch::BranchCreateRequest;
 use gitbutler_command_context::CommandContext;
@@ -20,7 +21,6 @@ mod absorb;
 mod action;
 mod auto_commit;
 mod branch_changes;
-mod emit;
 mod generate;
 mod grouping;
 mod openai;
@@ -39,15 +39,13 @@ use uuid::Uuid;
 pub use workflow::WorkflowList;
 pub use workflow::list_workflows;
 
-use crate::{
-    emit::EmitTokenEvent,
-    openai::{ToolCallContent, ToolResponseContent},
-};
+use crate::openai::{ToolCallContent, ToolResponseContent};
 
 pub fn freestyle(
     project_id: ProjectId,
     message_id: String,
-    app_handle: &tauri::AppHandle,
+    emitter: Arc<dyn Emitter>,
+    approver: &dyn Approver,
     ctx: &mut CommandContext,
     openai: &OpenAiProvider,
     chat_messages: Vec<openai::ChatMessage>,
@@ -60,7 +58,8 @@ pub fn freestyle(
         .map_err(|e| anyhow::anyhow!("Failed to serialize project status: {}", e))?;
 
     let mut toolset =
-        but_tools::workspace::workspace_toolset(ctx, Some(app_handle), message_id.clone())?;
+        but_tools::workspace::workspace_toolset(ctx, emitter.as_ref(), message_id.clone())?;
+    toolset.set_approver(approver);
 
     let system_message ="
     You are a GitButler agent that can perform various actions on a Git project.
@@ -124,9 +123,9 @@ pub fn freestyle(
         &mut toolset,
         model,
         Arc::new({
-            let app_handle = app_handle.clone();
+            let emitter = emitter.clone();
             move |token: &str| {
-                app_handle.emit_token_event(token, project_id, message_id.clone());
+                emitter.emit_token_event(token, project_id, message_id.clone());
             }
         }),
     )?;
@@ -135,30 +134,30 @@ pub fn freestyle(
 }
 
 pub fn absorb(
-    app_handle: &tauri::AppHandle,
+    emitter: &dyn Emitter,
     ctx: &mut CommandContext,
     openai: &OpenAiProvider,
     changes: Vec<TreeChange>,
 ) -> anyhow::Result<()> {
-    absorb::absorb(app_handle, ctx, openai, changes)
+    absorb::absorb(emitter, ctx, openai, changes)
 }
 
 pub fn branch_changes(
-    app_handle: &tauri::AppHandle,
+    emitter: &dyn Emitter,
     ctx: &mut CommandContext,
     openai: &OpenAiProvider,
     changes: Vec<TreeChange>,
 ) -> anyhow::Result<()> {
-    branch_changes::branch_changes(app_handle, ctx, openai, changes)
+    branch_changes::branch_changes(emitter, ctx, openai, changes)
 }
 
 pub fn auto_commit(
-    app_handle: &tauri::AppHandle,
+    emitter: &dyn Emitter,
     ctx: &mut CommandContext,
     openai: &OpenAiProvider,
     changes: Vec<TreeChange>,
 ) -> anyhow::Result<()> {
-    auto_commit::auto_commit(app_handle, ctx, openai, changes)
+    auto_commit::auto_commit(emitter, ctx, openai, changes)
 }
 
 pub fn handle_changes(
@@ -177,6 +176,9 @@ pub fn handle_changes(
             source,
             exclusive_stack,
         ),
+        ActionHandler::PolicyViolation => {
+            anyhow::bail!("Policy violations are recorded, but can't handle changes")
+        }
     }
 }
 
@@ -268,6 +270,8 @@ fn stacks_creating_if_none(
 pub enum ActionHandler {
     #[default]
     HandleChangesSimple,
+    /// Not a handler, but an agent was prevented from doing something by the project's policy.
+    PolicyViolation,
 }
 
 impl Display for ActionHandler {
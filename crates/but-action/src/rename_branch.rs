@@ -26,6 +26,16 @@ pub async fn rename_branch(
         current_branch_name,
     } = parameters;
 
+    let policy = but_tools::policy::policy(ctx)?;
+    if let Err(violation) = policy.check_branches("rename_branch", [current_branch_name.as_str()]) {
+        but_tools::policy::record_violation(
+            ctx,
+            format!("Rename the branch '{current_branch_name}'"),
+            &violation,
+        )?;
+        return Err(violation.into());
+    }
+
     let repo = &ctx.gix_repo_for_merging_non_persisting()?;
     let stacks = crate::stacks(ctx, repo)?;
     let existing_branch_names = stacks
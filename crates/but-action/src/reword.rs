@@ -25,6 +25,18 @@ pub async fn commit(
     event: CommitEvent,
 ) -> anyhow::Result<Option<(gix::ObjectId, String)>> {
     let ctx = &mut CommandContext::open(&event.project, event.app_settings)?;
+    let policy = but_tools::policy::policy(ctx)?;
+    if let Err(violation) = policy.check_branches("reword", [event.branch_name.as_str()]) {
+        but_tools::policy::record_violation(
+            ctx,
+            format!(
+                "Reword the commit {} on '{}'",
+                event.commit_id, event.branch_name
+            ),
+            &violation,
+        )?;
+        return Err(violation.into());
+    }
     let repo = &ctx.gix_repo_for_merging_non_persisting()?;
     let changes = but_core::diff::ui::commit_changes_by_worktree_dir(repo, event.commit_id)?;
     let diff = changes.try_as_unidiff_string(repo, ctx.app_settings().context_lines)?;
@@ -143,6 +143,18 @@ fn handle_changes_simple_inner(
         *specs = but_workspace::flatten_diff_specs(specs.clone());
     }
 
+    let commit_count = stack_assignments
+        .iter()
+        .filter(|(stack_id, diff_specs)| {
+            !diff_specs.is_empty()
+                && exclusive_stack.is_none_or(|exclusive| exclusive == **stack_id)
+        })
+        .count();
+    if let Err(violation) = but_tools::policy::policy(ctx)?.check_commit_count(0, commit_count) {
+        but_tools::policy::record_violation(ctx, change_summary.to_string(), &violation)?;
+        return Err(violation.into());
+    }
+
     let mut updated_branches = vec![];
 
     let commit_message = if let Some(prompt) = external_prompt {
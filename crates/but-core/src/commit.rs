@@ -1,6 +1,6 @@
 use crate::Commit;
 use anyhow::{Context, bail};
-use bstr::{BString, ByteSlice};
+use bstr::{BStr, BString, ByteSlice, ByteVec};
 use gix::prelude::ObjectIdExt;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
@@ -58,12 +58,21 @@ impl HeadersV2 {
                 );
                 None
             }
-        } else {
+        } else if let Some(change_id) = commit
+            .extra_headers()
+            .find(HEADERS_V1_CHANGE_ID_FIELD)
+            .filter(|id| !is_jj_change_id(id))
+        {
             // Parse v1 headers
-            let change_id = commit.extra_headers().find("change-id")?;
             let change_id = change_id.to_str().ok()?.to_string();
             let headers = HeadersV1 { change_id };
             Some(headers.into())
+        } else {
+            // Derive our headers from change-ids of other tools, so they are the same after each rewrite.
+            ForeignChangeId::from_commit(commit).map(|id| HeadersV2 {
+                change_id: id.to_change_id(),
+                conflicted: None,
+            })
         }
     }
 
@@ -129,6 +138,8 @@ impl From<HeadersV1> for HeadersV2 {
 }
 
 const HEADERS_VERSION_FIELD: &str = "gitbutler-headers-version";
+/// The field of the first version of our headers, which is also used by Jujutsu.
+const HEADERS_V1_CHANGE_ID_FIELD: &str = "change-id";
 const HEADERS_CHANGE_ID_FIELD: &str = "gitbutler-change-id";
 /// The name of the header field that stores the amount of conflicted files.
 pub const HEADERS_CONFLICTED_FIELD: &str = "gitbutler-conflicted";
@@ -157,6 +168,139 @@ impl From<&HeadersV2> for Vec<(BString, BString)> {
     }
 }
 
+/// The header field in which Jujutsu stores its change-id.
+pub const JJ_CHANGE_ID_FIELD: &str = HEADERS_V1_CHANGE_ID_FIELD;
+/// The token of the commit message trailer in which Gerrit stores its change-id.
+pub const GERRIT_CHANGE_ID_TRAILER: &str = "Change-Id";
+
+/// A change-id written by another tool, which identifies a commit across rewrites just like [`ChangeId`] does.
+///
+/// These are preserved when rewriting commits, and our own [`ChangeId`] is derived from them if a commit
+/// doesn't have one yet, so all tools agree on the identity of a commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForeignChangeId {
+    /// The value of the `change-id` header written by Jujutsu, like `zxkmqnvlsmsqtpwvyrzlvmypskluttlr`.
+    Jujutsu(String),
+    /// The value of the `Change-Id` trailer used by Gerrit, like `I` followed by 40 hexadecimal characters.
+    Gerrit(String),
+}
+
+impl ForeignChangeId {
+    /// Find the change-id another tool wrote into `commit`, preferring the one of Jujutsu over the one of Gerrit.
+    pub fn from_commit(commit: &gix::objs::Commit) -> Option<Self> {
+        Self::from_parts(
+            commit.extra_headers().find(JJ_CHANGE_ID_FIELD),
+            commit.message.as_bstr(),
+        )
+    }
+
+    /// Like [`from_commit()`](Self::from_commit()), but with the value of the `change-id` header,
+    /// if there is one, and the commit `message` passed directly.
+    pub fn from_parts(change_id_header: Option<&BStr>, message: &BStr) -> Option<Self> {
+        if let Some(id) = change_id_header.filter(|id| is_jj_change_id(id)) {
+            return Some(ForeignChangeId::Jujutsu(id.to_str().ok()?.to_owned()));
+        }
+        gerrit_change_id(message).map(|id| ForeignChangeId::Gerrit(id.to_owned()))
+    }
+
+    /// Return the change-id as written by the tool.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ForeignChangeId::Jujutsu(id) | ForeignChangeId::Gerrit(id) => id,
+        }
+    }
+
+    /// Derive our own change-id from this one, which is the same each time.
+    pub fn to_change_id(&self) -> ChangeId {
+        let mut bytes = [0u8; 16];
+        match self {
+            ForeignChangeId::Jujutsu(id) => {
+                // Jujutsu uses hexadecimal notation with the digits 'z' to 'k', from 0 to 15.
+                let nibbles = id.bytes().map(|b| b'z'.wrapping_sub(b) & 0xf);
+                for (idx, nibble) in nibbles.enumerate().take(bytes.len() * 2) {
+                    bytes[idx / 2] |= if idx % 2 == 0 { nibble << 4 } else { nibble };
+                }
+            }
+            ForeignChangeId::Gerrit(id) => {
+                let hex = id.strip_prefix('I').unwrap_or(id);
+                for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+                    *byte = std::str::from_utf8(pair)
+                        .ok()
+                        .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                        .unwrap_or_default();
+                }
+            }
+        }
+        uuid::Uuid::from_bytes(bytes).into()
+    }
+}
+
+/// Return the Gerrit change-id for `change_id`, to be used in a [`GERRIT_CHANGE_ID_TRAILER`].
+///
+/// Deriving a change-id from it with [`ForeignChangeId::to_change_id()`] yields `change_id` again.
+pub fn gerrit_change_id_for(change_id: ChangeId) -> String {
+    let uuid: uuid::Uuid = change_id.into();
+    // Gerrit expects 40 hexadecimal characters, but a change-id only has 32.
+    format!("I{}00000000", uuid.simple())
+}
+
+/// Return `message` with a [`GERRIT_CHANGE_ID_TRAILER`] set to `gerrit_change_id`, or `None` if it already has one.
+///
+/// The trailer is added to the trailers at the end of the message, or into a new paragraph if there are none.
+pub fn with_gerrit_change_id_trailer(message: &BStr, gerrit_change_id: &str) -> Option<BString> {
+    if gerrit_change_id(message).is_some() {
+        return None;
+    }
+    let message = message.trim_end();
+    let paragraphs: Vec<_> = message.split_str("\n\n").collect();
+    let has_trailers = paragraphs.len() > 1
+        && paragraphs
+            .last()
+            .is_some_and(|paragraph| paragraph.lines().all(|line| trailer(line).is_some()));
+    let mut out = BString::from(message);
+    out.push_str(if has_trailers { "\n" } else { "\n\n" });
+    out.push_str(format!("{GERRIT_CHANGE_ID_TRAILER}: {gerrit_change_id}\n"));
+    Some(out)
+}
+
+/// Return `true` if `id` looks like a change-id written by Jujutsu, and not by a previous version of GitButler.
+pub fn is_jj_change_id(id: &BStr) -> bool {
+    !id.is_empty() && id.len() % 2 == 0 && id.iter().all(|b| (b'k'..=b'z').contains(b))
+}
+
+/// Return the value of the last Gerrit [`Change-Id`](GERRIT_CHANGE_ID_TRAILER) trailer in the last paragraph
+/// of `message`, as long as it's not the subject.
+pub fn gerrit_change_id(message: &BStr) -> Option<&str> {
+    let mut paragraphs = message.trim_end().rsplit_str("\n\n");
+    let last_paragraph = paragraphs.next()?;
+    // The subject is never a trailer.
+    paragraphs.next()?;
+    let mut change_id = None;
+    for (token, value) in last_paragraph.lines().filter_map(trailer) {
+        if !token.eq_ignore_ascii_case(GERRIT_CHANGE_ID_TRAILER.as_bytes()) {
+            continue;
+        }
+        let Ok(value) = value.to_str() else { continue };
+        if value.len() == 41
+            && value.starts_with('I')
+            && value[1..].bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            change_id = Some(value);
+        }
+    }
+    change_id
+}
+
+/// Split `line` into the token and value of a trailer, if it is one.
+fn trailer(line: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (token, value) = line.split_once_str(":")?;
+    let is_token = !token.is_empty()
+        && token
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || *b == b'-');
+    is_token.then_some((token, value.trim()))
+}
+
 /// When commits are in conflicting state, they store various trees which to help deal with the conflict.
 ///
 /// This also includes variant that represents the blob which contains the
@@ -47,12 +47,13 @@ pub fn worktree_changes(repo: &gix::Repository) -> anyhow::Result<WorktreeChange
         .status(gix::progress::Discard)?
         .tree_index_track_renames(TrackRenames::Given(rewrites))
         .index_worktree_rewrites(rewrites)
-        // Learn about submodule changes, but only do the cheap checks, showing only what we could commit.
+        // Learn about submodule changes, but skip untracked files in submodules as these are expensive to find.
+        // Modified files are needed to tell the user that the submodule is dirty.
         .index_worktree_submodules(if has_submodule_ignore_configuration {
             gix::status::Submodule::AsConfigured { check_dirty: true }
         } else {
             gix::status::Submodule::Given {
-                ignore: gix::submodule::config::Ignore::Dirty,
+                ignore: gix::submodule::config::Ignore::Untracked,
                 check_dirty: true,
             }
         })
@@ -279,13 +280,21 @@ pub fn worktree_changes(repo: &gix::Repository) -> anyhow::Result<WorktreeChange
                     EntryStatus::Change(index_as_worktree::Change::SubmoduleModification(change)),
                 ..
             }) => {
+                // Changes in the worktree of the submodule can't be committed from here, but the user
+                // should know about them as they are lost to everyone else otherwise.
+                if change
+                    .changes
+                    .as_ref()
+                    .is_some_and(|changes| !changes.is_empty())
+                {
+                    ignored_changes.push(IgnoredWorktreeChange {
+                        path: rela_path.clone(),
+                        status: IgnoredWorktreeTreeChangeStatus::SubmoduleDirty,
+                    });
+                }
                 let Some(checked_out_head_id) = change.checked_out_head_id else {
                     continue;
                 };
-                // We can arrive here if the user configures to `ignore = none`, and there are
-                // only worktree changes.
-                // As we can't do anything with that unless submodules become first-class citizens,
-                // we ignore this case for now.
                 if entry.id == checked_out_head_id {
                     continue;
                 }
@@ -815,7 +824,7 @@ impl TreeChange {
     /// for obtaining a working tree to read files from disk.
     /// Note that the mount of lines of context around each hunk are currently hardcoded to `3` as it *might* be relevant for creating
     /// commits later.
-    /// Return `None` if this change cannot produce a diff, typically because a submodule is involved.
+    /// Return `None` if this change cannot produce a diff, typically because a submodule changed its type.
     pub fn unified_diff(
         &self,
         repo: &gix::Repository,
@@ -29,6 +29,12 @@ impl<const KIND: char> From<Uuid> for Id<KIND> {
     }
 }
 
+impl<const KIND: char> From<Id<KIND>> for Uuid {
+    fn from(value: Id<KIND>) -> Self {
+        value.0
+    }
+}
+
 impl<'de, const KIND: char> Deserialize<'de> for Id<KIND> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
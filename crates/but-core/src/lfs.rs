@@ -0,0 +1,184 @@
+use crate::{ChangeState, TreeStatus};
+use anyhow::Context;
+use bstr::{BStr, BString, ByteSlice};
+use gix::object::tree::EntryKind;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// The first line of each LFS pointer file.
+const VERSION_LINE: &[u8] = b"version https://git-lfs.github.com/spec/v1";
+/// Pointer files are tiny, anything larger than that is never a pointer.
+const MAX_POINTER_SIZE: usize = 1024;
+
+/// An LFS pointer file, as stored in Git in place of the object it points to.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Pointer {
+    /// The hex-encoded SHA-256 hash of the object.
+    pub oid: String,
+    /// The size of the object in bytes.
+    pub size: u64,
+}
+
+impl Pointer {
+    /// Parse `data` as LFS pointer file, or return `None` if it isn't one.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() > MAX_POINTER_SIZE {
+            return None;
+        }
+        let mut lines = data.lines();
+        if lines.next()? != VERSION_LINE {
+            return None;
+        }
+        let (mut oid, mut size) = (None, None);
+        for line in lines {
+            let (key, value) = line.split_once_str(" ")?;
+            match key {
+                b"oid" => oid = Some(value.strip_prefix(b"sha256:")?.to_str().ok()?),
+                b"size" => size = Some(value.to_str().ok()?.parse().ok()?),
+                _ => {}
+            }
+        }
+        let oid =
+            oid.filter(|oid| oid.len() == 64 && oid.bytes().all(|b| b.is_ascii_hexdigit()))?;
+        Some(Pointer {
+            oid: oid.to_owned(),
+            size: size?,
+        })
+    }
+
+    /// Return the path at which the object of this pointer is stored in the local LFS storage of `repo`,
+    /// which is `.git/lfs` unless configured otherwise with `lfs.storage`.
+    pub fn local_object_path(&self, repo: &gix::Repository) -> PathBuf {
+        let storage = match repo.config_snapshot().trusted_path("lfs.storage") {
+            Some(Ok(path)) => repo.common_dir().join(path),
+            _ => repo.common_dir().join("lfs"),
+        };
+        storage
+            .join("objects")
+            .join(&self.oid[..2])
+            .join(&self.oid[2..4])
+            .join(&self.oid)
+    }
+}
+
+/// Learn which paths are tracked by LFS, as configured with `filter=lfs` in `.gitattributes` files.
+pub struct Attributes<'repo> {
+    stack: gix::AttributeStack<'repo>,
+    matches: gix::attrs::search::Outcome,
+}
+
+impl<'repo> Attributes<'repo> {
+    /// Read the attributes of `repo` from its worktree, or from the index if they aren't checked out.
+    pub fn new(repo: &'repo gix::Repository) -> anyhow::Result<Self> {
+        let index = repo.index_or_empty()?;
+        let stack = repo.attributes_only(
+            &index,
+            gix::worktree::stack::state::attributes::Source::WorktreeThenIdMapping,
+        )?;
+        let matches = stack.selected_attribute_matches(Some("filter"));
+        Ok(Attributes { stack, matches })
+    }
+
+    /// Return `true` if the worktree-relative `rela_path` is tracked by LFS.
+    pub fn is_tracked(&mut self, rela_path: &BStr) -> anyhow::Result<bool> {
+        let entry = self.stack.at_entry(rela_path, None)?;
+        Ok(has_lfs_filter(&entry, &mut self.matches))
+    }
+}
+
+/// Return `true` if the worktree-relative `rela_path` is tracked by LFS according to `attributes`.
+///
+/// Use this to reuse attributes that were read already, like the ones of a diff platform, instead of
+/// reading them again with [`Attributes`].
+pub fn is_tracked_by(
+    attributes: &mut gix::worktree::Stack,
+    objects: &dyn gix::objs::Find,
+    rela_path: &BStr,
+) -> anyhow::Result<bool> {
+    let mut matches = attributes.selected_attribute_matches(Some("filter"));
+    let entry = attributes.at_entry(rela_path, None, objects)?;
+    Ok(has_lfs_filter(&entry, &mut matches))
+}
+
+fn has_lfs_filter(
+    entry: &gix::worktree::stack::Platform<'_>,
+    matches: &mut gix::attrs::search::Outcome,
+) -> bool {
+    entry.matching_attributes(matches)
+        && matches
+            .iter_selected()
+            .any(|attr| attr.assignment.state.as_bstr() == Some("lfs".into()))
+}
+
+/// Return the size of the LFS object that `state` at the worktree-relative `rela_path` refers to, which is read from
+/// its pointer, or is the size of the object itself if it wasn't replaced by a pointer, for instance because the clean
+/// filter didn't run.
+pub fn object_size(
+    repo: &gix::Repository,
+    rela_path: &BStr,
+    state: ChangeState,
+) -> anyhow::Result<u64> {
+    let (size, data) = if state.id.is_null() {
+        let path = repo
+            .workdir()
+            .context("need non-bare repository")?
+            .join(gix::path::from_bstr(rela_path));
+        let size = path.symlink_metadata()?.len();
+        let data = (size <= MAX_POINTER_SIZE as u64)
+            .then(|| std::fs::read(&path))
+            .transpose()?;
+        (size, data)
+    } else {
+        let size = repo.find_header(state.id)?.size();
+        let data = (size <= MAX_POINTER_SIZE as u64)
+            .then(|| repo.find_blob(state.id).map(|mut blob| blob.take_data()))
+            .transpose()?;
+        (size, data)
+    };
+    Ok(data
+        .and_then(|data| Pointer::from_bytes(&data))
+        .map_or(size, |pointer| pointer.size))
+}
+
+/// Return all LFS pointers, along with the path they were found at, that were added or modified by
+/// the commits reachable from `tips` but not from `hidden`, and whose objects are missing in the local LFS storage.
+/// Only paths that are [tracked by LFS](Attributes::is_tracked()) are considered.
+///
+/// These objects couldn't be uploaded when pushing these commits, leaving the remote with pointers to objects
+/// that nobody can obtain. `hidden` are typically the tips of the remote tracking branches of the remote to push to.
+pub fn missing_objects(
+    repo: &gix::Repository,
+    tips: impl IntoIterator<Item = impl Into<gix::ObjectId>>,
+    hidden: impl IntoIterator<Item = impl Into<gix::ObjectId>>,
+) -> anyhow::Result<Vec<(BString, Pointer)>> {
+    let mut attributes = Attributes::new(repo)?;
+    let mut seen = BTreeSet::new();
+    let mut missing = Vec::new();
+    for info in repo.rev_walk(tips).with_hidden(hidden).all()? {
+        let info = info?;
+        let (changes, _stats) =
+            crate::diff::tree_changes(repo, info.parent_ids.first().copied(), info.id)?;
+        for change in changes {
+            let state = match change.status {
+                TreeStatus::Addition { state, .. }
+                | TreeStatus::Modification { state, .. }
+                | TreeStatus::Rename { state, .. } => state,
+                TreeStatus::Deletion { .. } => continue,
+            };
+            if !matches!(state.kind, EntryKind::Blob | EntryKind::BlobExecutable)
+                || !attributes.is_tracked(change.path.as_bstr())?
+                || repo.find_header(state.id)?.size() > MAX_POINTER_SIZE as u64
+            {
+                continue;
+            }
+            let blob = repo.find_blob(state.id)?;
+            let Some(pointer) = Pointer::from_bytes(&blob.data) else {
+                continue;
+            };
+            if seen.insert(pointer.oid.clone()) && !pointer.local_object_path(repo).is_file() {
+                missing.push((change.path, pointer));
+            }
+        }
+    }
+    Ok(missing)
+}
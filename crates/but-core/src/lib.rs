@@ -67,6 +67,15 @@ pub mod unified_diff;
 /// utilities for command-invocation.
 pub mod cmd;
 
+/// Support for Git LFS, which commits small pointer files in place of large objects that are stored elsewhere.
+pub mod lfs;
+
+/// Compute patch-ids, which identify the changes of a commit independently of its ancestry,
+/// whitespace and line numbers, similar to `git patch-id --stable`.
+///
+/// They are computed in-process and are only comparable with each other, not with the ones Git produces.
+pub mod patch_id;
+
 /// Various settings
 pub mod settings;
 pub use settings::git::types::GitConfigSettings;
@@ -182,6 +191,25 @@ pub enum UnifiedDiff {
         /// The total amount of lines removed.
         lines_removed: u32,
     },
+    /// A file tracked by Git LFS changed, and as its pointer file is all that Git sees, only its size is shown.
+    #[serde(rename_all = "camelCase")]
+    Lfs {
+        /// The size of the previous version of the object, or `None` if it was added.
+        previous_size: Option<u64>,
+        /// The size of the current version of the object, or `None` if it was deleted.
+        size: Option<u64>,
+    },
+    /// A submodule changed the commit it points to, which can't be shown as patch.
+    /// Instead, the commits in between can be listed to show what changed.
+    #[serde(rename_all = "camelCase")]
+    Submodule {
+        /// The commit the submodule pointed to previously, or `None` if it was added.
+        #[serde(with = "gitbutler_serde::object_id_opt")]
+        previous_commit_id: Option<gix::ObjectId>,
+        /// The commit the submodule points to now, or `None` if it was deleted or if its `HEAD` is unborn.
+        #[serde(with = "gitbutler_serde::object_id_opt")]
+        commit_id: Option<gix::ObjectId>,
+    },
 }
 
 /// Either git reference or a virtual reference (i.e. a reference not visible in Git).
@@ -325,6 +353,9 @@ pub enum IgnoredWorktreeTreeChangeStatus {
     /// A tree-index change was effectively undone by an index-worktree change. Thus, the version in the worktree
     /// is the same as what Git is currently tracking.
     TreeIndexWorktreeChangeIneffective,
+    /// A submodule has changes in its worktree that have to be committed in the submodule itself before
+    /// the commit it points to can be committed here.
+    SubmoduleDirty,
 }
 
 /// A way to indicate that a path in the index isn't suitable for committing and needs to be dealt with.
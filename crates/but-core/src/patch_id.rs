@@ -0,0 +1,106 @@
+use crate::{ChangeState, TreeStatus, UnifiedDiff};
+use anyhow::Result;
+use bstr::ByteSlice;
+
+/// The patch-id of a set of changes.
+pub type PatchId = gix::ObjectId;
+
+/// Return `(commit_id, patch_id)` for each of `commits` in `repo`.
+/// Merge commits and commits without changes have no patch-id and are skipped.
+pub fn of_commits(
+    repo: &gix::Repository,
+    commits: impl IntoIterator<Item = gix::ObjectId>,
+) -> Result<Vec<(gix::ObjectId, PatchId)>> {
+    let mut out = Vec::new();
+    for commit_id in commits {
+        let commit = repo.find_commit(commit_id)?;
+        let mut parent_ids = commit.parent_ids();
+        let parent_id = parent_ids.next();
+        if parent_ids.next().is_some() {
+            continue;
+        }
+        let base_tree_id = match parent_id {
+            Some(parent_id) => repo.find_commit(parent_id)?.tree_id()?.detach(),
+            None => repo.empty_tree().id,
+        };
+        if let Some(patch_id) = of_tree_diff(repo, base_tree_id, commit.tree_id()?.detach())? {
+            out.push((commit_id, patch_id));
+        }
+    }
+    Ok(out)
+}
+
+/// Return the patch-id of all changes between `base_tree_id` and `tree_id` in `repo`,
+/// or `None` if there is no change.
+pub fn of_tree_diff(
+    repo: &gix::Repository,
+    base_tree_id: gix::ObjectId,
+    tree_id: gix::ObjectId,
+) -> Result<Option<PatchId>> {
+    if base_tree_id == tree_id {
+        return Ok(None);
+    }
+    let (changes, _) = crate::diff::tree_changes(repo, Some(base_tree_id), tree_id)?;
+    if changes.is_empty() {
+        return Ok(None);
+    }
+    // Like `--stable`, the order of files doesn't matter.
+    let mut file_ids = Vec::with_capacity(changes.len());
+    for change in changes {
+        let mut hash = gix::hash::hasher(gix::hash::Kind::Sha1);
+        hash.update(change.previous_path().unwrap_or(change.path.as_bstr()));
+        hash.update(b"\0");
+        hash.update(&change.path);
+        hash.update(b"\0");
+        match change.unified_diff(repo, 0)? {
+            Some(UnifiedDiff::Patch { hunks, .. }) => {
+                for hunk in hunks {
+                    // Only added and removed lines count, without the hunk header and its line numbers.
+                    for line in hunk.diff.lines() {
+                        if !matches!(line.first(), Some(b'+' | b'-')) {
+                            continue;
+                        }
+                        hash.update(&line[..1]);
+                        for word in line[1..].fields() {
+                            hash.update(word);
+                        }
+                        hash.update(b"\n");
+                    }
+                }
+            }
+            _ => {
+                let (previous_state, state) = states(&change.status);
+                for state in [previous_state, state] {
+                    match state {
+                        Some(ChangeState { id, .. }) => hash.update(id.as_slice()),
+                        None => hash.update(b"-"),
+                    }
+                }
+            }
+        }
+        file_ids.push(hash.try_finalize()?);
+    }
+    file_ids.sort();
+    let mut hash = gix::hash::hasher(gix::hash::Kind::Sha1);
+    for file_id in file_ids {
+        hash.update(file_id.as_slice());
+    }
+    Ok(Some(hash.try_finalize()?))
+}
+
+fn states(status: &TreeStatus) -> (Option<ChangeState>, Option<ChangeState>) {
+    match status {
+        TreeStatus::Addition { state, .. } => (None, Some(*state)),
+        TreeStatus::Deletion { previous_state } => (Some(*previous_state), None),
+        TreeStatus::Modification {
+            previous_state,
+            state,
+            ..
+        }
+        | TreeStatus::Rename {
+            previous_state,
+            state,
+            ..
+        } => (Some(*previous_state), Some(*state)),
+    }
+}
@@ -26,9 +26,31 @@ pub trait RepositoryExt {
         to_rebase_commit_id: gix::ObjectId,
         merge_options: gix::merge::tree::Options,
     ) -> anyhow::Result<gix::merge::tree::Outcome<'_>>;
+    /// Return the commit that `HEAD` points to in the repository at the worktree-relative `rela_path`, typically a submodule.
+    /// Return `None` if there is no repository at `rela_path`, or if its `HEAD` is unborn.
+    fn worktree_repository_head_id(
+        &self,
+        rela_path: &gix::bstr::BStr,
+    ) -> anyhow::Result<Option<gix::ObjectId>>;
 }
 
 impl RepositoryExt for gix::Repository {
+    fn worktree_repository_head_id(
+        &self,
+        rela_path: &gix::bstr::BStr,
+    ) -> anyhow::Result<Option<gix::ObjectId>> {
+        let path = self
+            .workdir()
+            .context("need non-bare repository")?
+            .join(gix::path::from_bstr(rela_path));
+        let repo = match gix::open_opts(path, gix::open::Options::isolated()) {
+            Ok(repo) => repo,
+            Err(gix::open::Error::NotARepository { .. }) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(repo.head()?.id().map(|id| id.detach()))
+    }
+
     fn cherry_pick_commits_to_tree(
         &self,
         new_base_commit_id: gix::ObjectId,
@@ -9,6 +9,7 @@ pub mod git {
 
     const GIT_SIGN_COMMITS: &str = "commit.gpgsign";
     const GITBUTLER_SIGN_COMMITS: &str = "gitbutler.signCommits";
+    const GITBUTLER_GERRIT_MODE: &str = "gitbutler.gerritMode";
     const SIGNING_KEY: &str = "user.signingKey";
     const SIGNING_FORMAT: &str = "gpg.format";
     const GPG_PROGRAM: &str = "gpg.program";
@@ -29,6 +30,7 @@ pub mod git {
             pub signing_format: Option<BStringForFrontend>,
             pub gpg_program: Option<BStringForFrontend>,
             pub gpg_ssh_program: Option<BStringForFrontend>,
+            pub gerrit_mode: Option<bool>,
         }
 
         impl From<crate::GitConfigSettings> for GitConfigSettings {
@@ -39,6 +41,7 @@ pub mod git {
                     signing_format,
                     gpg_program,
                     gpg_ssh_program,
+                    gitbutler_gerrit_mode,
                 }: crate::GitConfigSettings,
             ) -> Self {
                 GitConfigSettings {
@@ -49,6 +52,7 @@ pub mod git {
                         .and_then(|v| gix::path::os_string_into_bstring(v).ok().map(Into::into)),
                     gpg_ssh_program: gpg_ssh_program
                         .and_then(|v| gix::path::os_string_into_bstring(v).ok().map(Into::into)),
+                    gerrit_mode: gitbutler_gerrit_mode,
                 }
             }
         }
@@ -61,6 +65,7 @@ pub mod git {
                     signing_format,
                     gpg_program,
                     gpg_ssh_program,
+                    gerrit_mode,
                 }: GitConfigSettings,
             ) -> Self {
                 crate::GitConfigSettings {
@@ -69,6 +74,7 @@ pub mod git {
                     signing_format: signing_format.map(Into::into),
                     gpg_program: gpg_program.map(Into::into),
                     gpg_ssh_program: gpg_ssh_program.map(Into::into),
+                    gitbutler_gerrit_mode: gerrit_mode,
                 }
             }
         }
@@ -97,6 +103,9 @@ pub mod git {
             pub gpg_program: Option<OsString>,
             /// `gpg.ssh.program`
             pub gpg_ssh_program: Option<OsString>,
+            /// `gitbutler.gerritMode`, which if `true` makes GitButler add a `Change-Id` trailer to the
+            /// messages of the commits it writes, as needed when pushing to Gerrit.
+            pub gitbutler_gerrit_mode: Option<bool>,
         }
     }
     use types::GitConfigSettings;
@@ -112,24 +121,23 @@ pub mod git {
             let signing_format = config.string(SIGNING_FORMAT).map(Cow::into_owned);
             let gpg_program = config.trusted_program(GPG_PROGRAM).map(Cow::into_owned);
             let gpg_ssh_program = config.trusted_program(GPG_SSH_PROGRAM).map(Cow::into_owned);
+            let gitbutler_gerrit_mode = config.boolean(GITBUTLER_GERRIT_MODE);
             Ok(GitConfigSettings {
                 gitbutler_sign_commits,
                 signing_key,
                 signing_format,
                 gpg_program,
                 gpg_ssh_program,
+                gitbutler_gerrit_mode,
             })
         }
 
         /// Write our data back to the local `.git/config` file of the given `repo`.
         pub fn persist_to_local_config(&self, repo: &gix::Repository) -> Result<()> {
-            // TODO: make this easier in `gix`. Could use config-snapshot-mut, but there is no way to
-            //       auto-reload it/assure it's uptodate.
-            let local_config_path = repo.path().join("config");
-            let mut config = gix::config::File::from_path_no_includes(
-                local_config_path.clone(),
-                gix::config::Source::Local,
-            )?;
+            edit_local_config(repo, |config| self.write_to(config))
+        }
+
+        fn write_to(&self, config: &mut gix::config::File<'static>) -> Result<()> {
             if let Some(sign_commits) = self.gitbutler_sign_commits {
                 config.set_raw_value(
                     &GITBUTLER_SIGN_COMMITS,
@@ -152,12 +160,34 @@ pub mod git {
             {
                 config.set_raw_value(&GPG_SSH_PROGRAM, gpg_ssh_program.as_bstr())?;
             }
-
-            write_config(&mut config, &local_config_path)?;
+            if let Some(gerrit_mode) = self.gitbutler_gerrit_mode {
+                config.set_raw_value(
+                    &GITBUTLER_GERRIT_MODE,
+                    if gerrit_mode { "true" } else { "false" },
+                )?;
+            }
             Ok(())
         }
     }
 
+    /// Let `edit` change the local `.git/config` file of the given `repo`, and write it back afterwards.
+    ///
+    /// Note that `repo` won't see the changes until its configuration is reloaded.
+    pub fn edit_local_config(
+        repo: &gix::Repository,
+        edit: impl FnOnce(&mut gix::config::File<'static>) -> Result<()>,
+    ) -> Result<()> {
+        // TODO: make this easier in `gix`. Could use config-snapshot-mut, but there is no way to
+        //       auto-reload it/assure it's uptodate.
+        let local_config_path = repo.path().join("config");
+        let mut config = gix::config::File::from_path_no_includes(
+            local_config_path.clone(),
+            gix::config::Source::Local,
+        )?;
+        edit(&mut config)?;
+        write_config(&mut config, &local_config_path)
+    }
+
     fn write_config(config: &mut gix::config::File<'_>, local_config_path: &Path) -> Result<()> {
         // Note: we don't use a lock file here to not risk changing the mode, and it's what Git does.
         //       But we lock the file so there is no raciness.
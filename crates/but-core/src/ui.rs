@@ -98,12 +98,30 @@ fn changes_to_unidiff_string(
                     builder.push('\n');
                 }
             }
+            Some(UnifiedDiff::Lfs {
+                previous_size,
+                size,
+            }) => {
+                builder.push_str(&lfs_summary(previous_size, size));
+                builder.push('\n');
+            }
             _ => continue,
         }
     }
     Ok(builder)
 }
 
+/// Describe how an LFS object changed in size, without diffing its content.
+fn lfs_summary(previous_size: Option<u64>, size: Option<u64>) -> String {
+    let fmt_size =
+        |size: Option<u64>| size.map_or_else(|| "none".into(), |size| format!("{size} bytes"));
+    format!(
+        "LFS object changed ({} → {})",
+        fmt_size(previous_size),
+        fmt_size(size)
+    )
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TreeChange {
@@ -1,8 +1,10 @@
 use super::{ChangeState, UnifiedDiff};
+use crate::{RepositoryExt, lfs};
 use bstr::{BStr, BString, ByteSlice};
 use gix::diff::blob::ResourceKind;
 use gix::diff::blob::platform::prepare_diff::Operation;
 use gix::diff::blob::unified_diff::ContextSize;
+use gix::object::tree::EntryKind;
 use serde::Serialize;
 
 /// A hunk as used in a [UnifiedDiff], which also contains all added and removed lines.
@@ -62,12 +64,14 @@ impl UnifiedDiff {
     /// `current_state` is either the state we know the resource currently has, or is `None`, if there is no current state.
     /// `previous_state`, if `None`, indicates the file is new so there is nothing to compare to.
     /// Otherwise, it's the state of the resource as previously known.
-    /// Return `None` if the given states cannot produce a diff, typically because a submodule is involved.
+    /// Return `None` if the given states cannot produce a diff, typically because a submodule changed its type.
     ///
     /// ### Special Types
     ///
-    /// Note that *Submodules* won't render as patches, they have to be caught in the UI to render their previous hash
-    /// and current hash directly.
+    /// Files tracked by *Git LFS* won't render as patches either, but as [`UnifiedDiff::Lfs`] with the sizes of their objects.
+    /// Note that *Submodules* won't render as patches, but as [`UnifiedDiff::Submodule`] with their previous hash
+    /// and current hash, so the UI can show the commits in between. If the current hash isn't known yet, as is the case
+    /// for untracked repositories, it's read from the `HEAD` of the repository in the worktree.
     /// Type-changes, from file to submodule or vice-versa for instance, should be shown as typechange only, probably showing
     /// the old and the new type, without diff preview for now.
     pub fn compute(
@@ -106,6 +110,9 @@ impl UnifiedDiff {
     ) -> anyhow::Result<Option<Self>> {
         let current_state = current_state.into();
         let previous_state = previous_state.into();
+        if let Some(diff) = submodule_diff(repo, path, current_state, previous_state)? {
+            return Ok(Some(diff));
+        }
         match diff_filter.set_resource(
             current_state.map_or(repo.object_hash().null(), |state| state.id),
             current_state.map_or_else(
@@ -147,6 +154,17 @@ impl UnifiedDiff {
             Err(err) => return Err(err.into()),
         };
 
+        if let Some(diff) = lfs_diff(
+            repo,
+            path,
+            previous_path,
+            current_state,
+            previous_state,
+            diff_filter,
+        )? {
+            return Ok(Some(diff));
+        }
+
         let prep = diff_filter.prepare_diff()?;
         Ok(Some(match prep.operation {
             Operation::InternalDiff { algorithm } => {
@@ -248,6 +266,63 @@ fn compute_line_changes(hunks: &Vec<DiffHunk>) -> (u32, u32) {
     (lines_added, lines_removed)
 }
 
+/// Return a [`UnifiedDiff::Lfs`] if one of the resources in `diff_filter` is an LFS pointer, which is what the
+/// clean filter produces for LFS objects, or if `path` is tracked by LFS but the clean filter didn't run, leaving a binary object.
+fn lfs_diff(
+    repo: &gix::Repository,
+    path: &BStr,
+    previous_path: Option<&BStr>,
+    current_state: Option<ChangeState>,
+    previous_state: Option<ChangeState>,
+    diff_filter: &mut gix::diff::blob::Platform,
+) -> anyhow::Result<Option<UnifiedDiff>> {
+    use gix::diff::blob::platform::resource::Data;
+    let Some((old, new)) = diff_filter.resources() else {
+        return Ok(None);
+    };
+    let is_pointer = |data: &Data<'_>| matches!(data, Data::Buffer { buf, .. } if lfs::Pointer::from_bytes(buf).is_some());
+    let is_binary = |data: &Data<'_>| matches!(data, Data::Binary { .. });
+    let has_pointer = is_pointer(&old.data) || is_pointer(&new.data);
+    let has_binary = is_binary(&old.data) || is_binary(&new.data);
+    // The attributes of the diff platform are reused, so they don't have to be read again for each file.
+    if !has_pointer
+        && (!has_binary || !lfs::is_tracked_by(&mut diff_filter.attr_stack, &repo.objects, path)?)
+    {
+        return Ok(None);
+    }
+    Ok(Some(UnifiedDiff::Lfs {
+        previous_size: previous_state
+            .map(|state| lfs::object_size(repo, previous_path.unwrap_or(path), state))
+            .transpose()?,
+        size: current_state
+            .map(|state| lfs::object_size(repo, path, state))
+            .transpose()?,
+    }))
+}
+
+/// Return a [`UnifiedDiff::Submodule`] if each of the given states that is present is a submodule, or `None` otherwise.
+fn submodule_diff(
+    repo: &gix::Repository,
+    path: &BStr,
+    current_state: Option<ChangeState>,
+    previous_state: Option<ChangeState>,
+) -> anyhow::Result<Option<UnifiedDiff>> {
+    let is_submodule =
+        |state: Option<ChangeState>| state.is_none_or(|state| state.kind == EntryKind::Commit);
+    if !is_submodule(current_state) || !is_submodule(previous_state) {
+        return Ok(None);
+    }
+    let commit_id = match current_state {
+        None => None,
+        Some(state) if state.id.is_null() => repo.worktree_repository_head_id(path)?,
+        Some(state) => Some(state.id),
+    };
+    Ok(Some(UnifiedDiff::Submodule {
+        previous_commit_id: previous_state.map(|state| state.id),
+        commit_id,
+    }))
+}
+
 /// Produce a filter from `repo` and `state` using `mode` that is able to perform diffs of `state`.
 pub fn filter_from_state(
     repo: &gix::Repository,
@@ -11,6 +11,135 @@ fn is_conflicted() -> anyhow::Result<()> {
     Ok(())
 }
 
+mod foreign_change_id {
+    use but_core::commit::{
+        ForeignChangeId, HeadersV2, gerrit_change_id, gerrit_change_id_for,
+        with_gerrit_change_id_trailer,
+    };
+
+    const JJ_ID: &str = "zxkmqnvlsmsqtpwvyrzlvmypskluttlr";
+    const GERRIT_ID: &str = "I0123456789abcdef0123456789abcdef01234567";
+
+    #[test]
+    fn jujutsu_change_ids_are_read_from_headers() {
+        let commit = commit_with(&[("change-id", JJ_ID)], "subject\n");
+        let id = ForeignChangeId::from_commit(&commit).expect("present");
+        assert_eq!(id, ForeignChangeId::Jujutsu(JJ_ID.into()));
+        assert_eq!(
+            HeadersV2::try_from_commit(&commit)
+                .expect("derived")
+                .change_id,
+            id.to_change_id(),
+            "our change-id is derived from the one of Jujutsu, so it's stable"
+        );
+        assert_eq!(
+            ForeignChangeId::Jujutsu("zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz".into())
+                .to_change_id()
+                .to_string(),
+            "00000000-0000-0000-0000-000000000000"
+        );
+        assert_eq!(
+            ForeignChangeId::Jujutsu("kyzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz".into())
+                .to_change_id()
+                .to_string(),
+            "f1000000-0000-0000-0000-000000000000"
+        );
+    }
+
+    #[test]
+    fn previous_gitbutler_change_ids_are_not_mistaken_for_jujutsu() {
+        let uuid = "6f1b5b3c-8d7e-4b8a-9d2c-1e2f3a4b5c6d";
+        let commit = commit_with(&[("change-id", uuid)], "subject\n");
+        assert_eq!(ForeignChangeId::from_commit(&commit), None);
+        assert_eq!(
+            HeadersV2::try_from_commit(&commit)
+                .expect("v1 headers")
+                .change_id
+                .to_string(),
+            uuid
+        );
+    }
+
+    #[test]
+    fn gerrit_change_ids_are_read_from_the_last_paragraph() {
+        let message = format!("subject\n\nbody\n\nSigned-off-by: me\nChange-Id: {GERRIT_ID}\n");
+        let commit = commit_with(&[], &message);
+        assert_eq!(
+            ForeignChangeId::from_commit(&commit),
+            Some(ForeignChangeId::Gerrit(GERRIT_ID.into()))
+        );
+        assert_eq!(
+            HeadersV2::try_from_commit(&commit)
+                .expect("derived")
+                .change_id
+                .to_string(),
+            "01234567-89ab-cdef-0123-456789abcdef"
+        );
+
+        let commit = commit_with(&[("change-id", JJ_ID)], &message);
+        assert_eq!(
+            ForeignChangeId::from_commit(&commit),
+            Some(ForeignChangeId::Jujutsu(JJ_ID.into())),
+            "Jujutsu is preferred"
+        );
+
+        for message in [
+            format!("Change-Id: {GERRIT_ID}\n"),
+            format!("subject\n\nChange-Id: {GERRIT_ID}\n\nmore body\n"),
+            "subject\n\nChange-Id: I0123\n".into(),
+        ] {
+            assert_eq!(
+                gerrit_change_id(message.as_str().into()),
+                None,
+                "the subject, other paragraphs and invalid ids are ignored: {message:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn gerrit_trailers_are_added_unless_present() {
+        let change_id = ForeignChangeId::Gerrit(GERRIT_ID.into()).to_change_id();
+        let gerrit_id = gerrit_change_id_for(change_id);
+        assert_eq!(gerrit_id, "I0123456789abcdef0123456789abcdef00000000");
+
+        assert_eq!(
+            with_gerrit_change_id_trailer("subject\n".into(), &gerrit_id).expect("added"),
+            format!("subject\n\nChange-Id: {gerrit_id}\n")
+        );
+        assert_eq!(
+            with_gerrit_change_id_trailer("subject\n\nSigned-off-by: me\n".into(), &gerrit_id)
+                .expect("added"),
+            format!("subject\n\nSigned-off-by: me\nChange-Id: {gerrit_id}\n"),
+            "it's added to existing trailers"
+        );
+        assert_eq!(
+            with_gerrit_change_id_trailer(
+                format!("subject\n\nChange-Id: {GERRIT_ID}\n")
+                    .as_str()
+                    .into(),
+                &gerrit_id
+            ),
+            None,
+            "existing trailers are kept"
+        );
+    }
+
+    fn commit_with(headers: &[(&str, &str)], message: &str) -> gix::objs::Commit {
+        gix::objs::Commit {
+            tree: gix::ObjectId::empty_tree(gix::hash::Kind::Sha1),
+            parents: Default::default(),
+            author: Default::default(),
+            committer: Default::default(),
+            encoding: None,
+            message: message.into(),
+            extra_headers: headers
+                .iter()
+                .map(|(name, value)| ((*name).into(), (*value).into()))
+                .collect(),
+        }
+    }
+}
+
 pub fn conflict_repo(name: &str) -> anyhow::Result<gix::Repository> {
     let root = gix_testtools::scripted_fixture_read_only("conflict-commits.sh")
         .map_err(anyhow::Error::from_boxed)?;
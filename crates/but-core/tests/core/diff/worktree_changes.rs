@@ -295,10 +295,16 @@ fn submodule_added_in_unborn() -> Result<()> {
         ignored_changes: [],
     }
     "#);
-    assert_eq!(
-        unified_diffs(actual, &repo).unwrap_err().to_string(),
-        "Can only diff blobs and links, not Commit"
-    );
+    insta::assert_debug_snapshot!(unified_diffs(actual, &repo)?.last(), @r"
+    Some(
+        Submodule {
+            previous_commit_id: None,
+            commit_id: Some(
+                Sha1(e95516bd2f49a83a6cdb98cfec40b2717fbc2c1b),
+            ),
+        },
+    )
+    ");
     Ok(())
 }
 
@@ -337,7 +343,12 @@ fn submodule_changes_set_to_all_in_config_but_has_uncommittable_changes() -> Res
     insta::assert_debug_snapshot!(actual, @r#"
     WorktreeChanges {
         changes: [],
-        ignored_changes: [],
+        ignored_changes: [
+            IgnoredWorktreeChange {
+                path: "submodule",
+                status: SubmoduleDirty,
+            },
+        ],
     }
     "#);
     Ok(())
@@ -368,10 +379,18 @@ fn submodule_changed_head() -> Result<()> {
         ignored_changes: [],
     }
     "#);
-    assert_eq!(
-        unified_diffs(actual, &repo).unwrap_err().to_string(),
-        "Can only diff blobs and links, not Commit"
-    );
+    insta::assert_debug_snapshot!(unified_diffs(actual, &repo)?, @r"
+    [
+        Submodule {
+            previous_commit_id: Some(
+                Sha1(e95516bd2f49a83a6cdb98cfec40b2717fbc2c1b),
+            ),
+            commit_id: Some(
+                Sha1(800a5398d76f28db44bc976b561d8885687fd1b6),
+            ),
+        },
+    ]
+    ");
     Ok(())
 }
 
@@ -1804,7 +1823,7 @@ fn unified_diffs(
         .into_iter()
         .map(|c| c.unified_diff(repo, 3))
     {
-        out.push(diff?.context("Can only diff blobs and links, not Commit")?);
+        out.push(diff?.context("Can only diff blobs, links and submodules, not type changes")?);
     }
     Ok(out)
 }
@@ -0,0 +1,131 @@
+use but_core::lfs::{Pointer, missing_objects};
+use but_core::{diff, ui};
+
+use crate::diff::worktree_changes::repo_in;
+
+#[test]
+fn pointer_from_bytes() {
+    let oid = "e55ee9401d0d2cafa18697782a6df91ca5a670dc769efc384884e9e7891fc259";
+    assert_eq!(
+        Pointer::from_bytes(
+            format!("version https://git-lfs.github.com/spec/v1\noid sha256:{oid}\nsize 14\n")
+                .as_bytes()
+        ),
+        Some(Pointer {
+            oid: oid.into(),
+            size: 14
+        })
+    );
+    assert_eq!(
+        Pointer::from_bytes(b"version https://git-lfs.github.com/spec/v1\nsize 14\n"),
+        None,
+        "the object id is required"
+    );
+    assert_eq!(
+        Pointer::from_bytes(format!("oid sha256:{oid}\nsize 14\n").as_bytes()),
+        None,
+        "the version must come first"
+    );
+}
+
+#[test]
+fn modified_pointer_shows_object_sizes() -> anyhow::Result<()> {
+    let repo = repo("pointer-modified")?;
+    let worktree_changes = diff::worktree_changes(&repo)?;
+    let diffs = worktree_changes
+        .changes
+        .iter()
+        .map(|change| change.unified_diff(&repo, 3))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    insta::assert_debug_snapshot!(diffs, @r"
+    [
+        Some(
+            Lfs {
+                previous_size: Some(
+                    12,
+                ),
+                size: Some(
+                    20,
+                ),
+            },
+        ),
+    ]
+    ");
+
+    let unidiff = ui::WorktreeChanges::from(worktree_changes).try_as_unidiff_string(&repo, 3)?;
+    insta::assert_snapshot!(unidiff, @r"
+    --- a/object.bin
+    +++ b/object.bin
+    LFS object changed (12 bytes → 20 bytes)
+    ");
+    Ok(())
+}
+
+#[test]
+fn binary_object_is_recognized_by_attributes_without_clean_filter() -> anyhow::Result<()> {
+    let repo = repo("untracked-object-without-clean-filter")?;
+    let diffs = diff::worktree_changes(&repo)?
+        .changes
+        .iter()
+        .map(|change| Ok((change.path.clone(), change.unified_diff(&repo, 3)?)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    insta::assert_debug_snapshot!(diffs, @r#"
+    [
+        (
+            "not-tracked-by-lfs",
+            Some(
+                Binary,
+            ),
+        ),
+        (
+            "object.bin",
+            Some(
+                Lfs {
+                    previous_size: None,
+                    size: Some(
+                        14,
+                    ),
+                },
+            ),
+        ),
+    ]
+    "#);
+    Ok(())
+}
+
+#[test]
+fn missing_objects_of_unpushed_commits() -> anyhow::Result<()> {
+    let repo = repo("push")?;
+    let head_id = repo.head_id()?.detach();
+    let remote_id = repo
+        .find_reference("refs/remotes/origin/main")?
+        .id()
+        .detach();
+    insta::assert_debug_snapshot!(missing_objects(&repo, Some(head_id), Some(remote_id))?, @r#"
+    [
+        (
+            "missing.bin",
+            Pointer {
+                oid: "e55ee9401d0d2cafa18697782a6df91ca5a670dc769efc384884e9e7891fc259",
+                size: 14,
+            },
+        ),
+    ]
+    "#);
+
+    let paths: Vec<_> = missing_objects(&repo, Some(head_id), None::<gix::ObjectId>)?
+        .into_iter()
+        .map(|(path, _pointer)| path)
+        .collect();
+    assert_eq!(
+        paths,
+        ["missing.bin", "pushed.bin"],
+        "the pushed object only exists on the remote, which doesn't matter once it's pushed, \
+        and pointer-like files that aren't tracked by LFS are ignored"
+    );
+    Ok(())
+}
+
+fn repo(name: &str) -> anyhow::Result<gix::Repository> {
+    repo_in("lfs", name)
+}
@@ -2,5 +2,6 @@ mod cmd;
 mod commit;
 mod diff;
 mod json_samples;
+mod lfs;
 mod settings;
 mod unified_diff;
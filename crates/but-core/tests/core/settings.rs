@@ -22,6 +22,7 @@ mod git {
             signing_format: Some("signing format".into()),
             gpg_program: Some("gpg program".into()),
             gpg_ssh_program: Some("gpg ssh program".into()),
+            gitbutler_gerrit_mode: Some(true),
         };
         repo.set_git_settings(&expected)?;
         let actual = repo.git_settings()?;
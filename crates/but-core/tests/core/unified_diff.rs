@@ -127,7 +127,10 @@ fn big_file_20_in_worktree() -> anyhow::Result<()> {
     )?
     .expect("present");
     match actual {
-        UnifiedDiff::Binary | UnifiedDiff::Patch { .. } => {
+        UnifiedDiff::Binary
+        | UnifiedDiff::Patch { .. }
+        | UnifiedDiff::Lfs { .. }
+        | UnifiedDiff::Submodule { .. } => {
             unreachable!("Should be considered too large")
         }
         UnifiedDiff::TooLarge { size_in_bytes } => {
@@ -156,7 +159,10 @@ fn binary_file_in_worktree() -> anyhow::Result<()> {
     )?
     .expect("present");
     match actual {
-        UnifiedDiff::TooLarge { .. } | UnifiedDiff::Patch { .. } => {
+        UnifiedDiff::TooLarge { .. }
+        | UnifiedDiff::Patch { .. }
+        | UnifiedDiff::Lfs { .. }
+        | UnifiedDiff::Submodule { .. } => {
             unreachable!("Should be considered binary, but was {actual:?}");
         }
         UnifiedDiff::Binary => {
@@ -224,16 +230,28 @@ fn submodule_added() -> anyhow::Result<()> {
         },
     ]
     "#);
-    assert!(
-        changes[1].unified_diff(&repo, 3)?.is_none(),
-        "submodules produce no diffs"
-    );
+    insta::assert_debug_snapshot!(changes[1].unified_diff(&repo, 3)?, @r"
+    Some(
+        Submodule {
+            previous_commit_id: None,
+            commit_id: Some(
+                Sha1(e95516bd2f49a83a6cdb98cfec40b2717fbc2c1b),
+            ),
+        },
+    )
+    ");
     Ok(())
 }
 
 fn extract_patch(diff: Option<UnifiedDiff>) -> Vec<unified_diff::DiffHunk> {
     match diff {
-        None | Some(UnifiedDiff::Binary | UnifiedDiff::TooLarge { .. }) => {
+        None
+        | Some(
+            UnifiedDiff::Binary
+            | UnifiedDiff::TooLarge { .. }
+            | UnifiedDiff::Lfs { .. }
+            | UnifiedDiff::Submodule { .. },
+        ) => {
             unreachable!("should have patches")
         }
         Some(UnifiedDiff::Patch { hunks, .. }) => hunks,
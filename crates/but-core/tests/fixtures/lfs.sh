@@ -0,0 +1,64 @@
+#!/bin/bash
+
+set -eu -o pipefail
+
+function oid() {
+  if command -v sha256sum >/dev/null; then
+    printf '%s' "${1:?content}" | sha256sum | cut -d ' ' -f 1
+  else
+    printf '%s' "${1:?content}" | shasum -a 256 | cut -d ' ' -f 1
+  fi
+}
+
+# Print the LFS pointer file for the object with the given content.
+function pointer() {
+  local content=${1:?content}
+  printf 'version https://git-lfs.github.com/spec/v1\noid sha256:%s\nsize %s\n' "$(oid "$content")" "${#content}"
+}
+
+# Store the object with the given content in the LFS storage of the given git directory.
+function store-object() {
+  local git_dir=${1:?git dir} content=${2:?content}
+  local oid
+  oid=$(oid "$content")
+  mkdir -p "$git_dir/lfs/objects/${oid:0:2}/${oid:2:2}"
+  printf '%s' "$content" >"$git_dir/lfs/objects/${oid:0:2}/${oid:2:2}/$oid"
+}
+
+git init pointer-modified
+(cd pointer-modified
+  echo '*.bin filter=lfs diff=lfs merge=lfs -text' >.gitattributes
+  pointer "small object" >object.bin
+  git add . && git commit -m "init"
+  pointer "a much larger object" >object.bin
+)
+
+git init untracked-object-without-clean-filter
+(cd untracked-object-without-clean-filter
+  echo '*.bin filter=lfs diff=lfs merge=lfs -text' >.gitattributes
+  git add . && git commit -m "init"
+  printf 'binary\0content' >object.bin
+  printf 'binary\0content' >not-tracked-by-lfs
+)
+
+# The bare repository with its LFS storage is the stand-in for a remote with LFS server.
+git init --bare remote.git
+git clone remote.git push
+(cd push
+  echo '*.bin filter=lfs diff=lfs merge=lfs -text' >.gitattributes
+  pointer "pushed object" >pushed.bin
+  git add . && git commit -m "pushed object"
+  git push origin HEAD:main
+  store-object ../remote.git "pushed object"
+
+  pointer "local object" >local.bin
+  store-object .git "local object"
+  git add . && git commit -m "local object"
+
+  pointer "missing object" >missing.bin
+  git add . && git commit -m "missing object"
+
+  # Looks like a pointer, but isn't tracked by LFS, so there is nothing to upload.
+  pointer "not an object" >pointer-like.txt
+  git add . && git commit -m "pointer-like text"
+)
@@ -0,0 +1,17 @@
+-- This file should undo anything in `up.sql`
+
+CREATE TABLE `hunk_assignments_old`(
+	`hunk_header` TEXT,
+	`path` TEXT NOT NULL,
+	`path_bytes` BINARY NOT NULL,
+	`stack_id` TEXT,
+	`id` TEXT,
+	PRIMARY KEY(`path`, `hunk_header`)
+);
+
+INSERT OR IGNORE INTO `hunk_assignments_old` (`hunk_header`, `path`, `path_bytes`, `stack_id`, `id`)
+SELECT `hunk_header`, `path`, `path_bytes`, `stack_id`, `id` FROM `hunk_assignments` WHERE `line_ranges` = '';
+
+DROP TABLE `hunk_assignments`;
+
+ALTER TABLE `hunk_assignments_old` RENAME TO `hunk_assignments`;
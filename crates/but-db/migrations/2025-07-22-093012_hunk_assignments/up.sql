@@ -0,0 +1,21 @@
+-- Your SQL goes here
+
+-- Allow multiple assignments per hunk, each owning a subset of its lines.
+-- SQLite can't alter the primary key, so the table is recreated.
+CREATE TABLE `hunk_assignments_new`(
+	`id` TEXT,
+	`hunk_header` TEXT,
+	`path` TEXT NOT NULL,
+	`path_bytes` BINARY NOT NULL,
+	`stack_id` TEXT,
+	-- Empty if the assignment owns the whole hunk, as columns of the primary key can't be `NULL`.
+	`line_ranges` TEXT NOT NULL DEFAULT '',
+	PRIMARY KEY(`path`, `hunk_header`, `line_ranges`)
+);
+
+INSERT INTO `hunk_assignments_new` (`id`, `hunk_header`, `path`, `path_bytes`, `stack_id`, `line_ranges`)
+SELECT `id`, `hunk_header`, `path`, `path_bytes`, `stack_id`, '' FROM `hunk_assignments`;
+
+DROP TABLE `hunk_assignments`;
+
+ALTER TABLE `hunk_assignments_new` RENAME TO `hunk_assignments`;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE `hunk_assignments` DROP COLUMN `previous_path_bytes`;
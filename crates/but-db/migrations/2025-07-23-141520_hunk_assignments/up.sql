@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+ALTER TABLE `hunk_assignments` ADD COLUMN `previous_path_bytes` BINARY;
@@ -0,0 +1,20 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER IF EXISTS `butler_actions_insert_version`;
+DROP TRIGGER IF EXISTS `butler_actions_update_version`;
+DROP TRIGGER IF EXISTS `butler_actions_delete_version`;
+DROP TRIGGER IF EXISTS `workflows_insert_version`;
+DROP TRIGGER IF EXISTS `workflows_update_version`;
+DROP TRIGGER IF EXISTS `workflows_delete_version`;
+DROP TRIGGER IF EXISTS `hunk_assignments_insert_version`;
+DROP TRIGGER IF EXISTS `hunk_assignments_update_version`;
+DROP TRIGGER IF EXISTS `hunk_assignments_delete_version`;
+DROP TRIGGER IF EXISTS `workspace_rules_insert_version`;
+DROP TRIGGER IF EXISTS `workspace_rules_update_version`;
+DROP TRIGGER IF EXISTS `workspace_rules_delete_version`;
+DROP TRIGGER IF EXISTS `claude_code_sessions_insert_version`;
+DROP TRIGGER IF EXISTS `claude_code_sessions_update_version`;
+DROP TRIGGER IF EXISTS `claude_code_sessions_delete_version`;
+DROP TRIGGER IF EXISTS `file_write_locks_insert_version`;
+DROP TRIGGER IF EXISTS `file_write_locks_update_version`;
+DROP TRIGGER IF EXISTS `file_write_locks_delete_version`;
+DROP TABLE IF EXISTS `change_versions`;
@@ -0,0 +1,71 @@
+-- Your SQL goes here
+CREATE TABLE `change_versions`(
+	`table_name` TEXT NOT NULL PRIMARY KEY,
+	`version` BIGINT NOT NULL
+);
+
+INSERT INTO `change_versions` (`table_name`, `version`) VALUES
+	('butler_actions', 0),
+	('workflows', 0),
+	('hunk_assignments', 0),
+	('workspace_rules', 0),
+	('claude_code_sessions', 0),
+	('file_write_locks', 0);
+
+-- Every mutation moves the version of its table past the highest version of all tables,
+-- which makes the highest version a counter of all changes.
+-- Note that tables which are recreated in future migrations need their triggers to be recreated as well.
+CREATE TRIGGER `butler_actions_insert_version` AFTER INSERT ON `butler_actions` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'butler_actions';
+END;
+CREATE TRIGGER `butler_actions_update_version` AFTER UPDATE ON `butler_actions` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'butler_actions';
+END;
+CREATE TRIGGER `butler_actions_delete_version` AFTER DELETE ON `butler_actions` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'butler_actions';
+END;
+CREATE TRIGGER `workflows_insert_version` AFTER INSERT ON `workflows` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'workflows';
+END;
+CREATE TRIGGER `workflows_update_version` AFTER UPDATE ON `workflows` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'workflows';
+END;
+CREATE TRIGGER `workflows_delete_version` AFTER DELETE ON `workflows` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'workflows';
+END;
+CREATE TRIGGER `hunk_assignments_insert_version` AFTER INSERT ON `hunk_assignments` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'hunk_assignments';
+END;
+CREATE TRIGGER `hunk_assignments_update_version` AFTER UPDATE ON `hunk_assignments` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'hunk_assignments';
+END;
+CREATE TRIGGER `hunk_assignments_delete_version` AFTER DELETE ON `hunk_assignments` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'hunk_assignments';
+END;
+CREATE TRIGGER `workspace_rules_insert_version` AFTER INSERT ON `workspace_rules` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'workspace_rules';
+END;
+CREATE TRIGGER `workspace_rules_update_version` AFTER UPDATE ON `workspace_rules` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'workspace_rules';
+END;
+CREATE TRIGGER `workspace_rules_delete_version` AFTER DELETE ON `workspace_rules` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'workspace_rules';
+END;
+CREATE TRIGGER `claude_code_sessions_insert_version` AFTER INSERT ON `claude_code_sessions` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'claude_code_sessions';
+END;
+CREATE TRIGGER `claude_code_sessions_update_version` AFTER UPDATE ON `claude_code_sessions` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'claude_code_sessions';
+END;
+CREATE TRIGGER `claude_code_sessions_delete_version` AFTER DELETE ON `claude_code_sessions` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'claude_code_sessions';
+END;
+CREATE TRIGGER `file_write_locks_insert_version` AFTER INSERT ON `file_write_locks` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'file_write_locks';
+END;
+CREATE TRIGGER `file_write_locks_update_version` AFTER UPDATE ON `file_write_locks` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'file_write_locks';
+END;
+CREATE TRIGGER `file_write_locks_delete_version` AFTER DELETE ON `file_write_locks` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'file_write_locks';
+END;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER IF EXISTS `agent_policies_insert_version`;
+DROP TRIGGER IF EXISTS `agent_policies_update_version`;
+DROP TRIGGER IF EXISTS `agent_policies_delete_version`;
+DELETE FROM `change_versions` WHERE `table_name` = 'agent_policies';
+DROP TABLE IF EXISTS `agent_policies`;
@@ -0,0 +1,21 @@
+-- Your SQL goes here
+CREATE TABLE `agent_policies`(
+	`id` TEXT NOT NULL PRIMARY KEY,
+	`created_at` TIMESTAMP NOT NULL,
+	`allowed_tools` TEXT,
+	`protected_branches` TEXT NOT NULL,
+	`max_commits_per_action` INTEGER,
+	`approval_required_tools` TEXT NOT NULL
+);
+
+INSERT INTO `change_versions` (`table_name`, `version`) VALUES ('agent_policies', 0);
+
+CREATE TRIGGER `agent_policies_insert_version` AFTER INSERT ON `agent_policies` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'agent_policies';
+END;
+CREATE TRIGGER `agent_policies_update_version` AFTER UPDATE ON `agent_policies` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'agent_policies';
+END;
+CREATE TRIGGER `agent_policies_delete_version` AFTER DELETE ON `agent_policies` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'agent_policies';
+END;
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER IF EXISTS `ref_metadata_insert_version`;
+DROP TRIGGER IF EXISTS `ref_metadata_update_version`;
+DROP TRIGGER IF EXISTS `ref_metadata_delete_version`;
+DELETE FROM `change_versions` WHERE `table_name` = 'ref_metadata';
+DROP TABLE IF EXISTS `ref_metadata_imports`;
+DROP TABLE IF EXISTS `ref_metadata`;
@@ -0,0 +1,24 @@
+-- Your SQL goes here
+CREATE TABLE `ref_metadata`(
+	`ref_name` TEXT NOT NULL,
+	`kind` TEXT NOT NULL,
+	`value` TEXT NOT NULL,
+	PRIMARY KEY(`ref_name`, `kind`)
+);
+
+CREATE TABLE `ref_metadata_imports`(
+	`source` TEXT NOT NULL PRIMARY KEY,
+	`imported_at` TIMESTAMP NOT NULL
+);
+
+INSERT INTO `change_versions` (`table_name`, `version`) VALUES ('ref_metadata', 0);
+
+CREATE TRIGGER `ref_metadata_insert_version` AFTER INSERT ON `ref_metadata` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'ref_metadata';
+END;
+CREATE TRIGGER `ref_metadata_update_version` AFTER UPDATE ON `ref_metadata` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'ref_metadata';
+END;
+CREATE TRIGGER `ref_metadata_delete_version` AFTER DELETE ON `ref_metadata` BEGIN
+	UPDATE `change_versions` SET `version` = (SELECT MAX(`version`) FROM `change_versions`) + 1 WHERE `table_name` = 'ref_metadata';
+END;
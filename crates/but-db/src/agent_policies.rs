@@ -0,0 +1,63 @@
+use diesel::{Connection, OptionalExtension, RunQueryDsl};
+
+use crate::DbHandle;
+use crate::schema::agent_policies::dsl::agent_policies;
+
+use diesel::prelude::{Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+
+/// The policy restricting what agents may do in the project, of which there is at most one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::agent_policies)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct AgentPolicy {
+    /// UUID identifier of the policy.
+    pub id: String,
+    /// The time when the policy was set.
+    pub created_at: chrono::NaiveDateTime,
+    /// A JSON list of the names of tools agents may call, or `None` if all tools may be called.
+    pub allowed_tools: Option<String>,
+    /// A JSON list of glob patterns matching the names of branches agents must not rewrite or rename.
+    pub protected_branches: String,
+    /// The maximum number of commits a single action may create, or `None` if there is no limit.
+    pub max_commits_per_action: Option<i32>,
+    /// A JSON list of the names of tools which may only be called with the approval of a human.
+    pub approval_required_tools: String,
+}
+
+impl DbHandle {
+    pub fn agent_policies(&mut self) -> AgentPoliciesHandle {
+        AgentPoliciesHandle { db: self }
+    }
+}
+
+pub struct AgentPoliciesHandle<'a> {
+    db: &'a mut DbHandle,
+}
+
+impl AgentPoliciesHandle<'_> {
+    /// Return the policy of the project, if one was set.
+    pub fn get(&mut self) -> Result<Option<AgentPolicy>, diesel::result::Error> {
+        let policy = agent_policies
+            .first::<AgentPolicy>(&mut self.db.conn)
+            .optional()?;
+        Ok(policy)
+    }
+
+    /// Replace the policy of the project with `policy`.
+    pub fn set(&mut self, policy: AgentPolicy) -> Result<(), diesel::result::Error> {
+        self.db.conn.transaction(|conn| {
+            diesel::delete(agent_policies).execute(conn)?;
+            diesel::insert_into(agent_policies)
+                .values(policy)
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Remove the policy of the project, so agents are unrestricted.
+    pub fn delete(&mut self) -> Result<(), diesel::result::Error> {
+        diesel::delete(agent_policies).execute(&mut self.db.conn)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use diesel::RunQueryDsl;
+use diesel::sql_types::{BigInt, Nullable, Text};
+
+use crate::DbHandle;
+
+use diesel::prelude::QueryableByName;
+use serde::{Deserialize, Serialize};
+
+/// Information about a table that is safe to share, as it doesn't include any of its rows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableInfo {
+    /// The name of the table.
+    pub name: String,
+    /// The SQL statement that created the table, or `None` if it was created implicitly.
+    pub sql: Option<String>,
+    /// The number of rows in the table.
+    pub rows: i64,
+}
+
+impl DbHandle {
+    pub fn diagnostics(&mut self) -> DiagnosticsHandle {
+        DiagnosticsHandle { db: self }
+    }
+}
+
+pub struct DiagnosticsHandle<'a> {
+    db: &'a mut DbHandle,
+}
+
+#[derive(QueryableByName)]
+struct Table {
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    sql: Option<String>,
+}
+
+#[derive(QueryableByName)]
+struct RowCount {
+    #[diesel(sql_type = BigInt)]
+    row_count: i64,
+}
+
+impl DiagnosticsHandle<'_> {
+    /// Return the schema and the number of rows of all tables, ordered by name.
+    pub fn tables(&mut self) -> Result<Vec<TableInfo>, diesel::result::Error> {
+        let tables = diesel::sql_query(
+            "SELECT name, sql FROM sqlite_master WHERE type = 'table' ORDER BY name",
+        )
+        .load::<Table>(&mut self.db.conn)?;
+        let mut out = Vec::with_capacity(tables.len());
+        for Table { name, sql } in tables {
+            let rows = diesel::sql_query(format!(
+                "SELECT COUNT(*) AS row_count FROM \"{}\"",
+                name.replace('"', "\"\"")
+            ))
+            .get_result::<RowCount>(&mut self.db.conn)?
+            .row_count;
+            out.push(TableInfo { name, sql, rows });
+        }
+        Ok(out)
+    }
+}
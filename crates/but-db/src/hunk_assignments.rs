@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use diesel::RunQueryDsl;
 
 use crate::{DbHandle, schema::hunk_assignments::dsl::*};
@@ -5,7 +7,9 @@ use crate::{DbHandle, schema::hunk_assignments::dsl::*};
 use diesel::prelude::{Insertable, Queryable, Selectable};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Queryable, Selectable, Insertable)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Queryable, Selectable, Insertable,
+)]
 #[diesel(table_name = crate::schema::hunk_assignments)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
 pub struct HunkAssignment {
@@ -14,6 +18,10 @@ pub struct HunkAssignment {
     pub path: String,
     pub path_bytes: Vec<u8>,
     pub stack_id: Option<String>,
+    /// The lines of the hunk owned by this assignment, or empty if it owns the whole hunk.
+    pub line_ranges: String,
+    /// The previous location of the file, the source of a rename if there was one.
+    pub previous_path_bytes: Option<Vec<u8>>,
 }
 
 impl DbHandle {
@@ -35,12 +43,24 @@ impl HunkAssignmentsHandle<'_> {
 
     /// Sets the hunk assignments in the database to the provided values. Any existing entries
     /// that are not in the provided values are deleted.
+    ///
+    /// Nothing is written if the assignments are the same as the existing ones, so this isn't
+    /// considered a change.
     pub fn set_all(&mut self, assignments: Vec<HunkAssignment>) -> anyhow::Result<()> {
         // Set the hunk_assignments table to the values in `assignments`.
         // Any existing entries that are not in `assignments` are deleted.
         use crate::schema::hunk_assignments::dsl::hunk_assignments as all_assignments;
         use diesel::prelude::*;
         self.db.conn.transaction(|conn| {
+            let existing: HashSet<_> = all_assignments
+                .load::<HunkAssignment>(conn)?
+                .into_iter()
+                .collect();
+            if existing.len() == assignments.len()
+                && assignments.iter().all(|a| existing.contains(a))
+            {
+                return diesel::result::QueryResult::Ok(());
+            }
             // Delete all existing assignments
             diesel::delete(all_assignments).execute(conn)?;
             // Insert the new assignments
@@ -19,6 +19,12 @@ mod file_write_locks;
 pub use file_write_locks::FileWriteLock;
 mod workspace_rules;
 pub use workspace_rules::WorkspaceRule;
+mod agent_policies;
+pub use agent_policies::AgentPolicy;
+mod ref_metadata;
+pub use ref_metadata::{RefMetadataEntry, RefMetadataImport};
+mod diagnostics;
+pub use diagnostics::TableInfo;
 
 use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
@@ -1,5 +1,10 @@
+use std::time::{Duration, Instant};
+
 use crate::DbHandle;
+use crate::schema::change_versions::dsl::change_versions;
 use bitflags::bitflags;
+use diesel::prelude::{Queryable, Selectable};
+use diesel::{QueryDsl, QueryableByName, RunQueryDsl, SelectableHelper};
 use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
 
@@ -10,18 +15,149 @@ bitflags! {
         const Actions = 1 << 0;
         const Workflows = 1 << 1;
         const Assignments = 1 << 2;
+        const Rules = 1 << 3;
+        const ClaudeSessions = 1 << 4;
+        const FileWriteLocks = 1 << 5;
+        const AgentPolicies = 1 << 6;
+        const RefMetadata = 1 << 7;
+    }
+}
+
+impl ItemKind {
+    /// Return the kind of items stored in the table with `name`, if it's known.
+    fn from_table_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "butler_actions" => ItemKind::Actions,
+            "workflows" => ItemKind::Workflows,
+            "hunk_assignments" => ItemKind::Assignments,
+            "workspace_rules" => ItemKind::Rules,
+            "claude_code_sessions" => ItemKind::ClaudeSessions,
+            "file_write_locks" => ItemKind::FileWriteLocks,
+            "agent_policies" => ItemKind::AgentPolicies,
+            "ref_metadata" => ItemKind::RefMetadata,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::change_versions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct ChangeVersion {
+    table_name: String,
+    version: i64,
+}
+
+/// The versions of all tables at a point in time.
+///
+/// Each mutation of a table, no matter from which connection or process, moves its version past the highest
+/// version of all tables. This makes the highest version a counter of all changes, while the version of
+/// each table tells if it changed since a previously seen counter.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Versions {
+    tables: Vec<(ItemKind, i64)>,
+}
+
+impl Versions {
+    /// Return the highest version of all tables, which increases with each change to any of them.
+    pub fn latest(&self) -> i64 {
+        self.tables
+            .iter()
+            .map(|(_, version)| *version)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Return the version of the table holding items of `kind`, or `None` if `kind` isn't a single known kind.
+    pub fn of(&self, kind: ItemKind) -> Option<i64> {
+        self.tables
+            .iter()
+            .find_map(|(table_kind, version)| (*table_kind == kind).then_some(*version))
+    }
+
+    /// Return the kinds of items which changed after `version`, which typically is a previous [`latest()`](Self::latest()).
+    pub fn changed_since(&self, version: i64) -> ItemKind {
+        self.tables
+            .iter()
+            .filter(|(_, table_version)| *table_version > version)
+            .fold(ItemKind::empty(), |acc, (kind, _)| acc | *kind)
     }
 }
 
+#[derive(QueryableByName)]
+struct DataVersion {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    data_version: i64,
+}
+
+/// How long to wait before checking for changes for the first time while [waiting for them](DbHandle::wait_for_changes()).
+/// The wait time doubles with each check that didn't see a change.
+const WAIT_CHECK_INTERVAL_MIN: Duration = Duration::from_millis(10);
+/// The longest time to wait between checks for changes while [waiting for them](DbHandle::wait_for_changes()).
+const WAIT_CHECK_INTERVAL_MAX: Duration = Duration::from_millis(500);
+
 impl DbHandle {
+    /// Return the current versions of all tables.
+    pub fn change_versions(&mut self) -> anyhow::Result<Versions> {
+        let rows = change_versions
+            .select(ChangeVersion::as_select())
+            .load(&mut self.conn)?;
+        Ok(Versions {
+            tables: rows
+                .into_iter()
+                .filter_map(|row| {
+                    ItemKind::from_table_name(&row.table_name).map(|kind| (kind, row.version))
+                })
+                .collect(),
+        })
+    }
+
+    /// Block until any `kind` of items changed after `version`, which typically is the [latest version](Versions::latest())
+    /// seen previously, or until `timeout` elapsed.
+    /// Return the versions that contain the change, or `None` if nothing changed in time.
+    pub fn wait_for_changes(
+        &mut self,
+        kind: ItemKind,
+        version: i64,
+        timeout: Duration,
+    ) -> anyhow::Result<Option<Versions>> {
+        let deadline = Instant::now() + timeout;
+        let mut tracker = ChangeTracker {
+            data_version: self.data_version()?,
+            version,
+        };
+        // Changes may have happened before we started to wait, possibly through this very connection.
+        let versions = self.change_versions()?;
+        if !(versions.changed_since(version) & kind).is_empty() {
+            return Ok(Some(versions));
+        }
+        let mut interval = WAIT_CHECK_INTERVAL_MIN;
+        while Instant::now() < deadline {
+            std::thread::sleep(interval.min(deadline.saturating_duration_since(Instant::now())));
+            interval = (interval * 2).min(WAIT_CHECK_INTERVAL_MAX);
+            if let Some(versions) = tracker.versions_if_changed(self)? {
+                if !(versions.changed_since(version) & kind).is_empty() {
+                    return Ok(Some(versions));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return a number that changes whenever another connection commits a change to the database.
+    /// It's very cheap to obtain as it doesn't read any table.
+    fn data_version(&mut self) -> anyhow::Result<i64> {
+        let row: DataVersion =
+            diesel::sql_query("PRAGMA data_version").get_result(&mut self.conn)?;
+        Ok(row.data_version)
+    }
+
     /// Register polling at `interval` for any `kind` of data and return a channel to be informed about the changes
     /// for the respective kind.
     /// Drop the receiver for the polling to stop.
     /// Note that this opens a new connection.
     ///
-    /// ### Shortcoming
-    ///
-    /// The current implementation will send a change event the first time it starts up.
+    /// Only changes made after this call are sent.
     pub fn poll_changes(
         &self,
         kind: ItemKind,
@@ -29,61 +165,18 @@ impl DbHandle {
     ) -> anyhow::Result<std::sync::mpsc::Receiver<anyhow::Result<ItemKind>>> {
         let (tx, rx) = std::sync::mpsc::channel();
         let mut this = DbHandle::new_at_url(&self.url)?;
+        let mut tracker = ChangeTracker::new(&mut this)?;
         std::thread::Builder::new()
             .name("Gitbutler-DB-watcher".into())
             .spawn(move || {
-                let mut prev_assignments = Vec::new();
-                let mut prev_workflows = Vec::new();
-                let mut prev_actions = Vec::new();
-                'outer: loop {
+                loop {
                     std::thread::sleep(interval);
-                    for to_check in ItemKind::all().iter() {
-                        let send_result = if kind & to_check == ItemKind::Actions {
-                            let res = this.butler_actions().list(0, i64::MAX);
-                            match res {
-                                Ok((_num_items, items)) => {
-                                    if items != prev_actions {
-                                        prev_actions = items;
-                                        tx.send(Ok(ItemKind::Actions))
-                                    } else {
-                                        continue;
-                                    }
-                                }
-                                Err(e) => tx.send(Err(e)),
-                            }
-                        } else if kind & to_check == ItemKind::Workflows {
-                            let res = this.workflows().list(0, i64::MAX);
-                            match res {
-                                Ok((_num_items, items)) => {
-                                    if items != prev_workflows {
-                                        prev_workflows = items;
-                                        tx.send(Ok(ItemKind::Workflows))
-                                    } else {
-                                        continue;
-                                    }
-                                }
-                                Err(e) => tx.send(Err(e)),
-                            }
-                        } else if kind & to_check == ItemKind::Assignments {
-                            let res = this.hunk_assignments().list_all();
-                            match res {
-                                Ok(items) => {
-                                    if items != prev_assignments {
-                                        prev_assignments = items;
-                                        tx.send(Ok(ItemKind::Assignments))
-                                    } else {
-                                        continue;
-                                    }
-                                }
-                                Err(e) => tx.send(Err(e)),
-                            }
-                        } else {
-                            eprintln!("BUG: didn't implement a branch for {to_check:?}");
-                            break 'outer;
-                        };
-                        if send_result.is_err() {
-                            break 'outer;
-                        }
+                    let send_result = match tracker.changes(&mut this, kind) {
+                        Ok(changed) => changed.iter().try_for_each(|kind| tx.send(Ok(kind))),
+                        Err(e) => tx.send(Err(e)),
+                    };
+                    if send_result.is_err() {
+                        break;
                     }
                 }
             })?;
@@ -92,73 +185,34 @@ impl DbHandle {
 
     /// Register async polling at `interval` for any `kind` of data and return a channel to be informed about the changes
     /// for the respective kind. Uses async tokio task and channel for efficiency.
+    ///
+    /// Only changes made after this call are sent.
     pub fn poll_changes_async(
         &self,
         kind: ItemKind,
         interval: std::time::Duration,
     ) -> anyhow::Result<tokio::sync::mpsc::Receiver<anyhow::Result<ItemKind>>> {
         let (tx, rx) = tokio::sync::mpsc::channel(8);
-        let url = self.url.clone();
+        let mut this = DbHandle::new_at_url(&self.url)?;
+        let mut tracker = ChangeTracker::new(&mut this)?;
         tokio::spawn(async move {
-            let mut this = match DbHandle::new_at_url(&url) {
-                Ok(db) => db,
-                Err(e) => {
-                    let _ = tx.send(Err(e)).await;
-                    return;
-                }
-            };
-            let mut prev_assignments = Vec::new();
-            let mut prev_workflows = Vec::new();
-            let mut prev_actions = Vec::new();
             let mut ticker = tokio::time::interval(interval);
+            // The first tick completes immediately, but we know the state at this point already.
+            ticker.tick().await;
             loop {
                 ticker.tick().await;
-                for to_check in ItemKind::all().iter() {
-                    let send_result = if kind & to_check == ItemKind::Actions {
-                        let res = this.butler_actions().list(0, i64::MAX);
-                        match res {
-                            Ok((_num_items, items)) => {
-                                if items != prev_actions {
-                                    prev_actions = items;
-                                    tx.send(Ok(ItemKind::Actions)).await
-                                } else {
-                                    continue;
-                                }
+                match tracker.changes(&mut this, kind) {
+                    Ok(changed) => {
+                        for kind in changed.iter() {
+                            if tx.send(Ok(kind)).await.is_err() {
+                                return;
                             }
-                            Err(e) => tx.send(Err(e)).await,
                         }
-                    } else if kind & to_check == ItemKind::Workflows {
-                        let res = this.workflows().list(0, i64::MAX);
-                        match res {
-                            Ok((_num_items, items)) => {
-                                if items != prev_workflows {
-                                    prev_workflows = items;
-                                    tx.send(Ok(ItemKind::Workflows)).await
-                                } else {
-                                    continue;
-                                }
-                            }
-                            Err(e) => tx.send(Err(e)).await,
-                        }
-                    } else if kind & to_check == ItemKind::Assignments {
-                        let res = this.hunk_assignments().list_all();
-                        match res {
-                            Ok(items) => {
-                                if items != prev_assignments {
-                                    prev_assignments = items;
-                                    tx.send(Ok(ItemKind::Assignments)).await
-                                } else {
-                                    continue;
-                                }
-                            }
-                            Err(e) => tx.send(Err(e)).await,
+                    }
+                    Err(e) => {
+                        if tx.send(Err(e)).await.is_err() {
+                            return;
                         }
-                    } else {
-                        eprintln!("BUG: didn't implement a branch for {to_check:?}");
-                        return;
-                    };
-                    if send_result.is_err() {
-                        return;
                     }
                 }
             }
@@ -167,6 +221,43 @@ impl DbHandle {
     }
 }
 
+/// Keep track of what was last seen to cheaply learn about changes made by other connections.
+struct ChangeTracker {
+    /// The last seen data version of the connection, which only changes if other connections commit changes.
+    data_version: i64,
+    /// The last seen highest version of all tables.
+    version: i64,
+}
+
+impl ChangeTracker {
+    fn new(db: &mut DbHandle) -> anyhow::Result<Self> {
+        Ok(ChangeTracker {
+            data_version: db.data_version()?,
+            version: db.change_versions()?.latest(),
+        })
+    }
+
+    /// Return the current versions if another connection committed something since the last call.
+    fn versions_if_changed(&mut self, db: &mut DbHandle) -> anyhow::Result<Option<Versions>> {
+        let data_version = db.data_version()?;
+        if data_version == self.data_version {
+            return Ok(None);
+        }
+        self.data_version = data_version;
+        db.change_versions().map(Some)
+    }
+
+    /// Return the kinds of items among `kind` that changed since the last call.
+    fn changes(&mut self, db: &mut DbHandle, kind: ItemKind) -> anyhow::Result<ItemKind> {
+        let Some(versions) = self.versions_if_changed(db)? else {
+            return Ok(ItemKind::empty());
+        };
+        let changed = versions.changed_since(self.version) & kind;
+        self.version = versions.latest();
+        Ok(changed)
+    }
+}
+
 pub struct DBWatcherHandle {
     pub cancel_tx: Option<oneshot::Sender<()>>,
     pub handle: JoinHandle<()>,
@@ -186,10 +277,7 @@ pub fn watch_in_background(
     db: &mut DbHandle,
     send_event: impl Fn(ItemKind) -> anyhow::Result<()> + Send + Sync + 'static,
 ) -> anyhow::Result<DBWatcherHandle, anyhow::Error> {
-    let mut rx = db.poll_changes_async(
-        ItemKind::Actions | ItemKind::Workflows | ItemKind::Assignments,
-        std::time::Duration::from_millis(500),
-    )?;
+    let mut rx = db.poll_changes_async(ItemKind::all(), std::time::Duration::from_millis(500))?;
 
     let (cancel_tx, mut cancel_rx) = oneshot::channel();
     let handle = tokio::spawn(async move {
@@ -0,0 +1,111 @@
+use diesel::{BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+use crate::DbHandle;
+use crate::schema::{ref_metadata, ref_metadata_imports};
+
+use diesel::prelude::{Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+
+/// The metadata associated with a reference, like a branch or a workspace.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::ref_metadata)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RefMetadataEntry {
+    /// The full name of the reference, like `refs/heads/main`.
+    pub ref_name: String,
+    /// The kind of metadata, like `branch` or `workspace`, as a reference may have one of each.
+    pub kind: String,
+    /// The metadata itself, serialized in a format chosen by the caller.
+    pub value: String,
+}
+
+/// A record of metadata that was imported from elsewhere, so it's only imported once.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::ref_metadata_imports)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RefMetadataImport {
+    /// An identifier of where the metadata was imported from, like the path to a file.
+    pub source: String,
+    /// The time when the import happened.
+    pub imported_at: chrono::NaiveDateTime,
+}
+
+impl DbHandle {
+    pub fn ref_metadata(&mut self) -> RefMetadataHandle {
+        RefMetadataHandle { db: self }
+    }
+}
+
+pub struct RefMetadataHandle<'a> {
+    db: &'a mut DbHandle,
+}
+
+impl RefMetadataHandle<'_> {
+    /// Return the metadata of `kind` for the reference named `name`, if there is any.
+    pub fn get(
+        &mut self,
+        name: &str,
+        kind: &str,
+    ) -> Result<Option<RefMetadataEntry>, diesel::result::Error> {
+        ref_metadata::table
+            .filter(
+                ref_metadata::ref_name
+                    .eq(name)
+                    .and(ref_metadata::kind.eq(kind)),
+            )
+            .first::<RefMetadataEntry>(&mut self.db.conn)
+            .optional()
+    }
+
+    /// Return all metadata, ordered by reference name and kind.
+    pub fn list(&mut self) -> Result<Vec<RefMetadataEntry>, diesel::result::Error> {
+        ref_metadata::table
+            .order((ref_metadata::ref_name.asc(), ref_metadata::kind.asc()))
+            .load::<RefMetadataEntry>(&mut self.db.conn)
+    }
+
+    /// Insert `entry`, or replace the metadata of the same kind that its reference already has.
+    pub fn set(&mut self, entry: RefMetadataEntry) -> Result<(), diesel::result::Error> {
+        diesel::replace_into(ref_metadata::table)
+            .values(entry)
+            .execute(&mut self.db.conn)?;
+        Ok(())
+    }
+
+    /// Remove all metadata of the reference named `name`, and return `true` if there was any.
+    pub fn delete(&mut self, name: &str) -> Result<bool, diesel::result::Error> {
+        let deleted = diesel::delete(ref_metadata::table.filter(ref_metadata::ref_name.eq(name)))
+            .execute(&mut self.db.conn)?;
+        Ok(deleted > 0)
+    }
+
+    /// Add `entries` which were obtained from `import.source`, unless they were imported from there before.
+    /// Existing metadata of the same references is replaced.
+    ///
+    /// Return `true` if the entries were imported.
+    /// Note that this happens in a single transaction, so concurrent imports of the same source won't both succeed.
+    pub fn import_once(
+        &mut self,
+        import: RefMetadataImport,
+        entries: Vec<RefMetadataEntry>,
+    ) -> Result<bool, diesel::result::Error> {
+        self.db.conn.immediate_transaction(|conn| {
+            let previous = ref_metadata_imports::table
+                .filter(ref_metadata_imports::source.eq(&import.source))
+                .first::<RefMetadataImport>(conn)
+                .optional()?;
+            if previous.is_some() {
+                return Ok(false);
+            }
+            for entry in entries {
+                diesel::replace_into(ref_metadata::table)
+                    .values(entry)
+                    .execute(conn)?;
+            }
+            diesel::insert_into(ref_metadata_imports::table)
+                .values(import)
+                .execute(conn)?;
+            Ok(true)
+        })
+    }
+}
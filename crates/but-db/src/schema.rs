@@ -1,10 +1,12 @@
 diesel::table! {
-    hunk_assignments (path, hunk_header) {
+    hunk_assignments (path, hunk_header, line_ranges) {
         id -> Nullable<Text>,
         hunk_header -> Nullable<Text>,
         path -> Text,
         path_bytes -> Binary,
         stack_id -> Nullable<Text>,
+        line_ranges -> Text,
+        previous_path_bytes -> Nullable<Binary>,
     }
 }
 
@@ -62,3 +64,36 @@ diesel::table! {
         action -> Text,
     }
 }
+
+diesel::table! {
+    change_versions (table_name) {
+        table_name -> Text,
+        version -> BigInt,
+    }
+}
+
+diesel::table! {
+    agent_policies (id) {
+        id -> Text,
+        created_at -> Timestamp,
+        allowed_tools -> Nullable<Text>,
+        protected_branches -> Text,
+        max_commits_per_action -> Nullable<Integer>,
+        approval_required_tools -> Text,
+    }
+}
+
+diesel::table! {
+    ref_metadata (ref_name, kind) {
+        ref_name -> Text,
+        kind -> Text,
+        value -> Text,
+    }
+}
+
+diesel::table! {
+    ref_metadata_imports (source) {
+        source -> Text,
+        imported_at -> Timestamp,
+    }
+}
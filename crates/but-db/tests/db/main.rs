@@ -47,3 +47,314 @@ fn init_in_parallel() -> anyhow::Result<()> {
     });
     Ok(())
 }
+
+#[test]
+fn multiple_assignments_per_hunk_with_line_ranges() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let mut db = DbHandle::new_in_directory(tmp.path())?;
+    let assignment = |stack_id: &str, line_ranges: &str| but_db::HunkAssignment {
+        id: Some(format!("id-{stack_id}")),
+        hunk_header: Some("header".into()),
+        path: "file".into(),
+        path_bytes: b"file".into(),
+        stack_id: Some(stack_id.into()),
+        line_ranges: line_ranges.into(),
+        previous_path_bytes: None,
+    };
+    let assignments = vec![
+        assignment("a", "first-lines"),
+        assignment("b", "other-lines"),
+    ];
+    db.hunk_assignments().set_all(assignments.clone())?;
+    assert_eq!(
+        db.hunk_assignments().list_all()?,
+        assignments,
+        "the same hunk can be assigned multiple times if the assignments own different lines"
+    );
+
+    let assignments = vec![assignment("a", "")];
+    db.hunk_assignments().set_all(assignments.clone())?;
+    assert_eq!(db.hunk_assignments().list_all()?, assignments);
+
+    assert!(
+        db.hunk_assignments()
+            .set_all(vec![assignment("a", ""), assignment("b", "")])
+            .is_err(),
+        "the whole hunk can only be assigned once"
+    );
+    assert_eq!(db.hunk_assignments().list_all()?, assignments);
+    Ok(())
+}
+
+#[test]
+fn agent_policy_is_replaced_as_a_whole() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let mut db = DbHandle::new_in_directory(tmp.path())?;
+    assert_eq!(
+        db.agent_policies().get()?,
+        None,
+        "there is no policy by default"
+    );
+
+    let policy = |id: &str, max_commits: Option<i32>| but_db::AgentPolicy {
+        id: id.into(),
+        created_at: chrono::NaiveDateTime::default(),
+        allowed_tools: None,
+        protected_branches: r#"["main"]"#.into(),
+        max_commits_per_action: max_commits,
+        approval_required_tools: "[]".into(),
+    };
+    db.agent_policies().set(policy("first", None))?;
+    db.agent_policies().set(policy("second", Some(3)))?;
+    assert_eq!(
+        db.agent_policies().get()?,
+        Some(policy("second", Some(3))),
+        "there is only one policy per project"
+    );
+
+    db.agent_policies().delete()?;
+    assert_eq!(db.agent_policies().get()?, None);
+    Ok(())
+}
+
+#[test]
+fn ref_metadata_is_set_per_kind_and_imported_once() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let mut db = DbHandle::new_in_directory(tmp.path())?;
+    let entry = |ref_name: &str, kind: &str, value: &str| but_db::RefMetadataEntry {
+        ref_name: ref_name.into(),
+        kind: kind.into(),
+        value: value.into(),
+    };
+    db.ref_metadata()
+        .set(entry("refs/heads/a", "branch", "1"))?;
+    db.ref_metadata()
+        .set(entry("refs/heads/a", "workspace", "2"))?;
+    db.ref_metadata()
+        .set(entry("refs/heads/a", "branch", "3"))?;
+    assert_eq!(
+        db.ref_metadata().list()?,
+        [
+            entry("refs/heads/a", "branch", "3"),
+            entry("refs/heads/a", "workspace", "2")
+        ],
+        "each kind of metadata is replaced individually"
+    );
+    assert_eq!(
+        db.ref_metadata().get("refs/heads/a", "workspace")?,
+        Some(entry("refs/heads/a", "workspace", "2"))
+    );
+
+    let import = but_db::RefMetadataImport {
+        source: "virtual_branches.toml".into(),
+        imported_at: chrono::NaiveDateTime::default(),
+    };
+    assert!(
+        db.ref_metadata()
+            .import_once(import.clone(), vec![entry("refs/heads/b", "branch", "4")])?
+    );
+    assert!(
+        !db.ref_metadata()
+            .import_once(import, vec![entry("refs/heads/c", "branch", "5")])?,
+        "the same source is only imported once"
+    );
+    assert_eq!(db.ref_metadata().get("refs/heads/c", "branch")?, None);
+
+    assert!(db.ref_metadata().delete("refs/heads/a")?);
+    assert!(!db.ref_metadata().delete("refs/heads/a")?);
+    assert_eq!(
+        db.ref_metadata().list()?,
+        [entry("refs/heads/b", "branch", "4")]
+    );
+    Ok(())
+}
+
+#[test]
+fn diagnostics_show_tables_without_their_rows() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let mut db = DbHandle::new_in_directory(tmp.path())?;
+    db.agent_policies().set(but_db::AgentPolicy {
+        id: "secret-id".into(),
+        created_at: chrono::NaiveDateTime::default(),
+        allowed_tools: None,
+        protected_branches: "[]".into(),
+        max_commits_per_action: None,
+        approval_required_tools: "[]".into(),
+    })?;
+
+    let tables = db.diagnostics().tables()?;
+    let policies = tables
+        .iter()
+        .find(|t| t.name == "agent_policies")
+        .expect("table exists");
+    assert_eq!(policies.rows, 1);
+    assert!(
+        policies
+            .sql
+            .as_deref()
+            .is_some_and(|sql| sql.starts_with("CREATE TABLE")),
+        "the schema is included"
+    );
+    let mut names: Vec<_> = tables.iter().map(|t| t.name.as_str()).collect();
+    names.sort();
+    assert_eq!(
+        names,
+        tables.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(),
+        "tables are sorted by name"
+    );
+    Ok(())
+}
+
+#[test]
+fn hunk_assignments_remember_rename_source() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let mut db = DbHandle::new_in_directory(tmp.path())?;
+    let assignments = vec![but_db::HunkAssignment {
+        id: None,
+        hunk_header: None,
+        path: "new-name".into(),
+        path_bytes: b"new-name".into(),
+        stack_id: None,
+        line_ranges: String::new(),
+        previous_path_bytes: Some(b"old-name".into()),
+    }];
+    db.hunk_assignments().set_all(assignments.clone())?;
+    assert_eq!(db.hunk_assignments().list_all()?, assignments);
+    Ok(())
+}
+
+mod changes {
+    use std::time::Duration;
+
+    use but_db::{DbHandle, FileWriteLock, poll::ItemKind};
+
+    fn lock(path: &str) -> FileWriteLock {
+        FileWriteLock {
+            path: path.into(),
+            created_at: chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc(),
+            owner: "owner".into(),
+        }
+    }
+
+    #[test]
+    fn each_mutation_bumps_the_version_of_its_table() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let mut db = DbHandle::new_in_directory(tmp.path())?;
+        let initial = db.change_versions()?;
+        assert_eq!(initial.latest(), 0, "nothing was changed yet");
+        assert_eq!(initial.changed_since(0), ItemKind::empty());
+
+        db.file_write_locks().insert(lock("a"))?;
+        let versions = db.change_versions()?;
+        assert_eq!(versions.latest(), 1);
+        assert_eq!(versions.of(ItemKind::FileWriteLocks), Some(1));
+        assert_eq!(versions.changed_since(0), ItemKind::FileWriteLocks);
+
+        db.hunk_assignments().set_all(vec![])?;
+        db.file_write_locks().delete("a")?;
+        let latest = db.change_versions()?;
+        assert!(
+            latest.latest() > versions.latest(),
+            "the counter keeps growing"
+        );
+        assert_eq!(
+            latest.changed_since(versions.latest()),
+            ItemKind::FileWriteLocks,
+            "deleting nothing from the assignments doesn't count as change"
+        );
+        assert_eq!(latest.of(ItemKind::Assignments), Some(0));
+        assert_eq!(
+            latest.of(ItemKind::Actions | ItemKind::Workflows),
+            None,
+            "versions are only known for individual kinds"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn setting_the_same_assignments_is_no_change() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let mut db = DbHandle::new_in_directory(tmp.path())?;
+        let assignment = |file: &str| but_db::HunkAssignment {
+            id: Some(format!("id-{file}")),
+            hunk_header: None,
+            path: file.into(),
+            path_bytes: file.into(),
+            stack_id: None,
+            line_ranges: String::new(),
+            previous_path_bytes: None,
+        };
+        db.hunk_assignments()
+            .set_all(vec![assignment("a"), assignment("b")])?;
+        let version = db.change_versions()?.of(ItemKind::Assignments);
+
+        db.hunk_assignments()
+            .set_all(vec![assignment("b"), assignment("a")])?;
+        assert_eq!(
+            db.change_versions()?.of(ItemKind::Assignments),
+            version,
+            "the order of assignments doesn't matter"
+        );
+
+        db.hunk_assignments().set_all(vec![assignment("a")])?;
+        assert!(db.change_versions()?.of(ItemKind::Assignments) > version);
+        assert_eq!(db.hunk_assignments().list_all()?, [assignment("a")]);
+        Ok(())
+    }
+
+    #[test]
+    fn wait_for_changes_of_other_connections() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let mut db = DbHandle::new_in_directory(tmp.path())?;
+        let version = db.change_versions()?.latest();
+        assert_eq!(
+            db.wait_for_changes(ItemKind::all(), version, Duration::from_millis(50))?,
+            None,
+            "it times out if nothing changes"
+        );
+
+        std::thread::scope(|scope| -> anyhow::Result<()> {
+            scope.spawn(|| -> anyhow::Result<()> {
+                let mut other_db = DbHandle::new_in_directory(tmp.path())?;
+                std::thread::sleep(Duration::from_millis(50));
+                other_db.file_write_locks().insert(lock("a"))?;
+                Ok(())
+            });
+            let versions = db
+                .wait_for_changes(ItemKind::FileWriteLocks, version, Duration::from_secs(10))?
+                .expect("the other connection wrote in time");
+            assert_eq!(versions.changed_since(version), ItemKind::FileWriteLocks);
+            Ok(())
+        })?;
+
+        assert!(
+            db.wait_for_changes(ItemKind::FileWriteLocks, version, Duration::ZERO)?
+                .is_some(),
+            "changes that happened before waiting are seen immediately"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn poll_changes_only_sends_new_changes() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let mut db = DbHandle::new_in_directory(tmp.path())?;
+        db.file_write_locks().insert(lock("before"))?;
+
+        let rx = db.poll_changes(
+            ItemKind::FileWriteLocks | ItemKind::Assignments,
+            Duration::from_millis(10),
+        )?;
+        assert!(
+            rx.recv_timeout(Duration::from_millis(100)).is_err(),
+            "nothing is sent on startup"
+        );
+
+        db.file_write_locks().insert(lock("after"))?;
+        assert_eq!(
+            rx.recv_timeout(Duration::from_secs(10))??,
+            ItemKind::FileWriteLocks
+        );
+        Ok(())
+    }
+}
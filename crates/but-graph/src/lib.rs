@@ -212,6 +212,12 @@ pub mod projection;
 mod ref_metadata_legacy;
 pub use ref_metadata_legacy::{VirtualBranchesTomlMetadata, is_workspace_ref_name};
 
+mod ref_metadata_refs;
+pub use ref_metadata_refs::{METADATA_REF, RefsMetadata, RefsMetadataHandle};
+
+mod ref_metadata_db;
+pub use ref_metadata_db::{DbMetadata, DbMetadataHandle};
+
 pub mod virtual_branches_legacy_types;
 
 mod statistics;
@@ -479,21 +479,24 @@ bitflags! {
         /// Note that when multiple workspaces are included in the traversal, this flag is set by
         /// any of many target branches.
         const Integrated = 1 << 2;
+        /// The commit isn't reachable from the target branch, but its changes were found there
+        /// in an equivalent commit, for instance one with the same patch-id after a rebase or squash-merge.
+        const IntegratedByPatch = 1 << 3;
         // --- END OVERLAP ---
 
         /// This commit was pushed to *our* remote and thus could have been observed by others.
         /// This definitely means manipulation will require a force-push afterward.
         /// Implies `ReachableByRemote`, which is then also set for convenience.
-        const ReachableByMatchingRemote = 1 << 3;
+        const ReachableByMatchingRemote = 1 << 4;
         /// Whether the commit is in a conflicted state, a GitButler concept.
         /// GitButler will perform rebasing/reordering etc. without interruptions and flag commits as conflicted if needed.
         /// Conflicts are resolved via the Edit Mode mechanism.
         ///
         /// Note that even though GitButler won't push branches with conflicts, the user can still push such branches at will.
-        const HasConflicts = 1 << 4;
+        const HasConflicts = 1 << 5;
         /// The commit will appear 'snipped off' as it has parents, but the traversal stopped there due to hitting limits
         /// or because the commits weren't interesting.
-        const EarlyEnd = 1 << 5;
+        const EarlyEnd = 1 << 6;
     }
 }
 
@@ -502,7 +505,7 @@ impl StackCommitFlags {
     ///
     /// Note that this only displays flags that are not used when displaying [the whole commit](StackCommit::debug_string()).
     pub fn debug_string(&self) -> String {
-        let flags = *self & (Self::InWorkspace | Self::Integrated);
+        let flags = *self & (Self::InWorkspace | Self::Integrated | Self::IntegratedByPatch);
         if flags.is_empty() {
             "".into()
         } else {
@@ -511,6 +514,7 @@ impl StackCommitFlags {
             out[..out.len() - 1]
                 .to_string()
                 .replace("InWorkspace", "🏘️")
+                .replace("IntegratedByPatch", "≡")
                 .replace("Integrated", "✓")
                 .replace(" ", "")
         }
@@ -521,7 +525,11 @@ impl StackCommitFlags {
 impl From<CommitFlags> for StackCommitFlags {
     fn from(value: CommitFlags) -> Self {
         StackCommitFlags::from_bits_retain(
-            (value & (CommitFlags::Integrated | CommitFlags::InWorkspace)).bits() as u8,
+            (value
+                & (CommitFlags::Integrated
+                    | CommitFlags::InWorkspace
+                    | CommitFlags::IntegratedByPatch))
+                .bits() as u8,
         )
     }
 }
@@ -1,6 +1,6 @@
 use std::{
     cell::RefCell,
-    collections::{BTreeSet, VecDeque},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     fmt::Formatter,
 };
 
@@ -882,6 +882,65 @@ impl Workspace<'_> {
     }
 }
 
+/// Expensive processing
+impl Workspace<'_> {
+    /// Mark all commits of our stacks that aren't [integrated](StackCommitFlags::Integrated), but whose changes
+    /// are found in a commit of the target branch with the same [patch-id](but_core::patch_id),
+    /// as [integrated by patch](StackCommitFlags::IntegratedByPatch).
+    /// This detects commits that were rebased, cherry-picked or squash-merged upstream, and needs `repo` to diff commits.
+    ///
+    /// Return a mapping of each marked commit to the first upstream commit with the same patch-id,
+    /// which is empty if there is neither a target nor an extra target.
+    pub fn mark_integrated_by_patch(
+        &mut self,
+        repo: &gix::Repository,
+    ) -> anyhow::Result<BTreeMap<gix::ObjectId, gix::ObjectId>> {
+        let mut out = BTreeMap::new();
+        let Some(target_sidx) = self
+            .target
+            .as_ref()
+            .map(|t| t.segment_index)
+            .or(self.extra_target)
+        else {
+            return Ok(out);
+        };
+        let graph = self.graph;
+        let lower_bound = self
+            .lower_bound_segment_id
+            .map(|sidx| (sidx, graph[sidx].generation));
+        let mut upstream_commits = Vec::new();
+        Target::visit_upstream_commits(graph, target_sidx, lower_bound, |s| {
+            upstream_commits.extend(s.commits.iter().map(|c| c.id));
+        });
+        if upstream_commits.is_empty() {
+            return Ok(out);
+        }
+        let mut upstream_by_patch_id = BTreeMap::new();
+        for (commit_id, patch_id) in but_core::patch_id::of_commits(repo, upstream_commits)? {
+            upstream_by_patch_id.entry(patch_id).or_insert(commit_id);
+        }
+
+        for commit in self
+            .stacks
+            .iter_mut()
+            .flat_map(|stack| stack.segments.iter_mut())
+            .flat_map(|segment| segment.commits.iter_mut())
+            .filter(|commit| !commit.flags.contains(StackCommitFlags::Integrated))
+        {
+            let Some((_commit_id, patch_id)) =
+                but_core::patch_id::of_commits(repo, Some(commit.id))?.pop()
+            else {
+                continue;
+            };
+            if let Some(upstream_commit_id) = upstream_by_patch_id.get(&patch_id) {
+                commit.flags |= StackCommitFlags::IntegratedByPatch;
+                out.insert(commit.id, *upstream_commit_id);
+            }
+        }
+        Ok(out)
+    }
+}
+
 /// Query
 impl Workspace<'_> {
     /// Return `true` if this workspace is managed, meaning we control certain aspects of it.
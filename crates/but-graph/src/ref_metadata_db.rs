@@ -0,0 +1,254 @@
+use crate::VirtualBranchesTomlMetadata;
+use crate::ref_metadata_refs::{StoredBranch, StoredWorkspace};
+use anyhow::Context;
+use but_core::RefMetadata;
+use but_core::ref_metadata::{Branch, ValueInfo, Workspace};
+use but_db::{DbHandle, RefMetadataEntry, RefMetadataImport};
+use gix::refs::{FullName, FullNameRef};
+use std::any::Any;
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+
+/// The kind of entries holding [`Branch`] metadata.
+const BRANCH: &str = "branch";
+/// The kind of entries holding [`Workspace`] metadata.
+const WORKSPACE: &str = "workspace";
+/// The name under which the import of `virtual_branches.toml` is recorded.
+const LEGACY_TOML_SOURCE: &str = "virtual_branches.toml";
+
+/// An implementation to read and write metadata from the project database, see [`but_db`].
+///
+/// Each branch and workspace is stored in its own row, and each change is written immediately in its own
+/// transaction. This makes it safe to use from multiple processes at the same time, with the last
+/// write winning per branch or workspace.
+///
+/// Use [`migrate_from_toml()`](Self::migrate_from_toml()) to take over metadata from `virtual_branches.toml`,
+/// and [`export_to_toml()`](Self::export_to_toml()) to write it back for debugging.
+#[derive(Debug)]
+pub struct DbMetadata {
+    db: RefCell<DbHandle>,
+}
+
+impl DbMetadata {
+    /// Use `db` to store metadata.
+    pub fn new(db: DbHandle) -> Self {
+        DbMetadata { db: db.into() }
+    }
+
+    /// Open or create the database in `db_dir` to store metadata.
+    pub fn from_directory(db_dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(Self::new(DbHandle::new_in_directory(db_dir)?))
+    }
+
+    /// Import all metadata from the `virtual_branches.toml` file at `toml_path`, unless it was imported before.
+    /// Metadata that is already stored for the same names is replaced.
+    ///
+    /// Return `true` if the metadata was imported, and `false` if it was imported before or if there is no such file.
+    /// The file itself remains untouched.
+    pub fn migrate_from_toml(&mut self, toml_path: &Path) -> anyhow::Result<bool> {
+        if !toml_path.is_file() {
+            return Ok(false);
+        }
+        let toml = VirtualBranchesTomlMetadata::from_path(toml_path)?;
+        self.import_once(LEGACY_TOML_SOURCE, &toml)
+    }
+
+    /// Copy all metadata from `other` into this store in a single transaction, unless it was imported from
+    /// `source` before. Metadata that is already stored for the same names is replaced.
+    ///
+    /// Return `true` if the metadata was imported.
+    pub fn import_once(&mut self, source: &str, other: &impl RefMetadata) -> anyhow::Result<bool> {
+        let mut entries = Vec::new();
+        for res in other.iter() {
+            let (ref_name, value) = res?;
+            if let Some(branch) = value.downcast_ref::<Branch>() {
+                entries.push(branch_entry(ref_name.as_ref(), branch)?);
+            } else if let Some(workspace) = value.downcast_ref::<Workspace>() {
+                entries.push(workspace_entry(ref_name.as_ref(), workspace)?);
+            }
+        }
+        let import = RefMetadataImport {
+            source: source.to_owned(),
+            imported_at: chrono::Utc::now().naive_utc(),
+        };
+        Ok(self
+            .db
+            .get_mut()
+            .ref_metadata()
+            .import_once(import, entries)?)
+    }
+
+    /// Write all metadata as `virtual_branches.toml` file to `toml_path`, replacing the file if it exists.
+    ///
+    /// This is meant for debugging, as the file format can't represent everything, like metadata of
+    /// workspaces other than the GitButler workspace, or times at which branches were created.
+    pub fn export_to_toml(&self, toml_path: &Path) -> anyhow::Result<()> {
+        if let Err(err) = std::fs::remove_file(toml_path) {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                return Err(err.into());
+            }
+        }
+        let mut toml = VirtualBranchesTomlMetadata::from_path(toml_path)?;
+        let items = self.iter().collect::<anyhow::Result<Vec<_>>>()?;
+        // Workspaces first, so branches in stacks end up in the same stack.
+        for (ref_name, value) in &items {
+            let Some(workspace) = value.downcast_ref::<Workspace>() else {
+                continue;
+            };
+            let mut handle = toml.workspace(ref_name.as_ref())?;
+            *handle = workspace.clone();
+            toml.set_workspace(&handle)
+                .with_context(|| format!("Couldn't export workspace '{}'", ref_name.as_bstr()))?;
+        }
+        for (ref_name, value) in &items {
+            let Some(branch) = value.downcast_ref::<Branch>() else {
+                continue;
+            };
+            let mut handle = toml.branch(ref_name.as_ref())?;
+            *handle = branch.clone();
+            toml.set_branch(&handle)?;
+        }
+        // Dropping it writes the file.
+        drop(toml);
+        Ok(())
+    }
+}
+
+impl RefMetadata for DbMetadata {
+    type Handle<T> = DbMetadataHandle<T>;
+
+    fn iter(&self) -> impl Iterator<Item = anyhow::Result<(FullName, Box<dyn Any>)>> + '_ {
+        // Keep it simple and read everything at once, the connection can't be borrowed for longer.
+        let entries = match self.db.borrow_mut().ref_metadata().list() {
+            Ok(entries) => entries,
+            Err(err) => return vec![Err(err.into())].into_iter(),
+        };
+        entries
+            .into_iter()
+            .filter_map(|entry| parse_entry(entry).transpose())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn workspace(&self, ref_name: &FullNameRef) -> anyhow::Result<Self::Handle<Workspace>> {
+        let entry = self
+            .db
+            .borrow_mut()
+            .ref_metadata()
+            .get(&ref_name.as_bstr().to_string(), WORKSPACE)?;
+        let (is_default, value) = match entry {
+            Some(entry) => (
+                false,
+                toml::from_str::<StoredWorkspace>(&entry.value)?.into_workspace()?,
+            ),
+            None => (true, Workspace::default()),
+        };
+        Ok(DbMetadataHandle {
+            is_default,
+            ref_name: ref_name.to_owned(),
+            value,
+        })
+    }
+
+    fn branch(&self, ref_name: &FullNameRef) -> anyhow::Result<Self::Handle<Branch>> {
+        let entry = self
+            .db
+            .borrow_mut()
+            .ref_metadata()
+            .get(&ref_name.as_bstr().to_string(), BRANCH)?;
+        let (is_default, value) = match entry {
+            Some(entry) => (
+                false,
+                toml::from_str::<StoredBranch>(&entry.value)?.into_branch(),
+            ),
+            None => (true, Branch::default()),
+        };
+        Ok(DbMetadataHandle {
+            is_default,
+            ref_name: ref_name.to_owned(),
+            value,
+        })
+    }
+
+    fn set_workspace(&mut self, value: &Self::Handle<Workspace>) -> anyhow::Result<()> {
+        let entry = workspace_entry(value.ref_name.as_ref(), value)?;
+        self.db.get_mut().ref_metadata().set(entry)?;
+        Ok(())
+    }
+
+    fn set_branch(&mut self, value: &Self::Handle<Branch>) -> anyhow::Result<()> {
+        let entry = branch_entry(value.ref_name.as_ref(), value)?;
+        self.db.get_mut().ref_metadata().set(entry)?;
+        Ok(())
+    }
+
+    fn remove(&mut self, ref_name: &FullNameRef) -> anyhow::Result<bool> {
+        Ok(self
+            .db
+            .get_mut()
+            .ref_metadata()
+            .delete(&ref_name.as_bstr().to_string())?)
+    }
+}
+
+pub struct DbMetadataHandle<T> {
+    is_default: bool,
+    ref_name: FullName,
+    value: T,
+}
+
+impl<T> AsRef<FullNameRef> for DbMetadataHandle<T> {
+    fn as_ref(&self) -> &FullNameRef {
+        self.ref_name.as_ref()
+    }
+}
+
+impl<T> Deref for DbMetadataHandle<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for DbMetadataHandle<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T> ValueInfo for DbMetadataHandle<T> {
+    fn is_default(&self) -> bool {
+        self.is_default
+    }
+}
+
+fn branch_entry(ref_name: &FullNameRef, branch: &Branch) -> anyhow::Result<RefMetadataEntry> {
+    Ok(RefMetadataEntry {
+        ref_name: ref_name.as_bstr().to_string(),
+        kind: BRANCH.into(),
+        value: toml::to_string(&StoredBranch::new(branch))?,
+    })
+}
+
+fn workspace_entry(
+    ref_name: &FullNameRef,
+    workspace: &Workspace,
+) -> anyhow::Result<RefMetadataEntry> {
+    Ok(RefMetadataEntry {
+        ref_name: ref_name.as_bstr().to_string(),
+        kind: WORKSPACE.into(),
+        value: toml::to_string(&StoredWorkspace::new(workspace))?,
+    })
+}
+
+/// Parse `entry` into the name of the reference it belongs to and its value, or `None` if its kind is unknown.
+fn parse_entry(entry: RefMetadataEntry) -> anyhow::Result<Option<(FullName, Box<dyn Any>)>> {
+    let value: Box<dyn Any> = match entry.kind.as_str() {
+        BRANCH => Box::new(toml::from_str::<StoredBranch>(&entry.value)?.into_branch()),
+        WORKSPACE => Box::new(toml::from_str::<StoredWorkspace>(&entry.value)?.into_workspace()?),
+        _ => return Ok(None),
+    };
+    Ok(Some((FullName::try_from(entry.ref_name)?, value)))
+}
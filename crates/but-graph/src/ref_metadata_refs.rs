@@ -0,0 +1,662 @@
+use anyhow::Context;
+use but_core::RefMetadata;
+use but_core::ref_metadata::{
+    Branch, RefInfo, Review, StackId, ValueInfo, Workspace, WorkspaceStack, WorkspaceStackBranch,
+};
+use gix::refs::transaction::PreviousValue;
+use gix::refs::{FullName, FullNameRef};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
+
+/// The reference holding the history of all metadata, which can be pushed and fetched like any other.
+pub const METADATA_REF: &str = "refs/gitbutler/meta";
+
+/// The directory in the metadata tree with one file per branch.
+const BRANCHES_DIR: &str = "branches/";
+/// The directory in the metadata tree with one file per workspace.
+const WORKSPACES_DIR: &str = "workspaces/";
+const EXTENSION: &str = ".toml";
+
+/// An implementation to read and write metadata from a commit history in the Git repository itself,
+/// so it can be shared with others and across machines by pushing and fetching [`METADATA_REF`].
+///
+/// Each branch and workspace is stored in its own file, and each change creates a new commit on top of
+/// the previous one. Metadata fetched from a remote is [merged](Self::merge()) per file, so concurrent
+/// edits of different branches are all kept. If the same file was changed on both sides, the most recently
+/// changed version wins, and if both were changed at the same time, the greater content wins, so the outcome
+/// is the same no matter which side merges.
+///
+/// Changes are written immediately, and fail if [`METADATA_REF`] was changed by someone else in the meantime.
+#[derive(Debug)]
+pub struct RefsMetadata {
+    repo: gix::Repository,
+    /// The commit [`METADATA_REF`] pointed to when we last read or wrote it.
+    tip: Option<gix::ObjectId>,
+    /// Paths in the metadata tree, mapped to the file contents.
+    files: BTreeMap<String, String>,
+}
+
+impl RefsMetadata {
+    /// Read the metadata stored in `repo`, which may be empty.
+    pub fn from_repo(repo: gix::Repository) -> anyhow::Result<Self> {
+        let tip = repo
+            .try_find_reference(METADATA_REF)?
+            .map(|mut r| r.peel_to_id_in_place())
+            .transpose()?
+            .map(|id| id.detach());
+        let files = match tip {
+            Some(tip) => read_files(&repo, tip)?,
+            None => BTreeMap::new(),
+        };
+        Ok(RefsMetadata { repo, tip, files })
+    }
+
+    /// The refspec to push the metadata of this repository without ever overwriting it.
+    pub fn push_refspec() -> String {
+        format!("{METADATA_REF}:{METADATA_REF}")
+    }
+
+    /// The refspec to fetch the metadata from the remote named `remote` into [`remote_ref_name()`](Self::remote_ref_name()).
+    pub fn fetch_refspec(remote: &str) -> String {
+        format!("+{METADATA_REF}:{}", Self::remote_ref_name(remote))
+    }
+
+    /// The name of the reference the metadata of the remote named `remote` is fetched to.
+    pub fn remote_ref_name(remote: &str) -> String {
+        format!("refs/gitbutler/remotes/{remote}/meta")
+    }
+
+    /// Merge the metadata fetched from the remote named `remote` with [`fetch_refspec()`](Self::fetch_refspec()),
+    /// and return `true` if our metadata changed.
+    ///
+    /// It's fine if nothing was fetched yet.
+    pub fn merge_remote(&mut self, remote: &str) -> anyhow::Result<bool> {
+        let Some(theirs) = self
+            .repo
+            .try_find_reference(Self::remote_ref_name(remote).as_str())?
+            .map(|mut r| r.peel_to_id_in_place())
+            .transpose()?
+        else {
+            return Ok(false);
+        };
+        self.merge(theirs.detach())
+    }
+
+    /// Merge the metadata in the commit `theirs` into ours, and return `true` if our metadata changed.
+    ///
+    /// Our history fast-forwards to `theirs` if it contains ours, and otherwise a merge commit
+    /// with both histories as parents is created, so pushing the result never overwrites `theirs`.
+    pub fn merge(&mut self, theirs: gix::ObjectId) -> anyhow::Result<bool> {
+        let Some(ours) = self.tip else {
+            return self.fast_forward(theirs);
+        };
+        if ours == theirs {
+            return Ok(false);
+        }
+        let base = self
+            .repo
+            .merge_base(ours, theirs)
+            .ok()
+            .map(|id| id.detach());
+        if base == Some(theirs) {
+            return Ok(false);
+        }
+        if base == Some(ours) {
+            return self.fast_forward(theirs);
+        }
+
+        let base_files = match base {
+            Some(base) => read_files(&self.repo, base)?,
+            None => BTreeMap::new(),
+        };
+        let their_files = read_files(&self.repo, theirs)?;
+        let mut merged = BTreeMap::new();
+        for path in self.files.keys().chain(their_files.keys()) {
+            let (base, ours, theirs) = (
+                base_files.get(path),
+                self.files.get(path),
+                their_files.get(path),
+            );
+            let resolved = if ours == theirs || theirs == base {
+                ours
+            } else if ours == base {
+                theirs
+            } else {
+                resolve_conflict(ours, theirs)
+            };
+            if let Some(content) = resolved {
+                merged.insert(path.clone(), content.clone());
+            }
+        }
+        let changed = merged != self.files;
+        self.files = merged;
+        self.commit("merge metadata", &[ours, theirs])?;
+        Ok(changed)
+    }
+
+    /// Copy all metadata from `other` into this store with a single commit, replacing what's stored for the same names.
+    /// Nothing is committed if all metadata is stored already.
+    ///
+    /// This is useful to start sharing metadata that was so far only stored locally, and to share local changes.
+    pub fn import(&mut self, other: &impl RefMetadata) -> anyhow::Result<()> {
+        let mut changed = false;
+        for res in other.iter() {
+            let (ref_name, value) = res?;
+            if let Some(branch) = value.downcast_ref::<Branch>() {
+                changed |= self
+                    .insert_if_changed(branch_path(ref_name.as_ref()), StoredBranch::new(branch))?;
+            } else if let Some(workspace) = value.downcast_ref::<Workspace>() {
+                changed |= self.insert_if_changed(
+                    workspace_path(ref_name.as_ref()),
+                    StoredWorkspace::new(workspace),
+                )?;
+            }
+        }
+        if !changed {
+            return Ok(());
+        }
+        let parents: Vec<_> = self.tip.into_iter().collect();
+        self.commit("import metadata", &parents)
+    }
+
+    /// Copy the descriptions and reviews of all branches stored here into `other` if it knows these branches,
+    /// and return the number of branches that changed in `other`.
+    ///
+    /// This is useful to take over metadata that was [merged](Self::merge_remote()) from others, while
+    /// branches and workspaces that are only known to others remain untouched.
+    pub fn update_known_branches(&self, other: &mut impl RefMetadata) -> anyhow::Result<usize> {
+        let mut updated = 0;
+        for (path, content) in &self.files {
+            let Some(name) = ref_name_from_path(path, BRANCHES_DIR) else {
+                continue;
+            };
+            let Branch {
+                description,
+                review,
+                ref_info: _,
+            } = toml::from_str::<StoredBranch>(content)?.into_branch();
+            let mut branch = other.branch(FullName::try_from(name)?.as_ref())?;
+            if branch.is_default() || (branch.description == description && branch.review == review)
+            {
+                continue;
+            }
+            branch.description = description;
+            branch.review = review;
+            other.set_branch(&branch)?;
+            updated += 1;
+        }
+        Ok(updated)
+    }
+
+    fn fast_forward(&mut self, theirs: gix::ObjectId) -> anyhow::Result<bool> {
+        let files = read_files(&self.repo, theirs)?;
+        self.repo.reference(
+            METADATA_REF,
+            theirs,
+            self.previous_value(),
+            "GitButler: fast-forward metadata",
+        )?;
+        self.tip = Some(theirs);
+        let changed = files != self.files;
+        self.files = files;
+        Ok(changed)
+    }
+
+    fn previous_value(&self) -> PreviousValue {
+        match self.tip {
+            Some(tip) => PreviousValue::MustExistAndMatch(tip.into()),
+            None => PreviousValue::MustNotExist,
+        }
+    }
+
+    /// Write all files as a new commit with `parents`, and point [`METADATA_REF`] to it.
+    fn commit(&mut self, message: &str, parents: &[gix::ObjectId]) -> anyhow::Result<()> {
+        let mut tree = self.repo.empty_tree().edit()?;
+        for (path, content) in &self.files {
+            let blob = self.repo.write_blob(content.as_bytes())?;
+            tree.upsert(path.as_str(), gix::object::tree::EntryKind::Blob, blob)?;
+        }
+        let tree = tree.write()?.detach();
+        let commit = gix::objs::Commit {
+            tree,
+            parents: parents.iter().copied().collect(),
+            author: signature(),
+            committer: signature(),
+            encoding: None,
+            message: message.into(),
+            extra_headers: Vec::new(),
+        };
+        let commit = self.repo.write_object(commit)?.detach();
+        self.repo
+            .reference(
+                METADATA_REF,
+                commit,
+                self.previous_value(),
+                format!("GitButler: {message}"),
+            )
+            .with_context(|| {
+                format!("{METADATA_REF} was changed by someone else, please reload the metadata")
+            })?;
+        self.tip = Some(commit);
+        Ok(())
+    }
+
+    /// Store `content` at `path` and commit it, unless it's the same as before when ignoring the time of change.
+    fn write_file<T>(&mut self, path: String, content: T, message: &str) -> anyhow::Result<()>
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Changed,
+    {
+        if !self.insert_if_changed(path, content)? {
+            return Ok(());
+        }
+        let parents: Vec<_> = self.tip.into_iter().collect();
+        self.commit(message, &parents)
+    }
+
+    /// Store `content` at `path` without committing it, and return `true` if it's not the same as before
+    /// when ignoring the time of change.
+    fn insert_if_changed<T>(&mut self, path: String, content: T) -> anyhow::Result<bool>
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Changed,
+    {
+        let unchanged = self
+            .files
+            .get(&path)
+            .and_then(|existing| toml::from_str::<T>(existing).ok())
+            .is_some_and(|mut existing| {
+                existing.set_changed_at(content.changed_at());
+                existing == content
+            });
+        if unchanged {
+            return Ok(false);
+        }
+        self.files.insert(path, toml::to_string(&content)?);
+        Ok(true)
+    }
+}
+
+impl RefMetadata for RefsMetadata {
+    type Handle<T> = RefsMetadataHandle<T>;
+
+    fn iter(&self) -> impl Iterator<Item = anyhow::Result<(FullName, Box<dyn Any>)>> + '_ {
+        self.files
+            .iter()
+            .filter_map(|(path, content)| parse_file(path, content).transpose())
+    }
+
+    fn workspace(&self, ref_name: &FullNameRef) -> anyhow::Result<Self::Handle<Workspace>> {
+        let (is_default, value) = match self.files.get(&workspace_path(ref_name)) {
+            Some(content) => (
+                false,
+                toml::from_str::<StoredWorkspace>(content)?.into_workspace()?,
+            ),
+            None => (true, Workspace::default()),
+        };
+        Ok(RefsMetadataHandle {
+            is_default,
+            ref_name: ref_name.to_owned(),
+            value,
+        })
+    }
+
+    fn branch(&self, ref_name: &FullNameRef) -> anyhow::Result<Self::Handle<Branch>> {
+        let (is_default, value) = match self.files.get(&branch_path(ref_name)) {
+            Some(content) => (
+                false,
+                toml::from_str::<StoredBranch>(content)?.into_branch(),
+            ),
+            None => (true, Branch::default()),
+        };
+        Ok(RefsMetadataHandle {
+            is_default,
+            ref_name: ref_name.to_owned(),
+            value,
+        })
+    }
+
+    fn set_workspace(&mut self, value: &Self::Handle<Workspace>) -> anyhow::Result<()> {
+        let ref_name = value.ref_name.as_ref();
+        self.write_file(
+            workspace_path(ref_name),
+            StoredWorkspace::new(value),
+            &format!("update workspace {}", ref_name.as_bstr()),
+        )
+    }
+
+    fn set_branch(&mut self, value: &Self::Handle<Branch>) -> anyhow::Result<()> {
+        let ref_name = value.ref_name.as_ref();
+        self.write_file(
+            branch_path(ref_name),
+            StoredBranch::new(value),
+            &format!("update branch {}", ref_name.as_bstr()),
+        )
+    }
+
+    fn remove(&mut self, ref_name: &FullNameRef) -> anyhow::Result<bool> {
+        let removed_branch = self.files.remove(&branch_path(ref_name)).is_some();
+        let removed_workspace = self.files.remove(&workspace_path(ref_name)).is_some();
+        if !(removed_branch || removed_workspace) {
+            return Ok(false);
+        }
+        let parents: Vec<_> = self.tip.into_iter().collect();
+        self.commit(&format!("remove {}", ref_name.as_bstr()), &parents)?;
+        Ok(true)
+    }
+}
+
+pub struct RefsMetadataHandle<T> {
+    is_default: bool,
+    ref_name: FullName,
+    value: T,
+}
+
+impl<T> AsRef<FullNameRef> for RefsMetadataHandle<T> {
+    fn as_ref(&self) -> &FullNameRef {
+        self.ref_name.as_ref()
+    }
+}
+
+impl<T> Deref for RefsMetadataHandle<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for RefsMetadataHandle<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T> ValueInfo for RefsMetadataHandle<T> {
+    fn is_default(&self) -> bool {
+        self.is_default
+    }
+}
+
+fn branch_path(ref_name: &FullNameRef) -> String {
+    format!("{BRANCHES_DIR}{}{EXTENSION}", ref_name.as_bstr())
+}
+
+fn workspace_path(ref_name: &FullNameRef) -> String {
+    format!("{WORKSPACES_DIR}{}{EXTENSION}", ref_name.as_bstr())
+}
+
+/// Return the name of the reference whose metadata is stored at `path` if it's in `dir`.
+fn ref_name_from_path<'a>(path: &'a str, dir: &str) -> Option<&'a str> {
+    path.strip_prefix(dir)?.strip_suffix(EXTENSION)
+}
+
+/// Parse the file at `path` into the name of the reference it belongs to and its value, or `None` if it's unknown.
+fn parse_file(path: &str, content: &str) -> anyhow::Result<Option<(FullName, Box<dyn Any>)>> {
+    let value: Box<dyn Any>;
+    let name = if let Some(name) = ref_name_from_path(path, BRANCHES_DIR) {
+        value = Box::new(toml::from_str::<StoredBranch>(content)?.into_branch());
+        name
+    } else if let Some(name) = ref_name_from_path(path, WORKSPACES_DIR) {
+        value = Box::new(toml::from_str::<StoredWorkspace>(content)?.into_workspace()?);
+        name
+    } else {
+        return Ok(None);
+    };
+    Ok(Some((FullName::try_from(name)?, value)))
+}
+
+/// Return the paths of all files in the tree of `commit`, along with their contents.
+fn read_files(
+    repo: &gix::Repository,
+    commit: gix::ObjectId,
+) -> anyhow::Result<BTreeMap<String, String>> {
+    let tree = repo.find_commit(commit)?.tree()?;
+    let mut recorder = gix::traverse::tree::Recorder::default();
+    tree.traverse().breadthfirst(&mut recorder)?;
+    let mut files = BTreeMap::new();
+    for entry in recorder.records {
+        if !entry.mode.is_blob() {
+            continue;
+        }
+        let blob = repo.find_blob(entry.oid)?;
+        let content = std::str::from_utf8(&blob.data)
+            .with_context(|| format!("Metadata at '{}' isn't valid UTF-8", entry.filepath))?;
+        files.insert(entry.filepath.to_string(), content.to_owned());
+    }
+    Ok(files)
+}
+
+/// Pick the version of a file that was changed on both sides, with `None` meaning it was deleted.
+///
+/// The most recently changed version wins, and the greater content breaks ties.
+/// Deletions lose against changes so no data is lost.
+fn resolve_conflict<'a>(
+    ours: Option<&'a String>,
+    theirs: Option<&'a String>,
+) -> Option<&'a String> {
+    let (Some(ours), Some(theirs)) = (ours, theirs) else {
+        return ours.or(theirs);
+    };
+    let changed_at = |content: &str| {
+        toml::from_str::<ChangedAt>(content)
+            .map(|c| c.changed_at)
+            .unwrap_or_default()
+    };
+    let key = |content: &'a String| (changed_at(content), content);
+    Some(if key(theirs) > key(ours) {
+        theirs
+    } else {
+        ours
+    })
+}
+
+fn signature() -> gix::actor::Signature {
+    gix::actor::Signature {
+        name: "GitButler".into(),
+        email: "gitbutler@gitbutler.com".into(),
+        time: gix::date::Time::now_local_or_utc(),
+    }
+}
+
+fn now() -> gix::date::SecondsSinceUnixEpoch {
+    gix::date::Time::now_utc().seconds
+}
+
+/// Access to the time at which a stored value was changed, which isn't part of the value itself.
+trait Changed {
+    fn changed_at(&self) -> gix::date::SecondsSinceUnixEpoch;
+    fn set_changed_at(&mut self, time: gix::date::SecondsSinceUnixEpoch);
+}
+
+#[derive(Deserialize)]
+struct ChangedAt {
+    changed_at: gix::date::SecondsSinceUnixEpoch,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy)]
+struct StoredTime {
+    seconds: gix::date::SecondsSinceUnixEpoch,
+    offset: gix::date::OffsetInSeconds,
+}
+
+impl From<gix::date::Time> for StoredTime {
+    fn from(gix::date::Time { seconds, offset }: gix::date::Time) -> Self {
+        StoredTime { seconds, offset }
+    }
+}
+
+impl From<StoredTime> for gix::date::Time {
+    fn from(StoredTime { seconds, offset }: StoredTime) -> Self {
+        gix::date::Time { seconds, offset }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+struct StoredRefInfo {
+    created_at: Option<StoredTime>,
+    updated_at: Option<StoredTime>,
+}
+
+impl From<&RefInfo> for StoredRefInfo {
+    fn from(
+        RefInfo {
+            created_at,
+            updated_at,
+        }: &RefInfo,
+    ) -> Self {
+        StoredRefInfo {
+            created_at: created_at.map(Into::into),
+            updated_at: updated_at.map(Into::into),
+        }
+    }
+}
+
+impl From<StoredRefInfo> for RefInfo {
+    fn from(
+        StoredRefInfo {
+            created_at,
+            updated_at,
+        }: StoredRefInfo,
+    ) -> Self {
+        RefInfo {
+            created_at: created_at.map(Into::into),
+            updated_at: updated_at.map(Into::into),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub(crate) struct StoredBranch {
+    changed_at: gix::date::SecondsSinceUnixEpoch,
+    description: Option<String>,
+    pull_request: Option<usize>,
+    review_id: Option<String>,
+    ref_info: StoredRefInfo,
+}
+
+impl StoredBranch {
+    pub(crate) fn new(
+        Branch {
+            ref_info,
+            description,
+            review,
+        }: &Branch,
+    ) -> Self {
+        StoredBranch {
+            changed_at: now(),
+            description: description.clone(),
+            pull_request: review.pull_request,
+            review_id: review.review_id.clone(),
+            ref_info: ref_info.into(),
+        }
+    }
+
+    pub(crate) fn into_branch(self) -> Branch {
+        Branch {
+            ref_info: self.ref_info.into(),
+            description: self.description,
+            review: Review {
+                pull_request: self.pull_request,
+                review_id: self.review_id,
+            },
+        }
+    }
+}
+
+impl Changed for StoredBranch {
+    fn changed_at(&self) -> gix::date::SecondsSinceUnixEpoch {
+        self.changed_at
+    }
+    fn set_changed_at(&mut self, time: gix::date::SecondsSinceUnixEpoch) {
+        self.changed_at = time;
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub(crate) struct StoredWorkspace {
+    changed_at: gix::date::SecondsSinceUnixEpoch,
+    target_ref: Option<String>,
+    ref_info: StoredRefInfo,
+    #[serde(default)]
+    stacks: Vec<StoredStack>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+struct StoredStack {
+    id: StackId,
+    /// The full names of the branches in the stack, from its tip towards its base.
+    branches: Vec<StoredStackBranch>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+struct StoredStackBranch {
+    ref_name: String,
+    archived: bool,
+}
+
+impl StoredWorkspace {
+    pub(crate) fn new(
+        Workspace {
+            ref_info,
+            stacks,
+            target_ref,
+        }: &Workspace,
+    ) -> Self {
+        StoredWorkspace {
+            changed_at: now(),
+            target_ref: target_ref.as_ref().map(|name| name.as_bstr().to_string()),
+            ref_info: ref_info.into(),
+            stacks: stacks
+                .iter()
+                .map(|stack| StoredStack {
+                    id: stack.id,
+                    branches: stack
+                        .branches
+                        .iter()
+                        .map(|branch| StoredStackBranch {
+                            ref_name: branch.ref_name.as_bstr().to_string(),
+                            archived: branch.archived,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn into_workspace(self) -> anyhow::Result<Workspace> {
+        Ok(Workspace {
+            ref_info: self.ref_info.into(),
+            stacks: self
+                .stacks
+                .into_iter()
+                .map(|stack| {
+                    Ok(WorkspaceStack {
+                        id: stack.id,
+                        branches: stack
+                            .branches
+                            .into_iter()
+                            .map(|branch| {
+                                Ok(WorkspaceStackBranch {
+                                    ref_name: FullName::try_from(branch.ref_name)?,
+                                    archived: branch.archived,
+                                })
+                            })
+                            .collect::<anyhow::Result<_>>()?,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+            target_ref: self.target_ref.map(FullName::try_from).transpose()?,
+        })
+    }
+}
+
+impl Changed for StoredWorkspace {
+    fn changed_at(&self) -> gix::date::SecondsSinceUnixEpoch {
+        self.changed_at
+    }
+    fn set_changed_at(&mut self, time: gix::date::SecondsSinceUnixEpoch) {
+        self.changed_at = time;
+    }
+}
@@ -54,6 +54,12 @@ bitflags! {
         /// Note that when multiple workspaces are included in the traversal, this flag is set by
         /// any of many target branches.
         const Integrated = 1 << 2;
+        /// The commit isn't reachable from the target branch, but its changes were found there
+        /// in an equivalent commit, for instance one with the same patch-id after a rebase or squash-merge.
+        ///
+        /// This flag is never set or propagated during traversal, it's set on the commits of stacks by
+        /// [`Workspace::mark_integrated_by_patch()`](crate::projection::Workspace::mark_integrated_by_patch()).
+        const IntegratedByPatch = 1 << 3;
     }
 }
 
@@ -71,6 +77,7 @@ impl CommitFlags {
                 .to_string()
                 .replace("NotInRemote", "⌂")
                 .replace("InWorkspace", "🏘️")
+                .replace("IntegratedByPatch", "≡")
                 .replace("Integrated", "✓")
                 .replace(" ", "");
             if extra != 0 {
@@ -4,6 +4,7 @@ use crate::init::{StackState, add_stack_with_segments, add_workspace, id_at, id_
 use crate::init::{read_only_in_memory_scenario, standard_options};
 use crate::vis::utils::graph_workspace;
 use but_graph::Graph;
+use but_graph::projection::StackCommitFlags;
 use but_testsupport::visualize_commit_graph_all;
 
 #[test]
@@ -3498,6 +3499,54 @@ fn two_dependent_branches_first_merged_by_rebase() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn two_dependent_branches_first_merged_by_rebase_is_integrated_by_patch() -> anyhow::Result<()> {
+    let (repo, mut meta) =
+        read_only_in_memory_scenario("ws/two-dependent-branches-first-rebased-and-merged")?;
+    add_workspace(&mut meta);
+    let graph = Graph::from_head(&repo, &*meta, standard_options())?.validated()?;
+    let mut ws = graph.to_workspace()?;
+
+    let integrated = ws.mark_integrated_by_patch(&repo)?;
+    let (local_a, upstream_a) = (id_by_rev(&repo, "A"), id_by_rev(&repo, "origin/main"));
+    assert_eq!(
+        integrated.into_iter().collect::<Vec<_>>(),
+        [(local_a.detach(), upstream_a.detach())],
+        "A was cherry-picked onto the target, so it's integrated even though it's not reachable from it"
+    );
+
+    let flags_by_id: Vec<_> = ws.stacks[0]
+        .segments
+        .iter()
+        .flat_map(|s| &s.commits)
+        .map(|c| (c.id, c.flags))
+        .collect();
+    assert_eq!(
+        flags_by_id,
+        [
+            (
+                id_by_rev(&repo, "B").detach(),
+                StackCommitFlags::InWorkspace
+            ),
+            (
+                local_a.detach(),
+                StackCommitFlags::InWorkspace | StackCommitFlags::IntegratedByPatch
+            ),
+        ],
+        "B has changes that aren't upstream yet"
+    );
+    insta::assert_snapshot!(graph_workspace(&ws), @r"
+    📕🏘️:0:gitbutler/workspace <> ✓refs/remotes/origin/main⇣1 on 281456a
+    └── ≡:3:B on 281456a
+        ├── :3:B
+        │   └── ·da597e8 (🏘️)
+        └── :4:A <> origin/A →:5:⇡1⇣1
+            ├── 🟣0b6b861 (✓)
+            └── ·1818c17 (🏘️|≡)
+    ");
+    Ok(())
+}
+
 #[test]
 fn special_branch_names_do_not_end_up_in_segment() -> anyhow::Result<()> {
     let (repo, mut meta) = read_only_in_memory_scenario("ws/special-branches")?;
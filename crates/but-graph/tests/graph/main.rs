@@ -2,4 +2,6 @@ mod init;
 mod vis;
 pub use vis::utils::graph_tree;
 
+mod ref_metadata_db;
 mod ref_metadata_legacy;
+mod ref_metadata_refs;
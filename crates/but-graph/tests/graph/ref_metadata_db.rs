@@ -0,0 +1,172 @@
+use crate::ref_metadata_legacy::{
+    create_branch_from_scratch, create_workspace_from_scratch, roundtrip_journey, vb_fixture,
+};
+use but_core::RefMetadata;
+use but_core::ref_metadata::{Branch, ValueInfo};
+use but_graph::{DbMetadata, VirtualBranchesTomlMetadata};
+use but_testsupport::gix_testtools::tempfile::{TempDir, tempdir};
+
+#[test]
+fn journey() -> anyhow::Result<()> {
+    let (mut store, _tmp) = migrated_store("virtual-branches-01")?;
+    assert_eq!(store.iter().count(), 15, "There are items to test on");
+    roundtrip_journey(&mut store)?;
+    Ok(())
+}
+
+#[test]
+fn create_workspace_from_scratch_workspace_first() -> anyhow::Result<()> {
+    let tmp = tempdir()?;
+    let mut store = DbMetadata::from_directory(tmp.path())?;
+    let workspace_name = "refs/heads/gitbutler/integration".try_into()?;
+    let mut ws = create_workspace_from_scratch(&mut store, workspace_name)?;
+
+    ws.stacks.clear();
+    store.set_workspace(&ws)?;
+    let stored_ws = store.workspace(workspace_name)?;
+    assert_eq!(*stored_ws, *ws, "this state reproduces when queried");
+
+    let below_top = "refs/heads/one-below-top".try_into()?;
+    assert!(
+        store.branch(below_top)?.is_default(),
+        "unlike with the legacy backend, workspace branches aren't implicitly created"
+    );
+
+    assert!(store.remove(workspace_name)?);
+    assert!(store.workspace(workspace_name)?.is_default());
+    assert_eq!(store.iter().count(), 0);
+    Ok(())
+}
+
+#[test]
+fn create_branch_from_scratch_and_remove_it() -> anyhow::Result<()> {
+    let tmp = tempdir()?;
+    let mut store = DbMetadata::from_directory(tmp.path())?;
+    let branch_name = "refs/heads/feat".try_into()?;
+    let branch = create_branch_from_scratch(&mut store, branch_name)?;
+    assert_eq!(
+        *store.branch(branch_name)?,
+        *branch,
+        "values are stored as they are"
+    );
+
+    let ws = store.workspace("refs/heads/gitbutler/workspace".try_into()?)?;
+    assert!(
+        ws.is_default(),
+        "the branch isn't auto-added to the workspace"
+    );
+
+    assert!(store.remove(branch_name)?);
+    assert!(!store.remove(branch_name)?, "nothing left to remove");
+    assert!(store.branch(branch_name)?.is_default());
+    Ok(())
+}
+
+#[test]
+fn migration_from_toml_happens_only_once() -> anyhow::Result<()> {
+    let (mut store, tmp) = migrated_store("virtual-branches-01")?;
+    let legacy = VirtualBranchesTomlMetadata::from_path(tmp.path().join("vb.toml"))?;
+    assert_eq!(store.iter().count(), legacy.iter().count());
+    for res in legacy.iter() {
+        let (ref_name, _) = res?;
+        if but_graph::is_workspace_ref_name(ref_name.as_ref()) {
+            assert_eq!(
+                *store.workspace(ref_name.as_ref())?,
+                *legacy.workspace(ref_name.as_ref())?
+            );
+        } else {
+            assert_eq!(
+                *store.branch(ref_name.as_ref())?,
+                *legacy.branch(ref_name.as_ref())?
+            );
+        }
+    }
+
+    let (removed, _) = store.iter().next().expect("not empty")?;
+    assert!(store.remove(removed.as_ref())?);
+    assert!(
+        !store.migrate_from_toml(&tmp.path().join("vb.toml"))?,
+        "the file was imported before"
+    );
+    assert_eq!(
+        store.iter().count(),
+        legacy.iter().count() - 1,
+        "removed items don't come back"
+    );
+    assert!(
+        !store.migrate_from_toml(&tmp.path().join("does-not-exist.toml"))?,
+        "there is nothing to migrate without a file"
+    );
+    Ok(())
+}
+
+#[test]
+fn stores_on_the_same_database_see_each_others_changes() -> anyhow::Result<()> {
+    let tmp = tempdir()?;
+    let mut first = DbMetadata::from_directory(tmp.path())?;
+    let mut second = DbMetadata::from_directory(tmp.path())?;
+
+    let mut branch = first.branch("refs/heads/feature".try_into()?)?;
+    assert!(branch.is_default());
+    branch.description = Some("first".into());
+    first.set_branch(&branch)?;
+
+    let mut branch = second.branch("refs/heads/feature".try_into()?)?;
+    assert!(!branch.is_default(), "it was written by the other store");
+    assert_eq!(branch.description.as_deref(), Some("first"));
+    branch.description = Some("second".into());
+    second.set_branch(&branch)?;
+    assert_eq!(
+        first
+            .branch("refs/heads/feature".try_into()?)?
+            .description
+            .as_deref(),
+        Some("second"),
+        "the last write wins"
+    );
+
+    assert!(first.remove("refs/heads/feature".try_into()?)?);
+    assert!(!second.remove("refs/heads/feature".try_into()?)?);
+    assert_eq!(second.iter().count(), 0);
+    Ok(())
+}
+
+#[test]
+fn export_to_toml() -> anyhow::Result<()> {
+    let (store, tmp) = migrated_store("virtual-branches-01")?;
+    let exported_path = tmp.path().join("exported.toml");
+    store.export_to_toml(&exported_path)?;
+
+    let legacy = VirtualBranchesTomlMetadata::from_path(tmp.path().join("vb.toml"))?;
+    let exported = VirtualBranchesTomlMetadata::from_path(&exported_path)?;
+    assert_eq!(exported.iter().count(), legacy.iter().count());
+
+    let ws_name = "refs/heads/gitbutler/workspace".try_into()?;
+    assert_eq!(
+        exported.workspace(ws_name)?.stacks,
+        legacy.workspace(ws_name)?.stacks,
+        "stacks keep their ids and the order of their branches"
+    );
+    for res in legacy.iter() {
+        let (ref_name, value) = res?;
+        let Some(branch) = value.downcast_ref::<Branch>() else {
+            continue;
+        };
+        let exported_branch = exported.branch(ref_name.as_ref())?;
+        assert_eq!(exported_branch.description, branch.description);
+        assert_eq!(exported_branch.review, branch.review);
+    }
+    Ok(())
+}
+
+/// Return a store with the metadata of the `virtual_branches.toml` fixture called `name` migrated into it,
+/// with the database and a copy of the fixture, `vb.toml`, in the returned directory.
+fn migrated_store(name: &str) -> anyhow::Result<(DbMetadata, TempDir)> {
+    let tmp = tempdir()?;
+    let toml_path = tmp.path().join("vb.toml");
+    std::fs::copy(vb_fixture(name), &toml_path)?;
+
+    let mut store = DbMetadata::from_directory(tmp.path())?;
+    assert!(store.migrate_from_toml(&toml_path)?);
+    Ok((store, tmp))
+}
@@ -1,5 +1,7 @@
 use but_core::RefMetadata;
-use but_core::ref_metadata::{StackId, ValueInfo, WorkspaceStack, WorkspaceStackBranch};
+use but_core::ref_metadata::{
+    Branch, StackId, ValueInfo, Workspace, WorkspaceStack, WorkspaceStackBranch,
+};
 use but_graph::VirtualBranchesTomlMetadata;
 use but_testsupport::gix_testtools::tempfile::{TempDir, tempdir};
 use std::collections::HashMap;
@@ -381,17 +383,14 @@ fn create_workspace_and_stacks_with_branches_from_scratch() -> anyhow::Result<()
     let (mut store, _tmp) = empty_vb_store_rw()?;
     let toml_path = store.path().to_owned();
     let branch_name: gix::refs::FullName = "refs/heads/feat".try_into()?;
-    let mut branch = store.branch(branch_name.as_ref())?;
-    assert!(branch.is_default(), "nothing was there yet");
+    assert_eq!(
+        store.branch(branch_name.as_ref())?.stack_id(),
+        None,
+        "default values have no stack-id"
+    );
     assert!(!toml_path.exists(), "file wasn't written yet");
-    assert_eq!(branch.stack_id(), None, "default values have no stack-id");
 
-    branch.description = Some("mine".into());
-    branch.review = but_core::ref_metadata::Review {
-        pull_request: Some(42),
-        review_id: Some("review-id".into()),
-    };
-    store.set_branch(&branch)?;
+    let branch = create_branch_from_scratch(&mut store, branch_name.as_ref())?;
     let id = branch.stack_id().expect("now a stack-id was generated");
 
     let workspace_name: gix::refs::FullName = "refs/heads/gitbutler/workspace".try_into()?;
@@ -759,7 +758,52 @@ fn create_workspace_and_stacks_with_branches_from_scratch() -> anyhow::Result<()
 fn create_workspace_from_scratch_workspace_first() -> anyhow::Result<()> {
     let (mut store, _tmp) = empty_vb_store_rw()?;
     let workspace_name = "refs/heads/gitbutler/integration".try_into()?;
-    let mut ws = store.workspace(workspace_name)?;
+    let mut ws = create_workspace_from_scratch(&mut store, workspace_name)?;
+
+    // Remove the last branch, but leave the stack.
+    ws.stacks[1].branches.pop();
+
+    let err = store.set_workspace(&ws).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "BUG: do not pop off the last branch, remove the whole stack"
+    );
+    ws.stacks.pop();
+    assert_eq!(ws.stacks.len(), 1);
+
+    // The workspace is empty now, no sack left
+    ws.stacks.pop();
+    store.set_workspace(&ws)?;
+
+    let stored_ws = store.workspace(workspace_name)?;
+    assert_eq!(
+        stored_ws.deref(),
+        ws.deref(),
+        "this state reproduces when queried"
+    );
+
+    let toml_path = store.path().to_owned();
+    drop(store);
+
+    // Stacks are still there, but not in workspace, they carry data. But can't test it due to hashmap-instability.
+    let store = VirtualBranchesTomlMetadata::from_path(toml_path)?;
+    let below_top: &gix::refs::FullNameRef = "refs/heads/one-below-top".try_into()?;
+    let branch = store.branch(below_top)?;
+    assert!(
+        !branch.is_default(),
+        "Workspace branches are implicitly created, this isn't the case in a normal backend implementation"
+    );
+    Ok(())
+}
+
+/// Create a workspace named `workspace_name` with stacks in the empty `metadata` store, and assure
+/// it reads back as it was written, also after removing an archived branch.
+/// Return the workspace as it was last read back.
+pub(crate) fn create_workspace_from_scratch<M: RefMetadata>(
+    metadata: &mut M,
+    workspace_name: &gix::refs::FullNameRef,
+) -> anyhow::Result<M::Handle<Workspace>> {
+    let mut ws = metadata.workspace(workspace_name)?;
     ws.stacks.push(WorkspaceStack {
         id: StackId::from_number_for_testing(1),
         branches: vec![
@@ -825,14 +869,14 @@ fn create_workspace_from_scratch_workspace_first() -> anyhow::Result<()> {
         },
     ]
     "#);
-    store.set_workspace(&ws)?;
-    let stored_ws = store.workspace(workspace_name)?;
+    metadata.set_workspace(&ws)?;
+    let stored_ws = metadata.workspace(workspace_name)?;
     assert_eq!(stored_ws.deref(), ws.deref());
 
     // Pop archived branch.
     ws.stacks[0].branches.pop();
-    store.set_workspace(&ws)?;
-    let mut ws = store.workspace(workspace_name)?;
+    metadata.set_workspace(&ws)?;
+    let ws = metadata.workspace(workspace_name)?;
     insta::assert_debug_snapshot!(ws.stacks, @r#"
     [
         WorkspaceStack {
@@ -865,44 +909,34 @@ fn create_workspace_from_scratch_workspace_first() -> anyhow::Result<()> {
         },
     ]
     "#);
+    Ok(ws)
+}
 
-    // Remove the last branch, but leave the stack.
-    ws.stacks[1].branches.pop();
-
-    let err = store.set_workspace(&ws).unwrap_err();
-    assert_eq!(
-        err.to_string(),
-        "BUG: do not pop off the last branch, remove the whole stack"
-    );
-    ws.stacks.pop();
-    assert_eq!(ws.stacks.len(), 1);
-
-    // The workspace is empty now, no sack left
-    ws.stacks.pop();
-    store.set_workspace(&ws)?;
-
-    let stored_ws = store.workspace(workspace_name)?;
-    assert_eq!(
-        stored_ws.deref(),
-        ws.deref(),
-        "this state reproduces when queried"
-    );
+/// Create metadata for the branch named `branch_name` in `metadata`, where it didn't exist yet,
+/// and assure its values are persisted.
+/// Return the branch as it was written.
+pub(crate) fn create_branch_from_scratch<M: RefMetadata>(
+    metadata: &mut M,
+    branch_name: &gix::refs::FullNameRef,
+) -> anyhow::Result<M::Handle<Branch>> {
+    let mut branch = metadata.branch(branch_name)?;
+    assert!(branch.is_default(), "nothing was there yet");
 
-    let toml_path = store.path().to_owned();
-    drop(store);
+    branch.description = Some("mine".into());
+    branch.review = but_core::ref_metadata::Review {
+        pull_request: Some(42),
+        review_id: Some("review-id".into()),
+    };
+    metadata.set_branch(&branch)?;
 
-    // Stacks are still there, but not in workspace, they carry data. But can't test it due to hashmap-instability.
-    let store = VirtualBranchesTomlMetadata::from_path(toml_path)?;
-    let below_top: &gix::refs::FullNameRef = "refs/heads/one-below-top".try_into()?;
-    let branch = store.branch(below_top)?;
-    assert!(
-        !branch.is_default(),
-        "Workspace branches are implicitly created, this isn't the case in a normal backend implementation"
-    );
-    Ok(())
+    let stored = metadata.branch(branch_name)?;
+    assert!(!stored.is_default(), "the value was written");
+    assert_eq!(stored.description, branch.description);
+    assert_eq!(stored.review, branch.review);
+    Ok(branch)
 }
 
-fn vb_fixture(name: &str) -> PathBuf {
+pub(crate) fn vb_fixture(name: &str) -> PathBuf {
     format!("tests/fixtures/legacy/{name}.toml").into()
 }
 
@@ -923,7 +957,7 @@ fn empty_vb_store_rw() -> anyhow::Result<(VirtualBranchesTomlMetadata, TempDir)>
 
 /// Assure everything can round-trip and the data looks consistent, independently of the actual data,
 /// from a store that already contains data.
-fn roundtrip_journey(metadata: &mut impl RefMetadata) -> anyhow::Result<()> {
+pub(crate) fn roundtrip_journey(metadata: &mut impl RefMetadata) -> anyhow::Result<()> {
     // TODO: retrieve and set tests for all items, round-tripping
     let all_items = metadata.iter().map(Result::unwrap).collect::<Vec<_>>();
     for (ref_name, md) in &all_items {
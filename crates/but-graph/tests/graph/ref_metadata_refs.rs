@@ -0,0 +1,253 @@
+use but_core::RefMetadata;
+use but_core::ref_metadata::{StackId, ValueInfo, WorkspaceStack, WorkspaceStackBranch};
+use but_graph::{METADATA_REF, RefsMetadata, VirtualBranchesTomlMetadata};
+use but_testsupport::gix_testtools::tempfile::TempDir;
+use gix::refs::transaction::PreviousValue;
+
+#[test]
+fn branches_and_workspaces_round_trip() -> anyhow::Result<()> {
+    let (repo, _tmp) = unborn_repo()?;
+    let mut store = RefsMetadata::from_repo(repo.clone())?;
+    assert_eq!(store.iter().count(), 0, "nothing is stored initially");
+
+    let mut branch = store.branch("refs/heads/feature".try_into()?)?;
+    assert!(branch.is_default());
+    branch.description = Some("a description".into());
+    branch.review.pull_request = Some(42);
+    store.set_branch(&branch)?;
+
+    let mut ws = store.workspace("refs/heads/gitbutler/workspace".try_into()?)?;
+    ws.target_ref = Some("refs/remotes/origin/main".try_into()?);
+    ws.stacks.push(WorkspaceStack {
+        id: StackId::generate(),
+        branches: vec![WorkspaceStackBranch {
+            ref_name: "refs/heads/feature".try_into()?,
+            archived: false,
+        }],
+    });
+    store.set_workspace(&ws)?;
+    let tip = metadata_tip(&repo)?;
+
+    let store = RefsMetadata::from_repo(repo.clone())?;
+    let stored_branch = store.branch("refs/heads/feature".try_into()?)?;
+    assert!(
+        !stored_branch.is_default(),
+        "it's read back from the repository"
+    );
+    assert_eq!(*stored_branch, *branch);
+    let stored_ws = store.workspace("refs/heads/gitbutler/workspace".try_into()?)?;
+    assert_eq!(*stored_ws, *ws);
+    assert_eq!(store.iter().count(), 2);
+
+    let mut store = store;
+    store.set_branch(&stored_branch)?;
+    assert_eq!(
+        metadata_tip(&repo)?,
+        tip,
+        "setting the same value again doesn't create a commit"
+    );
+
+    assert!(store.remove("refs/heads/feature".try_into()?)?);
+    assert!(!store.remove("refs/heads/feature".try_into()?)?);
+    assert!(store.branch("refs/heads/feature".try_into()?)?.is_default());
+    assert_eq!(store.iter().count(), 1, "only the workspace is left");
+    Ok(())
+}
+
+#[test]
+fn concurrent_changes_to_the_same_store_are_rejected() -> anyhow::Result<()> {
+    let (repo, _tmp) = unborn_repo()?;
+    let mut first = RefsMetadata::from_repo(repo.clone())?;
+    let mut second = RefsMetadata::from_repo(repo.clone())?;
+
+    let mut branch = first.branch("refs/heads/feature".try_into()?)?;
+    branch.description = Some("first".into());
+    first.set_branch(&branch)?;
+
+    let mut branch = second.branch("refs/heads/feature".try_into()?)?;
+    branch.description = Some("second".into());
+    let err = second.set_branch(&branch).unwrap_err();
+    assert!(err.to_string().contains("was changed by someone else"));
+    Ok(())
+}
+
+#[test]
+fn changes_to_different_branches_are_merged() -> anyhow::Result<()> {
+    let (repo, _tmp) = unborn_repo()?;
+    let base = set_description(&repo, "refs/heads/shared", "base")?;
+    let theirs = set_description(&repo, "refs/heads/theirs", "theirs")?;
+    reset_metadata(&repo, base)?;
+    set_description(&repo, "refs/heads/ours", "ours")?;
+
+    repo.reference(
+        RefsMetadata::remote_ref_name("origin"),
+        theirs,
+        PreviousValue::Any,
+        "fetch",
+    )?;
+    let mut store = RefsMetadata::from_repo(repo.clone())?;
+    assert!(store.merge_remote("origin")?);
+    for (name, description) in [
+        ("refs/heads/shared", "base"),
+        ("refs/heads/theirs", "theirs"),
+        ("refs/heads/ours", "ours"),
+    ] {
+        assert_eq!(
+            store.branch(name.try_into()?)?.description.as_deref(),
+            Some(description)
+        );
+    }
+
+    let merge = metadata_tip(&repo)?;
+    let parents: Vec<_> = repo.find_commit(merge)?.parent_ids().collect();
+    assert_eq!(parents.len(), 2, "a merge commit keeps both histories");
+    assert!(
+        !store.merge(theirs)?,
+        "merging again does nothing as it's already contained"
+    );
+    Ok(())
+}
+
+#[test]
+fn conflicting_changes_are_resolved_the_same_way_on_both_sides() -> anyhow::Result<()> {
+    let (repo, _tmp) = unborn_repo()?;
+    let base = set_description(&repo, "refs/heads/feature", "base")?;
+    let theirs = set_description(&repo, "refs/heads/feature", "theirs")?;
+    reset_metadata(&repo, base)?;
+    let ours = set_description(&repo, "refs/heads/feature", "ours")?;
+
+    let mut store = RefsMetadata::from_repo(repo.clone())?;
+    store.merge(theirs)?;
+    let merged_into_ours = store.branch("refs/heads/feature".try_into()?)?;
+
+    reset_metadata(&repo, theirs)?;
+    let mut store = RefsMetadata::from_repo(repo.clone())?;
+    store.merge(ours)?;
+    let merged_into_theirs = store.branch("refs/heads/feature".try_into()?)?;
+
+    assert_eq!(*merged_into_ours, *merged_into_theirs);
+    Ok(())
+}
+
+#[test]
+fn a_store_without_metadata_fast_forwards() -> anyhow::Result<()> {
+    let (repo, _tmp) = unborn_repo()?;
+    let theirs = set_description(&repo, "refs/heads/feature", "theirs")?;
+    repo.find_reference(METADATA_REF)?.delete()?;
+
+    let mut store = RefsMetadata::from_repo(repo.clone())?;
+    assert!(store.merge(theirs)?);
+    assert_eq!(metadata_tip(&repo)?, theirs, "no merge commit is needed");
+    assert!(!store.merge_remote("origin")?, "nothing was fetched yet");
+    Ok(())
+}
+
+#[test]
+fn import_from_virtual_branches_toml() -> anyhow::Result<()> {
+    let (repo, tmp) = unborn_repo()?;
+    let toml_path = tmp.path().join("vb.toml");
+    std::fs::copy("tests/fixtures/legacy/virtual-branches-01.toml", &toml_path)?;
+    let legacy = VirtualBranchesTomlMetadata::from_path(&toml_path)?;
+
+    let mut store = RefsMetadata::from_repo(repo.clone())?;
+    store.import(&legacy)?;
+    assert_eq!(store.iter().count(), legacy.iter().count());
+    for res in legacy.iter() {
+        let (ref_name, _) = res?;
+        if but_graph::is_workspace_ref_name(ref_name.as_ref()) {
+            assert_eq!(
+                *store.workspace(ref_name.as_ref())?,
+                *legacy.workspace(ref_name.as_ref())?
+            );
+        } else {
+            assert_eq!(
+                *store.branch(ref_name.as_ref())?,
+                *legacy.branch(ref_name.as_ref())?
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn importing_unchanged_metadata_creates_no_commit() -> anyhow::Result<()> {
+    let (repo, tmp) = unborn_repo()?;
+    let toml_path = tmp.path().join("vb.toml");
+    std::fs::copy("tests/fixtures/legacy/virtual-branches-01.toml", &toml_path)?;
+    let legacy = VirtualBranchesTomlMetadata::from_path(&toml_path)?;
+
+    let mut store = RefsMetadata::from_repo(repo.clone())?;
+    store.import(&legacy)?;
+    let tip = metadata_tip(&repo)?;
+    store.import(&legacy)?;
+    assert_eq!(
+        metadata_tip(&repo)?,
+        tip,
+        "nothing changed since the last import"
+    );
+    Ok(())
+}
+
+#[test]
+fn known_branches_are_updated_from_shared_metadata() -> anyhow::Result<()> {
+    let (repo, tmp) = unborn_repo()?;
+    let toml_path = tmp.path().join("vb.toml");
+    std::fs::copy("tests/fixtures/legacy/virtual-branches-01.toml", &toml_path)?;
+    let mut legacy = VirtualBranchesTomlMetadata::from_path(&toml_path)?;
+
+    set_description(&repo, "refs/heads/A", "shared description")?;
+    set_description(&repo, "refs/heads/unknown", "only known to others")?;
+    let store = RefsMetadata::from_repo(repo.clone())?;
+    assert_eq!(store.update_known_branches(&mut legacy)?, 1);
+    assert_eq!(
+        legacy
+            .branch("refs/heads/A".try_into()?)?
+            .description
+            .as_deref(),
+        Some("shared description")
+    );
+    assert!(
+        legacy
+            .branch("refs/heads/unknown".try_into()?)?
+            .is_default(),
+        "branches that don't exist locally aren't created"
+    );
+    assert_eq!(
+        store.update_known_branches(&mut legacy)?,
+        0,
+        "nothing changes the second time"
+    );
+    Ok(())
+}
+
+fn unborn_repo() -> anyhow::Result<(gix::Repository, TempDir)> {
+    let tmp = gix_testtools::scripted_fixture_writable("scenarios.sh")
+        .map_err(anyhow::Error::from_boxed)?;
+    let repo = but_testsupport::open_repo(&tmp.path().join("unborn"))?;
+    Ok((repo, tmp))
+}
+
+fn metadata_tip(repo: &gix::Repository) -> anyhow::Result<gix::ObjectId> {
+    Ok(repo
+        .find_reference(METADATA_REF)?
+        .peel_to_id_in_place()?
+        .detach())
+}
+
+/// Set the description of the branch `name` in a fresh store, and return the commit with the change.
+fn set_description(
+    repo: &gix::Repository,
+    name: &str,
+    description: &str,
+) -> anyhow::Result<gix::ObjectId> {
+    let mut store = RefsMetadata::from_repo(repo.clone())?;
+    let mut branch = store.branch(name.try_into()?)?;
+    branch.description = Some(description.into());
+    store.set_branch(&branch)?;
+    metadata_tip(repo)
+}
+
+fn reset_metadata(repo: &gix::Repository, tip: gix::ObjectId) -> anyhow::Result<()> {
+    repo.reference(METADATA_REF, tip, PreviousValue::Any, "reset")?;
+    Ok(())
+}
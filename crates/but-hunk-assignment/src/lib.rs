@@ -9,6 +9,7 @@
 //!
 //! set_assignments
 
+mod lines;
 mod reconcile;
 mod state;
 
@@ -42,16 +43,26 @@ pub struct HunkAssignment {
     pub path: String,
     /// The file path of the hunk in bytes.
     pub path_bytes: BString,
+    /// The previous location of the file in bytes, the source of a rename if there was one.
+    #[serde(default)]
+    pub previous_path_bytes: Option<BString>,
     /// The stack to which the hunk is assigned. If None, the hunk is not assigned to any stack.
     pub stack_id: Option<StackId>,
     /// The dependencies(locks) that this hunk has. This determines where the hunk can be assigned.
     /// This field is ignored when HunkAssignment is passed by the UI to create a new assignment.
     #[serde(skip)]
     pub hunk_locks: Option<Vec<HunkLock>>,
-    /// The line numbers that were added in this hunk.
+    /// The line numbers that were added in this hunk, or in the lines of it owned by this assignment.
     pub line_nums_added: Option<Vec<usize>>,
-    /// The line numbers that were removed in this hunk.
+    /// The line numbers that were removed in this hunk, or in the lines of it owned by this assignment.
     pub line_nums_removed: Option<Vec<usize>>,
+    /// If set, this assignment owns only a subset of the changed lines of `hunk_header`, while other assignments
+    /// with the same `hunk_header` own the rest.
+    /// Each entry is a sub-hunk selection as understood by the commit engine, i.e. `-5,2 +0,0` owns the removed lines
+    /// 5 and 6 of the old image, and `-0,0 +7,1` owns the added line 7 of the new image. They are kept in diff order.
+    /// If `None`, the whole hunk is owned by this assignment.
+    #[serde(default)]
+    pub line_ranges: Option<Vec<HunkHeader>>,
 }
 
 impl TryFrom<but_db::HunkAssignment> for HunkAssignment {
@@ -66,15 +77,20 @@ impl TryFrom<but_db::HunkAssignment> for HunkAssignment {
             .as_ref()
             .and_then(|id| uuid::Uuid::parse_str(id).ok())
             .map(StackId::from);
+        let line_ranges = Some(value.line_ranges.as_str())
+            .filter(|r| !r.is_empty())
+            .and_then(|r| serde_json::from_str(r).ok());
         Ok(HunkAssignment {
             id: value.id.map(|id| Uuid::parse_str(&id)).transpose()?,
             hunk_header: header,
             path: value.path,
             path_bytes: value.path_bytes.into(),
+            previous_path_bytes: value.previous_path_bytes.map(Into::into),
             stack_id,
             hunk_locks: None,
             line_nums_added: None,   // derived data (not persisted)
             line_nums_removed: None, // derived data (not persisted)
+            line_ranges,
         })
     }
 }
@@ -89,19 +105,32 @@ impl TryFrom<HunkAssignment> for but_db::HunkAssignment {
                     .map_err(|e| anyhow::anyhow!("Failed to serialize hunk_header: {}", e))
             })
             .transpose()?;
+        let line_ranges = value
+            .line_ranges
+            .map(|r| {
+                serde_json::to_string(&r)
+                    .map_err(|e| anyhow::anyhow!("Failed to serialize line_ranges: {}", e))
+            })
+            .transpose()?
+            .unwrap_or_default();
         Ok(but_db::HunkAssignment {
             id: value.id.map(|id| id.to_string()),
             hunk_header: header,
             path: value.path,
             path_bytes: value.path_bytes.into(),
             stack_id: value.stack_id.map(|id| id.to_string()),
+            previous_path_bytes: value.previous_path_bytes.map(Into::into),
+            line_ranges,
         })
     }
 }
 
 impl From<HunkAssignment> for but_workspace::DiffSpec {
     fn from(value: HunkAssignment) -> Self {
-        let hunk_headers = if let Some(header) = value.hunk_header {
+        let hunk_headers = if let Some(line_ranges) = value.line_ranges {
+            // Sub-hunk selections are anchored to the worktree hunk they are part of.
+            line_ranges
+        } else if let Some(header) = value.hunk_header {
             vec![but_workspace::HunkHeader {
                 old_start: header.old_start,
                 old_lines: header.old_lines,
@@ -112,8 +141,8 @@ impl From<HunkAssignment> for but_workspace::DiffSpec {
             vec![]
         };
         but_workspace::DiffSpec {
-            previous_path: None, // TODO
-            path: value.path_bytes.clone(),
+            previous_path: value.previous_path_bytes,
+            path: value.path_bytes,
             hunk_headers,
         }
     }
@@ -143,6 +172,10 @@ pub struct HunkAssignmentRequest {
     /// The stack to which the hunk is assigned. If set to None, the hunk is set as "unassigned".
     /// If a stack id is set, it must be one of the applied stacks.
     pub stack_id: Option<StackId>,
+    /// If set, only the given lines of the hunk are assigned, using the sub-hunk selection format
+    /// of [`HunkAssignment::line_ranges`]. If `None`, the whole hunk is assigned.
+    #[serde(default)]
+    pub line_ranges: Option<Vec<HunkHeader>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -178,13 +211,25 @@ impl From<but_core::WorktreeChanges> for WorktreeChanges {
 
 impl HunkAssignmentRequest {
     pub fn matches_assignment(&self, assignment: &HunkAssignment) -> bool {
-        self.path_bytes == assignment.path_bytes && self.hunk_header == assignment.hunk_header
+        self.path_bytes == assignment.path_bytes
+            && self.hunk_header == assignment.hunk_header
+            && match (&self.line_ranges, &assignment.line_ranges) {
+                (Some(requested), Some(assigned)) => {
+                    let assigned = lines::expand(assigned);
+                    lines::expand(requested)
+                        .iter()
+                        .any(|line| assigned.contains(line))
+                }
+                _ => true,
+            }
     }
 }
 
 impl PartialEq for HunkAssignment {
     fn eq(&self, other: &Self) -> bool {
-        self.hunk_header == other.hunk_header && self.path_bytes == other.path_bytes
+        self.hunk_header == other.hunk_header
+            && self.path_bytes == other.path_bytes
+            && self.line_ranges == other.line_ranges
     }
 }
 
@@ -248,6 +293,7 @@ pub fn assign(
         worktree_assignments.extend(diff_to_assignments(
             diff.ok().flatten(),
             change.path.clone(),
+            change.previous_path().map(ToOwned::to_owned),
         ));
     }
 
@@ -338,6 +384,7 @@ pub fn assignments_with_fallback(
         worktree_assignments.extend(diff_to_assignments(
             diff.ok().flatten(),
             change.path.clone(),
+            change.previous_path().map(ToOwned::to_owned),
         ));
     }
     let reconciled = reconcile_with_worktree_and_locks(
@@ -426,10 +473,12 @@ fn hunk_dependency_assignments(deps: &HunkDependencies) -> Result<Vec<HunkAssign
             hunk_header: Some(hunk.into()),
             path: path.clone(),
             path_bytes: path.clone().into(),
+            previous_path_bytes: None,
             stack_id,
             hunk_locks: Some(locks.clone()),
             line_nums_added: None,   // derived data (not persisted)
             line_nums_removed: None, // derived data (not persisted)
+            line_ranges: None,
         };
         assignments.push(assignment);
     }
@@ -437,7 +486,11 @@ fn hunk_dependency_assignments(deps: &HunkDependencies) -> Result<Vec<HunkAssign
 }
 
 /// This also generates a UUID for the assignment
-fn diff_to_assignments(diff: Option<UnifiedDiff>, path: BString) -> Vec<HunkAssignment> {
+fn diff_to_assignments(
+    diff: Option<UnifiedDiff>,
+    path: BString,
+    previous_path_bytes: Option<BString>,
+) -> Vec<HunkAssignment> {
     let path_str = path.to_str_lossy();
     if let Some(diff) = diff {
         match diff {
@@ -446,21 +499,29 @@ fn diff_to_assignments(diff: Option<UnifiedDiff>, path: BString) -> Vec<HunkAssi
                 hunk_header: None,
                 path: path_str.into(),
                 path_bytes: path,
+                previous_path_bytes,
                 stack_id: None,
                 hunk_locks: None,
                 line_nums_added: None,
                 line_nums_removed: None,
+                line_ranges: None,
             }],
-            but_core::UnifiedDiff::TooLarge { .. } => vec![HunkAssignment {
-                id: Some(Uuid::new_v4()),
-                hunk_header: None,
-                path: path_str.into(),
-                path_bytes: path,
-                stack_id: None,
-                hunk_locks: None,
-                line_nums_added: None,
-                line_nums_removed: None,
-            }],
+            but_core::UnifiedDiff::TooLarge { .. }
+            | but_core::UnifiedDiff::Lfs { .. }
+            | but_core::UnifiedDiff::Submodule { .. } => {
+                vec![HunkAssignment {
+                    id: Some(Uuid::new_v4()),
+                    hunk_header: None,
+                    path: path_str.into(),
+                    path_bytes: path,
+                    previous_path_bytes,
+                    stack_id: None,
+                    hunk_locks: None,
+                    line_nums_added: None,
+                    line_nums_removed: None,
+                    line_ranges: None,
+                }]
+            }
             but_core::UnifiedDiff::Patch {
                 hunks,
                 is_result_of_binary_to_text_conversion,
@@ -473,10 +534,12 @@ fn diff_to_assignments(diff: Option<UnifiedDiff>, path: BString) -> Vec<HunkAssi
                         hunk_header: None,
                         path: path_str.into(),
                         path_bytes: path,
+                        previous_path_bytes,
                         stack_id: None,
                         hunk_locks: None,
                         line_nums_added: None,
                         line_nums_removed: None,
+                        line_ranges: None,
                     }]
                 } else {
                     hunks
@@ -489,10 +552,12 @@ fn diff_to_assignments(diff: Option<UnifiedDiff>, path: BString) -> Vec<HunkAssi
                                 hunk_header: Some(hunk.into()),
                                 path: path_str.clone().into(),
                                 path_bytes: path.clone(),
+                                previous_path_bytes: previous_path_bytes.clone(),
                                 stack_id: None,
                                 hunk_locks: None,
                                 line_nums_added: Some(line_nums_added_new),
                                 line_nums_removed: Some(line_nums_removed_old),
+                                line_ranges: None,
                             }
                         })
                         .collect()
@@ -505,10 +570,12 @@ fn diff_to_assignments(diff: Option<UnifiedDiff>, path: BString) -> Vec<HunkAssi
             hunk_header: None,
             path: path_str.into(),
             path_bytes: path.clone(),
+            previous_path_bytes,
             stack_id: None,
             hunk_locks: None,
             line_nums_added: None,
             line_nums_removed: None,
+            line_ranges: None,
         }]
     }
 }
@@ -564,10 +631,12 @@ fn requests_to_assignments(request: Vec<HunkAssignmentRequest>) -> Vec<HunkAssig
             hunk_header: req.hunk_header,
             path: req.path_bytes.to_str_lossy().into(),
             path_bytes: req.path_bytes,
+            previous_path_bytes: None,
             stack_id: req.stack_id,
             hunk_locks: None,
             line_nums_added: None,
             line_nums_removed: None,
+            line_ranges: req.line_ranges,
         };
         assignments.push(assignment);
     }
@@ -581,6 +650,7 @@ pub fn assignments_to_requests(assignments: Vec<HunkAssignment>) -> Vec<HunkAssi
             hunk_header: assignment.hunk_header,
             path_bytes: assignment.path_bytes,
             stack_id: assignment.stack_id,
+            line_ranges: assignment.line_ranges,
         })
         .collect()
 }
@@ -611,12 +681,36 @@ mod tests {
                 }),
                 path: path.to_string(),
                 path_bytes: BString::from(path),
+                previous_path_bytes: None,
                 stack_id: stack_id.map(stack_id_seq),
                 hunk_locks: None,
                 line_nums_added: None,
                 line_nums_removed: None,
+                line_ranges: None,
             }
         }
+
+        pub fn with_line_nums(mut self, added: &[usize], removed: &[usize]) -> HunkAssignment {
+            self.line_nums_added = Some(added.to_vec());
+            self.line_nums_removed = Some(removed.to_vec());
+            self
+        }
+
+        /// `ranges` are `(old_start, old_lines, new_start, new_lines)` tuples.
+        pub fn with_line_ranges(mut self, ranges: &[(u32, u32, u32, u32)]) -> HunkAssignment {
+            self.line_ranges = Some(
+                ranges
+                    .iter()
+                    .map(|&(old_start, old_lines, new_start, new_lines)| HunkHeader {
+                        old_start,
+                        old_lines,
+                        new_start,
+                        new_lines,
+                    })
+                    .collect(),
+            );
+            self
+        }
     }
 
     impl HunkAssignmentRequest {
@@ -635,6 +729,7 @@ mod tests {
                 }),
                 path_bytes: BString::from(path),
                 stack_id: stack_id.map(stack_id_seq),
+                line_ranges: None,
             }
         }
     }
@@ -808,6 +903,214 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_reconcile_partial_assignments_split_the_worktree_hunk() {
+        // Lines 3 and 6 were changed, and each change was assigned to a different stack.
+        let previous_assignments = vec![
+            HunkAssignment::new("foo.rs", 1, 7, Some(1), Some(1))
+                .with_line_ranges(&[(3, 1, 0, 0), (0, 0, 3, 1)]),
+            HunkAssignment::new("foo.rs", 1, 7, Some(2), Some(2))
+                .with_line_ranges(&[(6, 1, 0, 0), (0, 0, 6, 1)]),
+        ];
+        let worktree_assignments = vec![
+            HunkAssignment::new("foo.rs", 1, 7, None, Some(3)).with_line_nums(&[3, 6], &[3, 6]),
+        ];
+        let applied_stacks = vec![stack_id_seq(1), stack_id_seq(2)];
+        let result = reconcile::assignments(
+            &worktree_assignments,
+            &previous_assignments,
+            &applied_stacks,
+            MultipleOverlapping::SetMostLines,
+            true,
+        )
+        .unwrap();
+        assert_eq(result.clone(), previous_assignments);
+        assert_eq!(result[0].line_nums_added, Some(vec![3]));
+        assert_eq!(result[1].line_nums_removed, Some(vec![6]));
+    }
+
+    #[test]
+    fn test_reconcile_partial_assignments_follow_shifted_lines() {
+        let previous_assignments = vec![
+            HunkAssignment::new("foo.rs", 1, 7, Some(1), Some(1))
+                .with_line_ranges(&[(3, 1, 0, 0), (0, 0, 3, 1)]),
+            HunkAssignment::new("foo.rs", 1, 7, Some(2), Some(2))
+                .with_line_ranges(&[(6, 1, 0, 0), (0, 0, 6, 1)]),
+        ];
+        // Two lines were added above the hunk, shifting it in the new image.
+        let mut shifted = HunkAssignment::new("foo.rs", 1, 7, None, Some(3));
+        shifted.hunk_header = Some(HunkHeader {
+            old_start: 1,
+            old_lines: 7,
+            new_start: 3,
+            new_lines: 7,
+        });
+        let worktree_assignments = vec![shifted.clone().with_line_nums(&[5, 8], &[3, 6])];
+        let applied_stacks = vec![stack_id_seq(1), stack_id_seq(2)];
+        let result = reconcile::assignments(
+            &worktree_assignments,
+            &previous_assignments,
+            &applied_stacks,
+            MultipleOverlapping::SetMostLines,
+            true,
+        )
+        .unwrap();
+        let mut expected_first = shifted
+            .clone()
+            .with_line_ranges(&[(3, 1, 0, 0), (0, 0, 5, 1)]);
+        expected_first.stack_id = Some(stack_id_seq(1));
+        expected_first.id = Some(id_seq(1));
+        let mut expected_second = shifted.with_line_ranges(&[(6, 1, 0, 0), (0, 0, 8, 1)]);
+        expected_second.stack_id = Some(stack_id_seq(2));
+        expected_second.id = Some(id_seq(2));
+        assert_eq(result, vec![expected_first, expected_second]);
+    }
+
+    #[test]
+    fn test_reconcile_partial_assignments_to_the_same_stack_become_whole() {
+        let previous_assignments = vec![
+            HunkAssignment::new("foo.rs", 1, 7, Some(1), Some(1))
+                .with_line_ranges(&[(3, 1, 0, 0), (0, 0, 3, 1)]),
+            HunkAssignment::new("foo.rs", 1, 7, None, Some(2))
+                .with_line_ranges(&[(6, 1, 0, 0), (0, 0, 6, 1)]),
+        ];
+        let requests = vec![
+            HunkAssignment::new("foo.rs", 1, 7, Some(1), None)
+                .with_line_ranges(&[(6, 1, 0, 0), (0, 0, 6, 1)]),
+        ];
+        let worktree_assignments = vec![
+            HunkAssignment::new("foo.rs", 1, 7, None, Some(3)).with_line_nums(&[3, 6], &[3, 6]),
+        ];
+        let applied_stacks = vec![stack_id_seq(1)];
+        let with_worktree = reconcile::assignments(
+            &worktree_assignments,
+            &previous_assignments,
+            &applied_stacks,
+            MultipleOverlapping::SetMostLines,
+            true,
+        )
+        .unwrap();
+        assert_eq!(with_worktree.len(), 2, "the hunk is still split");
+        let result = reconcile::assignments(
+            &with_worktree,
+            &requests,
+            &applied_stacks,
+            MultipleOverlapping::SetMostLines,
+            true,
+        )
+        .unwrap();
+        assert_eq(
+            result.clone(),
+            vec![HunkAssignment::new("foo.rs", 1, 7, Some(1), Some(1))],
+        );
+        assert_eq!(result[0].line_nums_added, Some(vec![3, 6]));
+        assert_eq!(result[0].line_nums_removed, Some(vec![3, 6]));
+    }
+
+    #[test]
+    fn test_reconcile_partial_request_splits_whole_assignment() {
+        let previous_assignments = vec![
+            HunkAssignment::new("foo.rs", 1, 7, Some(1), Some(1)).with_line_nums(&[3, 6], &[3, 6]),
+        ];
+        let requests = vec![
+            HunkAssignment::new("foo.rs", 1, 7, Some(2), None).with_line_ranges(&[(0, 0, 6, 1)]),
+        ];
+        let applied_stacks = vec![stack_id_seq(1), stack_id_seq(2)];
+        let result = reconcile::assignments(
+            &previous_assignments,
+            &requests,
+            &applied_stacks,
+            MultipleOverlapping::SetMostLines,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            vec![
+                HunkAssignment::new("foo.rs", 1, 7, Some(1), None).with_line_ranges(&[
+                    (3, 1, 0, 0),
+                    (0, 0, 3, 1),
+                    (6, 1, 0, 0)
+                ]),
+                HunkAssignment::new("foo.rs", 1, 7, Some(2), None)
+                    .with_line_ranges(&[(0, 0, 6, 1)]),
+            ]
+        );
+        assert_eq!(result[0].stack_id, Some(stack_id_seq(1)));
+        assert_eq!(
+            result[0].id,
+            Some(id_seq(1)),
+            "the first piece keeps the id"
+        );
+        assert_eq!(result[1].stack_id, Some(stack_id_seq(2)));
+        assert_ne!(result[1].id, result[0].id, "pieces have their own id");
+    }
+
+    #[test]
+    fn test_partial_assignment_to_diff_spec() {
+        let spec: but_workspace::DiffSpec = HunkAssignment::new("foo.rs", 1, 7, Some(1), None)
+            .with_line_ranges(&[(6, 1, 0, 0), (0, 0, 6, 1)])
+            .into();
+        assert_eq!(
+            spec.hunk_headers,
+            vec![
+                HunkHeader {
+                    old_start: 6,
+                    old_lines: 1,
+                    new_start: 0,
+                    new_lines: 0,
+                },
+                HunkHeader {
+                    old_start: 0,
+                    old_lines: 0,
+                    new_start: 6,
+                    new_lines: 1,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_partial_request_matches_overlapping_lines_only() {
+        let assignment = HunkAssignment::new("foo.rs", 1, 7, Some(1), None)
+            .with_line_ranges(&[(3, 1, 0, 0), (0, 0, 3, 1)]);
+        let mut request = HunkAssignmentRequest::new("foo.rs", 1, 7, Some(2));
+        assert!(request.matches_assignment(&assignment));
+        request.line_ranges = assignment.line_ranges.clone();
+        assert!(request.matches_assignment(&assignment));
+        request.line_ranges = Some(vec![HunkHeader {
+            old_start: 0,
+            old_lines: 0,
+            new_start: 6,
+            new_lines: 1,
+        }]);
+        assert!(!request.matches_assignment(&assignment));
+    }
+
+    #[test]
+    fn test_renamed_assignment_to_diff_spec() {
+        let mut assignment = HunkAssignment::new("new.rs", 1, 7, Some(1), None);
+        assignment.previous_path_bytes = Some("old.rs".into());
+        let spec: but_workspace::DiffSpec = assignment.into();
+        assert_eq!(spec.path, "new.rs");
+        assert_eq!(
+            spec.previous_path,
+            Some("old.rs".into()),
+            "the rename source is needed to also remove the file at its previous location"
+        );
+    }
+
+    #[test]
+    fn test_diff_to_assignments_keeps_rename_source() {
+        let assignments = diff_to_assignments(
+            Some(UnifiedDiff::Binary),
+            "new.rs".into(),
+            Some("old.rs".into()),
+        );
+        assert_eq!(assignments.len(), 1);
+        assert_eq!(assignments[0].previous_path_bytes, Some("old.rs".into()));
+    }
+
     #[test]
     fn test_hunk_assignment_partial_eq() {
         let hunk1 = HunkAssignment::new("foo.rs", 10, 15, Some(1), Some(3));
@@ -0,0 +1,202 @@
+//! Utilities to deal with assignments that only own some of the lines of a hunk.
+//!
+//! Such line-level ownership is expressed with sub-hunk selections, the same format the commit engine
+//! uses, so they can be passed on as [`DiffSpec`](but_workspace::DiffSpec) hunks without conversion.
+use std::collections::HashSet;
+
+use but_workspace::HunkHeader;
+
+/// A single changed line of a hunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Line {
+    /// The line with the given 1-based number in the old image was removed.
+    Removed(u32),
+    /// The line with the given 1-based number in the new image was added.
+    Added(u32),
+}
+
+impl Line {
+    /// Return this line as it would appear in a hunk that shifted its new image by `delta` lines.
+    fn shifted(self, delta: i64) -> Option<Line> {
+        match self {
+            Line::Removed(_) => Some(self),
+            Line::Added(line) => u32::try_from(line as i64 + delta).ok().map(Line::Added),
+        }
+    }
+}
+
+/// Expand the sub-hunk `selections` into the individual lines they select, in order.
+/// Selections which aren't sub-hunk selections, i.e. whole hunks, are ignored.
+pub(crate) fn expand(selections: &[HunkHeader]) -> Vec<Line> {
+    let mut out = Vec::new();
+    for selection in selections {
+        if selection.new_range().is_null() {
+            out.extend((selection.old_start..selection.old_range().end()).map(Line::Removed));
+        } else if selection.old_range().is_null() {
+            out.extend((selection.new_start..selection.new_range().end()).map(Line::Added));
+        }
+    }
+    out
+}
+
+/// Turn the ordered `lines` into sub-hunk selections, merging consecutive lines of the same kind.
+pub(crate) fn to_selections(lines: &[Line]) -> Vec<HunkHeader> {
+    let mut out: Vec<HunkHeader> = Vec::new();
+    for line in lines {
+        match (*line, out.last_mut()) {
+            (Line::Removed(num), Some(last))
+                if last.new_range().is_null() && last.old_range().end() == num =>
+            {
+                last.old_lines += 1;
+            }
+            (Line::Added(num), Some(last))
+                if last.old_range().is_null() && last.new_range().end() == num =>
+            {
+                last.new_lines += 1;
+            }
+            (Line::Removed(num), _) => out.push(HunkHeader {
+                old_start: num,
+                old_lines: 1,
+                new_start: 0,
+                new_lines: 0,
+            }),
+            (Line::Added(num), _) => out.push(HunkHeader {
+                old_start: 0,
+                old_lines: 0,
+                new_start: num,
+                new_lines: 1,
+            }),
+        }
+    }
+    out
+}
+
+/// Return all changed lines of the hunk with `header`, in the order they appear in its diff, given the
+/// line numbers that were `added` in the new image, and `removed` from the old image.
+///
+/// Just like in a unified diff, removed lines are placed before added lines in each block of changes.
+pub(crate) fn in_diff_order(header: HunkHeader, added: &[usize], removed: &[usize]) -> Vec<Line> {
+    let added: HashSet<_> = added.iter().copied().collect();
+    let removed: HashSet<_> = removed.iter().copied().collect();
+    let (mut old, old_end) = (header.old_start, header.old_range().end());
+    let (mut new, new_end) = (header.new_start, header.new_range().end());
+    let mut out = Vec::with_capacity(added.len() + removed.len());
+    while old < old_end || new < new_end {
+        if old < old_end && removed.contains(&(old as usize)) {
+            out.push(Line::Removed(old));
+            old += 1;
+        } else if new < new_end && added.contains(&(new as usize)) {
+            out.push(Line::Added(new));
+            new += 1;
+        } else {
+            old += 1;
+            new += 1;
+        }
+    }
+    out
+}
+
+/// Return the amount of lines the new image of `new` is shifted by compared to the one of `old`, assuming
+/// both represent the same hunk at different points in time.
+///
+/// As both share the same old image, edits above the hunk only shift the new image.
+pub(crate) fn new_image_delta(old: HunkHeader, new: HunkHeader) -> i64 {
+    (new.new_start as i64 - new.old_start as i64) - (old.new_start as i64 - old.old_start as i64)
+}
+
+/// Map the `lines` of a hunk that was previously recorded as `old_header` to the lines of `new_header`.
+pub(crate) fn translate(
+    lines: &[Line],
+    old_header: HunkHeader,
+    new_header: HunkHeader,
+) -> Vec<Line> {
+    let delta = new_image_delta(old_header, new_header);
+    lines
+        .iter()
+        .filter_map(|line| line.shifted(delta))
+        .collect()
+}
+
+/// Return the added and removed line numbers of `lines`, in this order, in the form used by
+/// [`HunkAssignment`](crate::HunkAssignment).
+pub(crate) fn to_line_nums(lines: &[Line]) -> (Vec<usize>, Vec<usize>) {
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for line in lines {
+        match line {
+            Line::Added(num) => added.push(*num as usize),
+            Line::Removed(num) => removed.push(*num as usize),
+        }
+    }
+    (added, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(old_start: u32, old_lines: u32, new_start: u32, new_lines: u32) -> HunkHeader {
+        HunkHeader {
+            old_start,
+            old_lines,
+            new_start,
+            new_lines,
+        }
+    }
+
+    #[test]
+    fn selections_roundtrip() {
+        let lines = vec![
+            Line::Removed(5),
+            Line::Removed(6),
+            Line::Added(5),
+            Line::Removed(9),
+            Line::Added(8),
+            Line::Added(9),
+        ];
+        let selections = to_selections(&lines);
+        assert_eq!(
+            selections,
+            vec![
+                header(5, 2, 0, 0),
+                header(0, 0, 5, 1),
+                header(9, 1, 0, 0),
+                header(0, 0, 8, 2),
+            ]
+        );
+        assert_eq!(expand(&selections), lines);
+    }
+
+    #[test]
+    fn diff_order_puts_removals_before_additions_of_each_block() {
+        // Lines 3 and 6 were changed.
+        let lines = in_diff_order(header(1, 7, 1, 7), &[3, 6], &[3, 6]);
+        assert_eq!(
+            lines,
+            vec![
+                Line::Removed(3),
+                Line::Added(3),
+                Line::Removed(6),
+                Line::Added(6)
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_order_with_unbalanced_blocks() {
+        // Line 2 was removed, and two lines were added after old line 3.
+        let lines = in_diff_order(header(1, 5, 1, 6), &[3, 4], &[2]);
+        assert_eq!(
+            lines,
+            vec![Line::Removed(2), Line::Added(3), Line::Added(4)]
+        );
+    }
+
+    #[test]
+    fn translate_shifts_only_added_lines() {
+        let lines = vec![Line::Removed(10), Line::Added(10)];
+        // Two lines were added above the hunk.
+        let translated = translate(&lines, header(8, 5, 8, 5), header(8, 5, 10, 5));
+        assert_eq!(translated, vec![Line::Removed(10), Line::Added(12)]);
+    }
+}
@@ -1,10 +1,13 @@
 use std::cmp::Ordering;
+use std::collections::HashSet;
 
 use anyhow::Result;
 use but_workspace::StackId;
 use itertools::Itertools;
+use uuid::Uuid;
 
 use crate::HunkAssignment;
+use crate::lines::{self, Line};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MultipleOverlapping {
@@ -22,13 +25,16 @@ impl HunkAssignment {
         if other.id.is_some() {
             self.id = other.id;
         }
-        // Override the lines added only if the other assignment has them set
-        if other.line_nums_added.is_some() {
-            self.line_nums_added = other.line_nums_added.clone();
-        }
-        // Override the lines removed only if the other assignment has them set
-        if other.line_nums_removed.is_some() {
-            self.line_nums_removed = other.line_nums_removed.clone();
+        // The line numbers of partial assignments are the lines they own, which isn't for the other assignment to decide.
+        if self.line_ranges.is_none() {
+            // Override the lines added only if the other assignment has them set
+            if other.line_nums_added.is_some() {
+                self.line_nums_added = other.line_nums_added.clone();
+            }
+            // Override the lines removed only if the other assignment has them set
+            if other.line_nums_removed.is_some() {
+                self.line_nums_removed = other.line_nums_removed.clone();
+            }
         }
 
         // Override the stack_id only if the current assignment has a stack_id or if update_unassigned is true
@@ -49,6 +55,28 @@ impl HunkAssignment {
             }
         }
     }
+
+    /// Return the changed lines owned by this assignment in diff order, or `None` if they aren't known.
+    fn owned_lines(&self) -> Option<Vec<Line>> {
+        match &self.line_ranges {
+            Some(line_ranges) => Some(lines::expand(line_ranges)),
+            None => Some(lines::in_diff_order(
+                self.hunk_header?,
+                self.line_nums_added.as_ref()?,
+                self.line_nums_removed.as_ref()?,
+            )),
+        }
+    }
+
+    /// Return a copy of this assignment which only owns the given `lines`.
+    fn with_lines(&self, lines: &[Line]) -> Self {
+        let mut out = self.clone();
+        let (added, removed) = lines::to_line_nums(lines);
+        out.line_nums_added = Some(added);
+        out.line_nums_removed = Some(removed);
+        out.line_ranges = Some(lines::to_selections(lines));
+        out
+    }
 }
 
 pub(crate) fn assignments(
@@ -60,38 +88,204 @@ pub(crate) fn assignments(
 ) -> Result<Vec<HunkAssignment>> {
     let mut reconciled = vec![];
     for new_assignment in new {
-        let mut new_assignment = new_assignment.clone();
         let intersecting = old
             .iter()
             .filter(|current_entry| current_entry.intersects(new_assignment.clone()))
             .collect::<Vec<_>>();
 
-        match intersecting.len().cmp(&1) {
-            Ordering::Less => {
-                // No intersection - do nothing, the None assignment is kept
+        let (partial, whole): (Vec<&HunkAssignment>, Vec<&HunkAssignment>) = intersecting
+            .iter()
+            .copied()
+            .partition(|other| other.line_ranges.is_some());
+        // Without knowing which lines we own, partial assignments can only be treated as if they owned the whole hunk.
+        let (Some(owned_lines), Some(header)) = (
+            new_assignment.owned_lines().filter(|_| !partial.is_empty()),
+            new_assignment.hunk_header,
+        ) else {
+            reconciled.push(reconcile_with(
+                new_assignment.clone(),
+                &intersecting,
+                applied_stack_ids,
+                multiple_overlapping_resolution,
+                update_unassigned,
+            ));
+            continue;
+        };
+
+        // Partial assignments are more specific, so they claim their lines first, and the remaining lines
+        // are reconciled with the whole-hunk assignments.
+        let mut pieces = vec![];
+        let mut remaining = owned_lines.clone();
+        for other in partial {
+            let (Some(other_header), Some(other_ranges)) = (other.hunk_header, &other.line_ranges)
+            else {
+                continue;
+            };
+            let claimed: HashSet<Line> =
+                lines::translate(&lines::expand(other_ranges), other_header, header)
+                    .into_iter()
+                    .collect();
+            let (taken, rest): (Vec<_>, Vec<_>) = remaining
+                .into_iter()
+                .partition(|line| claimed.contains(line));
+            remaining = rest;
+            if taken.is_empty() {
+                continue;
             }
-            Ordering::Equal => {
-                new_assignment.set_from(intersecting[0], applied_stack_ids, update_unassigned);
+            let mut piece = new_assignment.with_lines(&taken);
+            piece.set_from(other, applied_stack_ids, update_unassigned);
+            pieces.push(piece);
+        }
+        if !remaining.is_empty() {
+            let rest = if remaining.len() == owned_lines.len() {
+                new_assignment.clone()
+            } else {
+                new_assignment.with_lines(&remaining)
+            };
+            pieces.push(reconcile_with(
+                rest,
+                &whole,
+                applied_stack_ids,
+                multiple_overlapping_resolution,
+                update_unassigned,
+            ));
+        }
+
+        // Keep the pieces in diff order, and make sure pieces of the same hunk remain distinguishable.
+        pieces.sort_by_key(|piece| {
+            piece
+                .owned_lines()
+                .and_then(|lines| lines.first().copied())
+                .and_then(|first| owned_lines.iter().position(|line| *line == first))
+        });
+        let mut seen_ids = HashSet::new();
+        for piece in &mut pieces {
+            if piece.id.is_some_and(|id| !seen_ids.insert(id)) {
+                piece.id = Some(Uuid::new_v4());
             }
-            Ordering::Greater => {
-                // Pick the hunk with the most lines to adopt the assignment info from.
-                let biggest_hunk = intersecting
-                    .iter()
-                    .max_by_key(|h| h.hunk_header.as_ref().map(|h| h.new_lines));
-                if let Some(other) = biggest_hunk {
-                    new_assignment.set_from(other, applied_stack_ids, update_unassigned);
-                }
+        }
+        reconciled.extend(pieces);
+    }
+    Ok(merge_partial_assignments(reconciled))
+}
 
-                // If requested, reset stack_id to none on multiple overlapping
-                let unique_stack_ids = intersecting.iter().filter_map(|a| a.stack_id).unique();
-                if multiple_overlapping_resolution == MultipleOverlapping::SetNone
-                    && unique_stack_ids.count() > 1
-                {
-                    new_assignment.stack_id = None;
-                }
+/// Reconcile `new_assignment` with all `intersecting` assignments as a whole, regardless of the lines they own.
+fn reconcile_with(
+    mut new_assignment: HunkAssignment,
+    intersecting: &[&HunkAssignment],
+    applied_stack_ids: &[StackId],
+    multiple_overlapping_resolution: MultipleOverlapping,
+    update_unassigned: bool,
+) -> HunkAssignment {
+    match intersecting.len().cmp(&1) {
+        Ordering::Less => {
+            // No intersection - do nothing, the None assignment is kept
+        }
+        Ordering::Equal => {
+            new_assignment.set_from(intersecting[0], applied_stack_ids, update_unassigned);
+        }
+        Ordering::Greater => {
+            // Pick the hunk with the most lines to adopt the assignment info from.
+            let biggest_hunk = intersecting
+                .iter()
+                .max_by_key(|h| h.hunk_header.as_ref().map(|h| h.new_lines));
+            if let Some(other) = biggest_hunk {
+                new_assignment.set_from(other, applied_stack_ids, update_unassigned);
             }
+
+            // If requested, reset stack_id to none on multiple overlapping
+            let unique_stack_ids = intersecting.iter().filter_map(|a| a.stack_id).unique();
+            if multiple_overlapping_resolution == MultipleOverlapping::SetNone
+                && unique_stack_ids.count() > 1
+            {
+                new_assignment.stack_id = None;
+            }
+        }
+    }
+    new_assignment
+}
+
+/// Merge partial assignments of the same hunk that are assigned to the same stack, and turn them back
+/// into a single whole-hunk assignment if all of them are.
+fn merge_partial_assignments(assignments: Vec<HunkAssignment>) -> Vec<HunkAssignment> {
+    let mut slots: Vec<Vec<HunkAssignment>> = Vec::with_capacity(assignments.len());
+    for assignment in assignments {
+        let slot = assignment
+            .line_ranges
+            .is_some()
+            .then(|| {
+                slots.iter().position(|slot| {
+                    slot[0].line_ranges.is_some()
+                        && slot[0].path_bytes == assignment.path_bytes
+                        && slot[0].hunk_header == assignment.hunk_header
+                })
+            })
+            .flatten();
+        match slot {
+            Some(idx) => slots[idx].push(assignment),
+            None => slots.push(vec![assignment]),
         }
-        reconciled.push(new_assignment);
     }
-    Ok(reconciled)
+    slots
+        .into_iter()
+        .flat_map(|slot| {
+            if slot[0].line_ranges.is_some() {
+                merge_pieces(slot)
+            } else {
+                slot
+            }
+        })
+        .collect()
+}
+
+/// Merge `pieces`, all partial assignments of the same hunk, by stack.
+fn merge_pieces(pieces: Vec<HunkAssignment>) -> Vec<HunkAssignment> {
+    let Some(header) = pieces[0].hunk_header else {
+        return pieces;
+    };
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for piece in &pieces {
+        let (Some(piece_added), Some(piece_removed)) =
+            (&piece.line_nums_added, &piece.line_nums_removed)
+        else {
+            return pieces;
+        };
+        added.extend_from_slice(piece_added);
+        removed.extend_from_slice(piece_removed);
+    }
+    let all_lines = lines::in_diff_order(header, &added, &removed);
+    if all_lines.len() != added.len() + removed.len() {
+        // The pieces don't add up to the hunk anymore, leave them as they are.
+        return pieces;
+    }
+
+    let stack_ids = pieces.iter().map(|p| p.stack_id).unique().collect_vec();
+    if stack_ids.len() == 1 {
+        let mut whole = pieces[0].clone();
+        whole.line_ranges = None;
+        whole.line_nums_added = Some(added);
+        whole.line_nums_removed = Some(removed);
+        return vec![whole];
+    }
+    stack_ids
+        .into_iter()
+        .map(|stack_id| {
+            let members = pieces
+                .iter()
+                .filter(|p| p.stack_id == stack_id)
+                .collect_vec();
+            let owned: HashSet<Line> = members
+                .iter()
+                .filter_map(|p| p.line_ranges.as_deref())
+                .flat_map(lines::expand)
+                .collect();
+            let lines = all_lines
+                .iter()
+                .copied()
+                .filter(|line| owned.contains(line))
+                .collect_vec();
+            members[0].with_lines(&lines)
+        })
+        .collect()
 }
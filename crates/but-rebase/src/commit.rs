@@ -1,6 +1,9 @@
 use anyhow::{Context, anyhow, bail};
 use bstr::{BStr, BString, ByteSlice};
 use but_core::cmd::prepare_with_shell_on_windows;
+use but_core::commit::{
+    HeadersV2, gerrit_change_id, gerrit_change_id_for, with_gerrit_change_id_trailer,
+};
 use but_core::{GitConfigSettings, RepositoryExt};
 use gitbutler_error::error::Code;
 use gix::objs::WriteTo;
@@ -25,6 +28,9 @@ pub enum DateMode {
 ///
 /// Signatures will be removed automatically if signing is disabled to prevent an amended commit
 /// to use the old signature.
+///
+/// If `gitbutler.gerritMode` is enabled, a Gerrit `Change-Id` trailer derived from the change-id of the
+/// commit is added to its message unless it already has one.
 #[allow(clippy::too_many_arguments)]
 pub fn create(
     repo: &gix::Repository,
@@ -41,13 +47,17 @@ pub fn create(
             update_author_time(repo, &mut commit)?;
         }
     }
+    let settings = repo.git_settings()?;
+    if settings.gitbutler_gerrit_mode.unwrap_or(false) {
+        add_gerrit_change_id_trailer(&mut commit);
+    }
     if let Some(pos) = commit
         .extra_headers()
         .find_pos(gix::objs::commit::SIGNATURE_FIELD_NAME)
     {
         commit.extra_headers.remove(pos);
     }
-    if repo.git_settings()?.gitbutler_sign_commits.unwrap_or(false) {
+    if settings.gitbutler_sign_commits.unwrap_or(false) {
         let mut buf = Vec::new();
         commit.write_to(&mut buf)?;
         match sign_buffer(repo, &buf) {
@@ -111,6 +121,28 @@ pub(crate) fn update_author_time(
     Ok(())
 }
 
+/// Replace the message of `commit` with `message`, but keep the Gerrit `Change-Id` trailer of its current
+/// message if `message` doesn't have one, so the commit keeps its identity when it's reworded.
+pub fn set_message(commit: &mut gix::objs::Commit, message: BString) {
+    let previous_change_id = gerrit_change_id(commit.message.as_ref()).map(ToOwned::to_owned);
+    commit.message = previous_change_id
+        .and_then(|change_id| with_gerrit_change_id_trailer(message.as_ref(), &change_id))
+        .unwrap_or(message);
+}
+
+/// Add a Gerrit `Change-Id` trailer for the change-id of `commit` to its message, unless it already has one.
+fn add_gerrit_change_id_trailer(commit: &mut gix::objs::Commit) {
+    let Some(headers) = HeadersV2::try_from_commit(commit) else {
+        return;
+    };
+    if let Some(message) = with_gerrit_change_id_trailer(
+        commit.message.as_ref(),
+        &gerrit_change_id_for(headers.change_id),
+    ) {
+        commit.message = message;
+    }
+}
+
 /// Sign the given `buffer` using configuration from `repo`, just like Git would.
 pub fn sign_buffer(repo: &gix::Repository, buffer: &[u8]) -> anyhow::Result<BString> {
     // TODO: support gpg.ssh.defaultKeyCommand to get the signing key if this value doesn't exist
@@ -5,7 +5,7 @@
 
 use crate::commit::DateMode;
 use anyhow::{Context, Ok, Result, anyhow, bail};
-use bstr::BString;
+use bstr::{BString, ByteSlice};
 use gix::objs::Exists;
 use gix::prelude::ObjectIdExt;
 use tracing::instrument;
@@ -46,6 +46,44 @@ pub enum RebaseStep {
         /// Optional message to use for newly produced commit
         new_message: Option<BString>,
     },
+    /// Squashes an existing commit into the one in the first `Pick` or `Merge` RebaseStep that precedes it, just like
+    /// [`SquashIntoPreceding`](Self::SquashIntoPreceding), but keeps the author of the commit it is squashed into
+    /// and lets `message` control the message of the new commit.
+    Fixup {
+        /// Id of an already existing commit
+        commit_id: gix::ObjectId,
+        /// The message to use for the newly produced commit.
+        message: FixupMessage,
+    },
+    /// Drop an existing commit so that the commits of the steps that follow are placed on top of the commit that precedes it.
+    ///
+    /// References following this step will point to the commit that precedes it.
+    Drop {
+        /// Id of an already existing commit
+        commit_id: gix::ObjectId,
+    },
+    /// Run `command` with the shell in a temporary checkout of the tree of the commit that precedes this step,
+    /// and halt the execution with an error if it fails, e.g. to assure each commit of a stack builds with `cargo check`.
+    ///
+    /// If the preceding commit is conflicted, its auto-resolution is checked out.
+    Exec {
+        /// The command to run, like `cargo check`.
+        command: String,
+    },
+    /// Re-create an existing merge commit on top of the commit that precedes this step, which becomes its first parent,
+    /// and merge `other_parents` into it.
+    ///
+    /// Each of `other_parents` that was rewritten by a previous step is substituted with its rewritten version,
+    /// which allows to preserve merges between branches that are rebased together.
+    /// Conflicts will cause the execution to halt with an error.
+    Merge {
+        /// Id of an already existing merge commit to use as template for the new one.
+        commit_id: gix::ObjectId,
+        /// The parents to merge into the commit that precedes this step, in order.
+        other_parents: Vec<gix::ObjectId>,
+        /// Optional message to use for newly produced commit
+        new_message: Option<BString>,
+    },
     /// Create a new reference pointing to the commit that precedes this step.
     /// If this is the first step in the list, the reference will be to the `base` commit.
     /// If the step before this one is another `Reference` step, this reference will point to the same commit.
@@ -57,12 +95,27 @@ impl RebaseStep {
     pub fn commit_id(&self) -> Option<&gix::oid> {
         match self {
             RebaseStep::Pick { commit_id, .. }
-            | RebaseStep::SquashIntoPreceding { commit_id, .. } => Some(commit_id),
-            RebaseStep::Reference { .. } => None,
+            | RebaseStep::SquashIntoPreceding { commit_id, .. }
+            | RebaseStep::Fixup { commit_id, .. }
+            | RebaseStep::Drop { commit_id }
+            | RebaseStep::Merge { commit_id, .. } => Some(commit_id),
+            RebaseStep::Exec { .. } | RebaseStep::Reference { .. } => None,
         }
     }
 }
 
+/// Determine the message of the commit produced by a [`RebaseStep::Fixup`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FixupMessage {
+    /// Keep the message of the commit that is squashed into, discarding the one of the fixup commit.
+    #[default]
+    KeepPreceding,
+    /// Use the message of the fixup commit.
+    KeepFixup,
+    /// Use the message of the commit that is squashed into, followed by the one of the fixup commit.
+    Combine,
+}
+
 /// Setup a list of [instructions](RebaseStep) for the actual [rebase operation](RebaseBuilder::rebase).
 #[derive(Debug)]
 pub struct Rebase<'repo> {
@@ -154,17 +207,22 @@ impl<'repo> Rebase<'repo> {
 }
 
 impl Rebase<'_> {
-    /// Pick, Merge and Fixup operations:
+    /// Pick, Merge, Fixup and Drop operations:
     /// - The commit must already exist in the repository
     /// - The commit must not be the base commit
-    ///
-    /// Pick and Merge operations:
-    /// - The commit must not be a commit that is already in a pick, merge or fixup step
+    /// - The commit must not be a commit that is already in a pick, merge, fixup or drop step
     ///
     /// Fixup operations:
     /// - Must not be a reference step immediately before it
     /// - Must not be the first operation
     ///
+    /// Merge operations:
+    /// - The commit must be a merge commit
+    /// - There must be at least one other parent, and all of them must exist
+    ///
+    /// Exec operations:
+    /// - The command must not be empty
+    ///
     /// Reference operations:
     /// - The refname must be a valid reference name
     fn validate_step(&self, step: &RebaseStep) -> Result<()> {
@@ -172,10 +230,8 @@ impl Rebase<'_> {
             RebaseStep::Pick { commit_id, .. } => {
                 self.assure_unique_step_and_existing_non_base(commit_id, "Picked")?;
             }
-            RebaseStep::SquashIntoPreceding {
-                commit_id,
-                new_message: _,
-            } => {
+            RebaseStep::SquashIntoPreceding { commit_id, .. }
+            | RebaseStep::Fixup { commit_id, .. } => {
                 self.assure_unique_step_and_existing_non_base(commit_id, "Fixup")?;
                 if matches!(self.steps.last(), Some(RebaseStep::Reference { .. })) {
                     bail!("Fixup commit must not come after a reference step");
@@ -184,6 +240,30 @@ impl Rebase<'_> {
                     bail!("Fixup must have a commit to work on");
                 }
             }
+            RebaseStep::Drop { commit_id } => {
+                self.assure_unique_step_and_existing_non_base(commit_id, "Dropped")?;
+            }
+            RebaseStep::Merge {
+                commit_id,
+                other_parents,
+                new_message: _,
+            } => {
+                self.assure_unique_step_and_existing_non_base(commit_id, "Merge")?;
+                if self.repo.find_commit(*commit_id)?.parent_ids().count() < 2 {
+                    bail!("Merge commit {commit_id} must have at least two parents");
+                }
+                if other_parents.is_empty() {
+                    bail!("Merge must have at least one other parent to merge");
+                }
+                for parent in other_parents {
+                    self.repo.find_commit(*parent)?;
+                }
+            }
+            RebaseStep::Exec { command } => {
+                if command.trim().is_empty() {
+                    bail!("Exec step must have a non-empty command");
+                }
+            }
             RebaseStep::Reference(name) => {
                 if matches!(name, but_core::Reference::Virtual(name) if name.is_empty()) {
                     return Err(anyhow!(
@@ -239,7 +319,7 @@ fn rebase(
                 if commit.parents.len() > 1 {
                     let mut merge_commit = commit;
                     if let Some(new_message) = new_message {
-                        merge_commit.message = new_message;
+                        commit::set_message(&mut merge_commit, new_message);
                     }
                     // Find any parent that we have seen during picking.
                     let parent_to_replace = match merge_commit.parents.iter_mut().find(|id| {
@@ -279,7 +359,7 @@ fn rebase(
                         None if commit.parents.is_empty() => {
                             let mut new_commit = commit;
                             if let Some(new_message) = new_message {
-                                new_commit.message = new_message;
+                                commit::set_message(&mut new_commit, new_message);
                             }
                             cursor = Some(commit::create(
                                 repo,
@@ -317,10 +397,76 @@ fn rebase(
                 let mut new_commit = repo.find_commit(new_commit)?.decode()?.to_owned();
                 new_commit.parents = base_commit.parent_ids().map(|id| id.detach()).collect();
                 if let Some(new_message) = new_message {
-                    new_commit.message = new_message;
+                    commit::set_message(&mut new_commit, new_message);
                 }
                 *cursor = commit::create(repo, new_commit, DateMode::CommitterUpdateAuthorKeep)?;
             }
+            RebaseStep::Fixup { commit_id, message } => {
+                let Some(cursor) = &mut cursor else {
+                    bail!("Can't squash if previous commit is missing");
+                };
+                last_seen_commit = Some(commit_id);
+                let base_commit = repo.find_commit(*cursor)?.decode()?.to_owned();
+                let new_commit = cherry_pick_one(
+                    repo,
+                    *cursor,
+                    commit_id,
+                    PickMode::Unconditionally,
+                    EmptyCommit::Keep,
+                )?;
+
+                // Like with squashing, pretend the base didn't exist by swapping parent with the parent of the base.
+                let mut new_commit = repo.find_commit(new_commit)?.decode()?.to_owned();
+                new_commit.message = match message {
+                    FixupMessage::KeepPreceding => base_commit.message,
+                    FixupMessage::KeepFixup => new_commit.message,
+                    FixupMessage::Combine => {
+                        let mut combined = base_commit.message.trim_end().to_owned();
+                        combined.extend_from_slice(b"\n\n");
+                        combined.extend_from_slice(&new_commit.message);
+                        combined.into()
+                    }
+                };
+                new_commit.parents = base_commit.parents;
+                new_commit.author = base_commit.author;
+                *cursor = commit::create(repo, new_commit, DateMode::CommitterUpdateAuthorKeep)?;
+            }
+            RebaseStep::Drop { commit_id } => {
+                // The commits that follow will be placed onto the cursor, as if this commit never existed.
+                last_seen_commit = Some(commit_id);
+            }
+            RebaseStep::Exec { command } => {
+                let Some(cursor) = cursor else {
+                    bail!("Can't run '{command}' if previous commit is missing");
+                };
+                exec(repo, cursor, &command)?;
+            }
+            RebaseStep::Merge {
+                commit_id,
+                other_parents,
+                new_message,
+            } => {
+                last_seen_commit = Some(commit_id);
+                let mut merge_commit = to_commit(repo, commit_id)?;
+                if let Some(new_message) = new_message {
+                    commit::set_message(&mut merge_commit, new_message);
+                }
+                merge_commit.parents = Some(cursor.context("Expecting a base for any merge")?)
+                    .into_iter()
+                    .chain(other_parents.into_iter().map(|parent| {
+                        commit_mapping
+                            .iter()
+                            .rev()
+                            .find_map(|(_base, old, new)| (*old == parent).then_some(*new))
+                            .unwrap_or(parent)
+                    }))
+                    .collect();
+                cursor = merge::octopus(repo, merge_commit, &mut graph)
+                    .context(
+                        "The rebase failed as a merge could not be repeated without conflicts",
+                    )?
+                    .into();
+            }
             RebaseStep::Reference(reference) => {
                 references.push(ReferenceSpec {
                     reference,
@@ -352,13 +498,58 @@ fn to_commit(repo: &gix::Repository, commit_id: gix::ObjectId) -> Result<gix::ob
         .into())
 }
 
+/// Check out the tree of `commit_id` into a temporary directory and run `command` in it with the shell.
+/// Files are checked out with the filters configured in `repo` applied, just like Git would.
+fn exec(repo: &gix::Repository, commit_id: gix::ObjectId, command: &str) -> Result<()> {
+    let tree_id =
+        but_core::Commit::from_id(commit_id.attach(repo))?.tree_id_or_auto_resolution()?;
+    let checkout = tempfile::TempDir::new()?;
+    let mut index = repo.index_from_tree(&tree_id)?;
+    let mut submodule_paths = Vec::new();
+    for (entry, path) in index.entries_mut_with_paths() {
+        if entry.mode == gix::index::entry::Mode::COMMIT {
+            entry.flags.insert(gix::index::entry::Flags::SKIP_WORKTREE);
+            submodule_paths.push(path.to_owned());
+        }
+    }
+    let mut opts =
+        repo.checkout_options(gix::worktree::stack::state::attributes::Source::IdMapping)?;
+    opts.destination_is_initially_empty = true;
+    gix::worktree::state::checkout(
+        &mut index,
+        checkout.path(),
+        repo.clone().objects.into_arc()?,
+        &gix::progress::Discard,
+        &gix::progress::Discard,
+        &gix::interrupt::IS_INTERRUPTED,
+        opts,
+    )?;
+    // Submodules are checked out as empty directories, just like Git does when they aren't initialized.
+    for path in submodule_paths {
+        std::fs::create_dir_all(checkout.path().join(gix::path::from_bstr(path.as_bstr())))?;
+    }
+
+    let output = std::process::Command::from(gix::command::prepare(command).with_shell())
+        .current_dir(checkout.path())
+        .output()
+        .with_context(|| format!("Failed to run '{command}'"))?;
+    if !output.status.success() {
+        bail!(
+            "'{command}' failed on commit {commit_id} with {status}:\n{stderr}",
+            status = output.status,
+            stderr = output.stderr.as_bstr()
+        );
+    }
+    Ok(())
+}
+
 fn reword_commit(
     repo: &gix::Repository,
     oid: gix::ObjectId,
     new_message: BString,
 ) -> Result<gix::ObjectId> {
     let mut new_commit = repo.find_commit(oid)?.decode()?.to_owned();
-    new_commit.message = new_message;
+    commit::set_message(&mut new_commit, new_message);
     Ok(commit::create(
         repo,
         new_commit,
@@ -216,3 +216,53 @@ fn empty_reference_step() -> anyhow::Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn non_merge_commit_in_merge_step() -> anyhow::Result<()> {
+    let (repo, commits) = four_commits()?;
+    let mut builder = Rebase::new(&repo, commits.base, None)?;
+    let result = builder.steps([RebaseStep::Merge {
+        commit_id: commits.a,
+        other_parents: vec![commits.b],
+        new_message: None,
+    }]);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        format!("Merge commit {} must have at least two parents", commits.a)
+    );
+    Ok(())
+}
+
+#[test]
+fn empty_command_in_exec_step() -> anyhow::Result<()> {
+    let (repo, commits) = four_commits()?;
+    let mut builder = Rebase::new(&repo, commits.base, None)?;
+    let result = builder.steps([RebaseStep::Exec {
+        command: "  ".into(),
+    }]);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Exec step must have a non-empty command"
+    );
+    Ok(())
+}
+
+#[test]
+fn dropping_a_picked_commit() -> anyhow::Result<()> {
+    let (repo, commits) = four_commits()?;
+    let mut builder = Rebase::new(&repo, commits.base, None)?;
+    let result = builder.steps([
+        RebaseStep::Pick {
+            commit_id: commits.a,
+            new_message: None,
+        },
+        RebaseStep::Drop {
+            commit_id: commits.a,
+        },
+    ]);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Picked commit already exists in a previous step"
+    );
+    Ok(())
+}
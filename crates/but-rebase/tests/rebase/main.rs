@@ -1,9 +1,10 @@
 use crate::utils::{
-    assure_nonconflicting, conflicted, fixture_writable, four_commits_writable, visualize_tree,
+    assure_nonconflicting, conflicted, fixture_writable, four_commits, four_commits_writable,
+    visualize_tree,
 };
 use anyhow::Result;
 use bstr::ByteSlice;
-use but_rebase::{Rebase, RebaseStep};
+use but_rebase::{FixupMessage, Rebase, RebaseStep};
 use but_testsupport::{assure_stable_env, visualize_commit_graph};
 use gix::prelude::ObjectIdExt;
 
@@ -623,6 +624,275 @@ fn pick_the_first_commit_with_no_parents_for_squashing() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn drop_commit_and_rebase_descendants() -> Result<()> {
+    assure_stable_env();
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    let mut builder = Rebase::new(&repo, commits.base, None)?;
+    let out = builder
+        .steps([
+            RebaseStep::Pick {
+                commit_id: commits.a,
+                new_message: None,
+            },
+            RebaseStep::Drop {
+                commit_id: commits.b,
+            },
+            RebaseStep::Reference(but_core::Reference::Virtual("after-drop".into())),
+            RebaseStep::Pick {
+                commit_id: commits.c,
+                new_message: None,
+            },
+        ])?
+        .rebase()?;
+    assert_eq!(messages(&repo, out.top_commit)?, ["c", "a", "base"]);
+    assert!(
+        !tree_has(&repo, out.top_commit, "b")?,
+        "the change of the dropped commit is gone"
+    );
+
+    let new_a = out.commit_mapping[0].2;
+    assert_eq!(
+        out.commit_mapping[1],
+        (Some(commits.base), commits.b, new_a),
+        "the dropped commit maps to the commit before it"
+    );
+    assert_eq!(out.references[0].commit_id, new_a);
+    assert_eq!(out.references[0].previous_commit_id, commits.b);
+    assure_nonconflicting(&repo, &out)?;
+    Ok(())
+}
+
+#[test]
+fn exec_runs_against_each_intermediate_tree() -> Result<()> {
+    assure_stable_env();
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    let mut builder = Rebase::new(&repo, commits.base, None)?;
+    let out = builder
+        .steps([
+            RebaseStep::Pick {
+                commit_id: commits.a,
+                new_message: None,
+            },
+            RebaseStep::Exec {
+                command: "test -f base && test -f a && test ! -e b".into(),
+            },
+            RebaseStep::Pick {
+                commit_id: commits.b,
+                new_message: None,
+            },
+            RebaseStep::Exec {
+                command: "test \"$(cat b)\" = b".into(),
+            },
+        ])?
+        .rebase()?;
+    assert_eq!(messages(&repo, out.top_commit)?, ["b", "a", "base"]);
+
+    let mut builder = Rebase::new(&repo, commits.base, None)?;
+    let err = builder
+        .steps([
+            RebaseStep::Pick {
+                commit_id: commits.a,
+                new_message: None,
+            },
+            RebaseStep::Exec {
+                command: "echo b is missing >&2; test -f b".into(),
+            },
+            RebaseStep::Pick {
+                commit_id: commits.b,
+                new_message: None,
+            },
+        ])?
+        .rebase()
+        .unwrap_err();
+    let err = err.to_string();
+    assert!(
+        err.starts_with("'echo b is missing >&2; test -f b' failed on commit"),
+        "{err}"
+    );
+    assert!(err.ends_with("b is missing\n"), "stderr is included: {err}");
+    Ok(())
+}
+
+#[test]
+fn fixup_with_message_retention() -> Result<()> {
+    assure_stable_env();
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    for (message, expected) in [
+        (FixupMessage::KeepPreceding, "a\n"),
+        (FixupMessage::KeepFixup, "b\n"),
+        (FixupMessage::Combine, "a\n\nb\n"),
+    ] {
+        let mut builder = Rebase::new(&repo, commits.base, None)?;
+        let out = builder
+            .steps([
+                RebaseStep::Pick {
+                    commit_id: commits.a,
+                    new_message: None,
+                },
+                RebaseStep::Fixup {
+                    commit_id: commits.b,
+                    message,
+                },
+            ])?
+            .rebase()?;
+        let commit = repo.find_commit(out.top_commit)?;
+        assert_eq!(commit.message_raw()?, expected, "{message:?}");
+        assert_eq!(
+            commit
+                .parent_ids()
+                .map(|id| id.detach())
+                .collect::<Vec<_>>(),
+            [commits.base],
+            "the fixup is squashed into the preceding commit"
+        );
+        assert!(tree_has(&repo, out.top_commit, "a")? && tree_has(&repo, out.top_commit, "b")?);
+        assure_nonconflicting(&repo, &out)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn merge_is_recreated_with_rewritten_parents() -> Result<()> {
+    assure_stable_env();
+    let (repo, _tmp) = fixture_writable("merge-in-the-middle")?;
+    let b = repo.rev_parse_single("B")?.detach();
+    let mut builder = Rebase::new(&repo, repo.rev_parse_single("base")?.detach(), None)?;
+    let out = builder
+        .steps([
+            RebaseStep::Pick {
+                commit_id: b,
+                new_message: Some("rewritten B".into()),
+            },
+            RebaseStep::Pick {
+                commit_id: repo.rev_parse_single("A")?.into(),
+                new_message: None,
+            },
+            RebaseStep::Merge {
+                commit_id: repo.rev_parse_single("with-inner-merge~1")?.into(),
+                other_parents: vec![b],
+                new_message: None,
+            },
+            RebaseStep::Pick {
+                commit_id: repo.rev_parse_single("with-inner-merge")?.into(),
+                new_message: None,
+            },
+        ])?
+        .rebase()?;
+
+    let new_b = out.commit_mapping[0].2;
+    let new_a = out.commit_mapping[1].2;
+    let merge = repo.find_commit(out.commit_mapping[2].2)?;
+    assert_eq!(
+        merge.message_raw()?,
+        "Merge branch 'B' into with-inner-merge\n"
+    );
+    assert_eq!(
+        merge.parent_ids().map(|id| id.detach()).collect::<Vec<_>>(),
+        [new_a, new_b],
+        "the merged parent was substituted with its rewritten version"
+    );
+    assert_eq!(
+        messages(&repo, out.top_commit)?,
+        [
+            "on top of inner merge",
+            "Merge branch 'B' into with-inner-merge",
+            "A: 10 lines on top",
+            "rewritten B",
+            "base"
+        ]
+    );
+    assert!(tree_has(&repo, out.top_commit, "new-file")?);
+    assure_nonconflicting(&repo, &out)?;
+    Ok(())
+}
+
+#[test]
+fn foreign_change_ids_survive_rewording() -> Result<()> {
+    assure_stable_env();
+    let (repo, commits) = four_commits()?;
+    let jj_change_id = "zxkmqnvlsmsqtpwvyrzlvmypskluttlr";
+    let gerrit_change_id = "I0123456789abcdef0123456789abcdef01234567";
+    let mut commit = repo.find_commit(commits.b)?.decode()?.to_owned();
+    commit
+        .extra_headers
+        .push(("change-id".into(), jj_change_id.into()));
+    commit.message = format!("b\n\nChange-Id: {gerrit_change_id}\n").into();
+    let b = repo.write_object(&commit)?.detach();
+    let change_id = but_core::Commit::from_id(b.attach(&repo))?
+        .headers()
+        .expect("derived from the foreign change-id")
+        .change_id;
+
+    let out = Rebase::new(&repo, commits.base, None)?
+        .steps([RebaseStep::Pick {
+            commit_id: b,
+            new_message: Some("reworded b".into()),
+        }])?
+        .rebase()?;
+    let rewritten = but_core::Commit::from_id(out.top_commit.attach(&repo))?;
+    assert_eq!(
+        rewritten.extra_headers().find("change-id"),
+        Some(jj_change_id.into()),
+        "the header of Jujutsu is kept"
+    );
+    assert_eq!(
+        rewritten.message,
+        format!("reworded b\n\nChange-Id: {gerrit_change_id}\n"),
+        "the trailer of Gerrit is kept even though the message was replaced"
+    );
+    assert_eq!(
+        rewritten.headers().expect("still derived").change_id,
+        change_id,
+        "our own change-id remains the same as well"
+    );
+    Ok(())
+}
+
+#[test]
+fn gerrit_mode_adds_change_id_trailers() -> Result<()> {
+    assure_stable_env();
+    let (mut repo, commits) = four_commits()?;
+    repo.config_snapshot_mut()
+        .set_raw_value(&"gitbutler.gerritMode", "true")?;
+
+    let out = Rebase::new(&repo, commits.base, None)?
+        .steps([RebaseStep::Pick {
+            commit_id: commits.b,
+            new_message: Some("reworded b".into()),
+        }])?
+        .rebase()?;
+    let rewritten = but_core::Commit::from_id(out.top_commit.attach(&repo))?;
+    let change_id = rewritten.headers().expect("added when picking").change_id;
+    assert_eq!(
+        rewritten.message,
+        format!(
+            "reworded b\n\nChange-Id: {}\n",
+            but_core::commit::gerrit_change_id_for(change_id)
+        ),
+        "the trailer is derived from the change-id, and kept when rewording"
+    );
+    Ok(())
+}
+
+/// The first lines of the messages of the first-parent chain of `tip`, starting at `tip`.
+fn messages(repo: &gix::Repository, tip: gix::ObjectId) -> Result<Vec<String>> {
+    let mut out = Vec::new();
+    for info in tip.attach(repo).ancestors().first_parent_only().all()? {
+        let commit = repo.find_commit(info?.id)?;
+        out.push(commit.message()?.summary().to_string());
+    }
+    Ok(out)
+}
+
+fn tree_has(repo: &gix::Repository, commit_id: gix::ObjectId, path: &str) -> Result<bool> {
+    Ok(repo
+        .find_commit(commit_id)?
+        .tree()?
+        .lookup_entry_by_path(path)?
+        .is_some())
+}
+
 pub mod utils {
     use anyhow::Result;
     use but_rebase::RebaseOutput;
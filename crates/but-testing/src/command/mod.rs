@@ -392,11 +392,10 @@ pub async fn watch(args: &super::Args) -> anyhow::Result<()> {
     let workdir = repo
         .workdir()
         .context("really only want to watch workdirs")?;
-    let _watcher = gitbutler_filemonitor::spawn(
-        project.map(|p| p.id).unwrap_or(ProjectId::generate()),
-        workdir,
-        tx,
-    )?;
+    let (project_id, backend) = project
+        .map(|p| (p.id, p.file_monitor_backend))
+        .unwrap_or_else(|| (ProjectId::generate(), Default::default()));
+    let _watcher = gitbutler_filemonitor::spawn(project_id, workdir, backend, tx)?;
     let elapsed = start.elapsed();
     eprintln!(
         "Started watching {workdir} in {elapsed:?}s - waiting for events",
@@ -152,6 +152,7 @@ async fn main() -> Result<()> {
                     new_start: *new_start,
                     new_lines: *new_lines,
                 }),
+                line_ranges: None,
             };
             command::assignment::assign_hunk(&args.current_dir, args.json, assignment)
         }
@@ -1,6 +1,5 @@
 use but_workspace::StackId;
 use gitbutler_project::ProjectId;
-use tauri::Emitter;
 
 pub trait EmitStackUpdate {
     /// Emits a stack update event with the given stack ID.
@@ -14,15 +13,7 @@ pub trait EmitStackUpdate {
     fn emit_stack_update(&self, project_id: ProjectId, stack_id: StackId);
 }
 
-impl EmitStackUpdate for tauri::AppHandle {
-    fn emit_stack_update(&self, project_id: ProjectId, stack_id: StackId) {
-        let name = format!("project://{}/stack_details_update", project_id);
-        let payload = serde_json::json!({ "stackId": stack_id });
-        self.emit(&name, payload)
-            .expect("Failed to emit stack details update");
-    }
-}
-
+#[derive(Debug, Clone)]
 pub struct ToolCall {
     pub name: String,
     pub parameters: String,
@@ -42,16 +33,109 @@ pub trait EmitToolCall {
     fn emit_tool_call(&self, project_id: ProjectId, message_id: String, tool_call: ToolCall);
 }
 
-impl EmitToolCall for tauri::AppHandle {
+pub trait EmitTokenEvent {
+    /// Emits an event with the Gen AI token.
+    ///
+    /// This method should be implemented to emit an event that updates the Gen AI token in the UI.
+    /// # Arguments
+    ///  * `project_id` - The ID of the project to which the token belongs.
+    ///  * `message_id` - The ID of the message that is being responded to.
+    /// * `token` - The token to emit.
+    fn emit_token_event(&self, token: &str, project_id: ProjectId, message_id: String);
+}
+
+/// The sink for all events emitted while tools run, so the UI or any other consumer can follow along.
+///
+/// It's implemented for everything that can emit all kinds of events.
+pub trait Emitter: EmitStackUpdate + EmitToolCall + EmitTokenEvent + Send + Sync {}
+
+impl<T> Emitter for T where T: EmitStackUpdate + EmitToolCall + EmitTokenEvent + Send + Sync {}
+
+/// An [`Emitter`] that discards all events, for when nobody is listening.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopEmitter;
+
+impl EmitStackUpdate for NoopEmitter {
+    fn emit_stack_update(&self, _project_id: ProjectId, _stack_id: StackId) {}
+}
+
+impl EmitToolCall for NoopEmitter {
+    fn emit_tool_call(&self, _project_id: ProjectId, _message_id: String, _tool_call: ToolCall) {}
+}
+
+impl EmitTokenEvent for NoopEmitter {
+    fn emit_token_event(&self, _token: &str, _project_id: ProjectId, _message_id: String) {}
+}
+
+/// An event as sent by the [`ChannelEmitter`].
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The details of the stack with `stack_id` changed.
+    StackUpdate {
+        project_id: ProjectId,
+        stack_id: StackId,
+    },
+    /// A tool was called while responding to the message with `message_id`.
+    ToolCall {
+        project_id: ProjectId,
+        message_id: String,
+        tool_call: ToolCall,
+    },
+    /// A token of the response to the message with `message_id` was received.
+    Token {
+        project_id: ProjectId,
+        message_id: String,
+        token: String,
+    },
+}
+
+/// An [`Emitter`] that sends all events into a channel, for headless consumers that forward them on their own terms.
+///
+/// Events are dropped silently once the receiving end is gone.
+#[derive(Debug, Clone)]
+pub struct ChannelEmitter {
+    sender: tokio::sync::mpsc::UnboundedSender<Event>,
+}
+
+impl ChannelEmitter {
+    /// Create a new emitter along with the receiver for its events.
+    pub fn new() -> (Self, tokio::sync::mpsc::UnboundedReceiver<Event>) {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        (ChannelEmitter { sender }, receiver)
+    }
+}
+
+impl EmitStackUpdate for ChannelEmitter {
+    fn emit_stack_update(&self, project_id: ProjectId, stack_id: StackId) {
+        self.sender
+            .send(Event::StackUpdate {
+                project_id,
+                stack_id,
+            })
+            .ok();
+    }
+}
+
+impl EmitToolCall for ChannelEmitter {
     fn emit_tool_call(&self, project_id: ProjectId, message_id: String, tool_call: ToolCall) {
-        let name = format!("project://{}/tool-call", project_id);
-        let payload = serde_json::json!({
-            "messageId": message_id,
-            "name": tool_call.name,
-            "parameters": tool_call.parameters,
-            "result": tool_call.result,
-        });
-        self.emit(&name, payload)
-            .expect("Failed to emit tool call event");
+        self.sender
+            .send(Event::ToolCall {
+                project_id,
+                message_id,
+                tool_call,
+            })
+            .ok();
+    }
+}
+
+impl EmitTokenEvent for ChannelEmitter {
+    fn emit_token_event(&self, token: &str, project_id: ProjectId, message_id: String) {
+        self.sender
+            .send(Event::Token {
+                project_id,
+                message_id,
+                token: token.to_owned(),
+            })
+            .ok();
     }
 }
@@ -1,4 +1,5 @@
 pub mod emit;
 pub mod openai;
+pub mod policy;
 pub mod tool;
 pub mod workspace;
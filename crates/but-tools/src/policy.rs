@@ -0,0 +1,454 @@
+//! Guardrails that restrict which tools an agent may call and which branches it may touch.
+//!
+//! The policy is stored per project and checked by the [`Toolset`](crate::tool::Toolset) before each tool call.
+//! Violations are returned to the agent as tool errors, and recorded as actions so users can see what was prevented.
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use but_workspace::StackId;
+use gitbutler_command_context::CommandContext;
+use gitbutler_oplog::OplogExt;
+use serde::{Deserialize, Serialize};
+
+/// The name under which violations are recorded as actions, matching `but_action::ActionHandler::PolicyViolation`.
+const VIOLATION_HANDLER: &str = "PolicyViolation";
+
+/// What agents may do in a project.
+///
+/// The default policy doesn't restrict agents at all.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Policy {
+    /// The names of the tools agents may call, or `None` if all tools may be called.
+    pub allowed_tools: Option<Vec<String>>,
+    /// Glob patterns matching the names of branches whose commits agents must not amend, squash, split or move,
+    /// and which agents must not rename.
+    pub protected_branches: Vec<String>,
+    /// The maximum number of commits a single action may create, or `None` if there is no limit.
+    pub max_commits_per_action: Option<usize>,
+    /// The names of the tools which may only be called if a human approves each call.
+    pub approval_required_tools: Vec<String>,
+}
+
+/// A way for a human to decide if a tool call may proceed.
+pub trait Approver: Send + Sync {
+    /// Return `true` if the tool `name` may be called with `parameters`.
+    fn approve(&self, name: &str, parameters: &serde_json::Value) -> bool;
+}
+
+/// The reason an agent was prevented from doing something.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "subject")]
+pub enum Violation {
+    /// The tool isn't among the allowed tools.
+    #[serde(rename_all = "camelCase")]
+    ToolNotAllowed { tool: String },
+    /// The tool or action would rewrite or rename a protected branch.
+    #[serde(rename_all = "camelCase")]
+    ProtectedBranch { tool: String, branch: String },
+    /// The action would create more commits than allowed.
+    #[serde(rename_all = "camelCase")]
+    CommitLimitExceeded { limit: usize },
+    /// The tool needs approval, which wasn't given.
+    #[serde(rename_all = "camelCase")]
+    ApprovalDenied { tool: String },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::ToolNotAllowed { tool } => {
+                write!(f, "The tool '{tool}' isn't allowed in this project")
+            }
+            Violation::ProtectedBranch { tool, branch } => {
+                write!(f, "'{tool}' would modify the protected branch '{branch}'")
+            }
+            Violation::CommitLimitExceeded { limit } => {
+                write!(f, "A single action may create at most {limit} commits")
+            }
+            Violation::ApprovalDenied { tool } => {
+                write!(f, "Calling '{tool}' requires approval, which wasn't given")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Violation {}
+
+impl Violation {
+    /// Return the structured tool error to pass to the agent.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "error": format!("Policy violation: {self}"),
+            "violation": self,
+        })
+    }
+}
+
+impl Policy {
+    /// Return an error if the tool `name` must not be called at all.
+    pub fn check_tool(&self, name: &str) -> Result<(), Violation> {
+        match &self.allowed_tools {
+            Some(allowed) if !allowed.iter().any(|tool| tool == name) => {
+                Err(Violation::ToolNotAllowed { tool: name.into() })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Return `true` if calling the tool `name` needs the approval of a human.
+    pub fn requires_approval(&self, name: &str) -> bool {
+        self.approval_required_tools.iter().any(|tool| tool == name)
+    }
+
+    /// Return an error if `tool` would modify any of `branches` while it's protected.
+    pub fn check_branches<'a>(
+        &self,
+        tool: &str,
+        branches: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), Violation> {
+        let patterns: Vec<_> = self
+            .protected_branches
+            .iter()
+            .filter_map(|pattern| glob::Pattern::new(pattern).ok())
+            .collect();
+        match branches
+            .into_iter()
+            .find(|branch| patterns.iter().any(|pattern| pattern.matches(branch)))
+        {
+            Some(branch) => Err(Violation::ProtectedBranch {
+                tool: tool.into(),
+                branch: branch.into(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Return how calling the tool `name` would violate this policy, if at all, given that it rewrites `branches`,
+    /// creates `additional` commits on top of the `created` ones, and that `approve` asks a human for approval.
+    ///
+    /// Approval is only asked for if there is no other violation.
+    pub fn check_call(
+        &self,
+        name: &str,
+        branches: &[String],
+        created: usize,
+        additional: usize,
+        approve: impl FnOnce() -> bool,
+    ) -> Option<Violation> {
+        self.check_tool(name)
+            .and_then(|()| self.check_branches(name, branches.iter().map(String::as_str)))
+            .and_then(|()| self.check_commit_count(created, additional))
+            .err()
+            .or_else(|| {
+                (self.requires_approval(name) && !approve())
+                    .then(|| Violation::ApprovalDenied { tool: name.into() })
+            })
+    }
+
+    /// Return an error if creating `additional` commits on top of the `created` ones would exceed the limit.
+    pub fn check_commit_count(&self, created: usize, additional: usize) -> Result<(), Violation> {
+        match self.max_commits_per_action {
+            Some(limit) if created + additional > limit => {
+                Err(Violation::CommitLimitExceeded { limit })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl TryFrom<but_db::AgentPolicy> for Policy {
+    type Error = anyhow::Error;
+    fn try_from(value: but_db::AgentPolicy) -> Result<Self, Self::Error> {
+        Ok(Policy {
+            allowed_tools: value
+                .allowed_tools
+                .map(|tools| serde_json::from_str(&tools))
+                .transpose()?,
+            protected_branches: serde_json::from_str(&value.protected_branches)?,
+            max_commits_per_action: value
+                .max_commits_per_action
+                .map(usize::try_from)
+                .transpose()?,
+            approval_required_tools: serde_json::from_str(&value.approval_required_tools)?,
+        })
+    }
+}
+
+impl TryFrom<Policy> for but_db::AgentPolicy {
+    type Error = anyhow::Error;
+    fn try_from(value: Policy) -> Result<Self, Self::Error> {
+        Ok(but_db::AgentPolicy {
+            id: uuid::Uuid::new_v4().to_string(),
+            created_at: chrono::Local::now().naive_local(),
+            allowed_tools: value
+                .allowed_tools
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+            protected_branches: serde_json::to_string(&value.protected_branches)?,
+            max_commits_per_action: value
+                .max_commits_per_action
+                .map(i32::try_from)
+                .transpose()?,
+            approval_required_tools: serde_json::to_string(&value.approval_required_tools)?,
+        })
+    }
+}
+
+/// Return the policy of the project, which doesn't restrict anything if none was set.
+pub fn policy(ctx: &mut CommandContext) -> anyhow::Result<Policy> {
+    ctx.db()?
+        .agent_policies()
+        .get()?
+        .map(TryInto::try_into)
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// Replace the policy of the project with `policy`.
+pub fn set_policy(ctx: &mut CommandContext, policy: Policy) -> anyhow::Result<Policy> {
+    for pattern in &policy.protected_branches {
+        glob::Pattern::new(pattern)
+            .map_err(|e| anyhow::anyhow!("Invalid branch pattern '{pattern}': {e}"))?;
+    }
+    ctx.db()?
+        .agent_policies()
+        .set(policy.clone().try_into()?)
+        .map_err(|e| anyhow::anyhow!("Failed to set agent policy: {}", e))?;
+    Ok(policy)
+}
+
+/// Record that `violation` prevented what's described in `summary`, so it shows up along with all other actions.
+pub fn record_violation(
+    ctx: &mut CommandContext,
+    summary: String,
+    violation: &Violation,
+) -> anyhow::Result<()> {
+    // Nothing changed, so the snapshots before and after are the same.
+    let snapshot = ctx
+        .oplog_head()?
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| gix::hash::Kind::Sha1.null().to_string());
+    ctx.db()?
+        .butler_actions()
+        .insert(but_db::ButlerAction {
+            id: uuid::Uuid::new_v4().to_string(),
+            created_at: chrono::Local::now().naive_local(),
+            external_prompt: None,
+            external_summary: summary,
+            handler: VIOLATION_HANDLER.into(),
+            snapshot_before: snapshot.clone(),
+            snapshot_after: snapshot,
+            response: None,
+            error: Some(violation.to_string()),
+            source: None,
+        })
+        .map_err(|e| anyhow::anyhow!("Failed to persist policy violation: {}", e))?;
+    Ok(())
+}
+
+/// Return the names of the branches in the stack with `stack_id` which contain any of `commit_ids`,
+/// which may have been rewritten since according to `commit_mapping`.
+///
+/// This is what tools which rewrite commits report as [rewritten branches](crate::tool::Tool::rewritten_branches()).
+pub(crate) fn branches_containing<'a>(
+    ctx: &CommandContext,
+    stack_id: &str,
+    commit_ids: impl IntoIterator<Item = &'a str>,
+    commit_mapping: &HashMap<gix::ObjectId, gix::ObjectId>,
+) -> anyhow::Result<Vec<String>> {
+    let stack_id = StackId::from_str(stack_id)?;
+    let commit_ids: Vec<_> = commit_ids
+        .into_iter()
+        .filter_map(|id| gix::ObjectId::from_str(id).ok())
+        .map(|id| crate::workspace::find_the_right_commit_id(id, commit_mapping))
+        .collect();
+    let details = if ctx.app_settings().feature_flags.ws3 {
+        let repo = ctx.gix_repo_for_merging_non_persisting()?;
+        let meta = crate::workspace::ref_metadata_toml(ctx.project())?;
+        but_workspace::stack_details_v3(Some(stack_id), &repo, &meta)
+    } else {
+        but_workspace::stack_details(&ctx.project().gb_dir(), stack_id, ctx)
+    }?;
+    Ok(details
+        .branch_details
+        .into_iter()
+        .filter(|branch| {
+            branch
+                .commits
+                .iter()
+                .any(|commit| commit_ids.contains(&commit.id))
+        })
+        .map(|branch| branch.name.to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::tool::Tool;
+    use crate::workspace::{Commit, CreateBranch, SplitCommit};
+
+    fn no_approval() -> bool {
+        panic!("approval isn't asked for")
+    }
+
+    #[test]
+    fn default_policy_allows_everything() {
+        let policy = Policy::default();
+        assert_eq!(
+            policy.check_call("amend", &["main".to_owned()], 100, 10, no_approval),
+            None
+        );
+    }
+
+    #[test]
+    fn only_allowed_tools_may_be_called() {
+        let policy = Policy {
+            allowed_tools: Some(vec!["commit".into()]),
+            ..Default::default()
+        };
+        assert_eq!(policy.check_call("commit", &[], 0, 1, no_approval), None);
+        assert_eq!(
+            policy.check_call("amend", &[], 0, 0, no_approval),
+            Some(Violation::ToolNotAllowed {
+                tool: "amend".into()
+            })
+        );
+
+        let policy = Policy {
+            allowed_tools: Some(Vec::new()),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.check_call("commit", &[], 0, 1, no_approval),
+            Some(Violation::ToolNotAllowed {
+                tool: "commit".into()
+            }),
+            "no tool is allowed"
+        );
+    }
+
+    #[test]
+    fn protected_branches_match_patterns() {
+        let policy = Policy {
+            protected_branches: vec!["main".into(), "release/*".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.check_call("amend", &["feature".to_owned()], 0, 0, no_approval),
+            None
+        );
+        assert_eq!(
+            policy.check_call(
+                "squash_commits",
+                &["feature".to_owned(), "release/1.0".to_owned()],
+                0,
+                0,
+                no_approval
+            ),
+            Some(Violation::ProtectedBranch {
+                tool: "squash_commits".into(),
+                branch: "release/1.0".into()
+            })
+        );
+        assert_eq!(
+            policy.check_call("amend", &["main".to_owned()], 0, 0, no_approval),
+            Some(Violation::ProtectedBranch {
+                tool: "amend".into(),
+                branch: "main".into()
+            })
+        );
+        assert_eq!(
+            policy.check_call("amend", &["mainline".to_owned()], 0, 0, no_approval),
+            None,
+            "patterns match the whole name"
+        );
+    }
+
+    #[test]
+    fn commits_are_limited_across_calls() {
+        let policy = Policy {
+            max_commits_per_action: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(policy.check_call("commit", &[], 0, 1, no_approval), None);
+        assert_eq!(policy.check_call("commit", &[], 1, 1, no_approval), None);
+        assert_eq!(
+            policy.check_call("commit", &[], 2, 1, no_approval),
+            Some(Violation::CommitLimitExceeded { limit: 2 })
+        );
+        assert_eq!(
+            policy.check_call("split_commit", &[], 0, 3, no_approval),
+            Some(Violation::CommitLimitExceeded { limit: 2 })
+        );
+        assert_eq!(
+            policy.check_call("amend", &[], 2, 0, no_approval),
+            None,
+            "tools which don't create commits can still be called"
+        );
+    }
+
+    #[test]
+    fn approval_is_asked_for_if_required_and_nothing_else_is_violated() {
+        let policy = Policy {
+            allowed_tools: Some(vec!["amend".into(), "commit".into()]),
+            approval_required_tools: vec!["amend".into(), "squash_commits".into()],
+            ..Default::default()
+        };
+        assert_eq!(policy.check_call("amend", &[], 0, 0, || true), None);
+        assert_eq!(
+            policy.check_call("amend", &[], 0, 0, || false),
+            Some(Violation::ApprovalDenied {
+                tool: "amend".into()
+            })
+        );
+        assert_eq!(
+            policy.check_call("commit", &[], 0, 1, no_approval),
+            None,
+            "the tool doesn't need approval"
+        );
+        assert_eq!(
+            policy.check_call("squash_commits", &[], 0, 0, no_approval),
+            Some(Violation::ToolNotAllowed {
+                tool: "squash_commits".into()
+            }),
+            "tools which aren't allowed are denied without asking"
+        );
+    }
+
+    #[test]
+    fn tools_report_the_commits_they_create() {
+        let tools: [Arc<dyn Tool>; 3] = [
+            Arc::new(Commit),
+            Arc::new(CreateBranch),
+            Arc::new(SplitCommit),
+        ];
+        let shards = json!({
+            "sourceStackId": "",
+            "sourceCommitId": "",
+            "shards": [
+                {"messageTitle": "a", "messageBody": "", "files": []},
+                {"messageTitle": "b", "messageBody": "", "files": []},
+                {"messageTitle": "c", "messageBody": "", "files": []},
+            ]
+        });
+        assert_eq!(
+            tools
+                .iter()
+                .map(|tool| tool.created_commits(&shards))
+                .collect::<Vec<_>>(),
+            [1, 0, 2],
+            "a split commit is replaced by its shards"
+        );
+        assert_eq!(
+            SplitCommit.created_commits(&json!({})),
+            0,
+            "invalid parameters fail the call"
+        );
+    }
+}
@@ -3,7 +3,8 @@ use std::{
     sync::Arc,
 };
 
-use crate::emit::EmitToolCall;
+use crate::emit::Emitter;
+use crate::policy::{Approver, Violation};
 use but_workspace::ui::StackEntryNoOpt;
 use but_workspace::{StackId, ui::StackEntry};
 use gitbutler_command_context::CommandContext;
@@ -12,31 +13,80 @@ use serde_json::json;
 
 pub struct Toolset<'a> {
     ctx: &'a mut CommandContext,
-    app_handle: Option<&'a tauri::AppHandle>,
+    emitter: &'a dyn Emitter,
     message_id: Option<String>,
     tools: BTreeMap<String, Arc<dyn Tool>>,
+    session: ToolSession,
+    /// Decides over calls to tools which need approval according to the project's policy.
+    approver: Option<&'a dyn Approver>,
+}
+
+/// What a [`Toolset`] remembers across tool calls, so it can be kept by consumers which can't keep the
+/// toolset itself, like servers which receive one tool call at a time.
+#[derive(Debug, Default, Clone)]
+pub struct ToolSession {
     commit_mapping: HashMap<ObjectId, ObjectId>,
+    /// The number of commits created by tool calls so far, to enforce the limit of the project's policy.
+    created_commits: usize,
+}
+
+impl ToolSession {
+    /// Continue this session with a new action, which may create as many commits as the policy allows,
+    /// while still knowing how commits were rewritten so far.
+    pub fn into_next_action(self) -> Self {
+        ToolSession {
+            created_commits: 0,
+            ..self
+        }
+    }
 }
 
 impl<'a> Toolset<'a> {
     pub fn new(
         ctx: &'a mut CommandContext,
-        app_handle: Option<&'a tauri::AppHandle>,
+        emitter: &'a dyn Emitter,
+        message_id: Option<String>,
+    ) -> Self {
+        Self::with_session(ctx, emitter, message_id, ToolSession::default())
+    }
+
+    /// Like [`new()`](Self::new()), but continue the `session` of a previous toolset.
+    pub fn with_session(
+        ctx: &'a mut CommandContext,
+        emitter: &'a dyn Emitter,
         message_id: Option<String>,
+        session: ToolSession,
     ) -> Self {
         Toolset {
             ctx,
-            app_handle,
+            emitter,
             message_id,
             tools: BTreeMap::new(),
-            commit_mapping: HashMap::new(),
+            session,
+            approver: None,
         }
     }
 
+    /// Return what was remembered across tool calls, to continue with it [later](Self::with_session()).
+    pub fn into_session(self) -> ToolSession {
+        self.session
+    }
+
+    /// Let `approver` decide over calls to tools which need approval, which are denied otherwise.
+    pub fn set_approver(&mut self, approver: &'a dyn Approver) {
+        self.approver = Some(approver);
+    }
+
     pub fn register_tool<T: Tool>(&mut self, tool: T) {
         self.tools.insert(tool.name(), Arc::new(tool));
     }
 
+    pub fn register_tools(&mut self, tools: impl IntoIterator<Item = Arc<dyn Tool>>) {
+        for tool in tools {
+            self.tools.insert(tool.name(), tool);
+        }
+    }
+
     pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
         self.tools.get(name).cloned()
     }
@@ -55,7 +105,49 @@ impl<'a> Toolset<'a> {
             .ok_or_else(|| anyhow::anyhow!("Tool '{}' not found", name))?;
         let params: serde_json::Value = serde_json::from_str(parameters)
             .map_err(|e| anyhow::anyhow!("Failed to parse parameters: {}", e))?;
-        tool.call(params, self.ctx, self.app_handle, &mut self.commit_mapping)
+        if let Some(violation) = self.policy_violation(tool.as_ref(), &params)? {
+            crate::policy::record_violation(
+                self.ctx,
+                format!("Call the tool '{name}' with {parameters}"),
+                &violation,
+            )?;
+            return Ok(violation.to_json());
+        }
+        let created_commits = tool.created_commits(&params);
+        let value = tool.call(
+            params,
+            self.ctx,
+            self.emitter,
+            &mut self.session.commit_mapping,
+        )?;
+        if value.get("error").is_none() {
+            self.session.created_commits += created_commits;
+        }
+        Ok(value)
+    }
+
+    /// Return how calling `tool` with `parameters` would violate the project's policy, if at all.
+    fn policy_violation(
+        &mut self,
+        tool: &dyn Tool,
+        parameters: &serde_json::Value,
+    ) -> anyhow::Result<Option<Violation>> {
+        let policy = crate::policy::policy(self.ctx)?;
+        let branches = if policy.protected_branches.is_empty() {
+            Vec::new()
+        } else {
+            tool.rewritten_branches(parameters, self.ctx, &self.session.commit_mapping)?
+        };
+        Ok(policy.check_call(
+            &tool.name(),
+            &branches,
+            self.session.created_commits,
+            tool.created_commits(parameters),
+            || {
+                self.approver
+                    .is_some_and(|approver| approver.approve(&tool.name(), parameters))
+            },
+        ))
     }
 
     pub fn call_tool(&mut self, name: &str, parameters: &str) -> serde_json::Value {
@@ -67,18 +159,16 @@ impl<'a> Toolset<'a> {
 
         // Emit the tool call event if a message ID is provided
         if let Some(message_id) = &self.message_id {
-            if let Some(app_handle) = self.app_handle {
-                let project_id = self.ctx.project().id;
-                app_handle.emit_tool_call(
-                    project_id,
-                    message_id.to_owned(),
-                    crate::emit::ToolCall {
-                        name: name.to_string(),
-                        parameters: parameters.to_string(),
-                        result: result.to_string(),
-                    },
-                );
-            }
+            let project_id = self.ctx.project().id;
+            self.emitter.emit_tool_call(
+                project_id,
+                message_id.to_owned(),
+                crate::emit::ToolCall {
+                    name: name.to_string(),
+                    parameters: parameters.to_string(),
+                    result: result.to_string(),
+                },
+            );
         }
 
         result
@@ -93,9 +183,25 @@ pub trait Tool: 'static + Send + Sync {
         self: Arc<Self>,
         parameters: serde_json::Value,
         ctx: &mut CommandContext,
-        app_handle: Option<&tauri::AppHandle>,
+        emitter: &dyn Emitter,
         commit_mapping: &mut HashMap<ObjectId, ObjectId>,
     ) -> anyhow::Result<serde_json::Value>;
+
+    /// Return the names of the branches whose commits a call with `parameters` would rewrite,
+    /// so that protected branches can be left alone.
+    fn rewritten_branches(
+        &self,
+        _parameters: &serde_json::Value,
+        _ctx: &CommandContext,
+        _commit_mapping: &HashMap<ObjectId, ObjectId>,
+    ) -> anyhow::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Return the number of commits a call with `parameters` would create, to enforce the commit limit.
+    fn created_commits(&self, _parameters: &serde_json::Value) -> usize {
+        0
+    }
 }
 
 pub fn error_to_json(error: &anyhow::Error, action_identifier: &str) -> serde_json::Value {
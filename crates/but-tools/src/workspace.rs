@@ -17,38 +17,47 @@ use gitbutler_project::Project;
 use gitbutler_reference::{LocalRefname, Refname};
 use gitbutler_stack::{PatchReferenceUpdate, VirtualBranchesHandle};
 use schemars::{JsonSchema, schema_for};
+use serde::Deserialize;
 
-use crate::emit::EmitStackUpdate;
+use crate::emit::Emitter;
 use crate::tool::{Tool, ToolResult, Toolset, error_to_json, result_to_json};
 
 /// Creates a toolset for any kind of workspace operations.
 pub fn workspace_toolset<'a>(
     ctx: &'a mut CommandContext,
-    app_handle: Option<&'a tauri::AppHandle>,
+    emitter: &'a dyn Emitter,
     message_id: String,
 ) -> anyhow::Result<Toolset<'a>> {
-    let mut toolset = Toolset::new(ctx, app_handle, Some(message_id));
+    let mut toolset = Toolset::new(ctx, emitter, Some(message_id));
 
-    toolset.register_tool(Commit);
-    toolset.register_tool(CreateBranch);
-    toolset.register_tool(Amend);
-    toolset.register_tool(SquashCommits);
-    toolset.register_tool(GetProjectStatus);
-    toolset.register_tool(MoveFileChanges);
-    toolset.register_tool(GetCommitDetails);
-    toolset.register_tool(GetBranchChanges);
-    toolset.register_tool(SplitBranch);
-    toolset.register_tool(SplitCommit);
+    toolset.register_tools(workspace_tools());
 
     Ok(toolset)
 }
 
+/// Returns all tools that operate on the workspace, as registered by [`workspace_toolset()`].
+pub fn workspace_tools() -> Vec<Arc<dyn Tool>> {
+    vec![
+        Arc::new(Commit),
+        Arc::new(CreateBranch),
+        Arc::new(Amend),
+        Arc::new(SquashCommits),
+        Arc::new(GetProjectStatus),
+        Arc::new(CreateBlankCommit),
+        Arc::new(MoveFileChanges),
+        Arc::new(GetCommitDetails),
+        Arc::new(GetBranchChanges),
+        Arc::new(SplitBranch),
+        Arc::new(SplitCommit),
+    ]
+}
+
 /// Creates a toolset for workspace-related operations.
 pub fn commit_toolset<'a>(
     ctx: &'a mut CommandContext,
-    app_handle: Option<&'a tauri::AppHandle>,
+    emitter: &'a dyn Emitter,
 ) -> anyhow::Result<Toolset<'a>> {
-    let mut toolset = Toolset::new(ctx, app_handle, None);
+    let mut toolset = Toolset::new(ctx, emitter, None);
 
     toolset.register_tool(Commit);
     toolset.register_tool(CreateBranch);
@@ -59,9 +68,9 @@ pub fn commit_toolset<'a>(
 /// Creates a toolset for amend operations.
 pub fn amend_toolset<'a>(
     ctx: &'a mut CommandContext,
-    app_handle: Option<&'a tauri::AppHandle>,
+    emitter: &'a dyn Emitter,
 ) -> anyhow::Result<Toolset<'a>> {
-    let mut toolset = Toolset::new(ctx, app_handle, None);
+    let mut toolset = Toolset::new(ctx, emitter, None);
 
     toolset.register_tool(Amend);
     toolset.register_tool(GetProjectStatus);
@@ -174,24 +183,28 @@ impl Tool for Commit {
         serde_json::to_value(&schema).unwrap_or_default()
     }
 
+    fn created_commits(&self, _parameters: &serde_json::Value) -> usize {
+        1
+    }
+
     fn call(
         self: Arc<Self>,
         parameters: serde_json::Value,
         ctx: &mut CommandContext,
-        app_handle: Option<&tauri::AppHandle>,
+        emitter: &dyn Emitter,
         _: &mut HashMap<gix::ObjectId, gix::ObjectId>,
     ) -> anyhow::Result<serde_json::Value> {
         let params: CommitParameters = serde_json::from_value(parameters)
             .map_err(|e| anyhow::anyhow!("Failed to parse input parameters: {}", e))?;
 
-        let value = create_commit(ctx, app_handle, params).to_json("create_commit");
+        let value = create_commit(ctx, emitter, params).to_json("create_commit");
         Ok(value)
     }
 }
 
 pub fn create_commit(
     ctx: &mut CommandContext,
-    app_handle: Option<&tauri::AppHandle>,
+    emitter: &dyn Emitter,
     params: CommitParameters,
 ) -> Result<but_workspace::commit_engine::ui::CreateCommitOutcome, anyhow::Error> {
     let repo = ctx.gix_repo()?;
@@ -266,11 +279,9 @@ pub fn create_commit(
         )
     });
 
-    // If there's an app handle provided, emit an event to update the stack details in the UI.
-    if let Some(app_handle) = app_handle {
-        let project_id = ctx.project().id;
-        app_handle.emit_stack_update(project_id, stack_id);
-    }
+    // Emit an event to update the stack details in the UI.
+    let project_id = ctx.project().id;
+    emitter.emit_stack_update(project_id, stack_id);
 
     let outcome: but_workspace::commit_engine::ui::CreateCommitOutcome = outcome?.into();
     Ok(outcome)
@@ -335,20 +346,20 @@ impl Tool for CreateBranch {
         self: Arc<Self>,
         parameters: serde_json::Value,
         ctx: &mut CommandContext,
-        app_handle: Option<&tauri::AppHandle>,
+        emitter: &dyn Emitter,
         _: &mut HashMap<gix::ObjectId, gix::ObjectId>,
     ) -> anyhow::Result<serde_json::Value> {
         let params: CreateBranchParameters = serde_json::from_value(parameters)
             .map_err(|e| anyhow::anyhow!("Failed to parse input parameters: {}", e))?;
 
-        let stack = create_branch(ctx, app_handle, params).to_json("create branch");
+        let stack = create_branch(ctx, emitter, params).to_json("create branch");
         Ok(stack)
     }
 }
 
 pub fn create_branch(
     ctx: &mut CommandContext,
-    app_handle: Option<&tauri::AppHandle>,
+    emitter: &dyn Emitter,
     params: CreateBranchParameters,
 ) -> Result<StackEntryNoOpt, anyhow::Error> {
     let mut guard = ctx.project().exclusive_worktree_access();
@@ -376,11 +387,9 @@ pub fn create_branch(
         },
     )?;
 
-    // If there's an app handle provided, emit an event to update the stack details in the UI.
-    if let Some(app_handle) = app_handle {
-        let project_id = ctx.project().id;
-        app_handle.emit_stack_update(project_id, stack.id);
-    }
+    // Emit an event to update the stack details in the UI.
+    let project_id = ctx.project().id;
+    emitter.emit_stack_update(project_id, stack.id);
 
     Ok(stack_entry)
 }
@@ -485,28 +494,45 @@ impl Tool for Amend {
         serde_json::to_value(&schema).unwrap_or_default()
     }
 
+    fn rewritten_branches(
+        &self,
+        parameters: &serde_json::Value,
+        ctx: &CommandContext,
+        commit_mapping: &HashMap<gix::ObjectId, gix::ObjectId>,
+    ) -> anyhow::Result<Vec<String>> {
+        let Ok(params) = AmendParameters::deserialize(parameters) else {
+            return Ok(Vec::new());
+        };
+        crate::policy::branches_containing(
+            ctx,
+            &params.stack_id,
+            [params.commit_id.as_str()],
+            commit_mapping,
+        )
+    }
+
     fn call(
         self: Arc<Self>,
         parameters: serde_json::Value,
         ctx: &mut CommandContext,
-        app_handle: Option<&tauri::AppHandle>,
+        emitter: &dyn Emitter,
         commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
     ) -> anyhow::Result<serde_json::Value> {
         let params: AmendParameters = serde_json::from_value(parameters)
             .map_err(|e| anyhow::anyhow!("Failed to parse input parameters: {}", e))?;
 
-        let value = amend_commit(ctx, app_handle, params, commit_mapping).to_json("amend_commit");
+        let value = amend_commit(ctx, emitter, params, commit_mapping).to_json("amend_commit");
         Ok(value)
     }
 }
 
 pub fn amend_commit(
     ctx: &mut CommandContext,
-    app_handle: Option<&tauri::AppHandle>,
+    emitter: &dyn Emitter,
     params: AmendParameters,
     commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
 ) -> Result<but_workspace::commit_engine::ui::CreateCommitOutcome, anyhow::Error> {
-    let outcome = amend_commit_inner(ctx, app_handle, params, Some(commit_mapping))?;
+    let outcome = amend_commit_inner(ctx, emitter, params, Some(commit_mapping))?;
 
     // Update the commit mapping with the new commit id.
     if let Some(rebase_output) = outcome.rebase_output.clone() {
@@ -520,7 +546,7 @@ pub fn amend_commit(
 
 pub fn amend_commit_inner(
     ctx: &mut CommandContext,
-    app_handle: Option<&tauri::AppHandle>,
+    emitter: &dyn Emitter,
     params: AmendParameters,
     commit_mapping: Option<&HashMap<gix::ObjectId, gix::ObjectId>>,
 ) -> anyhow::Result<but_workspace::commit_engine::CreateCommitOutcome> {
@@ -563,15 +589,20 @@ pub fn amend_commit_inner(
         file_changes,
         settings.context_lines,
         guard.write_permission(),
-    );
-
-    // If there's an app handle provided, emit an event to update the stack details in the UI.
-    if let Some(app_handle) = app_handle {
-        let project_id = ctx.project().id;
-        app_handle.emit_stack_update(project_id, stack_id);
+    )?;
+    let shared = but_workspace::rebase_stacks_and_branches_sharing_amended_commit(
+        ctx, stack_id, commit_id, &outcome,
+    )?;
+    if !shared.updated_references.is_empty() {
+        let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+        update_workspace_commit(&vb_state, ctx)?;
     }
 
-    outcome
+    // Emit an event to update the stack details in the UI.
+    let project_id = ctx.project().id;
+    emitter.emit_stack_update(project_id, stack_id);
+
+    Ok(outcome)
 }
 
 pub struct GetProjectStatus;
@@ -617,7 +648,7 @@ impl Tool for GetProjectStatus {
         self: Arc<Self>,
         parameters: serde_json::Value,
         ctx: &mut CommandContext,
-        _app_handle: Option<&tauri::AppHandle>,
+        _emitter: &dyn Emitter,
         _commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
     ) -> anyhow::Result<serde_json::Value> {
         let repo = ctx.gix_repo()?;
@@ -715,17 +746,39 @@ impl Tool for CreateBlankCommit {
         serde_json::to_value(&schema).unwrap_or_default()
     }
 
+    fn rewritten_branches(
+        &self,
+        parameters: &serde_json::Value,
+        ctx: &CommandContext,
+        commit_mapping: &HashMap<gix::ObjectId, gix::ObjectId>,
+    ) -> anyhow::Result<Vec<String>> {
+        // The commits on top of the parent are rebased onto the new commit.
+        let Ok(params) = CreateBlankCommitParameters::deserialize(parameters) else {
+            return Ok(Vec::new());
+        };
+        crate::policy::branches_containing(
+            ctx,
+            &params.stack_id,
+            [params.parent_id.as_str()],
+            commit_mapping,
+        )
+    }
+
+    fn created_commits(&self, _parameters: &serde_json::Value) -> usize {
+        1
+    }
+
     fn call(
         self: Arc<Self>,
         parameters: serde_json::Value,
         ctx: &mut CommandContext,
-        app_handle: Option<&tauri::AppHandle>,
+        emitter: &dyn Emitter,
         commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
     ) -> anyhow::Result<serde_json::Value> {
         let params: CreateBlankCommitParameters = serde_json::from_value(parameters)
             .map_err(|e| anyhow::anyhow!("Failed to parse input parameters: {}", e))?;
 
-        let value = create_blank_commit(ctx, app_handle, params, commit_mapping)
+        let value = create_blank_commit(ctx, emitter, params, commit_mapping)
             .to_json("create_blank_commit");
         Ok(value)
     }
@@ -733,7 +786,7 @@ impl Tool for CreateBlankCommit {
 
 pub fn create_blank_commit(
     ctx: &mut CommandContext,
-    app_handle: Option<&tauri::AppHandle>,
+    emitter: &dyn Emitter,
     params: CreateBlankCommitParameters,
     commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
 ) -> Result<gix::ObjectId, anyhow::Error> {
@@ -756,11 +809,9 @@ pub fn create_blank_commit(
         Some(&message),
     )?;
 
-    // If there's an app handle provided, emit an event to update the stack details in the UI.
-    if let Some(app_handle) = app_handle {
-        let project_id = ctx.project().id;
-        app_handle.emit_stack_update(project_id, stack_id);
-    }
+    // Emit an event to update the stack details in the UI.
+    let project_id = ctx.project().id;
+    emitter.emit_stack_update(project_id, stack_id);
 
     // Update the commit mapping with the new commit id.
     for (old_commit_id, new_commit_id) in outcome.iter() {
@@ -862,17 +913,41 @@ impl Tool for MoveFileChanges {
         serde_json::to_value(&schema).unwrap_or_default()
     }
 
+    fn rewritten_branches(
+        &self,
+        parameters: &serde_json::Value,
+        ctx: &CommandContext,
+        commit_mapping: &HashMap<gix::ObjectId, gix::ObjectId>,
+    ) -> anyhow::Result<Vec<String>> {
+        let Ok(params) = MoveFileChangesParameters::deserialize(parameters) else {
+            return Ok(Vec::new());
+        };
+        let mut branches = crate::policy::branches_containing(
+            ctx,
+            &params.source_stack_id,
+            [params.source_commit_id.as_str()],
+            commit_mapping,
+        )?;
+        branches.extend(crate::policy::branches_containing(
+            ctx,
+            &params.destination_stack_id,
+            [params.destination_commit_id.as_str()],
+            commit_mapping,
+        )?);
+        Ok(branches)
+    }
+
     fn call(
         self: Arc<Self>,
         parameters: serde_json::Value,
         ctx: &mut CommandContext,
-        app_handle: Option<&tauri::AppHandle>,
+        emitter: &dyn Emitter,
         commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
     ) -> anyhow::Result<serde_json::Value> {
         let params: MoveFileChangesParameters = serde_json::from_value(parameters)
             .map_err(|e| anyhow::anyhow!("Failed to parse input parameters: {}", e))?;
 
-        match move_file_changes(ctx, app_handle, params, commit_mapping) {
+        match move_file_changes(ctx, emitter, params, commit_mapping) {
             Ok(_) => Ok("Success".into()),
             Err(e) => Ok(error_to_json(&e, "move_file_changes")),
         }
@@ -881,7 +956,7 @@ impl Tool for MoveFileChanges {
 
 pub fn move_file_changes(
     ctx: &mut CommandContext,
-    app_handle: Option<&tauri::AppHandle>,
+    emitter: &dyn Emitter,
     params: MoveFileChangesParameters,
     commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
 ) -> Result<Vec<(gix::ObjectId, gix::ObjectId)>, anyhow::Error> {
@@ -892,13 +967,27 @@ pub fn move_file_changes(
         .map(|id| find_the_right_commit_id(id, commit_mapping))?;
     let destination_stack_id = StackId::from_str(&params.destination_stack_id)?;
 
+    // Moved renames must remain renames, so their previous path is taken from the source commit.
+    let repo = ctx.gix_repo()?;
+    let source_changes: Vec<but_core::TreeChange> =
+        but_core::diff::ui::commit_changes_by_worktree_dir(&repo, source_commit_id)?
+            .changes
+            .into_iter()
+            .map(Into::into)
+            .collect();
     let changes = params
         .files
         .iter()
-        .map(|f| but_workspace::DiffSpec {
-            path: BString::from(f.as_str()),
-            previous_path: None,
-            hunk_headers: vec![],
+        .map(|f| {
+            source_changes
+                .iter()
+                .find(|change| change.path == f.as_str())
+                .map(but_workspace::DiffSpec::from)
+                .unwrap_or_else(|| but_workspace::DiffSpec {
+                    path: BString::from(f.as_str()),
+                    previous_path: None,
+                    hunk_headers: vec![],
+                })
         })
         .collect::<Vec<_>>();
 
@@ -915,12 +1004,10 @@ pub fn move_file_changes(
     let vb_state = VirtualBranchesHandle::new(ctx.project().gb_dir());
     gitbutler_branch_actions::update_workspace_commit(&vb_state, ctx)?;
 
-    // If there's an app handle provided, emit an event to update the stack details in the UI.
-    if let Some(app_handle) = app_handle {
-        let project_id = ctx.project().id;
-        app_handle.emit_stack_update(project_id, source_stack_id);
-        app_handle.emit_stack_update(project_id, destination_stack_id);
-    }
+    // Emit an event to update the stack details in the UI.
+    let project_id = ctx.project().id;
+    emitter.emit_stack_update(project_id, source_stack_id);
+    emitter.emit_stack_update(project_id, destination_stack_id);
 
     // Update the commit mapping with the new commit ids.
     for (old_commit_id, new_commit_id) in result.replaced_commits.clone().iter() {
@@ -977,7 +1064,7 @@ impl Tool for GetCommitDetails {
         self: Arc<Self>,
         parameters: serde_json::Value,
         ctx: &mut CommandContext,
-        _app_handle: Option<&tauri::AppHandle>,
+        _emitter: &dyn Emitter,
         commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
     ) -> anyhow::Result<serde_json::Value> {
         let params: GetCommitDetailsParameters = serde_json::from_value(parameters)
@@ -1057,7 +1144,7 @@ impl Tool for GetBranchChanges {
         self: Arc<Self>,
         parameters: serde_json::Value,
         ctx: &mut CommandContext,
-        _app_handle: Option<&tauri::AppHandle>,
+        _emitter: &dyn Emitter,
         _commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
     ) -> anyhow::Result<serde_json::Value> {
         let params: GetBranchChangesParameters = serde_json::from_value(parameters)
@@ -1188,18 +1275,38 @@ impl Tool for SquashCommits {
         serde_json::to_value(&schema).unwrap_or_default()
     }
 
+    fn rewritten_branches(
+        &self,
+        parameters: &serde_json::Value,
+        ctx: &CommandContext,
+        commit_mapping: &HashMap<gix::ObjectId, gix::ObjectId>,
+    ) -> anyhow::Result<Vec<String>> {
+        let Ok(params) = SquashCommitsParameters::deserialize(parameters) else {
+            return Ok(Vec::new());
+        };
+        crate::policy::branches_containing(
+            ctx,
+            &params.stack_id,
+            params
+                .source_commit_ids
+                .iter()
+                .chain(Some(&params.destination_commit_id))
+                .map(String::as_str),
+            commit_mapping,
+        )
+    }
+
     fn call(
         self: Arc<Self>,
         parameters: serde_json::Value,
         ctx: &mut CommandContext,
-        app_handle: Option<&tauri::AppHandle>,
+        emitter: &dyn Emitter,
         commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
     ) -> anyhow::Result<serde_json::Value> {
         let params: SquashCommitsParameters = serde_json::from_value(parameters)
             .map_err(|e| anyhow::anyhow!("Failed to parse input parameters: {}", e))?;
 
-        let value =
-            squash_commits(ctx, app_handle, params, commit_mapping).to_json("squash_commits");
+        let value = squash_commits(ctx, emitter, params, commit_mapping).to_json("squash_commits");
 
         Ok(value)
     }
@@ -1207,7 +1314,7 @@ impl Tool for SquashCommits {
 
 pub fn squash_commits(
     ctx: &mut CommandContext,
-    app_handle: Option<&tauri::AppHandle>,
+    emitter: &dyn Emitter,
     params: SquashCommitsParameters,
     commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
 ) -> Result<gix::ObjectId, anyhow::Error> {
@@ -1243,11 +1350,9 @@ pub fn squash_commits(
         message.as_str(),
     )?;
 
-    // If there's an app handle provided, emit an event to update the stack details in the UI.
-    if let Some(app_handle) = app_handle {
-        let project_id = ctx.project().id;
-        app_handle.emit_stack_update(project_id, stack_id);
-    }
+    // Emit an event to update the stack details in the UI.
+    let project_id = ctx.project().id;
+    emitter.emit_stack_update(project_id, stack_id);
 
     // Update the commit mapping with the new commit id.
     commit_mapping.insert(destination_id.to_gix(), new_commit_id.to_gix());
@@ -1325,23 +1430,34 @@ impl Tool for SplitBranch {
         serde_json::to_value(&schema).unwrap_or_default()
     }
 
+    fn rewritten_branches(
+        &self,
+        parameters: &serde_json::Value,
+        _ctx: &CommandContext,
+        _commit_mapping: &HashMap<gix::ObjectId, gix::ObjectId>,
+    ) -> anyhow::Result<Vec<String>> {
+        Ok(SplitBranchParameters::deserialize(parameters)
+            .map(|params| vec![params.source_branch_name])
+            .unwrap_or_default())
+    }
+
     fn call(
         self: Arc<Self>,
         parameters: serde_json::Value,
         ctx: &mut CommandContext,
-        app_handle: Option<&tauri::AppHandle>,
+        emitter: &dyn Emitter,
         commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
     ) -> anyhow::Result<serde_json::Value> {
         let params: SplitBranchParameters = serde_json::from_value(parameters)
             .map_err(|e| anyhow::anyhow!("Failed to parse input parameters: {}", e))?;
 
-        Ok(split_branch(ctx, app_handle, params, commit_mapping).to_json("split_branch"))
+        Ok(split_branch(ctx, emitter, params, commit_mapping).to_json("split_branch"))
     }
 }
 
 pub fn split_branch(
     ctx: &mut CommandContext,
-    app_handle: Option<&tauri::AppHandle>,
+    emitter: &dyn Emitter,
     params: SplitBranchParameters,
     commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
 ) -> Result<StackId, anyhow::Error> {
@@ -1388,11 +1504,9 @@ pub fn split_branch(
         guard.write_permission(),
     )?;
 
-    // If there's an app handle provided, emit an event to update the stack details in the UI.
-    if let Some(app_handle) = app_handle {
-        let project_id = ctx.project().id;
-        app_handle.emit_stack_update(project_id, stack_id);
-    }
+    // Emit an event to update the stack details in the UI.
+    let project_id = ctx.project().id;
+    emitter.emit_stack_update(project_id, stack_id);
 
     // Update the commit mapping with the new commit ids.
     for (old_commit_id, new_commit_id) in move_result.replaced_commits.iter() {
@@ -1472,24 +1586,47 @@ impl Tool for SplitCommit {
         serde_json::to_value(&schema).unwrap_or_default()
     }
 
+    fn rewritten_branches(
+        &self,
+        parameters: &serde_json::Value,
+        ctx: &CommandContext,
+        commit_mapping: &HashMap<gix::ObjectId, gix::ObjectId>,
+    ) -> anyhow::Result<Vec<String>> {
+        let Ok(params) = SplitCommitParameters::deserialize(parameters) else {
+            return Ok(Vec::new());
+        };
+        crate::policy::branches_containing(
+            ctx,
+            &params.source_stack_id,
+            [params.source_commit_id.as_str()],
+            commit_mapping,
+        )
+    }
+
+    fn created_commits(&self, parameters: &serde_json::Value) -> usize {
+        // The split commit is replaced by its shards.
+        SplitCommitParameters::deserialize(parameters)
+            .map_or(0, |params| params.shards.len().saturating_sub(1))
+    }
+
     fn call(
         self: Arc<Self>,
         parameters: serde_json::Value,
         ctx: &mut CommandContext,
-        app_handle: Option<&tauri::AppHandle>,
+        emitter: &dyn Emitter,
         commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
     ) -> anyhow::Result<serde_json::Value> {
         let params = serde_json::from_value::<SplitCommitParameters>(parameters)
             .map_err(|e| anyhow::anyhow!("Failed to parse input parameters: {}", e))?;
 
-        let value = split_commit(ctx, params, app_handle, commit_mapping).to_json("split_commit");
+        let value = split_commit(ctx, params, emitter, commit_mapping).to_json("split_commit");
         Ok(value)
     }
 }
 pub fn split_commit(
     ctx: &mut CommandContext,
     params: SplitCommitParameters,
-    app_handle: Option<&tauri::AppHandle>,
+    emitter: &dyn Emitter,
     commit_mapping: &mut HashMap<gix::ObjectId, gix::ObjectId>,
 ) -> Result<Vec<gix::ObjectId>, anyhow::Error> {
     let source_stack_id = StackId::from_str(&params.source_stack_id)?;
@@ -1516,10 +1653,8 @@ pub fn split_commit(
     } = outcome;
 
     // Emit an stack update for the frontend.
-    if let Some(app_handle) = app_handle {
-        let project_id = ctx.project().id;
-        app_handle.emit_stack_update(project_id, source_stack_id);
-    }
+    let project_id = ctx.project().id;
+    emitter.emit_stack_update(project_id, source_stack_id);
 
     // Update the commit mapping with the new commit ids.
     for (old_commit_id, new_commit_id) in move_changes_result.replaced_commits.iter() {
@@ -1594,7 +1729,7 @@ impl From<CommitShard> for but_workspace::CommitFiles {
     }
 }
 
-fn ref_metadata_toml(project: &Project) -> anyhow::Result<VirtualBranchesTomlMetadata> {
+pub(crate) fn ref_metadata_toml(project: &Project) -> anyhow::Result<VirtualBranchesTomlMetadata> {
     VirtualBranchesTomlMetadata::from_path(project.gb_dir().join("virtual_branches.toml"))
 }
 
@@ -1985,7 +2120,7 @@ pub struct AbsorbSpec {
     pub commit_description: String,
 }
 
-fn find_the_right_commit_id(
+pub(crate) fn find_the_right_commit_id(
     commit_id: gix::ObjectId,
     commit_mapping: &HashMap<gix::ObjectId, gix::ObjectId>,
 ) -> gix::ObjectId {
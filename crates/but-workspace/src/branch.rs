@@ -416,7 +416,10 @@ use crate::ref_info;
 use anyhow::{Context, bail};
 use but_core::RefMetadata;
 use but_core::ref_metadata::StackId;
+use but_graph::CommitFlags;
+use gix::bstr::ByteSlice;
 use gix::prelude::ObjectIdExt;
+use gix::refs::Category;
 
 /// The result of [`add_branch_to_workspace`].
 #[derive(Debug, Clone)]
@@ -534,3 +537,123 @@ impl Stack {
 pub fn stack_segments(stack: Stack) -> anyhow::Result<Vec<ref_info::ui::Segment>> {
     todo!()
 }
+
+/// Return the names of the local branches that are stacked on top of each other, starting at the entrypoint of `graph`
+/// and following the first parent downward, top-most branch first.
+///
+/// This is useful to turn a chain of plain Git branches, like `feat-3` on top of `feat-2` on top of `feat-1`, into
+/// a single stack.
+/// Traverse the graph with the target commit set in its options, as the chain stops at the first integrated commit.
+/// It also stops after the first branch that contains a merge commit, and at commits which are pointed to by more than
+/// one local branch, as their order is ambiguous.
+/// The returned list is empty if the entrypoint isn't a local branch with commits that aren't integrated yet.
+pub fn linear_branch_chain(graph: &but_graph::Graph) -> anyhow::Result<Vec<gix::refs::FullName>> {
+    let mut out = Vec::new();
+    let mut segment = graph.lookup_entrypoint()?.segment;
+    loop {
+        let Some(ref_name) = segment
+            .ref_name
+            .as_ref()
+            .filter(|rn| rn.category() == Some(Category::LocalBranch))
+        else {
+            break;
+        };
+        let Some(first_commit) = segment.commits.first() else {
+            break;
+        };
+        if first_commit.flags.contains(CommitFlags::Integrated) {
+            break;
+        }
+        out.push(ref_name.to_owned());
+
+        let has_merge = segment.commits.iter().any(|c| c.parent_ids.len() > 1);
+        let has_ambiguous_branches = segment.commits.iter().skip(1).any(|c| {
+            c.refs
+                .iter()
+                .any(|rn| rn.category() == Some(Category::LocalBranch))
+        });
+        if has_merge || has_ambiguous_branches {
+            break;
+        }
+        let Some((_, below)) = graph.segments_below_in_order(segment.id).next() else {
+            break;
+        };
+        segment = &graph[below];
+    }
+    Ok(out)
+}
+
+/// Configure each of the local branches in `chain`, top-most branch first, to have the branch below it as upstream,
+/// so that tools for stacking branches as well as plain Git can see how they are stacked.
+/// The lowest branch gets `base` as upstream, which may be a local or a remote tracking branch, or is left alone if
+/// it's `None`.
+///
+/// Branches that track a branch in the same repository have `branch.<name>.remote` set to `.`, and
+/// `branch.<name>.merge` set to the full name of that branch. Previously configured upstreams are overwritten.
+/// Note that `repo` won't see the changes until its configuration is reloaded.
+pub fn set_upstreams_along_branch_chain(
+    repo: &gix::Repository,
+    chain: &[gix::refs::FullName],
+    base: Option<&gix::refs::FullNameRef>,
+) -> anyhow::Result<()> {
+    let mut upstreams = Vec::with_capacity(chain.len());
+    for (idx, ref_name) in chain.iter().enumerate() {
+        let (_, short_name) = ref_name
+            .category_and_short_name()
+            .filter(|(category, _)| *category == Category::LocalBranch)
+            .with_context(|| format!("'{}' isn't a local branch", ref_name.as_bstr()))?;
+        let upstream = match chain.get(idx + 1) {
+            Some(below) => Some((".".to_owned(), below.as_bstr().to_owned())),
+            None => base.map(|base| upstream_of(repo, base)).transpose()?,
+        };
+        if let Some((remote, merge)) = upstream {
+            upstreams.push((short_name.to_owned(), remote, merge));
+        }
+    }
+
+    but_core::settings::git::edit_local_config(repo, |config| {
+        for (short_name, remote, merge) in &upstreams {
+            let short_name = Some(short_name.as_bstr());
+            config.set_raw_value_by("branch", short_name, "remote", remote.as_str())?;
+            config.set_raw_value_by("branch", short_name, "merge", merge.as_bstr())?;
+        }
+        Ok(())
+    })
+}
+
+/// Return the remote name and the name of the reference on the remote that configure `ref_name` as upstream.
+fn upstream_of(
+    repo: &gix::Repository,
+    ref_name: &gix::refs::FullNameRef,
+) -> anyhow::Result<(String, gix::bstr::BString)> {
+    match ref_name.category_and_short_name() {
+        Some((Category::LocalBranch, _)) => Ok((".".to_owned(), ref_name.as_bstr().to_owned())),
+        Some((Category::RemoteBranch, short_name)) => {
+            let remote_names = repo.remote_names();
+            // Prefer the longest remote name in case remote names are prefixes of each other.
+            let (remote_name, branch_name) = remote_names
+                .iter()
+                .rev()
+                .find_map(|remote_name| {
+                    let remote: &[u8] = remote_name;
+                    short_name
+                        .strip_prefix(remote)
+                        .and_then(|rest| rest.strip_prefix(b"/"))
+                        .map(|branch_name| (remote_name, branch_name))
+                })
+                .with_context(|| {
+                    format!(
+                        "Could not find the remote of remote tracking branch '{}'",
+                        ref_name.as_bstr()
+                    )
+                })?;
+            let mut merge = gix::bstr::BString::from("refs/heads/");
+            merge.extend_from_slice(branch_name);
+            Ok((remote_name.to_string(), merge))
+        }
+        _ => bail!(
+            "'{}' must be a local or remote tracking branch to be used as upstream",
+            ref_name.as_bstr()
+        ),
+    }
+}
@@ -2,23 +2,25 @@
 //! Changesets can have IDs which uniquely identify a set of changes, independently of which trees it originated from.
 //!
 //! This property allows changeset IDs to be used to determine if two different commits, or sets of commits,
-//! represent the same change.
+//! represent the same change, which is what [`compare_ranges()`] uses to compare two versions of a branch.
 
 use crate::{
     RefInfo,
+    integrated::IntegrationMethod,
     ref_info::{
         ui,
         ui::{LocalCommit, LocalCommitRelation},
     },
     ui::PushStatus,
 };
+use anyhow::bail;
 use but_core::{ChangeState, commit::TreeKind};
 use gix::diff::tree::recorder::Change;
 use gix::{ObjectId, Repository, object::tree::EntryKind, prelude::ObjectIdExt};
 use std::ops::Deref;
 use std::{
     borrow::Borrow,
-    collections::{HashMap, HashSet, hash_map::Entry},
+    collections::{BTreeMap, HashMap, HashSet, hash_map::Entry},
 };
 
 /// The ID of a changeset, calculated as Git hash for convenience.
@@ -49,11 +51,14 @@ impl RefInfo {
     /// are left to test.
     ///
     /// If `expensive` is `true`, we will run checks that involve changeset-id computation and squash-merge trials.
+    /// `integrated_by_patch` maps local commits to the upstream commit with the same patch-id, as
+    /// [marked in the workspace](but_graph::projection::Workspace::mark_integrated_by_patch()).
     pub(crate) fn compute_similarity(
         &mut self,
         graph: &but_graph::Graph,
         repo: &gix::Repository,
         expensive: bool,
+        integrated_by_patch: &BTreeMap<gix::ObjectId, gix::ObjectId>,
     ) -> anyhow::Result<()> {
         let topmost_target_sidx = self
             .target
@@ -97,6 +102,7 @@ impl RefInfo {
             }),
             cost_info,
             expensive,
+            Some(MAX_DURATION),
         )?;
 
         // Cheap checks to see which local commits belong to rebased remote or upstream commits.
@@ -109,6 +115,7 @@ impl RefInfo {
                     segment.commits_on_remote.iter(),
                     cost_info,
                     expensive,
+                    Some(MAX_DURATION),
                 )?;
 
                 for local in segment
@@ -128,12 +135,20 @@ impl RefInfo {
                     })
                 {
                     let expensive = changeset_identifier(repo, expensive.then_some(local))?;
-                    if let Some(upstream_commit_id) =
-                        lookup_similar(&upstream_lut, local, expensive.as_ref(), ChangeId::Skip)
-                    {
+                    if let Some((upstream_commit_id, identifier)) = lookup_similar_identified(
+                        &upstream_lut,
+                        local,
+                        expensive.as_ref(),
+                        ChangeId::Skip,
+                    ) {
                         // Note that by keeping track of the upstream id, we can't abort early.
                         // Only expensive for expensive checks, so let's see.
                         local.relation = LocalCommitRelation::Integrated(*upstream_commit_id);
+                        local.integration = Some(identifier.integration_method());
+                    } else if let Some(upstream_commit_id) = integrated_by_patch.get(&local.id) {
+                        // Patch-ids also match if the same change was applied in a different context.
+                        local.relation = LocalCommitRelation::Integrated(*upstream_commit_id);
+                        local.integration = Some(IntegrationMethod::PatchId);
                     } else if let Some(remote_commit_id) =
                         lookup_similar(&remote_lut, local, expensive.as_ref(), ChangeId::Use)
                     {
@@ -186,7 +201,8 @@ impl RefInfo {
 
                 for segment in Some(segment).into_iter().chain(segments) {
                     for commit in &mut segment.commits {
-                        commit.relation = LocalCommitRelation::Integrated(squashed_commit_id)
+                        commit.relation = LocalCommitRelation::Integrated(squashed_commit_id);
+                        commit.integration = Some(IntegrationMethod::Squash);
                     }
                 }
                 break;
@@ -256,6 +272,131 @@ impl PushStatus {
     }
 }
 
+/// The commits reachable from `tip`, but not from `base`, following only the first parent.
+/// That way, `base` doesn't have to be an ancestor of `tip`, like in `main..feature`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitRange {
+    /// The topmost commit of the range, typically the tip of a branch.
+    pub tip: gix::ObjectId,
+    /// The commit at which the range stops, without being part of it.
+    /// If `None`, the merge-base with the other side of the [comparison](compare_ranges()) is used.
+    pub base: Option<gix::ObjectId>,
+}
+
+impl CommitRange {
+    /// Parse `spec` as a single revision like `feature` or `origin/feature`, or as a range like `main..feature`.
+    pub fn from_spec(repo: &gix::Repository, spec: &str) -> anyhow::Result<Self> {
+        let peel = |id: gix::ObjectId| -> anyhow::Result<gix::ObjectId> {
+            Ok(id.attach(repo).object()?.peel_to_commit()?.id)
+        };
+        Ok(match repo.rev_parse(spec)?.detach() {
+            gix::revision::plumbing::Spec::Include(tip) => CommitRange {
+                tip: peel(tip)?,
+                base: None,
+            },
+            gix::revision::plumbing::Spec::Range { from, to } => CommitRange {
+                tip: peel(to)?,
+                base: Some(peel(from)?),
+            },
+            other => bail!("'{spec}' must be a single revision or a range, got {other:?}"),
+        })
+    }
+}
+
+/// The outcome of [`compare_ranges()`], with all commits listed top to bottom as seen from their side.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RangeComparison {
+    /// `(ours, theirs)` commits which represent the same changes, as they have the same changeset ID
+    /// or are the same commit.
+    pub equivalent: Vec<(gix::ObjectId, gix::ObjectId)>,
+    /// `(ours, theirs)` commits which represent the same change as identified by change-id or by author and message,
+    /// but with different changes, for instance because they were edited after a rebase.
+    pub edited: Vec<(gix::ObjectId, gix::ObjectId)>,
+    /// Commits that only exist on our side.
+    pub only_ours: Vec<gix::ObjectId>,
+    /// Commits that only exist on their side.
+    pub only_theirs: Vec<gix::ObjectId>,
+}
+
+/// Compare the commits in the `ours` range to the ones in the `theirs` range to learn which of them represent
+/// the same change, even if they were rebased, and which ones differ.
+///
+/// This is useful to compare a local branch to a version of it pushed by someone else before force-pushing over it.
+/// Note that merge commits are compared by the changes to their first parent.
+pub fn compare_ranges(
+    repo: &gix::Repository,
+    ours: CommitRange,
+    theirs: CommitRange,
+) -> anyhow::Result<RangeComparison> {
+    let merge_base = if ours.base.is_none() || theirs.base.is_none() {
+        Some(repo.merge_base(ours.tip, theirs.tip)?.detach())
+    } else {
+        None
+    };
+    let ours = commits_in_range(repo, ours.tip, ours.base.or(merge_base))?;
+    let theirs = commits_in_range(repo, theirs.tip, theirs.base.or(merge_base))?;
+
+    let cost_info = (theirs.len(), repo.index_or_empty()?.entries().len());
+    // No time limit here, as commits that weren't diffed in time would incorrectly be reported as different.
+    let theirs_lut = create_similarity_lut(repo, theirs.iter(), cost_info, true, None)?;
+    let theirs_ids: HashSet<_> = theirs.iter().map(|c| c.id).collect();
+
+    let mut out = RangeComparison::default();
+    let mut matched = HashSet::new();
+    for commit in &ours {
+        if theirs_ids.contains(&commit.id) {
+            matched.insert(commit.id);
+            out.equivalent.push((commit.id, commit.id));
+            continue;
+        }
+        let changeset_id = changeset_identifier(repo, Some(commit))?;
+        let unmatched = |id: &&gix::ObjectId| !matched.contains(*id);
+        if let Some(theirs_id) = changeset_id
+            .and_then(|id| theirs_lut.get(&id))
+            .filter(unmatched)
+            .copied()
+        {
+            matched.insert(theirs_id);
+            out.equivalent.push((commit.id, theirs_id));
+        } else if let Some(theirs_id) = lookup_similar(&theirs_lut, commit, None, ChangeId::Use)
+            .filter(unmatched)
+            .copied()
+        {
+            matched.insert(theirs_id);
+            out.edited.push((commit.id, theirs_id));
+        } else {
+            out.only_ours.push(commit.id);
+        }
+    }
+    out.only_theirs = theirs
+        .iter()
+        .map(|c| c.id)
+        .filter(|id| !matched.contains(id))
+        .collect();
+    Ok(out)
+}
+
+/// Return the commits from `tip` down to `base`, or the merge-base of both, which is where `tip` would stop
+/// being exclusive to its range.
+fn commits_in_range(
+    repo: &gix::Repository,
+    tip: gix::ObjectId,
+    base: Option<gix::ObjectId>,
+) -> anyhow::Result<Vec<ui::Commit>> {
+    let base = base
+        .map(|base| repo.merge_base(tip, base).map(|id| id.detach()))
+        .transpose()?;
+    let mut commits = Vec::new();
+    for info in tip.attach(repo).ancestors().first_parent_only().all()? {
+        let info = info?;
+        if Some(info.id) == base {
+            break;
+        }
+        commits.push(but_core::Commit::from_id(info.id.attach(repo))?.into());
+    }
+    Ok(commits)
+}
+
 fn changeset_identifier(
     repo: &gix::Repository,
     commit: Option<&ui::Commit>,
@@ -284,21 +425,42 @@ fn lookup_similar<'a>(
     expensive: Option<&Identifier>,
     change_id: ChangeId,
 ) -> Option<&'a gix::ObjectId> {
+    lookup_similar_identified(map, commit, expensive, change_id).map(|(id, _)| id)
+}
+
+/// Like [`lookup_similar()`], but also return the identifier that matched.
+fn lookup_similar_identified<'a>(
+    map: &'a Identity,
+    commit: &ui::Commit,
+    expensive: Option<&Identifier>,
+    change_id: ChangeId,
+) -> Option<(&'a gix::ObjectId, &'a Identifier)> {
     commit
         .change_id
         .as_ref()
         .filter(|_| matches!(change_id, ChangeId::Use))
-        .and_then(|cid| map.get(&Identifier::ChangeId(*cid)))
-        .or_else(|| commit_data_id(commit).ok().and_then(|id| map.get(&id)))
-        .or_else(|| map.get(expensive?))
+        .and_then(|cid| map.get_key_value(&Identifier::ChangeId(*cid)))
+        .or_else(|| {
+            commit_data_id(commit)
+                .ok()
+                .and_then(|id| map.get_key_value(&id))
+        })
+        .or_else(|| map.get_key_value(expensive?))
+        .map(|(identifier, id)| (id, identifier))
 }
 
+/// The time after which expensive changeset computations are stopped when building the similarity lookup table
+/// for display, leaving the remaining commits to be matched by cheaper means only.
+const MAX_DURATION: std::time::Duration = std::time::Duration::from_secs(1);
+
 /// Returns the fully-loaded commits suitable to be passed to UI, to have better re-use.
+/// If `max_duration` is set, expensive computations stop after this time, which makes the result incomplete.
 fn create_similarity_lut(
     repo: &Repository,
     commits: impl Iterator<Item = impl Borrow<ui::Commit>>,
     (max_commits, num_tracked_files): (usize, usize),
     expensive: bool,
+    max_duration: Option<std::time::Duration>,
 ) -> anyhow::Result<Identity> {
     // experimental modern CPU perf, based on 120 diffs/s at 90k entries
     // Make this smaller to get more threads even with lower amounts of work.
@@ -332,12 +494,14 @@ fn create_similarity_lut(
     };
 
     let should_stop = |start: std::time::Instant, commit_idx: usize| {
-        const MAX_DURATION: std::time::Duration = std::time::Duration::from_secs(1);
-        let out_of_time = start.elapsed() > MAX_DURATION;
+        let Some(max_duration) = max_duration else {
+            return false;
+        };
+        let out_of_time = start.elapsed() > max_duration;
         if out_of_time {
             tracing::warn!(
                 "Stopping expensive changeset computation after {}s and {commit_idx} diffs computed ({throughput:02} diffs/s)",
-                MAX_DURATION.as_secs(),
+                max_duration.as_secs(),
                 throughput = commit_idx as f32 / start.elapsed().as_secs_f32(),
             );
         }
@@ -582,6 +746,17 @@ enum Identifier {
     ChangesetId(ChangesetID),
 }
 
+impl Identifier {
+    /// Return how a commit was integrated if it was found upstream by this identifier.
+    fn integration_method(&self) -> IntegrationMethod {
+        match self {
+            Identifier::ChangeId(_) => IntegrationMethod::ChangeId,
+            Identifier::CommitData(_) => IntegrationMethod::AuthorAndMessage,
+            Identifier::ChangesetId(_) => IntegrationMethod::ChangesetId,
+        }
+    }
+}
+
 fn commit_data_id(c: &ui::Commit) -> anyhow::Result<Identifier> {
     let mut hasher = gix::hash::hasher(gix::hash::Kind::Sha1);
 
@@ -97,15 +97,15 @@ pub struct HunkRange {
 
 /// A type used in [`CreateCommitOutcome`] to indicate how a reference was changed so it keeps pointing
 /// to the correct commit.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct UpdatedReference {
     /// The reference itself.
     // TODO: The virtual variant could contain stack-id as well, but it remains to be seen how useful this is.
-    reference: but_core::Reference,
+    pub reference: but_core::Reference,
     /// The commit to which `reference` pointed before the update.
-    old_commit_id: gix::ObjectId,
+    pub old_commit_id: gix::ObjectId,
     /// The commit to which `reference` points now.
-    new_commit_id: gix::ObjectId,
+    pub new_commit_id: gix::ObjectId,
 }
 
 /// Additional information about the outcome of a [`create_commit()`] call.
@@ -161,8 +161,9 @@ pub enum RejectionReason {
     /// A change with multiple hunks to be applied wasn't present in the base-tree.
     /// Previously this was possible when untracked files were added with their single hunk specified, but now this shouldn't be happening anymore.
     PathNotFoundInBaseTree,
-    /// There was a change, but the path pointed to something that wasn't a file or a link.
-    /// You would see this if also in case of submodules or repositories to be added with hunks, which shouldn't be easy to do accidentally even.
+    /// There was a change, but the path pointed to something that wasn't a file, a link or a repository.
+    /// You would see this if also in case of directories of submodules that aren't checked out, or of repositories with an unborn `HEAD`.
+    /// Repositories are also rejected unless they are listed in `.gitmodules` or already tracked as submodule.
     UnsupportedDirectoryEntry,
     /// The base version of a file to apply worktree changes to as present in a Git tree had an undiffable entry type.
     /// This can happen if the target tree has an entry that isn't of the same type as the source worktree changes.
@@ -256,7 +257,7 @@ pub fn create_commit(
                 let mut commit = but_core::Commit::from_id(commit_id.attach(repo))?;
                 commit.tree = new_tree;
                 if let Some(message) = new_message {
-                    commit.message = message.into();
+                    but_rebase::commit::set_message(&mut commit.inner, message.into());
                 }
                 Some(but_rebase::commit::create(
                     repo,
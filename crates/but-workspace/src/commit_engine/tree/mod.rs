@@ -143,6 +143,32 @@ pub fn create_tree(
     })
 }
 
+/// Return `true` if the repository at `rela_path` is a submodule as listed in `.gitmodules`, or if it's already tracked
+/// as such in the `index` or the `HEAD` tree.
+/// That way, other repositories that happen to be in the worktree, like clones of dependencies, aren't committed by accident.
+fn is_known_submodule(
+    repo: &gix::Repository,
+    index: &gix::index::State,
+    rela_path: &BStr,
+) -> anyhow::Result<bool> {
+    let is_listed_in_gitmodules = repo
+        .submodules()?
+        .into_iter()
+        .flatten()
+        .any(|sm| sm.path().ok().is_some_and(|sm_path| sm_path == rela_path));
+    if is_listed_in_gitmodules
+        || index
+            .entry_by_path(rela_path)
+            .is_some_and(|e| e.mode == gix::index::entry::Mode::COMMIT)
+    {
+        return Ok(true);
+    }
+    let head_tree = repo.head_tree_id_or_empty()?.object()?.into_tree();
+    Ok(head_tree
+        .lookup_entry_by_path(gix::path::from_bstr(rela_path))?
+        .is_some_and(|e| e.mode().is_commit()))
+}
+
 fn into_err_spec(input: &mut PossibleChange, reason: RejectionReason) {
     *input = match std::mem::replace(input, Ok(Default::default())) {
         // What we thought was a good change turned out to be a no-op, rejected.
@@ -174,6 +200,9 @@ pub fn apply_worktree_changes<'repo>(
     let worktree_changes = has_changes_with_hunks
         .then(|| but_core::diff::worktree_changes(repo).map(|wtc| wtc.changes))
         .transpose()?;
+    let mut lfs_attributes = has_changes_with_hunks
+        .then(|| but_core::lfs::Attributes::new(repo))
+        .transpose()?;
     let mut current_worktree = Vec::new();
 
     let work_dir = repo.workdir().expect("non-bare repo");
@@ -195,6 +224,30 @@ pub fn apply_worktree_changes<'repo>(
         if let Some(previous_path) = change_request.previous_path.as_ref().map(|p| p.as_bstr()) {
             base_tree_editor.remove(previous_path)?;
         }
+        if md.is_dir() {
+            // Only repositories, like submodules, can be committed as directory. They are recorded as the commit
+            // their `HEAD` points to, and hunks are meaningless for them.
+            let rela_path = change_request.path.as_bstr();
+            if !is_known_submodule(repo, &index, rela_path)? {
+                into_err_spec(possible_change, RejectionReason::UnsupportedDirectoryEntry);
+                continue;
+            }
+            match repo.worktree_repository_head_id(rela_path)? {
+                Some(id) => {
+                    base_tree_editor.upsert(rela_path, EntryKind::Commit, id)?;
+                }
+                None => into_err_spec(possible_change, RejectionReason::UnsupportedDirectoryEntry),
+            }
+            continue;
+        }
+        if let Some(lfs_attributes) = lfs_attributes.as_mut() {
+            // The pointer to an LFS object is produced by the clean filter, so only the whole file can be committed.
+            if !change_request.hunk_headers.is_empty()
+                && lfs_attributes.is_tracked(change_request.path.as_bstr())?
+            {
+                change_request.hunk_headers.clear();
+            }
+        }
         if change_request.hunk_headers.is_empty() {
             let rela_path = change_request.path.as_bstr();
             match pipeline.worktree_file_to_object(rela_path, &index)? {
@@ -1,6 +1,12 @@
 //! ### Detecting if a commit is integrated
 //!
 //! This code is a fork of the [`gitbutler_branch_actions::virtual::IsCommitIntegrated`]
+//!
+//! Besides commit-ids and change-ids, commits are matched with upstream commits by their patch-id,
+//! which detects rebased and cherry-picked commits, and by the patch-id of the branch up to the commit,
+//! which detects squash-merges. Each detected integration is returned with its [provenance](Integration).
+
+use std::collections::HashMap;
 
 use anyhow::anyhow;
 use anyhow::{Context, Result};
@@ -12,14 +18,66 @@ use gitbutler_repo::{
 };
 use gitbutler_stack::Target;
 use itertools::Itertools;
+use serde::Serialize;
+
+/// The way a local commit was found to be integrated into the target branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrationMethod {
+    /// The commit itself is part of the upstream commits.
+    CommitId,
+    /// An upstream commit has the same change-id.
+    ChangeId,
+    /// An upstream commit has the same author and message, so it's likely the commit was rebased.
+    AuthorAndMessage,
+    /// An upstream commit has the same changeset ID, which is computed from the changes it makes to its parent.
+    ChangesetId,
+    /// The commit is reachable from the previous target, and thus part of its history.
+    Ancestor,
+    /// An upstream commit has the same patch-id, so it's likely the commit was rebased or cherry-picked.
+    PatchId,
+    /// An upstream commit has the patch-id of all changes of the branch up to and including the commit,
+    /// so it's likely that the branch was squash-merged.
+    Squash,
+    /// Merging the commit into the upstream tree doesn't change it, so its changes are contained in it
+    /// without being attributable to specific upstream commits.
+    TreeMerge,
+}
+
+/// Where a local commit was found to be integrated into the target branch, and how.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Integration {
+    /// How the integration was detected.
+    pub method: IntegrationMethod,
+    /// The upstream commits that contain the changes of the local commit.
+    /// If they can't be attributed to specific commits, as with [`IntegrationMethod::Ancestor`] and
+    /// [`IntegrationMethod::TreeMerge`], it's the tip of the target branch.
+    #[serde(with = "gitbutler_serde::object_id_vec")]
+    pub upstream_commits: Vec<gix::ObjectId>,
+}
+
+impl Integration {
+    /// Create a new instance for `method` and the `upstream_commits` it found.
+    pub fn new(method: IntegrationMethod, upstream_commits: Vec<gix::ObjectId>) -> Self {
+        Integration {
+            method,
+            upstream_commits,
+        }
+    }
+}
 
 pub(crate) struct IsCommitIntegrated<'repo, 'cache, 'graph> {
     repo: &'repo gix::Repository,
     pub graph: &'graph mut MergeBaseCommitGraph<'repo, 'cache>,
     target_commit_id: gix::ObjectId,
+    /// The tip of the target branch, which integrates everything that can't be attributed to specific commits.
+    upstream_head_id: gix::ObjectId,
     upstream_tree_id: gix::ObjectId,
     upstream_commits: Vec<gix::ObjectId>,
-    upstream_change_ids: Vec<String>,
+    /// The change-ids of the upstream commits along with the commit, sorted by change-id.
+    upstream_change_ids: Vec<(String, gix::ObjectId)>,
+    upstream_patch_ids: UpstreamPatchIds,
 }
 
 impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
@@ -41,10 +99,10 @@ impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
             git2_repo.log(remote_head.id(), LogUntil::Commit(target.sha), true)?;
         let upstream_change_ids = upstream_commits
             .iter()
-            .filter_map(|commit| commit.change_id())
+            .filter_map(|commit| Some((commit.change_id()?, commit.id().to_gix())))
             .sorted()
             .collect();
-        let upstream_commits = upstream_commits
+        let upstream_commits: Vec<_> = upstream_commits
             .iter()
             .map(|commit| commit.id().to_gix())
             .sorted()
@@ -53,16 +111,23 @@ impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
             repo,
             graph,
             target_commit_id: git2_to_gix_object_id(target.sha),
+            upstream_head_id: remote_head.id().to_gix(),
             upstream_tree_id: git2_to_gix_object_id(upstream_tree_id),
+            upstream_patch_ids: UpstreamPatchIds::new(upstream_commits.clone()),
             upstream_commits,
             upstream_change_ids,
         })
     }
 
     pub(crate) fn is_integrated(&mut self, commit: &git2::Commit<'_>) -> Result<bool> {
+        Ok(self.integration(commit)?.is_some())
+    }
+
+    /// Return how `commit` was integrated into the target branch, or `None` if it wasn't.
+    pub(crate) fn integration(&mut self, commit: &git2::Commit<'_>) -> Result<Option<Integration>> {
         if self.target_commit_id == git2_to_gix_object_id(commit.id()) {
             // could not be integrated if heads are the same.
-            return Ok(false);
+            return Ok(None);
         }
 
         // TODO: this relies on knowing that we update the workspace, notice that something is
@@ -72,12 +137,23 @@ impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
         //       So this would have to be removed.
         if self.upstream_commits.is_empty() {
             // could not be integrated - there is nothing new upstream.
-            return Ok(false);
+            return Ok(None);
         }
 
         if let Some(change_id) = commit.change_id() {
-            if self.upstream_change_ids.binary_search(&change_id).is_ok() {
-                return Ok(true);
+            let start = self
+                .upstream_change_ids
+                .partition_point(|(upstream_change_id, _)| *upstream_change_id < change_id);
+            let upstream_commits: Vec<_> = self.upstream_change_ids[start..]
+                .iter()
+                .take_while(|(upstream_change_id, _)| *upstream_change_id == change_id)
+                .map(|(_, id)| *id)
+                .collect();
+            if !upstream_commits.is_empty() {
+                return Ok(Some(Integration::new(
+                    IntegrationMethod::ChangeId,
+                    upstream_commits,
+                )));
             }
         }
 
@@ -86,7 +162,10 @@ impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
             .binary_search(&commit.id().to_gix())
             .is_ok()
         {
-            return Ok(true);
+            return Ok(Some(Integration::new(
+                IntegrationMethod::CommitId,
+                vec![commit.id().to_gix()],
+            )));
         }
 
         let merge_base_id = self.repo.merge_base_with_graph(
@@ -97,10 +176,23 @@ impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
         if gix_to_git2_oid(merge_base_id).eq(&commit.id()) {
             // if merge branch is the same as branch head and there are upstream commits
             // then it's integrated
-            return Ok(true);
+            return Ok(Some(Integration::new(
+                IntegrationMethod::Ancestor,
+                vec![self.upstream_head_id],
+            )));
         }
 
         let merge_base_tree_id = self.repo.find_commit(merge_base_id)?.tree_id()?;
+        if let Some(integration) = self.upstream_patch_ids.integration(
+            self.repo,
+            commit.id().to_gix(),
+            commit.parent_ids().next().map(|id| id.to_gix()),
+            merge_base_id,
+            merge_base_tree_id.detach(),
+            commit.tree_id().to_gix(),
+        )? {
+            return Ok(Some(integration));
+        }
         // TODO: why this this fail in one of our tests? Are there wrong assumptions in general,
         //       or is this us having picked the wrong upstream_tree_id? `upstream_tree_id` seems
         //       to be correct though, so it's the merge_base tree comparison that's not really
@@ -124,7 +216,7 @@ impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
             .context("failed to merge trees")?;
 
         if merge_output.has_unresolved_conflicts(conflict_kind) {
-            return Ok(false);
+            return Ok(None);
         }
 
         let merge_tree_id = merge_output.tree.write()?.detach();
@@ -133,16 +225,144 @@ impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
         if let Some(parent_tree) = parent_tree {
             // if the commit tree is the same as its the parent tree, it must be an empty commit, so dont classify it as integrated
             if commit.tree_id() == parent_tree {
-                return Ok(false);
+                return Ok(None);
             }
         }
 
         // if the merge_tree is the same as the new_target_tree and there are no files (uncommitted changes)
         // then the vbranch is fully merged
-        Ok(merge_tree_id == self.upstream_tree_id)
+        Ok((merge_tree_id == self.upstream_tree_id)
+            .then(|| Integration::new(IntegrationMethod::TreeMerge, vec![self.upstream_head_id])))
+    }
+}
+
+/// Upstream commits along with a lookup table by their patch-id, which is only computed when first needed.
+///
+/// The patch-ids of local commits and ranges are remembered as well, so each is only computed once.
+pub struct UpstreamPatchIds {
+    commits: Vec<gix::ObjectId>,
+    by_patch_id: Option<HashMap<patch_id::PatchId, Vec<gix::ObjectId>>>,
+    /// The patch-ids of the commits that were looked up, or `None` if they don't have one.
+    local: HashMap<gix::ObjectId, Option<patch_id::PatchId>>,
+    /// The patch-ids of the changes between `(base_tree_id, tree_id)`, or `None` if there are none.
+    ranges: HashMap<(gix::ObjectId, gix::ObjectId), Option<patch_id::PatchId>>,
+}
+
+impl UpstreamPatchIds {
+    /// Create a new instance to find local commits among `upstream_commits`.
+    pub fn new(upstream_commits: Vec<gix::ObjectId>) -> Self {
+        UpstreamPatchIds {
+            commits: upstream_commits,
+            by_patch_id: None,
+            local: HashMap::new(),
+            ranges: HashMap::new(),
+        }
+    }
+
+    /// Return how the commit at `commit_id` with `parent_id` and `tree_id` is integrated, judging by patch-ids only.
+    ///
+    /// The patch of the commit itself is tried first, followed by the patch of all changes between
+    /// `merge_base_tree_id`, the tree of `merge_base_id` with the target, and `tree_id` to detect squash-merges.
+    pub fn integration(
+        &mut self,
+        repo: &gix::Repository,
+        commit_id: gix::ObjectId,
+        parent_id: Option<gix::ObjectId>,
+        merge_base_id: gix::ObjectId,
+        merge_base_tree_id: gix::ObjectId,
+        tree_id: gix::ObjectId,
+    ) -> Result<Option<Integration>> {
+        if self.commits.is_empty() {
+            return Ok(None);
+        }
+        if let Some(upstream_commits) = self.commits_with_patch_of(repo, commit_id)? {
+            return Ok(Some(Integration::new(
+                IntegrationMethod::PatchId,
+                upstream_commits.to_vec(),
+            )));
+        }
+
+        // If the commit sits right on top of the merge-base, the range is the commit itself.
+        if parent_id == Some(merge_base_id) {
+            return Ok(None);
+        }
+        let range_patch_id = match self.ranges.get(&(merge_base_tree_id, tree_id)) {
+            Some(patch_id) => *patch_id,
+            None => {
+                let patch_id = patch_id::of_tree_diff(repo, merge_base_tree_id, tree_id)?;
+                self.ranges.insert((merge_base_tree_id, tree_id), patch_id);
+                patch_id
+            }
+        };
+        if let Some(patch_id) = range_patch_id {
+            if let Some(upstream_commits) = self.lookup(repo, &patch_id)? {
+                return Ok(Some(Integration::new(
+                    IntegrationMethod::Squash,
+                    upstream_commits.to_vec(),
+                )));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return the upstream commits with the same patch as the commit at `commit_id`, if there are any.
+    pub fn commits_with_patch_of(
+        &mut self,
+        repo: &gix::Repository,
+        commit_id: gix::ObjectId,
+    ) -> Result<Option<&[gix::ObjectId]>> {
+        if self.commits.is_empty() {
+            return Ok(None);
+        }
+        self.prefetch(repo, Some(commit_id))?;
+        match self.local[&commit_id] {
+            Some(patch_id) => self.lookup(repo, &patch_id),
+            None => Ok(None),
+        }
+    }
+
+    /// Compute and remember the patch-ids of all `commits` which weren't seen before, for when they are
+    /// [looked up](Self::commits_with_patch_of()) later.
+    pub fn prefetch(
+        &mut self,
+        repo: &gix::Repository,
+        commits: impl IntoIterator<Item = gix::ObjectId>,
+    ) -> Result<()> {
+        if self.commits.is_empty() {
+            return Ok(());
+        }
+        let commits: Vec<_> = commits
+            .into_iter()
+            .filter(|id| !self.local.contains_key(id))
+            .collect();
+        let patch_ids: HashMap<_, _> = patch_id::of_commits(repo, commits.iter().copied())?
+            .into_iter()
+            .collect();
+        for id in commits {
+            self.local.insert(id, patch_ids.get(&id).copied());
+        }
+        Ok(())
+    }
+
+    fn lookup(
+        &mut self,
+        repo: &gix::Repository,
+        patch_id: &patch_id::PatchId,
+    ) -> Result<Option<&[gix::ObjectId]>> {
+        if self.by_patch_id.is_none() {
+            let mut by_patch_id = HashMap::<_, Vec<_>>::new();
+            for (commit_id, patch_id) in patch_id::of_commits(repo, self.commits.clone())? {
+                by_patch_id.entry(patch_id).or_default().push(commit_id);
+            }
+            self.by_patch_id = Some(by_patch_id);
+        }
+        let by_patch_id = self.by_patch_id.as_ref().expect("computed above");
+        Ok(by_patch_id.get(patch_id).map(Vec::as_slice))
     }
 }
 
+pub use but_core::patch_id;
+
 pub(crate) type MergeBaseCommitGraph<'repo, 'cache> = gix::revwalk::Graph<
     'repo,
     'cache,
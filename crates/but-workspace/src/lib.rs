@@ -32,7 +32,7 @@ use gitbutler_oxidize::OidExt;
 use gitbutler_stack::VirtualBranchesHandle;
 use serde::{Deserialize, Serialize};
 
-mod integrated;
+pub mod integrated;
 
 /// Types specifically for the user-interface.
 pub mod ui;
@@ -45,6 +45,7 @@ pub use tree_manipulation::{
     discard_worktree_changes::discard_workspace_changes,
     move_between_commits::move_changes_between_commits,
     remove_changes_from_commit_in_stack::remove_changes_from_commit_in_stack,
+    shared_commits::rebase_stacks_and_branches_sharing_amended_commit,
     split_branch::{split_branch, split_into_dependent_branch},
     split_commit::{CommitFiles, CommmitSplitOutcome, split_commit},
 };
@@ -55,7 +56,7 @@ pub use head::{head, merge_worktree_with_workspace};
 /// Ignore the name of this module; it's just a place to put code by now.
 pub mod branch;
 
-mod changeset;
+pub mod changeset;
 
 mod commit;
 
@@ -18,6 +18,7 @@ pub mod ui {
     use std::borrow::Cow;
     use std::ops::{Deref, DerefMut};
 
+    use crate::integrated::IntegrationMethod;
     use crate::ui;
     use bstr::BString;
     use but_core::ref_metadata;
@@ -117,6 +118,9 @@ pub mod ui {
         /// Provide additional information on how this commit relates to other points of reference, like its remote branch,
         /// or the target branch to integrate with.
         pub relation: LocalCommitRelation,
+        /// How the commit was found to be integrated into the target branch, if its [relation](Self::relation)
+        /// is [integrated](LocalCommitRelation::Integrated).
+        pub integration: Option<IntegrationMethod>,
     }
 
     impl std::fmt::Debug for LocalCommit {
@@ -375,6 +379,12 @@ pub(crate) mod function {
         repo: &gix::Repository,
         opts: super::Options,
     ) -> anyhow::Result<RefInfo> {
+        let mut workspace = graph.to_workspace()?;
+        let integrated_by_patch = if opts.expensive_commit_info {
+            workspace.mark_integrated_by_patch(repo)?
+        } else {
+            Default::default()
+        };
         let but_graph::projection::Workspace {
             graph,
             id,
@@ -385,7 +395,7 @@ pub(crate) mod function {
             metadata,
             lower_bound: _,
             lower_bound_segment_id,
-        } = graph.to_workspace()?;
+        } = workspace;
 
         let (workspace_ref_name, is_managed_commit, ancestor_workspace_commit) = match kind {
             WorkspaceKind::Managed { ref_name } => (Some(ref_name), true, None),
@@ -454,7 +464,12 @@ pub(crate) mod function {
             // That way it's clear this must be fixed first.
             info.stacks.clear();
         }
-        info.compute_similarity(graph, repo, opts.expensive_commit_info)?;
+        info.compute_similarity(
+            graph,
+            repo,
+            opts.expensive_commit_info,
+            &integrated_by_patch,
+        )?;
         Ok(info)
     }
 
@@ -548,6 +563,10 @@ pub(crate) mod function {
                 } else {
                     LocalCommitRelation::LocalOnly
                 },
+                // Integrated commits are reachable from the target branch, they are upstream commits themselves.
+                integration: flags
+                    .contains(StackCommitFlags::Integrated)
+                    .then_some(IntegrationMethod::CommitId),
             })
         }
     }
@@ -580,6 +580,7 @@ impl From<&LocalCommit> for ui::Commit {
                     change_id: _,
                 },
             relation,
+            integration: _,
         }: &LocalCommit,
     ) -> Self {
         ui::Commit {
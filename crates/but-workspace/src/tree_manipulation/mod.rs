@@ -1,4 +1,7 @@
+use crate::commit_engine::UpdatedReference;
+
 /// Provides data that helps describe the effect of the move changes operaiton.
+#[derive(Debug, Default)]
 pub struct MoveChangesResult {
     /// A list of commits that were replaced as part of any rebases that were
     /// performed. Provided as a list of tuples where the first item in the
@@ -7,6 +10,9 @@ pub struct MoveChangesResult {
     ///
     /// If a commit was unaffected then it will not be included in this list.
     pub replaced_commits: Vec<(gix::ObjectId, gix::ObjectId)>,
+    /// All references that were moved to point to rewritten commits, in the stacks that were operated on
+    /// as well as in all other stacks and local branches that contained a rewritten commit.
+    pub updated_references: Vec<UpdatedReference>,
 }
 
 impl MoveChangesResult {
@@ -28,12 +34,24 @@ impl MoveChangesResult {
         }
 
         self.replaced_commits = new_replaced_commits;
+
+        for updated in other.updated_references {
+            match self
+                .updated_references
+                .iter_mut()
+                .find(|existing| existing.reference == updated.reference)
+            {
+                Some(existing) => existing.new_commit_id = updated.new_commit_id,
+                None => self.updated_references.push(updated),
+            }
+        }
     }
 }
 
 pub(super) mod discard_worktree_changes;
 pub(super) mod move_between_commits;
 pub(super) mod remove_changes_from_commit_in_stack;
+pub(super) mod shared_commits;
 pub(super) mod split_branch;
 pub(super) mod split_commit;
 
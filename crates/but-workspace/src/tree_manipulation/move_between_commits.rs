@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use anyhow::{Result, bail};
 use but_rebase::{Rebase, RebaseStep, replace_commit_tree};
 use gitbutler_command_context::CommandContext;
@@ -8,6 +10,10 @@ use crate::{DiffSpec, stack_ext::StackExt};
 
 use super::{
     MoveChangesResult,
+    shared_commits::{
+        branch_heads, one_to_one_replacements, rebase_stacks_and_branches_sharing_commits,
+        updated_branch_heads,
+    },
     utils::{
         ChangesSource, create_tree_without_diff, rebase_mapping_with_overrides,
         replace_pick_with_commit,
@@ -18,12 +24,13 @@ use super::{
 ///
 /// The commits may either be in the same branch or two different branches.
 ///
-/// ## Limitations / Assumptions
+/// ## Commits shared with other stacks and branches
 ///
-/// Currently this function does not take into consideration the possiblity
-/// that the commit _might_ be part of two different stacks. As such, the
-/// other stacks may end up referring to stale commits and potentially cause
-/// a merge conflict when combining them in the workspace.
+/// A commit _might_ be part of more than one stack, or of local branches outside of
+/// the workspace. All stacks and branches that contain any of the rewritten commits
+/// are rebased onto them, so they keep sharing the same commits instead of referring
+/// to stale ones, which would cause merge conflicts when combining them in the workspace.
+/// All references that were moved are listed in [`MoveChangesResult::updated_references`].
 ///
 /// This function updates the stacks in question, but does not touch the working
 /// directory. After calling this function on stacks in the workspace, you may
@@ -65,9 +72,7 @@ pub fn move_changes_between_commits(
     context_lines: u32,
 ) -> Result<MoveChangesResult> {
     if source_commit_id == destination_commit_id {
-        return Ok(MoveChangesResult {
-            replaced_commits: vec![],
-        });
+        return Ok(MoveChangesResult::default());
     }
 
     let vb_state = VirtualBranchesHandle::new(ctx.project().gb_dir());
@@ -90,6 +95,7 @@ pub fn move_changes_between_commits(
     }
 
     let source_stack = vb_state.get_stack_in_workspace(source_stack_id)?;
+    let source_heads_before = branch_heads(&source_stack, &repository)?;
     let mut source_stack_steps = source_stack.as_rebase_steps(ctx, &git2_repository)?;
 
     let rewritten_source_commit = replace_commit_tree(
@@ -113,13 +119,10 @@ pub fn move_changes_between_commits(
         [(source_commit_id, rewritten_source_commit)],
     );
 
-    let destination_commit_id: gix::ObjectId = if source_stack_id == destination_stack_id {
-        *source_stack_mapping
-            .get(&destination_commit_id)
-            .unwrap_or(&destination_commit_id)
-    } else {
-        destination_commit_id
-    };
+    // The destination may also be a commit the destination stack shares with the source stack.
+    let destination_commit_id: gix::ObjectId = *source_stack_mapping
+        .get(&destination_commit_id)
+        .unwrap_or(&destination_commit_id);
 
     let destination_tree_id = repository.find_commit(destination_commit_id)?.tree_id()?;
 
@@ -137,7 +140,9 @@ pub fn move_changes_between_commits(
     }
     let final_destination_tree = final_destination.tree.write()?;
 
-    if source_stack_id == destination_stack_id {
+    let (mut output_commit_mapping, mut updated_references) = if source_stack_id
+        == destination_stack_id
+    {
         // We need to rebase the source stack a second time. This loop both
         // updates the steps to consider the first rebase, and also injects the
         // new destination commit's tree.
@@ -182,13 +187,20 @@ pub fn move_changes_between_commits(
         let mut source_stack = source_stack;
         source_stack.set_heads_from_rebase_output(ctx, result.references)?;
 
-        Ok(MoveChangesResult {
-            replaced_commits: output_commit_mapping.into_iter().collect::<Vec<_>>(),
-        })
+        let updated_references =
+            updated_branch_heads(source_heads_before, &source_stack, &repository)?;
+        (output_commit_mapping, updated_references)
     } else {
         let destination_stack = vb_state.get_stack_in_workspace(destination_stack_id)?;
+        let destination_heads_before = branch_heads(&destination_stack, &repository)?;
         let mut destination_stack_steps =
             destination_stack.as_rebase_steps(ctx, &git2_repository)?;
+        // Pick up the commits the destination stack shares with the source stack in their rewritten form.
+        for step in &mut destination_stack_steps {
+            if let RebaseStep::Pick { commit_id, .. } = step {
+                *commit_id = *source_stack_mapping.get(commit_id).unwrap_or(commit_id);
+            }
+        }
 
         let rewritten_destination_commit = replace_commit_tree(
             &repository,
@@ -207,7 +219,7 @@ pub fn move_changes_between_commits(
         let result = rebase.rebase()?;
         let (mut source_stack, mut destination_stack) = (source_stack, destination_stack);
 
-        let output_commit_mapping = source_stack_mapping
+        let output_commit_mapping: HashMap<_, _> = source_stack_mapping
             .into_iter()
             .chain(rebase_mapping_with_overrides(
                 &result,
@@ -218,8 +230,27 @@ pub fn move_changes_between_commits(
         source_stack.set_heads_from_rebase_output(ctx, source_stack_result.references)?;
         destination_stack.set_heads_from_rebase_output(ctx, result.references)?;
 
-        Ok(MoveChangesResult {
-            replaced_commits: output_commit_mapping,
-        })
-    }
+        let mut updated_references =
+            updated_branch_heads(source_heads_before, &source_stack, &repository)?;
+        updated_references.extend(updated_branch_heads(
+            destination_heads_before,
+            &destination_stack,
+            &repository,
+        )?);
+        (output_commit_mapping, updated_references)
+    };
+
+    let shared = rebase_stacks_and_branches_sharing_commits(
+        ctx,
+        &repository,
+        &[source_stack_id, destination_stack_id],
+        &one_to_one_replacements(&output_commit_mapping),
+    )?;
+    output_commit_mapping.extend(shared.replaced_commits);
+    updated_references.extend(shared.updated_references);
+
+    Ok(MoveChangesResult {
+        replaced_commits: output_commit_mapping.into_iter().collect(),
+        updated_references,
+    })
 }
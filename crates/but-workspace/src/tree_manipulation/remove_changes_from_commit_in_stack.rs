@@ -8,9 +8,15 @@ use gix::ObjectId;
 use crate::{
     DiffSpec,
     stack_ext::StackExt,
-    tree_manipulation::utils::{
-        ChangesSource, create_tree_without_diff, rebase_mapping_with_overrides,
-        replace_pick_with_commit,
+    tree_manipulation::{
+        shared_commits::{
+            branch_heads, one_to_one_replacements, rebase_stacks_and_branches_sharing_commits,
+            updated_branch_heads,
+        },
+        utils::{
+            ChangesSource, create_tree_without_diff, rebase_mapping_with_overrides,
+            replace_pick_with_commit,
+        },
     },
 };
 
@@ -26,12 +32,8 @@ use super::MoveChangesResult;
 /// cause the specified change to be dropped from the working directory. Not
 /// using it will result in the change showing up as an uncommited change.
 ///
-/// ## Assumptions
-///
-/// Currently this function does not take into consideration the possiblity
-/// that the commit _might_ be part of two different stacks. As such, the
-/// other stacks may end up referring to stale commits and potentially cause
-/// a merge conflict when combining them in the workspace.
+/// All other stacks in the workspace and local branches that contain the rewritten commit
+/// are rebased as well, so they keep sharing the same commits.
 pub fn remove_changes_from_commit_in_stack(
     ctx: &CommandContext,
     source_stack_id: StackId,
@@ -46,6 +48,7 @@ pub fn remove_changes_from_commit_in_stack(
     let rewritten_source_commit =
         remove_changes_from_commit(ctx, source_commit_id, changes, context_lines)?;
 
+    let heads_before = branch_heads(&source_stack, &repository)?;
     let mut steps = source_stack.as_rebase_steps(ctx, &repository)?;
     replace_pick_with_commit(&mut steps, source_commit_id, rewritten_source_commit)?;
     let base = source_stack.merge_base(ctx)?;
@@ -53,14 +56,25 @@ pub fn remove_changes_from_commit_in_stack(
     rebase.steps(steps)?;
     rebase.rebase_noops(false);
     let result = rebase.rebase()?;
-    let commit_mapping =
+    let mut commit_mapping =
         rebase_mapping_with_overrides(&result, [(source_commit_id, rewritten_source_commit)]);
 
     let mut source_stack = source_stack;
     source_stack.set_heads_from_rebase_output(ctx, result.references)?;
+    let mut updated_references = updated_branch_heads(heads_before, &source_stack, &repository)?;
+
+    let shared = rebase_stacks_and_branches_sharing_commits(
+        ctx,
+        &repository,
+        &[source_stack_id],
+        &one_to_one_replacements(&commit_mapping),
+    )?;
+    commit_mapping.extend(shared.replaced_commits);
+    updated_references.extend(shared.updated_references);
 
     Ok(MoveChangesResult {
         replaced_commits: commit_mapping.into_iter().collect(),
+        updated_references,
     })
 }
 
@@ -0,0 +1,262 @@
+//! Keep stacks and branches consistent when commits they share with a rewritten stack change.
+//!
+//! A commit can be reachable from more than one stack, and from local branches outside of the workspace.
+//! When it is rewritten, all of them have to be rebased onto the rewritten commit, or they keep referring
+//! to the stale one, which later conflicts when the stacks are merged into the workspace commit.
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use but_rebase::{Rebase, RebaseOutput, RebaseStep};
+use gitbutler_command_context::CommandContext;
+use gitbutler_oxidize::OidExt;
+use gitbutler_stack::{Stack, StackId, VirtualBranchesHandle};
+use gix::refs::transaction::PreviousValue;
+
+use crate::{
+    commit_engine::{CreateCommitOutcome, UpdatedReference},
+    stack_ext::StackExt,
+};
+
+use super::{MoveChangesResult, utils::rebase_mapping_with_overrides};
+
+/// What changed when rebasing everything that contained a rewritten commit.
+#[derive(Default)]
+pub(crate) struct SharedCommitsRebase {
+    /// The commits that changed in the process, `old -> new`.
+    pub replaced_commits: HashMap<gix::ObjectId, gix::ObjectId>,
+    /// All references that now point to a different commit.
+    pub updated_references: Vec<UpdatedReference>,
+}
+
+/// Rebase all stacks in the workspace other than `rewritten_stacks`, along with all local branches which don't
+/// belong to a stack, if they contain any of the commits in `replacements`.
+///
+/// `replacements` maps each commit that was rewritten to the commits that replace it, which is more than one if it was split.
+/// As the replacements already sit on top of their rewritten parents, the commits shared with the rewritten stacks end up
+/// exactly the same in all stacks and branches, and only the commits on top of them are rebased.
+///
+/// The checked-out branch and branches managed by GitButler are never touched.
+pub(crate) fn rebase_stacks_and_branches_sharing_commits(
+    ctx: &CommandContext,
+    repo: &gix::Repository,
+    rewritten_stacks: &[StackId],
+    replacements: &HashMap<gix::ObjectId, Vec<gix::ObjectId>>,
+) -> Result<SharedCommitsRebase> {
+    let mut out = SharedCommitsRebase::default();
+    if replacements.is_empty() {
+        return Ok(out);
+    }
+
+    let vb_state = VirtualBranchesHandle::new(ctx.project().gb_dir());
+    let mut stack_refs = HashSet::new();
+    for mut stack in vb_state.list_stacks_in_workspace()? {
+        for branch in &stack.heads {
+            stack_refs.insert(branch.full_name()?);
+        }
+        if rewritten_stacks.contains(&stack.id) {
+            continue;
+        }
+        let steps = stack.as_rebase_steps(ctx, repo)?;
+        let heads_before = branch_heads(&stack, repo)?;
+        let Some(result) =
+            rebase_with_replacements(repo, stack.merge_base(ctx)?, steps, replacements)?
+        else {
+            continue;
+        };
+        out.replaced_commits
+            .extend(rebase_mapping_with_overrides(&result, None));
+        stack.set_heads_from_rebase_output(ctx, result.references)?;
+        out.updated_references
+            .extend(updated_branch_heads(heads_before, &stack, repo)?);
+    }
+
+    let target_id = vb_state.get_default_target()?.sha.to_gix();
+    let checked_out = repo.head_name()?;
+    let branches: Vec<_> = repo
+        .references()?
+        .local_branches()?
+        .filter_map(Result::ok)
+        .filter_map(|r| {
+            let id = r.try_id()?.detach();
+            Some((r.inner.name, id))
+        })
+        .filter(|(name, _)| {
+            !stack_refs.contains(name)
+                && checked_out.as_ref() != Some(name)
+                && !name.as_bstr().starts_with(b"refs/heads/gitbutler/")
+        })
+        .collect();
+    for (name, tip) in branches {
+        let Ok(merge_base) = repo.merge_base(tip, target_id).map(|id| id.detach()) else {
+            continue;
+        };
+        let commits: Vec<_> = tip
+            .attach(repo)
+            .ancestors()
+            .first_parent_only()
+            .all()?
+            .filter_map(Result::ok)
+            .map(|info| info.id)
+            .take_while(|id| *id != merge_base)
+            .collect();
+        let Some(oldest_replaced) = commits.iter().rposition(|id| replacements.contains_key(id))
+        else {
+            continue;
+        };
+        let base = repo
+            .find_commit(commits[oldest_replaced])?
+            .parent_ids()
+            .next()
+            .map(|id| id.detach());
+        let steps = commits[..=oldest_replaced]
+            .iter()
+            .rev()
+            .map(|commit_id| RebaseStep::Pick {
+                commit_id: *commit_id,
+                new_message: None,
+            })
+            .chain(Some(RebaseStep::Reference(but_core::Reference::Git(
+                name.clone(),
+            ))))
+            .collect();
+        let Some(result) = rebase_with_replacements(repo, base, steps, replacements)? else {
+            continue;
+        };
+        let new_tip = result
+            .references
+            .first()
+            .context("BUG: the branch reference is always rebased")?
+            .commit_id;
+        if new_tip == tip {
+            continue;
+        }
+        repo.reference(
+            name.clone(),
+            new_tip,
+            PreviousValue::MustExistAndMatch(tip.into()),
+            "GitButler: rebase onto rewritten commits shared with the workspace",
+        )?;
+        out.replaced_commits
+            .extend(rebase_mapping_with_overrides(&result, None));
+        out.updated_references.push(UpdatedReference {
+            reference: but_core::Reference::Git(name),
+            old_commit_id: tip,
+            new_commit_id: new_tip,
+        });
+    }
+    Ok(out)
+}
+
+/// Rebase all stacks in the workspace other than `stack_id`, along with all local branches which don't belong to a stack,
+/// if they contain `amended_commit_id` or any of the commits that were rebased onto it as described by `outcome`
+/// of [amending](crate::commit_engine::Destination::AmendCommit) it, so they keep sharing the same commits.
+///
+/// This only updates stacks and branches. After calling it, you may want to call `update_workspace_commit`
+/// so the workspace commit contains the rebased stacks.
+pub fn rebase_stacks_and_branches_sharing_amended_commit(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    amended_commit_id: gix::ObjectId,
+    outcome: &CreateCommitOutcome,
+) -> Result<MoveChangesResult> {
+    let Some(new_commit) = outcome.new_commit else {
+        return Ok(MoveChangesResult::default());
+    };
+    let mut commit_mapping = outcome
+        .rebase_output
+        .as_ref()
+        .map(|rebase| rebase_mapping_with_overrides(rebase, None))
+        .unwrap_or_default();
+    commit_mapping.insert(amended_commit_id, new_commit);
+
+    let repo = ctx.gix_repo()?;
+    let shared = rebase_stacks_and_branches_sharing_commits(
+        ctx,
+        &repo,
+        &[stack_id],
+        &one_to_one_replacements(&commit_mapping),
+    )?;
+    Ok(MoveChangesResult {
+        replaced_commits: shared.replaced_commits.into_iter().collect(),
+        updated_references: shared.updated_references,
+    })
+}
+
+/// Turn `commit_mapping` of `old -> new` commits into replacements for use in
+/// [`rebase_stacks_and_branches_sharing_commits()`].
+pub(crate) fn one_to_one_replacements(
+    commit_mapping: &HashMap<gix::ObjectId, gix::ObjectId>,
+) -> HashMap<gix::ObjectId, Vec<gix::ObjectId>> {
+    commit_mapping
+        .iter()
+        .map(|(old, new)| (*old, vec![*new]))
+        .collect()
+}
+
+/// Rebase `steps` onto `base` with all picks of commits in `replacements` substituted with their replacements,
+/// or return `None` if none of the picked commits was replaced, leaving nothing to do.
+fn rebase_with_replacements(
+    repo: &gix::Repository,
+    base: impl Into<Option<gix::ObjectId>>,
+    steps: Vec<RebaseStep>,
+    replacements: &HashMap<gix::ObjectId, Vec<gix::ObjectId>>,
+) -> Result<Option<RebaseOutput>> {
+    let mut found_replacement = false;
+    let mut new_steps = Vec::with_capacity(steps.len());
+    for step in steps {
+        match step {
+            RebaseStep::Pick { commit_id, .. } if replacements.contains_key(&commit_id) => {
+                found_replacement = true;
+                new_steps.extend(replacements[&commit_id].iter().map(|commit_id| {
+                    RebaseStep::Pick {
+                        commit_id: *commit_id,
+                        new_message: None,
+                    }
+                }));
+            }
+            step => new_steps.push(step),
+        }
+    }
+    if !found_replacement {
+        return Ok(None);
+    }
+
+    let mut rebase = Rebase::new(repo, base, None)?;
+    rebase.steps(new_steps)?;
+    rebase.rebase_noops(false);
+    Ok(Some(rebase.rebase()?))
+}
+
+/// Return the commit each non-archived branch of `stack` points to.
+pub(crate) fn branch_heads(
+    stack: &Stack,
+    repo: &gix::Repository,
+) -> Result<Vec<(gix::refs::FullName, gix::ObjectId)>> {
+    stack
+        .heads
+        .iter()
+        .filter(|branch| !branch.archived)
+        .map(|branch| Ok((branch.full_name()?, branch.head_oid(repo)?)))
+        .collect()
+}
+
+/// Compare `heads_before` as obtained with [`branch_heads()`] to the current branch heads of `stack`
+/// and return the ones that changed.
+pub(crate) fn updated_branch_heads(
+    heads_before: Vec<(gix::refs::FullName, gix::ObjectId)>,
+    stack: &Stack,
+    repo: &gix::Repository,
+) -> Result<Vec<UpdatedReference>> {
+    let heads_after: HashMap<_, _> = branch_heads(stack, repo)?.into_iter().collect();
+    Ok(heads_before
+        .into_iter()
+        .filter_map(|(name, old_commit_id)| {
+            let new_commit_id = *heads_after.get(&name)?;
+            (new_commit_id != old_commit_id).then(|| UpdatedReference {
+                reference: but_core::Reference::Git(name),
+                old_commit_id,
+                new_commit_id,
+            })
+        })
+        .collect())
+}
@@ -1,6 +1,9 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
 use but_core::Reference;
 use but_rebase::Rebase;
+use but_rebase::RebaseOutput;
 use but_rebase::RebaseStep;
 use but_rebase::ReferenceSpec;
 use gitbutler_cherry_pick::GixRepositoryExt;
@@ -16,6 +19,11 @@ use crate::MoveChangesResult;
 use crate::stack_ext::StackExt;
 use crate::tree_manipulation::remove_changes_from_commit_in_stack::keep_only_file_changes_in_commit;
 use crate::tree_manipulation::remove_changes_from_commit_in_stack::remove_file_changes_from_commit;
+use crate::tree_manipulation::shared_commits::{
+    branch_heads, one_to_one_replacements, rebase_stacks_and_branches_sharing_commits,
+    updated_branch_heads,
+};
+use crate::tree_manipulation::utils::rebase_mapping_with_overrides;
 
 /// Splits a branch by creating a new branch with the specified changes.
 ///
@@ -30,6 +38,8 @@ use crate::tree_manipulation::remove_changes_from_commit_in_stack::remove_file_c
 /// 2. Remove all the specified changes from the source branch.
 /// 3. Remove all but the specified changes from the new branch.
 /// 4. Create a stack from out of the new branch.
+/// 5. Rebase all other stacks in the workspace and local branches that contain commits rewritten
+///    in the source branch, so they keep sharing the same commits.
 pub fn split_branch(
     ctx: &CommandContext,
     stack_id: StackId,
@@ -47,22 +57,16 @@ pub fn split_branch(
     let push_details = source_stack.push_details(ctx, source_branch_name.clone())?;
     let branch_head = push_details.head;
 
-    // Create a new branch from the source branch's head
+    // The new branch is created once its commits are known
     let new_branch_ref_name = format!("refs/heads/{}", new_branch_name);
     let new_branch_log_message = format!(
         "Split off changes from branch '{}' into new branch '{}'",
         source_branch_name, new_branch_name
     );
 
-    let mut new_ref = repository.reference(
-        new_branch_ref_name.clone(),
-        branch_head.to_gix(),
-        gix::refs::transaction::PreviousValue::Any,
-        new_branch_log_message.clone(),
-    )?;
-
     // Remove all the specified changes from the source branch, dropping empty rewritten commits
-    let source_result = filter_file_changes_in_branch(
+    let heads_before = branch_heads(&source_stack, &repository)?;
+    let (source_result, rewritten_commits) = filter_file_changes_in_branch(
         ctx,
         &repository,
         file_changes_to_split_off,
@@ -72,14 +76,14 @@ pub fn split_branch(
         context_lines,
     )?;
 
-    let replaced_commits = source_result
+    let mut replaced_commits: HashMap<_, _> = source_result
         .commit_mapping
         .iter()
         .filter(|(_, old, new)| old != new)
         .map(|(_, old, new)| (*old, *new))
         .collect();
-
-    let move_changes_result = MoveChangesResult { replaced_commits };
+    let source_stack = vb_state.get_stack_in_workspace(stack_id)?;
+    let mut updated_references = updated_branch_heads(heads_before, &source_stack, &repository)?;
 
     // Remove all but the specified changes from the new branch
     let new_branch_commits =
@@ -87,9 +91,11 @@ pub fn split_branch(
             .l(branch_head, LogUntil::Commit(merge_base.to_git2()), false)?;
 
     // Branch as rebase steps
+    let new_branch_full_name: gix::refs::FullName = new_branch_ref_name.try_into()?;
     let mut steps: Vec<RebaseStep> = Vec::new();
 
-    let reference_step = RebaseStep::Reference(but_core::Reference::Git(new_ref.name().to_owned()));
+    let reference_step =
+        RebaseStep::Reference(but_core::Reference::Git(new_branch_full_name.clone()));
     steps.push(reference_step);
 
     for commit in new_branch_commits {
@@ -115,8 +121,6 @@ pub fn split_branch(
     rebase.rebase_noops(false);
     let result = rebase.rebase()?;
 
-    let new_branch_full_name: gix::refs::FullName = new_branch_ref_name.try_into()?;
-
     let new_branch_ref = result
         .references
         .into_iter()
@@ -126,7 +130,27 @@ pub fn split_branch(
         })
         .ok_or_else(|| anyhow::anyhow!("New branch reference not found in rebase output"))?;
 
-    new_ref.set_target_id(new_branch_ref.commit_id, new_branch_log_message)?;
+    repository.reference(
+        new_branch_full_name,
+        new_branch_ref.commit_id,
+        gix::refs::transaction::PreviousValue::Any,
+        new_branch_log_message,
+    )?;
+
+    // The new branch only has new commits, so it's left alone as the commits shared with the source branch are rebased.
+    let shared = rebase_stacks_and_branches_sharing_commits(
+        ctx,
+        &repository,
+        &[stack_id],
+        &replacements_after_rebase(rewritten_commits, &source_result),
+    )?;
+    replaced_commits.extend(shared.replaced_commits);
+    updated_references.extend(shared.updated_references);
+
+    let move_changes_result = MoveChangesResult {
+        replaced_commits: replaced_commits.into_iter().collect(),
+        updated_references,
+    };
 
     Ok((new_branch_ref, move_changes_result))
 }
@@ -143,6 +167,8 @@ pub fn split_branch(
 /// 3. Remove all but the specified changes from the new branch.
 /// 4. Insert the new branch as a dependent branch in the stack.
 /// 5. Update the stack
+/// 6. Rebase all other stacks in the workspace and local branches that contain commits rewritten
+///    in the source branch, so they contain both of their parts instead.
 pub fn split_into_dependent_branch(
     ctx: &CommandContext,
     stack_id: StackId,
@@ -186,6 +212,7 @@ pub fn split_into_dependent_branch(
     let reference_step = RebaseStep::Reference(but_core::Reference::Git(new_ref.name().to_owned()));
     dependent_branch_steps.push(reference_step);
 
+    let mut split_off_commits = HashMap::new();
     for commit in new_branch_commits {
         let commit_id = commit.to_gix();
         if let Some(new_commit_id) = keep_only_file_changes_in_commit(
@@ -195,6 +222,7 @@ pub fn split_into_dependent_branch(
             context_lines,
             true,
         )? {
+            split_off_commits.insert(commit_id, new_commit_id);
             let pick_step = RebaseStep::Pick {
                 commit_id: new_commit_id,
                 new_message: None,
@@ -208,7 +236,7 @@ pub fn split_into_dependent_branch(
         dependent_branch_steps
     );
 
-    let steps = construct_source_steps(
+    let (steps, mut rewritten_commits) = construct_source_steps(
         ctx,
         &repository,
         file_changes_to_split_off,
@@ -217,6 +245,12 @@ pub fn split_into_dependent_branch(
         context_lines,
         Some(&dependent_branch_steps),
     )?;
+    // Each commit that was split now lives on in both branches, with the split-off part below.
+    for (commit_id, parts) in &mut rewritten_commits {
+        if let Some(split_off_commit_id) = split_off_commits.get(commit_id) {
+            parts.insert(0, *split_off_commit_id);
+        }
+    }
 
     println!(
         "Rebasing source branch '{}' with steps: {:?}",
@@ -231,6 +265,7 @@ pub fn split_into_dependent_branch(
     let new_head = repository.find_commit(source_result.top_commit)?;
 
     let mut source_stack = source_stack;
+    let heads_before = branch_heads(&source_stack, &repository)?;
 
     source_stack.add_series(
         ctx,
@@ -255,21 +290,34 @@ pub fn split_into_dependent_branch(
     )?;
     source_stack.set_heads_from_rebase_output(ctx, source_result.clone().references)?;
 
-    let move_changes_result = MoveChangesResult {
-        replaced_commits: source_result
-            .commit_mapping
-            .iter()
-            .filter(|(_, old, new)| old != new)
-            .map(|(_, old, new)| (*old, *new))
-            .collect(),
-    };
+    let mut replaced_commits: HashMap<_, _> = source_result
+        .commit_mapping
+        .iter()
+        .filter(|(_, old, new)| old != new)
+        .map(|(_, old, new)| (*old, *new))
+        .collect();
+    let mut updated_references = updated_branch_heads(heads_before, &source_stack, &repository)?;
+
+    let shared = rebase_stacks_and_branches_sharing_commits(
+        ctx,
+        &repository,
+        &[stack_id],
+        &replacements_after_rebase(rewritten_commits, &source_result),
+    )?;
+    replaced_commits.extend(shared.replaced_commits);
+    updated_references.extend(shared.updated_references);
 
-    Ok(move_changes_result)
+    Ok(MoveChangesResult {
+        replaced_commits: replaced_commits.into_iter().collect(),
+        updated_references,
+    })
 }
 
 /// Filters out the specified file changes from the branch.
 ///
 /// All commits that end up empty after removing the specified file changes will be dropped.
+/// Return the rebase output, along with the commits that were rewritten, as [returned](construct_source_steps())
+/// by [`construct_source_steps()`].
 fn filter_file_changes_in_branch(
     ctx: &CommandContext,
     repository: &gix::Repository,
@@ -278,8 +326,8 @@ fn filter_file_changes_in_branch(
     source_branch_name: String,
     merge_base: gix::ObjectId,
     context_lines: u32,
-) -> Result<but_rebase::RebaseOutput, anyhow::Error> {
-    let source_steps = construct_source_steps(
+) -> Result<(but_rebase::RebaseOutput, RewrittenCommits), anyhow::Error> {
+    let (source_steps, rewritten_commits) = construct_source_steps(
         ctx,
         repository,
         file_changes_to_split_off,
@@ -298,9 +346,15 @@ fn filter_file_changes_in_branch(
 
     source_stack.set_heads_from_rebase_output(ctx, source_result.clone().references)?;
 
-    Ok(source_result)
+    Ok((source_result, rewritten_commits))
 }
 
+/// Commits of the source branch that were rewritten before rebasing it, mapped to the commits they
+/// were rewritten to, which is none if they were dropped.
+type RewrittenCommits = HashMap<gix::ObjectId, Vec<gix::ObjectId>>;
+
+/// Return the rebase steps of the source branch with `file_changes_to_split_off` removed from all of its commits,
+/// along with the commits that were rewritten in the process.
 fn construct_source_steps(
     ctx: &CommandContext,
     repository: &gix::Repository,
@@ -309,7 +363,7 @@ fn construct_source_steps(
     source_branch_name: String,
     context_lines: u32,
     steps_to_insert: Option<&[RebaseStep]>,
-) -> Result<Vec<RebaseStep>, anyhow::Error> {
+) -> Result<(Vec<RebaseStep>, RewrittenCommits), anyhow::Error> {
     let source_steps = source_stack.as_rebase_steps_rev(ctx, repository)?;
     let mut new_source_steps = Vec::new();
     let mut inside_branch = false;
@@ -324,6 +378,7 @@ fn construct_source_steps(
     let branch_ref_name = branch_ref.name().to_owned();
 
     let mut inserted_steps = false;
+    let mut rewritten_commits = RewrittenCommits::new();
     for step in source_steps {
         if let RebaseStep::Reference(but_core::Reference::Git(name)) = &step {
             if *name == branch_ref_name {
@@ -365,6 +420,7 @@ fn construct_source_steps(
             )? {
                 Some(rewritten_commit_id) if *commit_id != rewritten_commit_id => {
                     // Commit was rewritten, add updated step
+                    rewritten_commits.insert(*commit_id, vec![rewritten_commit_id]);
                     let mut new_step = step.clone();
                     if let RebaseStep::Pick { commit_id, .. } = &mut new_step {
                         *commit_id = rewritten_commit_id;
@@ -377,7 +433,7 @@ fn construct_source_steps(
                 }
                 None => {
                     // Commit became empty, drop it
-                    // Do nothing
+                    rewritten_commits.insert(*commit_id, Vec::new());
                 }
             }
         } else {
@@ -386,5 +442,23 @@ fn construct_source_steps(
         }
     }
     new_source_steps.reverse();
-    Ok(new_source_steps)
+    Ok((new_source_steps, rewritten_commits))
+}
+
+/// Map each of the `rewritten_commits` to what its rewritten commits became in `rebase`, and add all
+/// other commits that changed in `rebase`, for use in [`rebase_stacks_and_branches_sharing_commits()`].
+fn replacements_after_rebase(
+    rewritten_commits: RewrittenCommits,
+    rebase: &RebaseOutput,
+) -> HashMap<gix::ObjectId, Vec<gix::ObjectId>> {
+    let rebased = rebase_mapping_with_overrides(rebase, None);
+    let mut replacements = one_to_one_replacements(&rebased);
+    for (commit_id, rewritten) in rewritten_commits {
+        let rewritten = rewritten
+            .into_iter()
+            .map(|id| rebased.get(&id).copied().unwrap_or(id))
+            .collect();
+        replacements.insert(commit_id, rewritten);
+    }
+    replacements
 }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
 use but_rebase::Rebase;
 use gitbutler_command_context::CommandContext;
@@ -8,6 +10,10 @@ use crate::{
     stack_ext::StackExt,
     tree_manipulation::{
         remove_changes_from_commit_in_stack::keep_only_file_changes_in_commit,
+        shared_commits::{
+            branch_heads, one_to_one_replacements, rebase_stacks_and_branches_sharing_commits,
+            updated_branch_heads,
+        },
         utils::replace_pick_with_multiple_commits,
     },
 };
@@ -20,6 +26,8 @@ use crate::{
 /// 1. Create new commits for each specified piece of the original commit.
 /// 2. Replace the original commit in the stack with the new commits.
 /// 3. Update the stack to reflect the new commits.
+/// 4. Rebase all other stacks in the workspace and local branches that contain the original commit
+///    so they contain the new commits instead.
 pub fn split_commit(
     ctx: &mut CommandContext,
     stack_id: StackId,
@@ -32,6 +40,7 @@ pub fn split_commit(
 
     let source_stack = vb_state.get_stack_in_workspace(stack_id)?;
 
+    let heads_before = branch_heads(&source_stack, &repository)?;
     let mut steps = source_stack.as_rebase_steps(ctx, &repository)?;
     let commit_pieces = new_commits(ctx, source_commit_id, pieces, context_lines)?;
     replace_pick_with_multiple_commits(&mut steps, source_commit_id, &commit_pieces)?;
@@ -41,7 +50,7 @@ pub fn split_commit(
     rebase.rebase_noops(false);
     let result = rebase.rebase()?;
 
-    let commit_mapping = result
+    let mut commit_mapping: HashMap<_, _> = result
         .commit_mapping
         .iter()
         .filter_map(
@@ -53,13 +62,28 @@ pub fn split_commit(
 
     let mut source_stack = source_stack;
     source_stack.set_heads_from_rebase_output(ctx, result.references)?;
+    let mut updated_references = updated_branch_heads(heads_before, &source_stack, &repository)?;
 
     let new_commits = commit_pieces.iter().map(|(id, _)| *id).collect::<Vec<_>>();
 
+    let mut replacements = one_to_one_replacements(&commit_mapping);
+    replacements.insert(
+        source_commit_id,
+        new_commits
+            .iter()
+            .map(|id| commit_mapping.get(id).copied().unwrap_or(*id))
+            .collect(),
+    );
+    let shared =
+        rebase_stacks_and_branches_sharing_commits(ctx, &repository, &[stack_id], &replacements)?;
+    commit_mapping.extend(shared.replaced_commits);
+    updated_references.extend(shared.updated_references);
+
     Ok(CommmitSplitOutcome {
         new_commits,
         move_changes_result: MoveChangesResult {
-            replaced_commits: commit_mapping,
+            replaced_commits: commit_mapping.into_iter().collect(),
+            updated_references,
         },
     })
 }
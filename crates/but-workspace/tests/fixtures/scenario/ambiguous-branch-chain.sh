@@ -0,0 +1,19 @@
+#!/usr/bin/env bash
+
+### Description
+# A chain of branches on top of `main`, `feat-3` on top of `feat-2` on top of `feat-1`,
+# with `feat-1-copy` pointing to the same commit as `feat-1`.
+set -eu -o pipefail
+
+git init
+echo init >file && git add . && git commit -m init
+
+git checkout -b feat-1
+echo 1 >file && git commit -am "1"
+git branch feat-1-copy
+
+git checkout -b feat-2
+echo 2 >file && git commit -am "2"
+
+git checkout -b feat-3
+echo 3 >file && git commit -am "3"
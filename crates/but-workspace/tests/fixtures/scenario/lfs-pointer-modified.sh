@@ -0,0 +1,20 @@
+#!/usr/bin/env bash
+
+### Description
+# A file tracked by Git LFS whose pointer changed in the worktree.
+set -eu -o pipefail
+
+git init
+echo '*.bin filter=lfs diff=lfs merge=lfs -text' >.gitattributes
+cat <<EOT >object.bin
+version https://git-lfs.github.com/spec/v1
+oid sha256:6f618eee15121e413fb8ecfd19430529d07546b42c083683a5956c2c4fda084b
+size 13
+EOT
+git add . && git commit -m "init"
+
+cat <<EOT >object.bin
+version https://git-lfs.github.com/spec/v1
+oid sha256:06ab85b927905ef4904e83483de91922fd1529d33f98554ca2b47a03949f4e82
+size 12
+EOT
@@ -0,0 +1,32 @@
+#!/usr/bin/env bash
+
+### Description
+# `main` contains the change of `picked` as cherry-pick with a different message, along with
+# the changes of both commits of `squashed` in a single commit.
+set -eu -o pipefail
+
+source "${BASH_SOURCE[0]%/*}/shared.sh"
+
+git init
+tick
+seq 10 >file && git add . && git commit -m "init"
+
+git checkout -b picked
+tick
+seq 11 >file && git commit -am "append 11"
+
+git checkout -b squashed main
+tick
+echo b >b && git add . && git commit -m "add b"
+tick
+echo c >c && git add . && git commit -m "add c"
+
+git checkout main
+tick
+echo 0 >other && git add . && git commit -m "main advanced"
+tick
+git cherry-pick --no-commit picked
+git commit -m "reworded pick of 'append 11'"
+tick
+git checkout squashed -- b c
+git commit -m "squash of b and c"
@@ -0,0 +1,32 @@
+#!/usr/bin/env bash
+
+### Description
+# `ours` and `theirs` are two versions of the same branch. `theirs` was rebased onto an advanced `main`,
+# with the second commit edited and the last commit replaced by another one.
+set -eu -o pipefail
+
+source "${BASH_SOURCE[0]%/*}/shared.sh"
+
+git init
+tick
+echo base >base && git add . && git commit -m "init"
+
+git checkout -b ours
+tick
+echo a >a && git add . && git commit -m "add a"
+tick
+echo b >b && git add . && git commit -m "add b"
+tick
+echo c >c && git add . && git commit -m "add c"
+
+git checkout main
+tick
+echo main >main && git add . && git commit -m "main advanced"
+
+git checkout -b theirs
+tick
+git cherry-pick ours~2
+git cherry-pick ours~1
+echo "b edited" >b && git add . && git commit --amend --no-edit
+tick
+echo d >d && git add . && git commit -m "add d"
@@ -0,0 +1,31 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+CLI=${1:?The first argument is the GitButler CLI}
+
+git init remote
+(cd remote
+  echo first > file
+  git add . && git commit -m "init"
+)
+
+export GITBUTLER_CLI_DATA_DIR=../user/gitbutler/app-data
+git clone remote two-stacks-sharing-a-commit
+(cd two-stacks-sharing-a-commit
+  local_tracking_ref="$(git rev-parse --symbolic-full-name @{u})";
+
+  git checkout -b one main
+  echo shared > shared && echo split-off > split-off && git add . && git commit -m "shared"
+  echo one > one && git add one && git commit -m "one"
+
+  # `two` and `outside` share the first commit of `one`.
+  git checkout -b two one~1
+  echo two > two && git add two && git commit -m "two"
+
+  git checkout -b outside one~1
+  echo outside > outside && git add outside && git commit -m "outside"
+
+  git checkout main
+  $CLI project add --switch-to-workspace "$local_tracking_ref"
+  $CLI branch apply -b one
+  $CLI branch apply -b two
+)
@@ -0,0 +1,162 @@
+mod linear_branch_chain {
+    use crate::utils::read_only_in_memory_scenario;
+    use but_graph::VirtualBranchesTomlMetadata;
+    use but_workspace::branch::linear_branch_chain;
+
+    #[test]
+    fn stops_at_integrated_commits() -> anyhow::Result<()> {
+        let repo = read_only_in_memory_scenario("single-branch-10-commits-multi-segment")?;
+        let target = repo.rev_parse_single("three")?.detach();
+        let chain = chain_from(&repo, "nine", Some(target))?;
+        assert_eq!(
+            names(&chain),
+            ["nine", "six"],
+            "'three' is the target, so it's not part of the chain anymore"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn without_target_it_goes_down_to_the_root() -> anyhow::Result<()> {
+        let repo = read_only_in_memory_scenario("single-branch-10-commits-multi-segment")?;
+        let chain = chain_from(&repo, "nine", None)?;
+        assert_eq!(names(&chain), ["nine", "six", "three", "one"]);
+
+        let chain = chain_from(&repo, "one", None)?;
+        assert_eq!(names(&chain), ["one"], "the chain only goes downward");
+        Ok(())
+    }
+
+    #[test]
+    fn ambiguous_branches_end_the_chain() -> anyhow::Result<()> {
+        let repo = read_only_in_memory_scenario("ambiguous-branch-chain")?;
+        let chain = chain_from(&repo, "feat-3", None)?;
+        assert_eq!(
+            names(&chain),
+            ["feat-3", "feat-2"],
+            "'feat-1-copy' also points to the tip of 'feat-1', so we can't know which one is below 'feat-2'"
+        );
+        Ok(())
+    }
+
+    fn chain_from(
+        repo: &gix::Repository,
+        short_name: &str,
+        target: Option<gix::ObjectId>,
+    ) -> anyhow::Result<Vec<gix::refs::FullName>> {
+        let meta = std::mem::ManuallyDrop::new(VirtualBranchesTomlMetadata::from_path(
+            repo.path().join("should-never-be-written.toml"),
+        )?);
+        let mut reference = repo.find_reference(short_name)?;
+        let tip = reference.peel_to_id_in_place()?;
+        let graph = but_graph::Graph::from_commit_traversal(
+            tip,
+            reference.name().to_owned(),
+            &*meta,
+            but_graph::init::Options {
+                extra_target_commit_id: target,
+                ..Default::default()
+            },
+        )?;
+        linear_branch_chain(&graph)
+    }
+
+    fn names(chain: &[gix::refs::FullName]) -> Vec<String> {
+        chain.iter().map(|rn| rn.shorten().to_string()).collect()
+    }
+}
+
+mod set_upstreams_along_branch_chain {
+    use crate::utils::writable_scenario;
+    use but_workspace::branch::set_upstreams_along_branch_chain;
+
+    #[test]
+    fn each_branch_tracks_the_one_below() -> anyhow::Result<()> {
+        let (repo, _tmp) = writable_scenario("single-branch-10-commits-multi-segment");
+        let chain = ["nine", "six", "three"].map(local_branch);
+        set_upstreams_along_branch_chain(&repo, &chain, Some(local_branch("one").as_ref()))?;
+
+        let config = reloaded_config(&repo)?;
+        for (name, upstream) in [("nine", "six"), ("six", "three"), ("three", "one")] {
+            assert_eq!(
+                value(&config, &format!("branch.{name}.remote")).as_deref(),
+                Some(".")
+            );
+            assert_eq!(
+                value(&config, &format!("branch.{name}.merge")),
+                Some(format!("refs/heads/{upstream}"))
+            );
+        }
+        assert_eq!(
+            value(&config, "branch.one.merge").as_deref(),
+            None,
+            "the base itself isn't touched"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn remote_tracking_branches_as_base() -> anyhow::Result<()> {
+        let (mut repo, _tmp) = writable_scenario("single-branch-10-commits-multi-segment");
+        repo.config_snapshot_mut()
+            .set_raw_value(&"remote.origin.url", "https://example.com/repo")?;
+
+        set_upstreams_along_branch_chain(
+            &repo,
+            &[local_branch("nine")],
+            Some("refs/remotes/origin/main".try_into()?),
+        )?;
+        let config = reloaded_config(&repo)?;
+        assert_eq!(
+            value(&config, "branch.nine.remote").as_deref(),
+            Some("origin")
+        );
+        assert_eq!(
+            value(&config, "branch.nine.merge").as_deref(),
+            Some("refs/heads/main")
+        );
+
+        let err = set_upstreams_along_branch_chain(
+            &repo,
+            &[local_branch("nine")],
+            Some("refs/remotes/unknown/main".try_into()?),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Could not find the remote of remote tracking branch 'refs/remotes/unknown/main'"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn without_base_the_lowest_branch_is_left_alone() -> anyhow::Result<()> {
+        let (repo, _tmp) = writable_scenario("single-branch-10-commits-multi-segment");
+        set_upstreams_along_branch_chain(&repo, &["nine", "six"].map(local_branch), None)?;
+
+        let config = reloaded_config(&repo)?;
+        assert_eq!(
+            value(&config, "branch.nine.merge").as_deref(),
+            Some("refs/heads/six")
+        );
+        assert_eq!(value(&config, "branch.six.merge").as_deref(), None);
+        Ok(())
+    }
+
+    fn local_branch(short_name: &str) -> gix::refs::FullName {
+        format!("refs/heads/{short_name}")
+            .try_into()
+            .expect("valid name")
+    }
+
+    fn value(config: &gix::config::File<'_>, key: &str) -> Option<String> {
+        config.string(key).map(|value| value.to_string())
+    }
+
+    fn reloaded_config(repo: &gix::Repository) -> anyhow::Result<gix::config::File<'static>> {
+        Ok(gix::config::File::from_path_no_includes(
+            repo.path().join("config"),
+            gix::config::Source::Local,
+        )?)
+    }
+}
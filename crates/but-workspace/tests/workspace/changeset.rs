@@ -0,0 +1,57 @@
+use but_workspace::changeset::{CommitRange, RangeComparison, compare_ranges};
+
+use crate::utils::read_only_in_memory_scenario;
+
+#[test]
+fn rebased_edited_and_unique_commits() -> anyhow::Result<()> {
+    let repo = read_only_in_memory_scenario("rebased-and-edited-branch")?;
+    let id =
+        |spec: &str| -> anyhow::Result<gix::ObjectId> { Ok(repo.rev_parse_single(spec)?.detach()) };
+
+    let ours = CommitRange::from_spec(&repo, "ours")?;
+    let theirs = CommitRange::from_spec(&repo, "main..theirs")?;
+    assert_eq!(
+        theirs,
+        CommitRange {
+            tip: id("theirs")?,
+            base: Some(id("main")?),
+        }
+    );
+
+    assert_eq!(
+        compare_ranges(&repo, ours, theirs)?,
+        RangeComparison {
+            equivalent: vec![(id("ours~2")?, id("theirs~2")?)],
+            edited: vec![(id("ours~1")?, id("theirs~1")?)],
+            only_ours: vec![id("ours")?],
+            only_theirs: vec![id("theirs")?],
+        },
+        "the commit introducing 'a' was only rebased, the one introducing 'b' was changed"
+    );
+
+    let without_base = CommitRange::from_spec(&repo, "theirs")?;
+    assert_eq!(
+        compare_ranges(&repo, ours, without_base)?.only_theirs,
+        vec![id("theirs")?, id("main")?],
+        "the merge-base is used as base, which makes the commit in main unique to their side"
+    );
+    Ok(())
+}
+
+#[test]
+fn identical_ranges_are_equivalent() -> anyhow::Result<()> {
+    let repo = read_only_in_memory_scenario("rebased-and-edited-branch")?;
+    let ours = CommitRange::from_spec(&repo, "main..ours")?;
+    let outcome = compare_ranges(&repo, ours, ours)?;
+    assert_eq!(outcome.equivalent.len(), 3);
+    assert!(
+        outcome
+            .equivalent
+            .iter()
+            .all(|(ours, theirs)| ours == theirs),
+        "the very same commits match by identity"
+    );
+    assert!(outcome.edited.is_empty());
+    assert!(outcome.only_ours.is_empty() && outcome.only_theirs.is_empty());
+    Ok(())
+}
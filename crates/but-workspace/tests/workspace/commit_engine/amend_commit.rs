@@ -163,6 +163,38 @@ fn make_a_file_empty() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn gerrit_change_id_is_kept_when_rewording() -> anyhow::Result<()> {
+    assure_stable_env();
+
+    let (repo, _tmp) = writable_scenario("merge-with-two-branches-line-offset");
+    let gerrit_change_id = "I0123456789abcdef0123456789abcdef01234567";
+    let mut commit = repo
+        .rev_parse_single("merge")?
+        .object()?
+        .into_commit()
+        .decode()?
+        .to_owned();
+    commit.message = format!("merge\n\nChange-Id: {gerrit_change_id}\n").into();
+    let commit_id = repo.write_object(&commit)?.detach();
+
+    write_sequence(&repo, "file", [(40, 70)])?;
+    let outcome = commit_whole_files_and_all_hunks_from_workspace(
+        &repo,
+        Destination::AmendCommit {
+            commit_id,
+            new_message: Some("reworded merge".into()),
+        },
+    )?;
+    let new_commit = commit_from_outcome(&repo, &outcome)?;
+    assert_eq!(
+        new_commit.message,
+        format!("reworded merge\n\nChange-Id: {gerrit_change_id}\n"),
+        "the trailer of Gerrit is kept even though the message was replaced"
+    );
+    Ok(())
+}
+
 #[test]
 fn new_file_and_deletion_onto_merge_commit_with_hunks() -> anyhow::Result<()> {
     assure_stable_env();
@@ -257,7 +257,7 @@ fn from_first_commit_all_file_types_changed() -> anyhow::Result<()> {
 }
 
 #[test]
-fn unborn_with_added_submodules() -> anyhow::Result<()> {
+fn unborn_with_added_submodules_and_embedded_repository() -> anyhow::Result<()> {
     assure_stable_env();
 
     let (repo, _tmp) = writable_scenario("unborn-with-submodules");
@@ -277,17 +277,24 @@ fn unborn_with_added_submodules() -> anyhow::Result<()> {
         CONTEXT_LINES,
     )?;
 
-    assert_eq!(
-        outcome.rejected_specs,
-        vec![],
-        "Everything could be added to the repository"
-    );
+    // `module` is just a repository in the worktree, not a submodule, so it's not committed.
+    insta::assert_debug_snapshot!(outcome.rejected_specs, @r#"
+    [
+        (
+            UnsupportedDirectoryEntry,
+            DiffSpec {
+                previous_path: None,
+                path: "module",
+                hunk_headers: [],
+            },
+        ),
+    ]
+    "#);
     let tree = visualize_tree(&repo, &outcome)?;
     insta::assert_snapshot!(tree, @r#"
-    6260c86
+    a93fa73
     ├── .gitmodules:100644:49dc605 "[submodule \"m1\"]\n\tpath = m1\n\turl = ./module\n"
-    ├── m1:160000:a047f81 
-    └── module:160000:a047f81
+    └── m1:160000:a047f81
     "#);
     Ok(())
 }
@@ -506,6 +513,65 @@ fn renames() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn renames_taken_from_commits_are_committed_as_renames() -> anyhow::Result<()> {
+    assure_stable_env();
+
+    let repo = read_only_in_memory_scenario("all-file-types-renamed-and-modified")?;
+    let head_commit_id = repo.rev_parse_single("HEAD")?.detach();
+    let outcome = commit_whole_files_and_all_hunks_from_workspace(
+        &repo,
+        Destination::NewCommit {
+            parent_commit_id: Some(head_commit_id),
+            message: "all renames".into(),
+            stack_segment: None,
+        },
+    )?;
+    let renamed_commit_id = outcome.new_commit.expect("a commit was created");
+
+    // Specs of changes in commits, like the ones moved between commits, keep their rename source.
+    let (changes, _) =
+        but_core::diff::tree_changes(&repo, Some(head_commit_id), renamed_commit_id)?;
+    let spec = changes
+        .iter()
+        .find(|change| change.path == "file-renamed")
+        .map(DiffSpec::from)
+        .expect("the rename is detected");
+    assert_eq!(spec, diff_spec(Some("file"), "file-renamed", None));
+
+    let outcome = but_workspace::commit_engine::create_commit(
+        &repo,
+        Destination::NewCommit {
+            parent_commit_id: Some(head_commit_id),
+            message: "just the rename".into(),
+            stack_segment: None,
+        },
+        None,
+        vec![spec],
+        CONTEXT_LINES,
+    )?;
+    assert!(outcome.rejected_specs.is_empty());
+    let tree = outcome
+        .new_commit
+        .expect("a commit was created")
+        .attach(&repo)
+        .object()?
+        .peel_to_tree()?;
+    assert!(
+        tree.lookup_entry_by_path("file-renamed")?.is_some(),
+        "the rename destination is added"
+    );
+    assert!(
+        tree.lookup_entry_by_path("file")?.is_none(),
+        "the rename source is removed"
+    );
+    assert!(
+        tree.lookup_entry_by_path("executable")?.is_some(),
+        "other renames aren't affected"
+    );
+    Ok(())
+}
+
 #[test]
 fn renames_with_selections() -> anyhow::Result<()> {
     assure_stable_env();
@@ -849,6 +915,70 @@ fn submodule_typechanges() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn submodule_pointer_change() -> anyhow::Result<()> {
+    assure_stable_env();
+
+    let (repo, _tmp) = writable_scenario("modified-submodule-and-embedded-repo");
+    let outcome = commit_engine::create_commit(
+        &repo,
+        Destination::NewCommit {
+            parent_commit_id: Some(repo.rev_parse_single("HEAD")?.into()),
+            message: "the submodule is committed with the commit checked out in it".into(),
+            stack_segment: None,
+        },
+        None,
+        // Hunks make no sense for submodules, but they are ignored instead of causing a rejection.
+        vec![diff_spec(
+            None,
+            "submodule",
+            Some(hunk_header("-1,1", "+1,1")),
+        )],
+        CONTEXT_LINES,
+    )?;
+
+    assert_eq!(outcome.rejected_specs, vec![], "nothing was rejected");
+    let tree = visualize_tree(&repo, &outcome)?;
+    insta::assert_snapshot!(tree, @r#"
+    0d8318e
+    ├── .gitmodules:100644:51f8807 "[submodule \"submodule\"]\n\tpath = submodule\n\turl = ./embedded-repository\n"
+    ├── embedded-repository:160000:a047f81 
+    └── submodule:160000:6d5e0a5
+    "#);
+    Ok(())
+}
+
+#[test]
+fn lfs_pointer_is_committed_whole() -> anyhow::Result<()> {
+    assure_stable_env();
+
+    let (repo, _tmp) = writable_scenario("lfs-pointer-modified");
+    let outcome = commit_engine::create_commit(
+        &repo,
+        Destination::NewCommit {
+            parent_commit_id: Some(repo.rev_parse_single("HEAD")?.into()),
+            message: "a partial pointer makes no sense, so the selection is ignored".into(),
+            stack_segment: None,
+        },
+        None,
+        vec![diff_spec(
+            None,
+            "object.bin",
+            Some(hunk_header("-2,1", "+2,1")),
+        )],
+        CONTEXT_LINES,
+    )?;
+
+    assert_eq!(outcome.rejected_specs, vec![], "nothing was rejected");
+    let tree = visualize_tree(&repo, &outcome)?;
+    insta::assert_snapshot!(tree, @r#"
+    b69e228
+    ├── .gitattributes:100644:4edd5ac "*.bin filter=lfs diff=lfs merge=lfs -text\n"
+    └── object.bin:100644:357620b "version https://git-lfs.github.com/spec/v1\noid sha256:06ab85b927905ef4904e83483de91922fd1529d33f98554ca2b47a03949f4e82\nsize 12\n"
+    "#);
+    Ok(())
+}
+
 #[test]
 fn commit_to_one_below_tip() -> anyhow::Result<()> {
     assure_stable_env();
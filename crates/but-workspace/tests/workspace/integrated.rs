@@ -0,0 +1,91 @@
+use but_workspace::integrated::{Integration, IntegrationMethod, UpstreamPatchIds, patch_id};
+
+use crate::utils::read_only_in_memory_scenario;
+
+#[test]
+fn patch_ids_ignore_messages_and_ancestry() -> anyhow::Result<()> {
+    let repo = read_only_in_memory_scenario("patch-id-integration")?;
+    let id =
+        |spec: &str| -> anyhow::Result<gix::ObjectId> { Ok(repo.rev_parse_single(spec)?.detach()) };
+
+    let patch_ids = patch_id::of_commits(&repo, [id("picked")?, id("main~1")?, id("squashed")?])?;
+    assert_eq!(
+        patch_ids
+            .iter()
+            .map(|(commit_id, _)| *commit_id)
+            .collect::<Vec<_>>(),
+        [id("picked")?, id("main~1")?, id("squashed")?],
+        "patch-ids are returned in order"
+    );
+    assert_eq!(
+        patch_ids[0].1, patch_ids[1].1,
+        "the cherry-pick has a different parent and message, but the same patch"
+    );
+    assert_ne!(patch_ids[0].1, patch_ids[2].1);
+    assert!(patch_id::of_commits(&repo, None)?.is_empty());
+
+    let main_tree = repo.find_commit(id("main")?)?.tree_id()?.detach();
+    assert_eq!(
+        patch_id::of_tree_diff(&repo, main_tree, main_tree)?,
+        None,
+        "no changes, no patch-id"
+    );
+    Ok(())
+}
+
+#[test]
+fn integration_by_patch_id_and_squash() -> anyhow::Result<()> {
+    let repo = read_only_in_memory_scenario("patch-id-integration")?;
+    let id =
+        |spec: &str| -> anyhow::Result<gix::ObjectId> { Ok(repo.rev_parse_single(spec)?.detach()) };
+    let mut upstream = UpstreamPatchIds::new(vec![id("main")?, id("main~1")?, id("main~2")?]);
+    upstream.prefetch(&repo, [id("picked")?, id("squashed")?, id("squashed~1")?])?;
+
+    assert_eq!(
+        integration(&repo, &mut upstream, "picked")?,
+        Some(Integration::new(
+            IntegrationMethod::PatchId,
+            vec![id("main~1")?]
+        )),
+        "the reworded cherry-pick is found by its patch"
+    );
+    assert_eq!(
+        integration(&repo, &mut upstream, "squashed")?,
+        Some(Integration::new(
+            IntegrationMethod::Squash,
+            vec![id("main")?]
+        )),
+        "all changes of the branch up to the commit are in one upstream commit"
+    );
+    assert_eq!(
+        integration(&repo, &mut upstream, "squashed~1")?,
+        None,
+        "the first commit of the squashed branch is only part of the upstream commit"
+    );
+    assert_eq!(
+        upstream.commits_with_patch_of(&repo, id("picked")?)?,
+        Some([id("main~1")?].as_slice())
+    );
+
+    let mut nothing_upstream = UpstreamPatchIds::new(Vec::new());
+    assert_eq!(integration(&repo, &mut nothing_upstream, "picked")?, None);
+    Ok(())
+}
+
+fn integration(
+    repo: &gix::Repository,
+    upstream: &mut UpstreamPatchIds,
+    spec: &str,
+) -> anyhow::Result<Option<Integration>> {
+    let commit = repo.rev_parse_single(spec)?.object()?.into_commit();
+    let merge_base = repo.merge_base(commit.id, repo.rev_parse_single("main")?.detach())?;
+    let merge_base_tree_id = repo.find_commit(merge_base)?.tree_id()?.detach();
+    upstream.integration(
+        repo,
+        commit.id,
+        commit.parent_ids().next().map(|id| id.detach()),
+        merge_base.detach(),
+        merge_base_tree_id,
+        commit.tree_id()?.detach(),
+    )
+}
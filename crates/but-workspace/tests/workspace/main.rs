@@ -1,8 +1,11 @@
 use but_workspace::{DiffSpec, HunkHeader, flatten_diff_specs};
 
+mod branch;
 mod branch_details;
+mod changeset;
 mod commit_engine;
 mod flatten_diff_specs;
+mod integrated;
 mod ref_info;
 mod tree_manipulation;
 mod ui;
@@ -1,6 +1,7 @@
 //! A collection of tests that build on top of each other, like a progression of steps a user could take.
 use but_graph::VirtualBranchesTomlMetadata;
 use but_testsupport::visualize_commit_graph_all;
+use but_workspace::integrated::IntegrationMethod;
 
 use crate::ref_info::utils::standard_options;
 use crate::ref_info::with_workspace_commit::journey::utils::standard_options_with_extra_target;
@@ -675,6 +676,15 @@ The remote squash-merges S1 *and* changes the 'file' so it looks entirely differ
 
     add_stack_with_segments(&mut meta, 0, "S1", StackState::InWorkspace, &[]);
     let info = but_workspace::head_info(&repo, &*meta, standard_options());
+    assert_eq!(
+        info.as_ref().expect("no error").stacks[0].segments[0]
+            .commits
+            .iter()
+            .map(|c| c.integration)
+            .collect::<Vec<_>>(),
+        [Some(IntegrationMethod::Squash); 2],
+        "both commits are found to be integrated by the squash-merge"
+    );
     insta::assert_debug_snapshot!(info, @r#"
     Ok(
         RefInfo {
@@ -1,2 +1,3 @@
 mod file;
 mod hunk;
+mod shared_commits;
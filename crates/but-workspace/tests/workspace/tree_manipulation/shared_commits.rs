@@ -0,0 +1,222 @@
+use but_core::Reference;
+use but_workspace::{
+    DiffSpec, MoveChangesResult,
+    commit_engine::{self, Destination},
+    rebase_stacks_and_branches_sharing_amended_commit, split_branch, split_into_dependent_branch,
+};
+use gitbutler_command_context::CommandContext;
+use gitbutler_stack::{StackId, VirtualBranchesHandle};
+
+#[test]
+fn amend_rebases_stacks_and_branches_sharing_the_commit() -> anyhow::Result<()> {
+    let (ctx, _tmp) = fixture()?;
+    let repo = ctx.gix_repo()?;
+    let stack_id = stack_id_by_branch_name(&ctx, "one")?;
+    let shared = id(&repo, "one~1");
+    let before = ids(&repo, ["one", "two", "outside"]);
+
+    std::fs::write(
+        repo.workdir().expect("non-bare").join("shared"),
+        "amended\n",
+    )?;
+    let mut guard = ctx.project().exclusive_worktree_access();
+    let outcome = commit_engine::create_commit_and_update_refs_with_project(
+        &repo,
+        ctx.project(),
+        Some(stack_id),
+        Destination::AmendCommit {
+            commit_id: shared,
+            new_message: None,
+        },
+        None,
+        vec![DiffSpec {
+            previous_path: None,
+            path: "shared".into(),
+            hunk_headers: vec![],
+        }],
+        3,
+        guard.write_permission(),
+    )?;
+    let amended = outcome.new_commit.expect("the amend succeeded");
+    assert_eq!(id(&repo, "one~1"), amended);
+    assert_eq!(
+        id(&repo, "two~1"),
+        shared,
+        "the commit engine only rebases the stack of the amended commit"
+    );
+
+    let result =
+        rebase_stacks_and_branches_sharing_amended_commit(&ctx, stack_id, shared, &outcome)?;
+    assert_eq!(id(&repo, "two~1"), amended, "the other stack was rebased");
+    assert_eq!(
+        id(&repo, "outside~1"),
+        amended,
+        "as was the branch outside the workspace"
+    );
+    assert_eq!(
+        updated_references(&result),
+        [
+            ("outside".to_owned(), before[2], id(&repo, "outside")),
+            ("two".to_owned(), before[1], id(&repo, "two")),
+        ]
+    );
+    assert_eq!(
+        replaced(&result, before[1]),
+        Some(id(&repo, "two")),
+        "the rebased commits are listed as well"
+    );
+    Ok(())
+}
+
+#[test]
+fn split_branch_rebases_stacks_and_branches_sharing_split_commits() -> anyhow::Result<()> {
+    let (ctx, _tmp) = fixture()?;
+    let repo = ctx.gix_repo()?;
+    let stack_id = stack_id_by_branch_name(&ctx, "one")?;
+    let before = ids(&repo, ["one", "two", "outside"]);
+
+    let (new_branch, result) = split_branch(
+        &ctx,
+        stack_id,
+        "one".into(),
+        "split".into(),
+        &["split-off".to_owned()],
+        3,
+    )?;
+    assert_eq!(
+        new_branch.reference,
+        Reference::Git("refs/heads/split".try_into()?)
+    );
+    assert_eq!(id(&repo, "split"), new_branch.commit_id);
+    assert_eq!(
+        id(&repo, "split~1"),
+        id(&repo, "main"),
+        "only the split-off part of the shared commit is in the new branch"
+    );
+    assert!(has_file(&repo, "split", "split-off"));
+    assert!(!has_file(&repo, "split", "shared"));
+
+    let shared = id(&repo, "one~1");
+    assert!(!has_file(&repo, "one", "split-off"));
+    assert!(has_file(&repo, "one", "shared"));
+    assert_eq!(id(&repo, "two~1"), shared, "the other stack was rebased");
+    assert_eq!(
+        id(&repo, "outside~1"),
+        shared,
+        "as was the branch outside the workspace"
+    );
+    assert!(!has_file(&repo, "two", "split-off"));
+
+    assert_eq!(
+        updated_references(&result),
+        [
+            ("one".to_owned(), before[0], id(&repo, "one")),
+            ("outside".to_owned(), before[2], id(&repo, "outside")),
+            ("two".to_owned(), before[1], id(&repo, "two")),
+        ],
+        "the new branch was created, not updated"
+    );
+    Ok(())
+}
+
+#[test]
+fn split_into_dependent_branch_rebases_stacks_and_branches_onto_both_parts() -> anyhow::Result<()> {
+    let (ctx, _tmp) = fixture()?;
+    let repo = ctx.gix_repo()?;
+    let stack_id = stack_id_by_branch_name(&ctx, "one")?;
+    let before = ids(&repo, ["one", "two", "outside"]);
+
+    let result = split_into_dependent_branch(
+        &ctx,
+        stack_id,
+        "one".into(),
+        "dependent".into(),
+        &["split-off".to_owned()],
+        3,
+    )?;
+    let split_off = id(&repo, "dependent");
+    assert_eq!(
+        id(&repo, "one~2"),
+        split_off,
+        "the new branch is below the source branch"
+    );
+    assert!(has_file(&repo, "dependent", "split-off"));
+    assert!(!has_file(&repo, "dependent", "shared"));
+
+    let remainder = id(&repo, "one~1");
+    assert_eq!(
+        [id(&repo, "two~2"), id(&repo, "two~1")],
+        [split_off, remainder],
+        "the other stack contains both parts of the split commit"
+    );
+    assert_eq!(
+        [id(&repo, "outside~2"), id(&repo, "outside~1")],
+        [split_off, remainder],
+        "as does the branch outside the workspace"
+    );
+
+    let updated = updated_references(&result);
+    for (name, old, branch) in [
+        ("one", before[0], "one"),
+        ("two", before[1], "two"),
+        ("outside", before[2], "outside"),
+    ] {
+        assert!(
+            updated.contains(&(name.to_owned(), old, id(&repo, branch))),
+            "{name} is listed as updated: {updated:?}"
+        );
+    }
+    Ok(())
+}
+
+fn fixture() -> anyhow::Result<(CommandContext, tempfile::TempDir)> {
+    gitbutler_testsupport::writable::fixture("shared-commits.sh", "two-stacks-sharing-a-commit")
+}
+
+fn stack_id_by_branch_name(ctx: &CommandContext, name: &str) -> anyhow::Result<StackId> {
+    VirtualBranchesHandle::new(ctx.project().gb_dir())
+        .list_stacks_in_workspace()?
+        .into_iter()
+        .find(|stack| stack.heads.iter().any(|branch| branch.name() == name))
+        .map(|stack| stack.id)
+        .ok_or_else(|| anyhow::anyhow!("no stack with branch {name}"))
+}
+
+fn id(repo: &gix::Repository, rev: &str) -> gix::ObjectId {
+    repo.rev_parse_single(rev)
+        .unwrap_or_else(|err| panic!("{rev} can be resolved: {err}"))
+        .detach()
+}
+
+fn ids<const N: usize>(repo: &gix::Repository, revs: [&str; N]) -> [gix::ObjectId; N] {
+    revs.map(|rev| id(repo, rev))
+}
+
+fn has_file(repo: &gix::Repository, rev: &str, path: &str) -> bool {
+    repo.rev_parse_single(format!("{rev}:{path}").as_str())
+        .is_ok()
+}
+
+/// Return `(ref-name, old, new)` of all updated references, sorted by name.
+fn updated_references(result: &MoveChangesResult) -> Vec<(String, gix::ObjectId, gix::ObjectId)> {
+    let mut out: Vec<_> = result
+        .updated_references
+        .iter()
+        .map(|updated| {
+            (
+                updated.reference.to_string(),
+                updated.old_commit_id,
+                updated.new_commit_id,
+            )
+        })
+        .collect();
+    out.sort();
+    out
+}
+
+fn replaced(result: &MoveChangesResult, old: gix::ObjectId) -> Option<gix::ObjectId> {
+    result
+        .replaced_commits
+        .iter()
+        .find_map(|(from, to)| (*from == old).then_some(*to))
+}
@@ -142,9 +142,16 @@ pub fn to_change_specs_all_hunks_with_context_lines(
                         path: change.path,
                         hunk_headers: hunks.into_iter().map(Into::into).collect(),
                     },
-                    Some(_) => unreachable!("tests won't be binary or too large"),
-                    None => {
-                        // Assume it's a submodule or something without content, don't do hunks then.
+                    Some(
+                        but_core::UnifiedDiff::Binary | but_core::UnifiedDiff::TooLarge { .. },
+                    ) => {
+                        unreachable!("tests won't be binary or too large")
+                    }
+                    Some(
+                        but_core::UnifiedDiff::Submodule { .. } | but_core::UnifiedDiff::Lfs { .. },
+                    )
+                    | None => {
+                        // It's a submodule or something that is committed whole, don't do hunks then.
                         DiffSpec {
                             path: change.path,
                             ..Default::default()
@@ -38,12 +38,27 @@ For examples `but rub --help`.")]
         /// The target entity to combine with the source
         target: String,
     },
+    /// Resolves the conflicts of a conflicted commit, file by file.
+    ///
+    /// Conflicted files are written to the worktree with conflict markers, and can be edited directly
+    /// or with the configured `merge.tool`.
+    Resolve(resolve::Platform),
     /// Starts up the MCP server.
     Mcp {
         /// Starts the internal MCP server which has more granular tools.
         #[clap(long, short = 'i', hide = true)]
         internal: bool,
     },
+    /// Watches projects in the background and serves their changes and a JSON-RPC API on a Unix domain socket.
+    ///
+    /// This allows editors to receive live updates and drive GitButler without the GUI.
+    Daemon {
+        /// The worktrees of the projects to watch, defaulting to the project in the current directory.
+        projects: Vec<PathBuf>,
+        /// The path of the socket to listen on, defaulting to one in the application data directory.
+        #[clap(long, short = 's', value_name = "PATH")]
+        socket: Option<PathBuf>,
+    },
     /// GitButler Actions are automated tasks (like macros) that can be peformed on a repository.
     #[clap(hide = true)]
     Actions(actions::Platform),
@@ -68,6 +83,8 @@ pub enum CommandName {
     Status,
     #[clap(alias = "rub")]
     Rub,
+    #[clap(alias = "resolve")]
+    Resolve,
     #[clap(
         alias = "claude-pre-tool",
         alias = "claudepretool",
@@ -134,3 +151,57 @@ pub mod claude {
         Stop,
     }
 }
+
+pub mod resolve {
+    #[derive(Debug, clap::Parser)]
+    pub struct Platform {
+        #[clap(subcommand)]
+        pub cmd: Subcommands,
+    }
+    #[derive(Debug, clap::Subcommand)]
+    pub enum Subcommands {
+        /// Enters edit mode for a conflicted commit and writes its conflicted files to the worktree.
+        Start {
+            /// The commit to resolve.
+            commit: String,
+            /// The style of the conflict markers, defaulting to `merge.conflictStyle`.
+            #[clap(long, short = 's', value_enum)]
+            style: Option<Style>,
+        },
+        /// Lists the conflicted files and whether they are resolved.
+        Status,
+        /// Writes conflicted files with conflict markers once more, discarding their changes.
+        Markers {
+            /// The style of the conflict markers, defaulting to `merge.conflictStyle`.
+            #[clap(long, short = 's', value_enum)]
+            style: Option<Style>,
+            /// The files to write, defaulting to all unresolved files.
+            paths: Vec<String>,
+        },
+        /// Runs the merge tool configured with `merge.tool` on a conflicted file.
+        Tool {
+            /// The conflicted file to resolve.
+            path: String,
+        },
+        /// Marks conflicted files as resolved.
+        Mark {
+            /// The files to mark as resolved.
+            #[clap(required = true)]
+            paths: Vec<String>,
+        },
+        /// Rewrites the commit with all resolutions and returns to the workspace.
+        Finish,
+        /// Returns to the workspace without changing the commit.
+        Abort,
+    }
+
+    #[derive(Debug, Clone, Copy, clap::ValueEnum)]
+    pub enum Style {
+        /// Show our side and their side.
+        Merge,
+        /// Show our side, their side and the common ancestor.
+        Diff3,
+        /// Like diff3, but with lines that are the same on both sides moved out of the conflict.
+        Zdiff3,
+    }
+}
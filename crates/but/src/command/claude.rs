@@ -361,6 +361,7 @@ pub(crate) fn handle_post_tool_call() -> anyhow::Result<ClaudeHookOutput> {
             hunk_header: a.hunk_header,
             path_bytes: a.path_bytes,
             stack_id: Some(stack_id),
+            line_ranges: a.line_ranges,
         })
         .collect();
 
@@ -0,0 +1,143 @@
+//! Implementations of the requests that need to access a project.
+use but_hunk_assignment::{HunkAssignmentRequest, WorktreeChanges};
+use but_hunk_dependency::ui::hunk_dependencies_for_workspace_changes_by_worktree_dir;
+use but_workspace::{StackId, commit_engine, ui::StackEntry};
+use gitbutler_command_context::CommandContext;
+use gitbutler_oplog::{OplogExt, SnapshotExt};
+use gitbutler_project::ProjectId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{rpc, unix::State};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusParams {
+    project_id: ProjectId,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Status {
+    /// All stacks applied to the workspace.
+    stacks: Vec<StackEntry>,
+    /// Uncommitted changes along with their assignments.
+    #[serde(flatten)]
+    changes: WorktreeChanges,
+}
+
+/// Return the applied stacks and the uncommitted changes of a project, just like the GUI sees them.
+pub fn status(state: &State, params: StatusParams) -> Result<Value, rpc::Error> {
+    let ctx = &mut open(state, params.project_id)?;
+    let repo = ctx.gix_repo_for_merging_non_persisting()?;
+    let stacks = if ctx.app_settings().feature_flags.ws3 {
+        let meta = crate::mcp_internal::project::ref_metadata_toml(ctx.project())?;
+        but_workspace::stacks_v3(&repo, &meta, Default::default())
+    } else {
+        but_workspace::stacks(ctx, &ctx.project().gb_dir(), &repo, Default::default())
+    }?;
+
+    let changes = but_core::diff::worktree_changes(&ctx.gix_repo()?)?;
+    let dependencies = hunk_dependencies_for_workspace_changes_by_worktree_dir(
+        ctx,
+        &ctx.project().path,
+        &ctx.project().gb_dir(),
+        Some(changes.changes.clone()),
+    );
+    let (assignments, assignments_error) = match &dependencies {
+        Ok(dependencies) => but_hunk_assignment::assignments_with_fallback(
+            ctx,
+            false,
+            Some(changes.changes.clone()),
+            Some(dependencies),
+        )?,
+        Err(e) => (
+            vec![],
+            Some(anyhow::anyhow!("failed to get hunk dependencies: {}", e)),
+        ),
+    };
+
+    Ok(serde_json::to_value(Status {
+        stacks,
+        changes: WorktreeChanges {
+            worktree_changes: changes.into(),
+            assignments,
+            assignments_error: assignments_error.map(|err| serde_error::Error::new(&*err)),
+            dependencies: dependencies.as_ref().ok().cloned(),
+            dependencies_error: dependencies
+                .as_ref()
+                .err()
+                .map(|err| serde_error::Error::new(&**err)),
+        },
+    })
+    .map_err(anyhow::Error::from)?)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignParams {
+    project_id: ProjectId,
+    assignments: Vec<HunkAssignmentRequest>,
+}
+
+/// Assign hunks to stacks, and return the requests that were rejected.
+pub fn assign(state: &State, params: AssignParams) -> Result<Value, rpc::Error> {
+    let ctx = &mut open(state, params.project_id)?;
+    let rejections = but_hunk_assignment::assign(ctx, params.assignments, None)?;
+    Ok(serde_json::to_value(rejections).map_err(anyhow::Error::from)?)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitParams {
+    project_id: ProjectId,
+    /// The stack to commit to.
+    stack_id: StackId,
+    /// The name of the branch in the stack to commit to.
+    stack_branch_name: String,
+    /// The hex id of the commit to use as parent, or the tip of the branch if unset.
+    #[serde(default)]
+    parent_id: Option<String>,
+    /// The changes to commit.
+    changes: Vec<but_workspace::DiffSpec>,
+    message: String,
+}
+
+/// Commit changes from the worktree to a branch, recording a snapshot for undo just like the GUI does.
+pub fn commit(state: &State, params: CommitParams) -> Result<Value, rpc::Error> {
+    let ctx = open(state, params.project_id)?;
+    let parent_id = params
+        .parent_id
+        .map(|id| gix::ObjectId::from_hex(id.as_bytes()))
+        .transpose()
+        .map_err(|err| rpc::Error::new(rpc::INVALID_PARAMS, err.to_string()))?;
+
+    let mut guard = ctx.project().exclusive_worktree_access();
+    let snapshot_tree = ctx.prepare_snapshot(guard.read_permission());
+    let outcome = commit_engine::create_commit_simple(
+        &ctx,
+        params.stack_id,
+        parent_id,
+        params.changes,
+        params.message.clone(),
+        params.stack_branch_name,
+        guard.write_permission(),
+    );
+    let _ = snapshot_tree.and_then(|snapshot_tree| {
+        ctx.snapshot_commit_creation(
+            snapshot_tree,
+            outcome.as_ref().err(),
+            params.message,
+            None,
+            guard.write_permission(),
+        )
+    });
+
+    let outcome: commit_engine::ui::CreateCommitOutcome = outcome?.into();
+    Ok(serde_json::to_value(outcome).map_err(anyhow::Error::from)?)
+}
+
+fn open(state: &State, project_id: ProjectId) -> anyhow::Result<CommandContext> {
+    let project = state.project(project_id)?;
+    CommandContext::open(project, state.app_settings.get()?.clone())
+}
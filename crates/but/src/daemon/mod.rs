@@ -0,0 +1,397 @@
+//! A headless daemon which watches projects just like the GUI does, and serves their changes along with
+//! a request API as JSON-RPC over a Unix domain socket, so that editors can drive GitButler without it.
+//!
+//! Clients send one request per line and receive one response per line. After calling `subscribe`,
+//! they also receive `change` notifications which carry the same events the GUI would receive.
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+#[cfg(unix)]
+mod methods;
+#[cfg(unix)]
+mod rpc;
+
+/// Watch all projects at `project_dirs`, or the one in `current_dir` if there are none, and serve them
+/// on the Unix domain `socket`, or the default socket in the application data directory.
+#[cfg(unix)]
+pub async fn start(
+    current_dir: &Path,
+    project_dirs: &[PathBuf],
+    socket: Option<&Path>,
+) -> Result<()> {
+    unix::start(current_dir, project_dirs, socket).await
+}
+
+#[cfg(not(unix))]
+pub async fn start(
+    _current_dir: &Path,
+    _project_dirs: &[PathBuf],
+    _socket: Option<&Path>,
+) -> Result<()> {
+    anyhow::bail!("The daemon is only available on Unix for now")
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        os::unix::fs::PermissionsExt,
+        path::{Path, PathBuf},
+        sync::Arc,
+    };
+
+    use anyhow::{Context, Result, bail};
+    use but_settings::AppSettingsWithDiskSync;
+    use gitbutler_project::{Project, ProjectId};
+    use gitbutler_watcher::Change;
+    use serde::Deserialize;
+    use serde_json::{Value, json};
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::{UnixListener, UnixStream, unix::OwnedWriteHalf},
+        sync::broadcast,
+    };
+
+    use super::{
+        methods,
+        rpc::{self, Notification, Request, Response},
+    };
+
+    /// The amount of changes that may queue up for a slow client before it misses some.
+    const CHANGE_CAPACITY: usize = 256;
+
+    /// The path of the socket to listen on if none is given.
+    fn default_socket_path() -> Result<PathBuf> {
+        Ok(but_path::app_data_dir()?.join("but-daemon.sock"))
+    }
+
+    /// The state shared by all connections.
+    pub(super) struct State {
+        pub projects: Vec<Project>,
+        pub app_settings: AppSettingsWithDiskSync,
+        changes: broadcast::Sender<Change>,
+    }
+
+    impl State {
+        pub fn project(&self, id: ProjectId) -> Result<&Project> {
+            self.projects
+                .iter()
+                .find(|p| p.id == id)
+                .with_context(|| format!("Project {id} isn't watched by this daemon"))
+        }
+    }
+
+    pub async fn start(
+        current_dir: &Path,
+        project_dirs: &[PathBuf],
+        socket: Option<&Path>,
+    ) -> Result<()> {
+        let projects = if project_dirs.is_empty() {
+            vec![registered_project(current_dir)?]
+        } else {
+            project_dirs
+                .iter()
+                .map(|dir| registered_project(dir))
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        let config_dir = dirs_next::config_dir()
+            .context("missing config dir")?
+            .join("gitbutler");
+        std::fs::create_dir_all(&config_dir)?;
+        let mut app_settings = AppSettingsWithDiskSync::new(config_dir)?;
+        app_settings.watch_in_background(|_settings| Ok(()))?;
+
+        let (changes, _) = broadcast::channel(CHANGE_CAPACITY);
+        let users = gitbutler_user::Controller::from_path(but_path::app_data_dir()?);
+        let mut watchers = Vec::with_capacity(projects.len());
+        for project in &projects {
+            let exclusive_access = project.try_exclusive_access();
+            if let Err(err) = &exclusive_access {
+                tracing::warn!(project_id = %project.id, ?err, "Watching project which is opened elsewhere");
+            }
+            let handler = gitbutler_watcher::Handler::new(users.clone(), {
+                let changes = changes.clone();
+                move |change| {
+                    // It's fine if there is no subscriber.
+                    changes.send(change).ok();
+                    Ok(())
+                }
+            });
+            let watcher = gitbutler_watcher::watch_in_background(
+                handler,
+                project.path.clone(),
+                project.id,
+                project.file_monitor_backend,
+                app_settings.clone(),
+            )?;
+            watchers.push((watcher, exclusive_access.ok()));
+        }
+
+        let socket = match socket {
+            Some(socket) => socket.to_owned(),
+            None => default_socket_path()?,
+        };
+        let listener = bind(&socket).await?;
+        eprintln!(
+            "Watching {} project(s), listening on {}",
+            projects.len(),
+            socket.display()
+        );
+
+        let state = Arc::new(State {
+            projects,
+            app_settings,
+            changes,
+        });
+        let outcome = tokio::select! {
+            res = accept_connections(listener, state) => res,
+            res = tokio::signal::ctrl_c() => res.map_err(Into::into),
+        };
+        std::fs::remove_file(&socket).ok();
+        drop(watchers);
+        outcome
+    }
+
+    /// Return the project that was added to GitButler for the worktree containing `dir`.
+    ///
+    /// Only these can be watched as the watcher looks them up by ID, and they carry settings like the
+    /// file monitor backend to use.
+    fn registered_project(dir: &Path) -> Result<Project> {
+        let repo = gix::discover(dir)
+            .with_context(|| format!("Could not find a Git repository at {}", dir.display()))?;
+        let worktree = repo
+            .workdir()
+            .with_context(|| format!("Repository at {} has no worktree", dir.display()))?;
+        let worktree = gix::path::realpath(worktree)?;
+        gitbutler_project::list()?
+            .into_iter()
+            .find(|project| gix::path::realpath(&project.path).is_ok_and(|path| path == worktree))
+            .with_context(|| {
+                format!(
+                    "{} isn't a GitButler project yet - add it in the app first",
+                    worktree.display()
+                )
+            })
+    }
+
+    /// Listen on `socket`, replacing it if it's left over from a daemon that isn't running anymore.
+    async fn bind(socket: &Path) -> Result<UnixListener> {
+        if socket.exists() {
+            if UnixStream::connect(socket).await.is_ok() {
+                bail!("A daemon is already listening on {}", socket.display());
+            }
+            std::fs::remove_file(socket)?;
+        }
+        if let Some(parent) = socket.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let listener = UnixListener::bind(socket)
+            .with_context(|| format!("Could not listen on {}", socket.display()))?;
+        // Only the current user may drive their projects.
+        std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))?;
+        Ok(listener)
+    }
+
+    async fn accept_connections(listener: UnixListener, state: Arc<State>) -> Result<()> {
+        loop {
+            let (stream, _addr) = listener.accept().await?;
+            let state = state.clone();
+            tokio::spawn(async move {
+                if let Err(err) = serve_connection(stream, state).await {
+                    tracing::debug!(?err, "Client connection closed with error");
+                }
+            });
+        }
+    }
+
+    /// The changes a client asked to be notified about.
+    struct Subscription {
+        changes: broadcast::Receiver<Change>,
+        /// Only send changes for this project, or for all projects if `None`.
+        project_id: Option<ProjectId>,
+    }
+
+    impl Subscription {
+        async fn next(subscription: &mut Option<Self>) -> Option<Change> {
+            let Some(subscription) = subscription else {
+                return std::future::pending().await;
+            };
+            loop {
+                match subscription.changes.recv().await {
+                    Ok(change) => {
+                        if subscription
+                            .project_id
+                            .is_none_or(|id| id == project_id(&change))
+                        {
+                            return Some(change);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "Client is too slow to receive all changes");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    }
+
+    async fn serve_connection(stream: UnixStream, state: Arc<State>) -> Result<()> {
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        let mut subscription = None;
+        loop {
+            tokio::select! {
+                line = lines.next_line() => {
+                    let Some(line) = line? else {
+                        break;
+                    };
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    if let Some(response) = handle_line(&line, &state, &mut subscription).await {
+                        send(&mut write, &response).await?;
+                    }
+                }
+                Some(change) = Subscription::next(&mut subscription) => {
+                    send(&mut write, &Notification::new("change", change_to_json(change))).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn send(out: &mut OwnedWriteHalf, message: &impl serde::Serialize) -> Result<()> {
+        let mut buf = serde_json::to_vec(message)?;
+        buf.push(b'\n');
+        out.write_all(&buf).await?;
+        Ok(())
+    }
+
+    /// Handle the request in `line` and return the response to send, if any.
+    async fn handle_line(
+        line: &str,
+        state: &Arc<State>,
+        subscription: &mut Option<Subscription>,
+    ) -> Option<Response> {
+        let request: Request = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(err) => {
+                return Some(Response::error(
+                    Value::Null,
+                    rpc::Error::new(rpc::PARSE_ERROR, err.to_string()),
+                ));
+            }
+        };
+        let id = request.id.clone();
+        let outcome = if request.jsonrpc != "2.0" {
+            Err(rpc::Error::new(
+                rpc::INVALID_REQUEST,
+                "Only JSON-RPC 2.0 is supported",
+            ))
+        } else {
+            handle_request(request, state, subscription).await
+        };
+        // Notifications are never answered.
+        let id = id?;
+        Some(match outcome {
+            Ok(result) => Response::result(id, result),
+            Err(err) => Response::error(id, err),
+        })
+    }
+
+    #[derive(Debug, Default, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct SubscribeParams {
+        #[serde(default)]
+        project_id: Option<ProjectId>,
+    }
+
+    async fn handle_request(
+        request: Request,
+        state: &Arc<State>,
+        subscription: &mut Option<Subscription>,
+    ) -> Result<Value, rpc::Error> {
+        match request.method.as_str() {
+            "subscribe" => {
+                let params: SubscribeParams = if request.params.is_null() {
+                    SubscribeParams::default()
+                } else {
+                    params(request.params)?
+                };
+                *subscription = Some(Subscription {
+                    changes: state.changes.subscribe(),
+                    project_id: params.project_id,
+                });
+                Ok(Value::Bool(true))
+            }
+            "unsubscribe" => {
+                *subscription = None;
+                Ok(Value::Bool(true))
+            }
+            "projects" => Ok(json!(
+                state
+                    .projects
+                    .iter()
+                    .map(|p| json!({ "id": p.id, "title": p.title, "path": p.path }))
+                    .collect::<Vec<_>>()
+            )),
+            "status" | "assign" | "commit" => {
+                let state = state.clone();
+                tokio::task::spawn_blocking(move || {
+                    let method = request.method.as_str();
+                    let params = request.params;
+                    match method {
+                        "status" => methods::status(&state, self::params(params)?),
+                        "assign" => methods::assign(&state, self::params(params)?),
+                        "commit" => methods::commit(&state, self::params(params)?),
+                        _ => unreachable!("BUG: only known methods are dispatched"),
+                    }
+                })
+                .await
+                .map_err(|err| rpc::Error::new(rpc::SERVER_ERROR, err.to_string()))?
+            }
+            method => Err(rpc::Error::new(
+                rpc::METHOD_NOT_FOUND,
+                format!("Unknown method '{method}'"),
+            )),
+        }
+    }
+
+    fn params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, rpc::Error> {
+        serde_json::from_value(params)
+            .map_err(|err| rpc::Error::new(rpc::INVALID_PARAMS, err.to_string()))
+    }
+
+    fn project_id(change: &Change) -> ProjectId {
+        match change {
+            Change::GitFetch(project_id) | Change::GitActivity(project_id) => *project_id,
+            Change::GitHead { project_id, .. } | Change::WorktreeChanges { project_id, .. } => {
+                *project_id
+            }
+        }
+    }
+
+    /// Turn `change` into the same event the GUI would receive.
+    fn change_to_json(change: Change) -> Value {
+        let project_id = project_id(&change);
+        let (name, payload) = match change {
+            Change::GitFetch(_) => ("git/fetch", json!({})),
+            Change::GitHead {
+                head,
+                operating_mode,
+                ..
+            } => (
+                "git/head",
+                json!({ "head": head, "operatingMode": operating_mode }),
+            ),
+            Change::GitActivity(_) => ("git/activity", json!({})),
+            Change::WorktreeChanges { changes, .. } => ("worktree_changes", json!(&changes)),
+        };
+        json!({
+            "projectId": project_id,
+            "name": format!("project://{project_id}/{name}"),
+            "payload": payload,
+        })
+    }
+}
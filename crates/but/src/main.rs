@@ -7,11 +7,13 @@ use metrics::{Event, Metrics, Props, metrics_if_configured};
 
 use crate::command::claude::OutputAsJson;
 mod command;
+mod daemon;
 mod id;
 mod log;
 mod mcp;
 mod mcp_internal;
 mod metrics;
+mod resolve;
 mod rub;
 mod status;
 
@@ -22,6 +24,8 @@ async fn main() -> Result<()> {
 
     let namespace = option_env!("IDENTIFIER").unwrap_or("com.gitbutler.app");
     gitbutler_secret::secret::set_application_namespace(namespace);
+    // SAFETY: This is safe because we are initializing the askpass broker before anything could use it.
+    unsafe { gitbutler_repo_actions::askpass::init_terminal() };
     let start = std::time::Instant::now();
 
     match &args.cmd {
@@ -29,9 +33,12 @@ async fn main() -> Result<()> {
             if *internal {
                 mcp_internal::start(app_settings).await
             } else {
-                mcp::start(app_settings).await
+                mcp::start(&args.current_dir, app_settings).await
             }
         }
+        Subcommands::Daemon { projects, socket } => {
+            daemon::start(&args.current_dir, projects, socket.as_deref()).await
+        }
         Subcommands::Actions(actions::Platform { cmd }) => match cmd {
             Some(actions::Subcommands::HandleChanges {
                 description,
@@ -96,6 +103,14 @@ async fn main() -> Result<()> {
             metrics_if_configured(app_settings, CommandName::Status, props(start, &result)).ok();
             Ok(())
         }
+        Subcommands::Resolve(platform) => {
+            let result = resolve::handle(&args.current_dir, args.json, &platform.cmd);
+            if let Err(e) = &result {
+                eprintln!("{e:#}");
+            }
+            metrics_if_configured(app_settings, CommandName::Resolve, props(start, &result)).ok();
+            Ok(())
+        }
         Subcommands::Rub { source, target } => {
             let result = rub::handle(&args.current_dir, args.json, source, target)
                 .context("Rubbed the wrong way.");
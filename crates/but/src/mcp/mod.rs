@@ -1,27 +1,36 @@
 use std::{
-    path::PathBuf,
+    path::{Path, PathBuf},
     str::FromStr,
     sync::{Arc, Mutex},
 };
 
 mod event;
+mod resources;
+mod workspace;
 use anyhow::Result;
 use but_action::{ActionHandler, Outcome, Source, reword::CommitEvent};
 use but_settings::AppSettings;
+use but_tools::emit::ChannelEmitter;
 use gitbutler_command_context::CommandContext;
 use gitbutler_project::Project;
 use rmcp::{
-    Error as McpError, ServerHandler, ServiceExt,
+    Error as McpError, RoleServer, ServerHandler, ServiceExt,
+    handler::server::tool::ToolCallContext,
     model::{
-        CallToolResult, Content, Implementation, ProtocolVersion, ServerCapabilities, ServerInfo,
+        CallToolRequestParam, CallToolResult, Content, Implementation, ListResourceTemplatesResult,
+        ListResourcesResult, ListToolsResult, PaginatedRequestParam, ProtocolVersion,
+        ReadResourceRequestParam, ReadResourceResult, ServerCapabilities, ServerInfo,
+        SubscribeRequestParam, UnsubscribeRequestParam,
     },
-    schemars, tool,
+    schemars,
+    service::RequestContext,
+    tool,
 };
 use tracing_subscriber::{self, EnvFilter};
 
 use crate::metrics::{Event, EventKind, Metrics};
 
-pub(crate) async fn start(app_settings: AppSettings) -> Result<()> {
+pub(crate) async fn start(current_dir: &Path, app_settings: AppSettings) -> Result<()> {
     // Initialize the tracing subscriber with file and stdout logging
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::DEBUG.into()))
@@ -32,14 +41,22 @@ pub(crate) async fn start(app_settings: AppSettings) -> Result<()> {
     tracing::info!("Starting MCP server");
 
     let client_info = Arc::new(Mutex::new(None));
+    let (emitter, events) = ChannelEmitter::new();
+    let resources = resources::Resources::new(current_dir, app_settings.clone());
     let transport = (tokio::io::stdin(), tokio::io::stdout());
-    let service = Mcp::new(app_settings, client_info.clone())
-        .serve(transport)
-        .await?;
+    let service = Mcp::new(
+        app_settings,
+        client_info.clone(),
+        resources.clone(),
+        emitter,
+    )
+    .serve(transport)
+    .await?;
     let info = service.peer_info();
     if let Ok(mut guard) = client_info.lock() {
         guard.replace(info.client_info.clone());
     }
+    tokio::spawn(resources.notify_changes(service.peer().clone(), events));
     service.waiting().await?;
     Ok(())
 }
@@ -50,11 +67,21 @@ pub struct Mcp {
     metrics: Metrics,
     client_info: Arc<Mutex<Option<Implementation>>>,
     event_handler: event::Handler,
+    /// The workspace tools, which are offered in addition to the ones in the tool box.
+    tools: workspace::Tools,
+    resources: resources::Resources,
+    /// Receives the stack updates of the workspace tools to notify about changed resources.
+    emitter: ChannelEmitter,
 }
 
 #[tool(tool_box)]
 impl Mcp {
-    pub fn new(app_settings: AppSettings, client_info: Arc<Mutex<Option<Implementation>>>) -> Self {
+    pub fn new(
+        app_settings: AppSettings,
+        client_info: Arc<Mutex<Option<Implementation>>>,
+        resources: resources::Resources,
+        emitter: ChannelEmitter,
+    ) -> Self {
         let metrics = Metrics::new_with_background_handling(&app_settings);
         let event_handler = event::Handler::new_with_background_handling();
         Self {
@@ -62,6 +89,9 @@ impl Mcp {
             metrics,
             client_info,
             event_handler,
+            tools: workspace::Tools::new(),
+            resources,
+            emitter,
         }
     }
 
@@ -163,6 +193,43 @@ impl Mcp {
         }
         Ok(outcome)
     }
+
+    /// Call one of the workspace tools, which report failures as part of their result.
+    fn call_workspace_tool(
+        &self,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, McpError> {
+        let client_info = self
+            .client_info
+            .lock()
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?
+            .clone();
+        let start_time = std::time::Instant::now();
+        let result = self.tools.call(
+            &request.name,
+            request.arguments,
+            &self.app_settings,
+            &self.emitter,
+        );
+        let error = match &result {
+            Ok(value) => value.get("error").map(ToString::to_string),
+            Err(err) => Some(err.to_string()),
+        };
+        let event = &mut Event::new(EventKind::Mcp);
+        event.insert_prop("endpoint", &*request.name);
+        event.insert_prop("durationMs", start_time.elapsed().as_millis());
+        event.insert_prop("error", error.clone());
+        event.insert_prop("clientName", client_info.clone().map(|i| i.name));
+        event.insert_prop("clientVersion", client_info.clone().map(|i| i.version));
+        self.metrics.capture(event);
+
+        let content = vec![Content::json(result?)?];
+        Ok(if error.is_some() {
+            CallToolResult::error(content)
+        } else {
+            CallToolResult::success(content)
+        })
+    }
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
@@ -180,12 +247,16 @@ pub struct GitButlerUpdateBranchesRequest {
     pub current_working_directory: String,
 }
 
-#[tool(tool_box)]
 impl ServerHandler for Mcp {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             instructions: Some("GitButler MCP server".into()),
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_resources_list_changed()
+                .build(),
             server_info: Implementation {
                 name: "GitButler MCP Server".into(),
                 version: "1.0.0".into(),
@@ -193,4 +264,87 @@ impl ServerHandler for Mcp {
             protocol_version: ProtocolVersion::LATEST,
         }
     }
+
+    async fn list_tools(
+        &self,
+        _request: PaginatedRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        let mut tools = Self::tool_box().list();
+        tools.extend(self.tools.list());
+        Ok(ListToolsResult {
+            next_cursor: None,
+            tools,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.tools.contains(&request.name) {
+            self.call_workspace_tool(request)
+        } else {
+            Self::tool_box()
+                .call(ToolCallContext::new(self, request, context))
+                .await
+        }
+    }
+
+    async fn list_resources(
+        &self,
+        _request: PaginatedRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let resources = self
+            .resources
+            .list()
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        Ok(ListResourcesResult {
+            next_cursor: None,
+            resources,
+        })
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: PaginatedRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, McpError> {
+        Ok(ListResourceTemplatesResult {
+            next_cursor: None,
+            resource_templates: self.resources.templates(),
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        ReadResourceRequestParam { uri }: ReadResourceRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        self.resources
+            .read(&uri)
+            .map_err(|e| McpError::resource_not_found(e.to_string(), None))
+    }
+
+    async fn subscribe(
+        &self,
+        SubscribeRequestParam { uri }: SubscribeRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.resources
+            .subscribe(uri)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))
+    }
+
+    async fn unsubscribe(
+        &self,
+        UnsubscribeRequestParam { uri }: UnsubscribeRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.resources
+            .unsubscribe(&uri)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))
+    }
 }
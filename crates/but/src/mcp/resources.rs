@@ -0,0 +1,373 @@
+//! MCP resources for the project the server was started in, which clients can subscribe to for changes.
+use std::{
+    collections::HashSet,
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, bail};
+use but_settings::AppSettings;
+use but_tools::emit::Event;
+use but_workspace::StackId;
+use gitbutler_command_context::CommandContext;
+use gitbutler_project::Project;
+use rmcp::{
+    Peer, RoleServer,
+    model::{
+        AnnotateAble, RawResource, RawResourceTemplate, ReadResourceResult, Resource,
+        ResourceContents, ResourceTemplate, ResourceUpdatedNotificationParam,
+    },
+};
+
+/// The status of the project, with its stacks and uncommitted changes.
+pub const STATUS_URI: &str = "gitbutler://status";
+/// The details of a stack, followed by its id.
+const STACK_URI_PREFIX: &str = "gitbutler://stacks/";
+
+fn stack_uri(stack_id: StackId) -> String {
+    format!("{STACK_URI_PREFIX}{stack_id}")
+}
+
+#[derive(Debug, Clone)]
+pub struct Resources {
+    /// The project to provide resources for, or `None` if the server wasn't started in one.
+    project: Option<Project>,
+    app_settings: AppSettings,
+    /// The URIs clients want to be notified about when they change.
+    subscriptions: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Resources {
+    pub fn new(current_dir: &Path, app_settings: AppSettings) -> Self {
+        Resources {
+            project: Project::from_path(current_dir).ok(),
+            app_settings,
+            subscriptions: Default::default(),
+        }
+    }
+
+    /// List the project status and the details of each stack in the workspace.
+    pub fn list(&self) -> anyhow::Result<Vec<Resource>> {
+        if self.project.is_none() {
+            return Ok(Vec::new());
+        }
+        let mut resources = vec![
+            RawResource {
+                description: Some(
+                    "The stacks applied to the workspace and the uncommitted file changes".into(),
+                ),
+                mime_type: Some("application/json".into()),
+                ..RawResource::new(STATUS_URI, "Project status")
+            }
+            .no_annotation(),
+        ];
+        let ctx = self.ctx()?;
+        for stack in self.stacks(&ctx)? {
+            let Some(stack_id) = stack.id else {
+                continue;
+            };
+            let name = stack
+                .name()
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| stack_id.to_string());
+            resources.push(
+                RawResource {
+                    description: Some(format!("The branches and commits of the stack '{name}'")),
+                    mime_type: Some("application/json".into()),
+                    ..RawResource::new(stack_uri(stack_id), format!("Stack {name}"))
+                }
+                .no_annotation(),
+            );
+        }
+        Ok(resources)
+    }
+
+    pub fn templates(&self) -> Vec<ResourceTemplate> {
+        vec![
+            RawResourceTemplate {
+                uri_template: format!("{STACK_URI_PREFIX}{{stackId}}"),
+                name: "Stack details".into(),
+                description: Some("The branches and commits of the stack with the given id".into()),
+                mime_type: Some("application/json".into()),
+            }
+            .no_annotation(),
+        ]
+    }
+
+    pub fn read(&self, uri: &str) -> anyhow::Result<ReadResourceResult> {
+        let mut ctx = self.ctx()?;
+        let value = if uri == STATUS_URI {
+            let repo = ctx.gix_repo()?;
+            serde_json::to_value(but_tools::workspace::get_project_status(
+                &mut ctx, &repo, None,
+            )?)?
+        } else if let Some(stack_id) = uri.strip_prefix(STACK_URI_PREFIX) {
+            let stack_id = StackId::from_str(stack_id)
+                .with_context(|| format!("Invalid stack id in '{uri}'"))?;
+            serde_json::to_value(self.stack_details(&ctx, stack_id)?)?
+        } else {
+            bail!("Unknown resource '{uri}'");
+        };
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text(
+                serde_json::to_string_pretty(&value)?,
+                uri,
+            )],
+        })
+    }
+
+    pub fn subscribe(&self, uri: String) -> anyhow::Result<()> {
+        self.subscriptions
+            .lock()
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .insert(uri);
+        Ok(())
+    }
+
+    pub fn unsubscribe(&self, uri: &str) -> anyhow::Result<()> {
+        self.subscriptions
+            .lock()
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .remove(uri);
+        Ok(())
+    }
+
+    /// Turn the stack updates among `events`, and changes to the project made elsewhere like in the GUI,
+    /// into notifications for `peer`, until there are no more events.
+    ///
+    /// The status and the updated stacks are reported if subscribed to, and the list of resources is reported
+    /// as changed whenever stacks were added or removed.
+    pub async fn notify_changes(
+        self,
+        peer: Peer<RoleServer>,
+        mut events: tokio::sync::mpsc::UnboundedReceiver<Event>,
+    ) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let (file_events, mut file_changes) = tokio::sync::mpsc::unbounded_channel();
+        // Keep the monitor alive for as long as we notify. Without it, only changes made by our tools are noticed.
+        let _monitor = gitbutler_filemonitor::spawn(
+            project.id,
+            &project.path,
+            project.file_monitor_backend,
+            file_events,
+        )
+        .inspect_err(|err| tracing::warn!("Failed to watch the project for changes: {err}"))
+        .ok();
+
+        let mut known_stacks = self.stack_ids().unwrap_or_default();
+        loop {
+            let updated_stack = tokio::select! {
+                Some(event) = events.recv() => {
+                    let Event::StackUpdate { stack_id, .. } = event else {
+                        continue;
+                    };
+                    Some(stack_id)
+                }
+                // Any stack may have changed, as we can't tell what a file change means.
+                Some(_) = file_changes.recv() => None,
+                else => break,
+            };
+            let stacks = self.stack_ids().unwrap_or_default();
+            let Some(uris) = updated_uris(updated_stack, &stacks, &known_stacks) else {
+                continue;
+            };
+            for uri in uris {
+                if !self.is_subscribed(&uri) {
+                    continue;
+                }
+                if let Err(err) = peer
+                    .notify_resource_updated(ResourceUpdatedNotificationParam { uri })
+                    .await
+                {
+                    tracing::warn!("Failed to notify about resource update: {err}");
+                }
+            }
+            if stacks != known_stacks {
+                known_stacks = stacks;
+                if let Err(err) = peer.notify_resource_list_changed().await {
+                    tracing::warn!("Failed to notify about resource list change: {err}");
+                }
+            }
+        }
+    }
+
+    fn is_subscribed(&self, uri: &str) -> bool {
+        self.subscriptions
+            .lock()
+            .map(|subscriptions| subscriptions.contains(uri))
+            .unwrap_or_default()
+    }
+
+    fn ctx(&self) -> anyhow::Result<CommandContext> {
+        let project = self
+            .project
+            .as_ref()
+            .context("The MCP server wasn't started in a GitButler project")?;
+        CommandContext::open(project, self.app_settings.clone())
+    }
+
+    fn stack_ids(&self) -> anyhow::Result<HashSet<StackId>> {
+        let ctx = self.ctx()?;
+        Ok(self
+            .stacks(&ctx)?
+            .into_iter()
+            .filter_map(|stack| stack.id)
+            .collect())
+    }
+
+    fn stacks(&self, ctx: &CommandContext) -> anyhow::Result<Vec<but_workspace::ui::StackEntry>> {
+        let repo = ctx.gix_repo_for_merging_non_persisting()?;
+        if ctx.app_settings().feature_flags.ws3 {
+            let meta = crate::mcp_internal::project::ref_metadata_toml(ctx.project())?;
+            but_workspace::stacks_v3(&repo, &meta, but_workspace::StacksFilter::InWorkspace)
+        } else {
+            but_workspace::stacks(
+                ctx,
+                &ctx.project().gb_dir(),
+                &repo,
+                but_workspace::StacksFilter::InWorkspace,
+            )
+        }
+    }
+
+    fn stack_details(
+        &self,
+        ctx: &CommandContext,
+        stack_id: StackId,
+    ) -> anyhow::Result<but_workspace::ui::StackDetails> {
+        if ctx.app_settings().feature_flags.ws3 {
+            let repo = ctx.gix_repo_for_merging_non_persisting()?;
+            let meta = crate::mcp_internal::project::ref_metadata_toml(ctx.project())?;
+            but_workspace::stack_details_v3(Some(stack_id), &repo, &meta)
+        } else {
+            but_workspace::stack_details(&ctx.project().gb_dir(), stack_id, ctx)
+        }
+    }
+}
+
+/// Return the URIs of the resources that may have changed after `updated_stack` was updated,
+/// or after anything in the project changed if it's `None`, or `None` if the update isn't about our project.
+///
+/// `stacks` are the stacks in the workspace now, and `known_stacks` are the ones that were in it before.
+fn updated_uris(
+    updated_stack: Option<StackId>,
+    stacks: &HashSet<StackId>,
+    known_stacks: &HashSet<StackId>,
+) -> Option<Vec<String>> {
+    let updated_stacks: Vec<_> = match updated_stack {
+        // Tools may run in any project, but stack ids are unique so they tell whether it's ours.
+        Some(stack_id) if !stacks.contains(&stack_id) && !known_stacks.contains(&stack_id) => {
+            return None;
+        }
+        Some(stack_id) => vec![stack_id],
+        None => {
+            let mut all: Vec<_> = stacks.union(known_stacks).copied().collect();
+            all.sort();
+            all
+        }
+    };
+    Some(
+        std::iter::once(STATUS_URI.to_owned())
+            .chain(updated_stacks.into_iter().map(stack_uri))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stacks(ids: &[StackId]) -> HashSet<StackId> {
+        ids.iter().copied().collect()
+    }
+
+    #[test]
+    fn updates_of_a_stack_affect_the_status_and_the_stack() {
+        let (ours, other) = (StackId::generate(), StackId::generate());
+        assert_eq!(
+            updated_uris(Some(ours), &stacks(&[ours]), &stacks(&[ours])),
+            Some(vec![STATUS_URI.to_owned(), stack_uri(ours)])
+        );
+        assert_eq!(
+            updated_uris(Some(ours), &stacks(&[]), &stacks(&[ours])),
+            Some(vec![STATUS_URI.to_owned(), stack_uri(ours)]),
+            "removed stacks are still ours"
+        );
+        assert_eq!(
+            updated_uris(Some(other), &stacks(&[ours]), &stacks(&[ours])),
+            None,
+            "stacks of other projects are ignored"
+        );
+    }
+
+    #[test]
+    fn changes_in_the_project_affect_all_stacks() {
+        let (kept, added, removed) = (
+            StackId::generate(),
+            StackId::generate(),
+            StackId::generate(),
+        );
+        let uris = updated_uris(None, &stacks(&[kept, added]), &stacks(&[kept, removed]))
+            .expect("changes to files are always ours");
+        assert_eq!(uris[0], STATUS_URI);
+        let mut expected = vec![stack_uri(kept), stack_uri(added), stack_uri(removed)];
+        expected.sort();
+        let mut actual = uris[1..].to_vec();
+        actual.sort();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn subscriptions() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let resources = Resources::new(tmp.path(), AppSettings::default());
+        let uri = stack_uri(StackId::generate());
+        assert!(!resources.is_subscribed(&uri));
+
+        resources.subscribe(uri.clone())?;
+        assert!(resources.is_subscribed(&uri));
+        assert!(!resources.is_subscribed(STATUS_URI));
+
+        resources.unsubscribe(&uri)?;
+        assert!(!resources.is_subscribed(&uri));
+        Ok(())
+    }
+
+    #[test]
+    fn nothing_is_provided_outside_of_a_project() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let resources = Resources::new(tmp.path(), AppSettings::default());
+        assert!(resources.list()?.is_empty());
+        let err = resources.read(STATUS_URI).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The MCP server wasn't started in a GitButler project"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_resources_cannot_be_read() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        gix::init(tmp.path())?;
+        let resources = Resources::new(tmp.path(), AppSettings::default());
+
+        let err = resources.read("gitbutler://unknown").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown resource 'gitbutler://unknown'");
+
+        let uri = format!("{STACK_URI_PREFIX}not-an-id");
+        let err = resources.read(&uri).unwrap_err();
+        assert_eq!(err.to_string(), format!("Invalid stack id in '{uri}'"));
+
+        let templates = resources.templates();
+        assert_eq!(templates.len(), 1);
+        assert_eq!(
+            templates[0].raw.uri_template,
+            format!("{STACK_URI_PREFIX}{{stackId}}")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+//! The workspace tools of [`but_tools`], turned into MCP tools.
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use but_settings::AppSettings;
+use but_tools::{
+    emit::Emitter,
+    tool::{Tool, ToolSession, Toolset},
+};
+use gitbutler_command_context::CommandContext;
+use gitbutler_project::Project;
+use rmcp::{Error as McpError, model::JsonObject};
+
+/// The parameter added to each tool to learn which project it should run in.
+const CWD_PARAMETER: &str = "currentWorkingDirectory";
+
+/// All workspace tools, along with what they remember across the calls of a session.
+#[derive(Clone)]
+pub struct Tools {
+    tools: Vec<Arc<dyn Tool>>,
+    /// The sessions of the toolsets per project worktree, as each call may be for a different project.
+    /// They remember how commits were rewritten, but each call is its own action.
+    sessions: Arc<Mutex<HashMap<PathBuf, ToolSession>>>,
+}
+
+impl std::fmt::Debug for Tools {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tools")
+            .field(
+                "tools",
+                &self.tools.iter().map(|t| t.name()).collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl Tools {
+    pub fn new() -> Self {
+        Tools {
+            tools: but_tools::workspace::workspace_tools(),
+            sessions: Default::default(),
+        }
+    }
+
+    /// Return `true` if a tool named `name` is one of ours.
+    pub fn contains(&self, name: &str) -> bool {
+        self.tools.iter().any(|tool| tool.name() == name)
+    }
+
+    /// Describe all tools, with their input schemas derived from the parameters of each tool.
+    pub fn list(&self) -> Vec<rmcp::model::Tool> {
+        self.tools
+            .iter()
+            .map(|tool| {
+                rmcp::model::Tool::new(
+                    tool.name(),
+                    tool.description().trim().to_owned(),
+                    input_schema(tool.as_ref()),
+                )
+            })
+            .collect()
+    }
+
+    /// Call the tool named `name` with `arguments` in the project they point to, and return its JSON result.
+    ///
+    /// Like all tool calls, it's subject to the policy of the project, with each call counting as its own action.
+    /// MCP clients can't approve calls on behalf of the user, so tools which need approval are refused.
+    pub fn call(
+        &self,
+        name: &str,
+        arguments: Option<JsonObject>,
+        app_settings: &AppSettings,
+        emitter: &dyn Emitter,
+    ) -> Result<serde_json::Value, McpError> {
+        if !self.contains(name) {
+            return Err(McpError::invalid_params(
+                format!("Tool '{name}' not found"),
+                None,
+            ));
+        }
+        let mut arguments = arguments.unwrap_or_default();
+        let current_working_directory = arguments
+            .remove(CWD_PARAMETER)
+            .and_then(|value| value.as_str().map(ToOwned::to_owned))
+            .filter(|path| !path.is_empty())
+            .ok_or_else(|| {
+                McpError::invalid_params(format!("{CWD_PARAMETER} cannot be empty"), None)
+            })?;
+
+        let project = Project::from_path(&PathBuf::from(current_working_directory))
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        let ctx = &mut CommandContext::open(&project, app_settings.clone())
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let session = sessions
+            .remove(&project.path)
+            .unwrap_or_default()
+            .into_next_action();
+        let mut toolset = Toolset::with_session(ctx, emitter, None, session);
+        toolset.register_tools(self.tools.iter().cloned());
+        let result = toolset.call_tool(name, &serde_json::Value::Object(arguments).to_string());
+        sessions.insert(project.path.clone(), toolset.into_session());
+        Ok(result)
+    }
+}
+
+/// Add the required [`CWD_PARAMETER`] to the parameter schema of `tool`, as the server isn't bound to a single project.
+fn input_schema(tool: &dyn Tool) -> JsonObject {
+    let mut schema = match tool.parameters() {
+        serde_json::Value::Object(schema) => schema,
+        _ => JsonObject::new(),
+    };
+    schema.insert("type".into(), "object".into());
+    if let serde_json::Value::Object(properties) = schema
+        .entry("properties")
+        .or_insert_with(|| serde_json::Value::Object(JsonObject::new()))
+    {
+        properties.insert(
+            CWD_PARAMETER.into(),
+            serde_json::json!({
+                "type": "string",
+                "description": "The full root path of the Git project the agent is actively working in"
+            }),
+        );
+    }
+    if let serde_json::Value::Array(required) = schema
+        .entry("required")
+        .or_insert_with(|| serde_json::Value::Array(Vec::new()))
+    {
+        required.push(CWD_PARAMETER.into());
+    }
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use but_tools::{
+        emit::ChannelEmitter,
+        policy::{Policy, Violation},
+    };
+    use gix::ObjectId;
+    use serde_json::json;
+
+    use super::*;
+
+    struct FakeTool {
+        parameters: serde_json::Value,
+    }
+
+    impl Tool for FakeTool {
+        fn name(&self) -> String {
+            "fake".into()
+        }
+
+        fn description(&self) -> String {
+            "A tool for testing".into()
+        }
+
+        fn parameters(&self) -> serde_json::Value {
+            self.parameters.clone()
+        }
+
+        fn call(
+            self: Arc<Self>,
+            _parameters: serde_json::Value,
+            _ctx: &mut CommandContext,
+            _emitter: &dyn Emitter,
+            _commit_mapping: &mut HashMap<ObjectId, ObjectId>,
+        ) -> anyhow::Result<serde_json::Value> {
+            unreachable!("the tool isn't called")
+        }
+    }
+
+    #[test]
+    fn input_schema_requires_the_working_directory_along_with_the_parameters() {
+        let tool = FakeTool {
+            parameters: json!({
+                "type": "object",
+                "properties": {"name": {"type": "string"}},
+                "required": ["name"]
+            }),
+        };
+        let schema = serde_json::Value::Object(input_schema(&tool));
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["properties"]["name"], json!({"type": "string"}));
+        assert_eq!(schema["properties"][CWD_PARAMETER]["type"], "string");
+        assert_eq!(schema["required"], json!(["name", CWD_PARAMETER]));
+    }
+
+    #[test]
+    fn input_schema_of_tools_without_parameters() {
+        let tool = FakeTool {
+            parameters: serde_json::Value::Null,
+        };
+        let schema = serde_json::Value::Object(input_schema(&tool));
+        assert_eq!(schema["type"], "object");
+        assert_eq!(
+            schema["properties"]
+                .as_object()
+                .expect("properties are added")
+                .keys()
+                .collect::<Vec<_>>(),
+            [CWD_PARAMETER]
+        );
+        assert_eq!(schema["required"], json!([CWD_PARAMETER]));
+    }
+
+    #[test]
+    fn all_workspace_tools_are_listed_with_the_working_directory() {
+        let tools = Tools::new().list();
+        let mut names: Vec<_> = tools.iter().map(|tool| tool.name.to_string()).collect();
+        names.sort();
+        let mut expected: Vec<_> = but_tools::workspace::workspace_tools()
+            .iter()
+            .map(|tool| tool.name())
+            .collect();
+        expected.sort();
+        assert_eq!(names, expected);
+        for tool in &tools {
+            assert!(
+                tool.input_schema["required"]
+                    .as_array()
+                    .is_some_and(|required| required.contains(&json!(CWD_PARAMETER))),
+                "{} requires the working directory",
+                tool.name
+            );
+        }
+    }
+
+    #[test]
+    fn calls_need_a_known_tool_and_the_working_directory() {
+        let tools = Tools::new();
+        let (emitter, _events) = ChannelEmitter::new();
+        let app_settings = AppSettings::default();
+
+        let err = tools
+            .call("unknown", None, &app_settings, &emitter)
+            .unwrap_err();
+        assert_eq!(err.message, "Tool 'unknown' not found");
+
+        for arguments in [
+            None,
+            Some(JsonObject::new()),
+            Some(json!({CWD_PARAMETER: ""}).as_object().cloned().unwrap()),
+        ] {
+            let err = tools
+                .call("commit", arguments, &app_settings, &emitter)
+                .unwrap_err();
+            assert_eq!(err.message, format!("{CWD_PARAMETER} cannot be empty"));
+        }
+    }
+
+    #[test]
+    fn tools_which_need_approval_are_refused() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        gix::init(tmp.path())?;
+        let app_settings = AppSettings::default();
+        let project = Project::from_path(tmp.path())?;
+        let ctx = &mut CommandContext::open(&project, app_settings.clone())?;
+        but_tools::policy::set_policy(
+            ctx,
+            Policy {
+                approval_required_tools: vec!["commit".into()],
+                ..Default::default()
+            },
+        )?;
+
+        let tools = Tools::new();
+        let (emitter, _events) = ChannelEmitter::new();
+        // The call is refused before its parameters are even looked at.
+        let arguments = json!({ CWD_PARAMETER: tmp.path() });
+        let result = tools
+            .call(
+                "commit",
+                arguments.as_object().cloned(),
+                &app_settings,
+                &emitter,
+            )
+            .expect("violations are tool results");
+        assert_eq!(
+            result,
+            Violation::ApprovalDenied {
+                tool: "commit".into()
+            }
+            .to_json(),
+            "the client can't approve on behalf of the user"
+        );
+        Ok(())
+    }
+}
@@ -1,7 +1,10 @@
 use std::path::Path;
 
 use bstr::BString;
+use but_settings::AppSettings;
 use but_workspace::commit_engine::StackSegmentId;
+use gitbutler_command_context::CommandContext;
+use gitbutler_project::Project;
 use rmcp::schemars;
 use serde::{Deserialize, Serialize};
 
@@ -26,6 +29,8 @@ pub fn commit(
         )
     })?;
 
+    check_policy(&project, "commit", &[], 1)?;
+
     let branch_full_name = normalize_stack_segment_ref(&branch_name)?;
     let parent_commit_id = parent_id
         .map(|id| resolve_parent_id(&repo, &id))
@@ -97,6 +102,8 @@ pub fn amend(
         )
     })?;
 
+    check_policy(&project, "amend", &[branch_name.clone()], 0)?;
+
     let commit_id = resolve_parent_id(&repo, &commit_id)?;
 
     let stack_id = gitbutler_stack::VirtualBranchesHandle::new(project.gb_dir())
@@ -125,6 +132,32 @@ pub fn amend(
     Ok(outcome.into())
 }
 
+/// Fail with the [violation](but_tools::policy::Violation) of the project's policy that calling the tool `name`
+/// would cause, given that it rewrites `branches` and creates `created_commits`, after recording it.
+///
+/// Just like the workspace tools, each call is its own action, and MCP clients can't approve calls,
+/// so tools which need approval are refused.
+fn check_policy(
+    project: &Project,
+    name: &str,
+    branches: &[String],
+    created_commits: usize,
+) -> anyhow::Result<()> {
+    let ctx = &mut CommandContext::open(project, AppSettings::load_from_default_path_creating()?)?;
+    let policy = but_tools::policy::policy(ctx)?;
+    match policy.check_call(name, branches, 0, created_commits, || false) {
+        Some(violation) => {
+            but_tools::policy::record_violation(
+                ctx,
+                format!("Call the tool '{name}' over MCP"),
+                &violation,
+            )?;
+            Err(violation.into())
+        }
+        None => Ok(()),
+    }
+}
+
 /// Determines the parent commit ID based on the provided `parent_revspec`.
 fn resolve_parent_id(repo: &gix::Repository, parent_id: &str) -> anyhow::Result<gix::ObjectId> {
     repo.rev_parse_single(parent_id)
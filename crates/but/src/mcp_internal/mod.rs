@@ -112,8 +112,11 @@ impl Mcp {
             params.diff_spec,
             params.parent_id,
             params.branch_name,
-        )
-        .map_err(|e| rmcp::Error::internal_error(e.to_string(), None))?;
+        );
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(err) => return policy_violation_or_internal_error(err),
+        };
 
         let event = &mut Event::new(EventKind::McpInternal);
         event.insert_prop("endpoint", "commit");
@@ -144,8 +147,11 @@ impl Mcp {
             params.diff_spec,
             params.commit_id,
             params.branch_name,
-        )
-        .map_err(|e| rmcp::Error::internal_error(e.to_string(), None))?;
+        );
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(err) => return policy_violation_or_internal_error(err),
+        };
 
         let event = &mut Event::new(EventKind::McpInternal);
         event.insert_prop("endpoint", "amend");
@@ -222,6 +228,16 @@ impl Mcp {
     }
 }
 
+/// Pass policy violations to the agent as structured tool errors, and turn everything else into an internal error.
+fn policy_violation_or_internal_error(err: anyhow::Error) -> Result<CallToolResult, rmcp::Error> {
+    match err.downcast::<but_tools::policy::Violation>() {
+        Ok(violation) => Ok(CallToolResult::error(vec![rmcp::model::Content::json(
+            violation.to_json(),
+        )?])),
+        Err(err) => Err(rmcp::Error::internal_error(err.to_string(), None)),
+    }
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProjectStatusParams {
@@ -28,6 +28,7 @@ pub enum Command {
     Log,
     Status,
     Rub,
+    Resolve,
     ClaudePreTool,
     ClaudePostTool,
     ClaudeStop,
@@ -40,6 +41,7 @@ impl From<CommandName> for EventKind {
             CommandName::Log => EventKind::Cli(Command::Log),
             CommandName::Status => EventKind::Cli(Command::Status),
             CommandName::Rub => EventKind::Cli(Command::Rub),
+            CommandName::Resolve => EventKind::Cli(Command::Resolve),
             CommandName::ClaudePreTool => EventKind::Cli(Command::ClaudePreTool),
             CommandName::ClaudePostTool => EventKind::Cli(Command::ClaudePostTool),
             CommandName::ClaudeStop => EventKind::Cli(Command::ClaudeStop),
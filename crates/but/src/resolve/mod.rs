@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use but_settings::AppSettings;
+use colored::Colorize;
+use gitbutler_command_context::CommandContext;
+use gitbutler_edit_mode::commands;
+use gitbutler_edit_mode::resolve::ConflictStyle;
+use gitbutler_oxidize::ObjectIdExt;
+use gitbutler_project::Project;
+use gitbutler_stack::VirtualBranchesHandle;
+
+use crate::args::resolve::{Style, Subcommands};
+
+pub(crate) fn handle(repo_path: &Path, json: bool, cmd: &Subcommands) -> anyhow::Result<()> {
+    let project = Project::from_path(repo_path).expect("Failed to create project from path");
+    let ctx = &CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+
+    match cmd {
+        Subcommands::Start { commit, style } => {
+            start(ctx, commit, *style)?;
+            status(ctx, json)
+        }
+        Subcommands::Status => status(ctx, json),
+        Subcommands::Markers { style, paths } => {
+            let style = style.map_or_else(|| ConflictStyle::from_config(ctx.repo()), Into::into);
+            commands::materialize_conflicts(ctx, paths, style)?;
+            status(ctx, json)
+        }
+        Subcommands::Tool { path } => {
+            if commands::run_mergetool(ctx, path)? {
+                println!("Resolved {}", path.green());
+            } else {
+                println!("{} is still unresolved", path.red());
+            }
+            Ok(())
+        }
+        Subcommands::Mark { paths } => {
+            commands::mark_conflicts_resolved(ctx, paths)?;
+            status(ctx, json)
+        }
+        Subcommands::Finish => {
+            commands::finish_conflict_resolution(ctx)?;
+            println!("All conflicts resolved, returned to the workspace");
+            Ok(())
+        }
+        Subcommands::Abort => {
+            commands::abort_and_return_to_workspace(ctx)?;
+            println!("Returned to the workspace without changes");
+            Ok(())
+        }
+    }
+}
+
+/// Enter edit mode for the conflicted `commit` and write its conflicted files with markers in `style`.
+fn start(ctx: &CommandContext, commit: &str, style: Option<Style>) -> anyhow::Result<()> {
+    let oid = ctx
+        .gix_repo()?
+        .rev_parse_single(commit)?
+        .object()?
+        .peel_to_commit()?
+        .id;
+    let stack_id = crate::rub::undo::stack_id_by_commit_id(ctx, &oid)?;
+    let stack =
+        VirtualBranchesHandle::new(ctx.project().gb_dir()).get_stack_in_workspace(stack_id)?;
+    commands::enter_edit_mode(ctx, oid.to_git2(), stack.refname()?.to_string().into())?;
+    if let Some(style) = style {
+        commands::materialize_conflicts(ctx, &[], style.into())?;
+    }
+    Ok(())
+}
+
+fn status(ctx: &CommandContext, json: bool) -> anyhow::Result<()> {
+    let paths = commands::conflicted_paths(ctx)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&paths)?);
+        return Ok(());
+    }
+    if paths.is_empty() {
+        println!("No conflicts to resolve, finish with `but resolve finish`");
+        return Ok(());
+    }
+    for conflict in &paths {
+        if conflict.resolved {
+            println!("{} {}", "resolved  ".green(), conflict.path);
+        } else {
+            println!("{} {}", "unresolved".red(), conflict.path);
+        }
+    }
+    if paths.iter().all(|c| c.resolved) {
+        println!("\nAll conflicts resolved, finish with `but resolve finish`");
+    }
+    Ok(())
+}
+
+impl From<Style> for ConflictStyle {
+    fn from(style: Style) -> Self {
+        match style {
+            Style::Merge => ConflictStyle::Merge,
+            Style::Diff3 => ConflictStyle::Diff3,
+            Style::Zdiff3 => ConflictStyle::ZDiff3,
+        }
+    }
+}
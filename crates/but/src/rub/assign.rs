@@ -48,6 +48,7 @@ pub(crate) fn assign_all(
                 hunk_header: assignment.hunk_header,
                 path_bytes: assignment.path_bytes,
                 stack_id: to_stack_id,
+                line_ranges: assignment.line_ranges,
             });
         }
     }
@@ -117,6 +118,7 @@ fn to_assignment_request(
                 hunk_header: assignment.hunk_header,
                 path_bytes: assignment.path_bytes,
                 stack_id,
+                line_ranges: assignment.line_ranges,
             });
         }
     }
@@ -9,7 +9,7 @@ mod amend;
 mod assign;
 mod move_commit;
 mod squash;
-mod undo;
+pub(crate) mod undo;
 
 use crate::id::CliId;
 
@@ -0,0 +1,173 @@
+#![cfg(unix)]
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use serde_json::{Value, json};
+
+/// How long to wait for the daemon to start and for changes to arrive.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+#[test]
+fn subscribers_receive_worktree_changes_of_registered_projects() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = gix::path::realpath(tmp.path())?;
+    let home = root.join("home");
+    let worktree = root.join("repo");
+    std::fs::create_dir_all(&worktree)?;
+    git(&worktree, &["init", "--initial-branch=main"]);
+    std::fs::write(worktree.join("file"), "content\n")?;
+    git(&worktree, &["add", "file"]);
+    git(&worktree, &["commit", "-m", "init"]);
+    let project =
+        gitbutler_project::add_with_path(app_data_dir(&home)?, worktree.clone(), None, None)?;
+
+    let socket = root.join("daemon.sock");
+    let _daemon = Daemon(
+        Command::new(env!("CARGO_BIN_EXE_but"))
+            .args(["-C"])
+            .arg(&worktree)
+            .args(["daemon", "--socket"])
+            .arg(&socket)
+            .env("HOME", &home)
+            .env_remove("XDG_DATA_HOME")
+            .env_remove("XDG_CONFIG_HOME")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?,
+    );
+    let stream = connect(&socket)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut lines = BufReader::new(stream.try_clone()?).lines();
+    let mut stream = stream;
+
+    writeln!(
+        stream,
+        "{}",
+        json!({"jsonrpc": "2.0", "id": 1, "method": "projects"})
+    )?;
+    let projects: Value = serde_json::from_str(&lines.next().expect("response")?)?;
+    assert_eq!(
+        projects["result"][0]["id"],
+        json!(project.id),
+        "the daemon picks up the registered project, not a new one"
+    );
+
+    writeln!(
+        stream,
+        "{}",
+        json!({"jsonrpc": "2.0", "id": 2, "method": "subscribe", "params": {"projectId": project.id}})
+    )?;
+    let subscribed: Value = serde_json::from_str(&lines.next().expect("response")?)?;
+    assert_eq!(
+        subscribed,
+        json!({"jsonrpc": "2.0", "id": 2, "result": true})
+    );
+
+    std::fs::write(worktree.join("new-file"), "new\n")?;
+    let deadline = Instant::now() + TIMEOUT;
+    let change = loop {
+        assert!(
+            Instant::now() < deadline,
+            "no worktree change arrived in time"
+        );
+        let message: Value = serde_json::from_str(&lines.next().expect("notification")?)?;
+        if message["params"]["name"] == json!(format!("project://{}/worktree_changes", project.id))
+        {
+            break message;
+        }
+    };
+    assert_eq!(change["method"], "change");
+    assert_eq!(change["params"]["projectId"], json!(project.id));
+    assert!(
+        change["params"]["payload"]["changes"]
+            .as_array()
+            .expect("changes are listed")
+            .iter()
+            .any(|change| change["path"] == "new-file"),
+        "the new file is among the changes: {change:#}"
+    );
+    Ok(())
+}
+
+#[test]
+fn unregistered_projects_are_rejected() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = gix::path::realpath(tmp.path())?;
+    let worktree = root.join("repo");
+    std::fs::create_dir_all(&worktree)?;
+    git(&worktree, &["init"]);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_but"))
+        .args(["-C"])
+        .arg(&worktree)
+        .args(["daemon", "--socket"])
+        .arg(root.join("daemon.sock"))
+        .env("HOME", root.join("home"))
+        .env_remove("XDG_DATA_HOME")
+        .env_remove("XDG_CONFIG_HOME")
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("isn't a GitButler project yet"),
+        "unexpected error: {stderr}"
+    );
+    Ok(())
+}
+
+/// Kill the daemon when the test ends, even if it fails.
+struct Daemon(Child);
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        self.0.kill().ok();
+        self.0.wait().ok();
+    }
+}
+
+/// The application data directory the daemon will use if `HOME` is set to `home`.
+fn app_data_dir(home: &Path) -> anyhow::Result<PathBuf> {
+    let data_dir = if cfg!(target_os = "macos") {
+        home.join("Library/Application Support")
+    } else {
+        home.join(".local/share")
+    };
+    let identifier = but_path::app_data_dir()?
+        .file_name()
+        .expect("the identifier is the last component")
+        .to_owned();
+    Ok(data_dir.join(identifier))
+}
+
+fn git(worktree: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args([
+            "-c",
+            "user.name=Author",
+            "-c",
+            "user.email=author@example.com",
+        ])
+        .args(args)
+        .current_dir(worktree)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .status()
+        .expect("git can be launched");
+    assert!(status.success(), "git {args:?} failed");
+}
+
+fn connect(socket: &Path) -> anyhow::Result<UnixStream> {
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        match UnixStream::connect(socket) {
+            Ok(stream) => return Ok(stream),
+            Err(_) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(50)),
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
@@ -10,6 +10,7 @@ use crate::upstream_integration::{
 use crate::{
     base,
     base::BaseBranch,
+    branch,
     branch_manager::BranchManagerExt,
     file::RemoteBranchFile,
     remote,
@@ -18,7 +19,7 @@ use crate::{
 };
 use anyhow::{Context, Result};
 use but_workspace::{commit_engine, stack_heads_info, ui, DiffSpec};
-use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
+use gitbutler_branch::{BranchCreateRequest, BranchIdentity, BranchUpdateRequest};
 use gitbutler_command_context::CommandContext;
 use gitbutler_operating_modes::ensure_open_workspace_mode;
 use gitbutler_oplog::{
@@ -125,6 +126,30 @@ pub fn delete_local_branch(
     Ok(())
 }
 
+/// Deletes all local branches which aren't applied and are integrated into the target branch,
+/// along with their virtual branches, and records the deletion in the oplog.
+/// Returns the names of the deleted branches.
+pub fn delete_stale_branches(ctx: &CommandContext) -> Result<Vec<BranchIdentity>> {
+    let mut guard = ctx.project().exclusive_worktree_access();
+    ctx.verify(guard.write_permission())?;
+    let snapshot_tree = ctx.prepare_snapshot(guard.read_permission());
+    let result = branch::delete_stale_branches(ctx, guard.write_permission());
+    let _ = snapshot_tree.and_then(|snapshot_tree| {
+        let names = result.as_ref().map(|deleted| {
+            deleted
+                .iter()
+                .map(|name| name.as_bstr().to_string())
+                .collect::<Vec<_>>()
+        });
+        ctx.snapshot_stale_branches_deletion(
+            snapshot_tree,
+            names.as_ref().map(Vec::as_slice).map_err(|err| *err),
+            guard.write_permission(),
+        )
+    });
+    result
+}
+
 pub fn list_commit_files(
     ctx: &CommandContext,
     commit_oid: git2::Oid,
@@ -277,6 +302,16 @@ fn amend_with_commit_engine(
         "Failed to amend with commit engine. Rejected specs: {:?}",
         outcome.rejected_specs
     ))?;
+    let shared = but_workspace::rebase_stacks_and_branches_sharing_amended_commit(
+        ctx,
+        stack_id,
+        commit_oid.to_gix(),
+        &outcome,
+    )?;
+    if !shared.updated_references.is_empty() {
+        let vb_state = ctx.project().virtual_branches();
+        crate::integration::update_workspace_commit(&vb_state, ctx)?;
+    }
     Ok(new_commit.to_git2())
 }
 
@@ -462,6 +497,43 @@ pub fn create_virtual_branch_from_branch(
     )
 }
 
+/// Create a stack from the local branch `branch` and all local branches below it, with each branch as a series.
+#[instrument(level = tracing::Level::DEBUG, skip(ctx), err(Debug))]
+pub fn create_virtual_branch_from_branch_chain(
+    ctx: &CommandContext,
+    branch: &Refname,
+) -> Result<StackId> {
+    let mut guard = ctx.project().exclusive_worktree_access();
+    ctx.verify(guard.write_permission())?;
+    ensure_open_workspace_mode(ctx)
+        .context("Creating a virtual branch from a branch chain requires open workspace mode")?;
+    let branch_manager = ctx.branch_manager();
+    branch_manager.create_virtual_branch_from_branch_chain(branch, guard.write_permission())
+}
+
+/// Configure the series of the stack with `stack_id` as normal branches which have the branch below them as upstream,
+/// with the lowest one tracking the target branch. That way, other tools and plain Git can see how they are stacked.
+pub fn export_stack_as_branch_chain(ctx: &CommandContext, stack_id: StackId) -> Result<()> {
+    let mut guard = ctx.project().exclusive_worktree_access();
+    ctx.verify(guard.write_permission())?;
+    let vb_state = ctx.project().virtual_branches();
+    let stack = vb_state.get_stack(stack_id)?;
+    let default_target = vb_state.get_default_target()?;
+    let chain = stack
+        .branches()
+        .iter()
+        .rev()
+        .filter(|branch| !branch.archived)
+        .map(|branch| branch.full_name())
+        .collect::<Result<Vec<_>>>()?;
+    let base = gix::refs::FullName::try_from(default_target.branch.to_string())?;
+    but_workspace::branch::set_upstreams_along_branch_chain(
+        &ctx.gix_repo()?,
+        &chain,
+        Some(base.as_ref()),
+    )
+}
+
 pub fn get_uncommited_files(ctx: &CommandContext) -> Result<Vec<RemoteBranchFile>> {
     let guard = ctx.project().exclusive_worktree_access();
     crate::branch::get_uncommited_files(ctx, guard.read_permission())
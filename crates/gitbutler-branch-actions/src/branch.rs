@@ -1,4 +1,5 @@
 use crate::gravatar::gravatar_url_from_email;
+use crate::r#virtual::IsCommitIntegrated;
 use crate::{RemoteBranchFile, VirtualBranchesExt};
 use anyhow::{bail, Context, Result};
 use bstr::{BStr, BString, ByteSlice};
@@ -8,7 +9,7 @@ use gitbutler_branch::ReferenceExtGix;
 use gitbutler_command_context::CommandContext;
 use gitbutler_diff::DiffByPathMap;
 use gitbutler_oxidize::{git2_to_gix_object_id, gix_to_git2_oid, GixRepositoryExt};
-use gitbutler_project::access::WorktreeReadPermission;
+use gitbutler_project::access::{WorktreeReadPermission, WorktreeWritePermission};
 use gitbutler_reference::normalize_branch_name;
 use gitbutler_reference::RemoteRefname;
 use gitbutler_serde::BStringForFrontend;
@@ -59,6 +60,12 @@ pub fn list_branches(
     repo.object_cache_size_if_unset(1024 * 1024);
     let has_filter = filter.is_some();
     let filter = filter.unwrap_or_default();
+    let name_pattern = filter
+        .name
+        .as_deref()
+        .map(glob::Pattern::new)
+        .transpose()
+        .context("Invalid branch name pattern")?;
     let vb_handle = ctx.project().virtual_branches();
     let platform = repo.references()?;
     let mut branches: Vec<GroupBranch> = vec![];
@@ -91,10 +98,11 @@ pub fn list_branches(
 
     let stacks = vb_handle.list_all_stacks()?;
     branches.extend(stacks.iter().map(|s| GroupBranch::Virtual(s.clone())));
-    let mut branches = combine_branches(branches, &repo, vb_handle.get_default_target()?)?;
+    let target = vb_handle.get_default_target()?;
+    let mut branches = combine_branches(branches, &repo, target.clone())?;
 
     // Apply the filter
-    branches.retain(|branch| !has_filter || matches_all(branch, filter));
+    branches.retain(|branch| !has_filter || matches_all(branch, &filter, name_pattern.as_ref()));
 
     // Filter out virtual branches which have no local or remote branches
     branches.retain(|branch| {
@@ -152,10 +160,167 @@ pub fn list_branches(
         true
     });
 
+    // Checking authors and integration is expensive, so only do it for the branches that are left.
+    if let Some(author) = filter.author.as_deref() {
+        let heads_with_author = heads_with_author(
+            &repo,
+            &target,
+            branches.iter().map(|branch| branch.head),
+            author,
+        )?;
+        branches.retain(|branch| heads_with_author.contains(&branch.head));
+    }
+    if let Some(integrated) = filter.integrated {
+        let integrated_heads = integrated_heads(ctx, branches.iter().map(|branch| branch.head))?;
+        branches.retain(|branch| integrated_heads.contains(&branch.head) == integrated);
+    }
+
     Ok(branches)
 }
 
-fn matches_all(branch: &BranchListing, filter: BranchListingFilter) -> bool {
+/// Returns a page of the branches associated with this project, after applying `filter`
+/// and ordering them as configured in `order`.
+pub fn list_branches_page(
+    ctx: &CommandContext,
+    filter: Option<BranchListingFilter>,
+    order: BranchListingOrder,
+) -> Result<BranchListingPage> {
+    let mut branches = list_branches(ctx, filter, None)?;
+    order.sort(&mut branches);
+    let total = branches.len();
+    let branches = branches
+        .into_iter()
+        .skip(order.offset)
+        .take(order.limit.unwrap_or(usize::MAX))
+        .collect();
+    Ok(BranchListingPage { branches, total })
+}
+
+/// Deletes all local branches which aren't applied and are integrated into the target branch,
+/// along with the virtual branches they belong to.
+/// Returns the names of the deleted branches.
+pub(crate) fn delete_stale_branches(
+    ctx: &CommandContext,
+    _permission: &mut WorktreeWritePermission,
+) -> Result<Vec<BranchIdentity>> {
+    let stale_branches = list_branches(
+        ctx,
+        Some(BranchListingFilter {
+            local: Some(true),
+            applied: Some(false),
+            integrated: Some(true),
+            ..Default::default()
+        }),
+        None,
+    )?;
+
+    let repo = ctx.repo();
+    let checked_out_branch = repo
+        .head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(ToOwned::to_owned));
+    let handle = ctx.project().virtual_branches();
+    let mut deleted = Vec::new();
+    for branch in stale_branches {
+        let mut local_branch_names = vec![branch.name.as_bstr().to_str_lossy().into_owned()];
+        if let Some(stack) = &branch.stack {
+            local_branch_names.extend(stack.branches.iter().cloned());
+        }
+        if checked_out_branch
+            .as_ref()
+            .is_some_and(|checked_out| local_branch_names.contains(checked_out))
+        {
+            continue;
+        }
+
+        if let Some(stack) = &branch.stack {
+            handle.delete_branch_entry(&stack.id)?;
+        }
+        for name in local_branch_names.iter().unique() {
+            if let Ok(mut local_branch) = repo.find_branch(name, git2::BranchType::Local) {
+                local_branch.delete()?;
+            }
+        }
+        deleted.push(branch.name);
+    }
+    Ok(deleted)
+}
+
+/// Return the subset of `heads` that is integrated into the target branch, either because they are
+/// reachable from it or because their changes are contained in it.
+fn integrated_heads(
+    ctx: &CommandContext,
+    heads: impl IntoIterator<Item = git2::Oid>,
+) -> Result<HashSet<git2::Oid>> {
+    let repo = ctx.repo();
+    let target = ctx.project().virtual_branches().get_default_target()?;
+    let target_tip = repo
+        .find_reference(&target.branch.fullname())?
+        .peel_to_commit()?
+        .id();
+    let gix_repo = ctx.gix_repo_for_merging_non_persisting()?;
+    let cache = gix_repo.commit_graph_if_enabled()?;
+    let mut graph = gix_repo.revision_graph(cache.as_ref());
+    let mut check_commit = IsCommitIntegrated::new(ctx, &target, &gix_repo, &mut graph)?;
+
+    let mut integrated = HashSet::new();
+    for head in heads {
+        let is_integrated = head == target_tip
+            || repo.graph_descendant_of(target_tip, head)?
+            || check_commit.is_integrated(&repo.find_commit(head)?)?;
+        if is_integrated {
+            integrated.insert(head);
+        }
+    }
+    Ok(integrated)
+}
+
+/// Return the subset of `heads` with at least one commit that isn't reachable from the target branch
+/// and that was authored by someone whose name or email contains `author`, ignoring case.
+fn heads_with_author(
+    repo: &gix::Repository,
+    target: &Target,
+    heads: impl IntoIterator<Item = git2::Oid>,
+    author: &str,
+) -> Result<HashSet<git2::Oid>> {
+    let author = author.to_lowercase();
+    let target_tip = repo
+        .find_reference(target.branch.fullname().as_str())?
+        .peel_to_commit()?
+        .id;
+    let mailmap = repo.open_mailmap();
+    let cache = repo.commit_graph_if_enabled()?;
+    let mut graph = repo.revision_graph(cache.as_ref());
+
+    let mut out = HashSet::new();
+    for head in heads {
+        let head_id = git2_to_gix_object_id(head);
+        let base = repo
+            .merge_base_with_graph(target_tip, head_id, &mut graph)
+            .ok()
+            .map(gix::Id::detach);
+        for info in repo.rev_walk(Some(head_id)).with_boundary(base).all()? {
+            let commit = repo.find_commit(info?.id)?;
+            let commit_author = Author::from_signature_with_mailmap(commit.author()?, &mailmap);
+            if [&commit_author.name, &commit_author.email]
+                .into_iter()
+                .flatten()
+                .any(|field| field.to_str_lossy().to_lowercase().contains(&author))
+            {
+                out.insert(head);
+                break;
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn matches_all(
+    branch: &BranchListing,
+    filter: &BranchListingFilter,
+    name_pattern: Option<&glob::Pattern>,
+) -> bool {
     let mut conditions = vec![];
     if let Some(applied) = filter.applied {
         if let Some(vb) = branch.stack.as_ref() {
@@ -167,6 +332,18 @@ fn matches_all(branch: &BranchListing, filter: BranchListingFilter) -> bool {
     if let Some(local) = filter.local {
         conditions.push((branch.has_local || branch.stack.is_some()) && local);
     }
+    if let Some(name_pattern) = name_pattern {
+        conditions.push(name_pattern.matches(&branch.name.as_bstr().to_str_lossy()));
+    }
+    if let Some(updated_after) = filter.updated_after {
+        conditions.push(branch.updated_at >= updated_after);
+    }
+    if let Some(updated_before) = filter.updated_before {
+        conditions.push(branch.updated_at < updated_before);
+    }
+    if let Some(has_remote) = filter.has_remote {
+        conditions.push(!branch.remotes.is_empty() == has_remote);
+    }
     conditions.iter().all(|&x| x)
 }
 
@@ -177,6 +354,7 @@ fn combine_branches(
 ) -> Result<Vec<BranchListing>> {
     let remotes = repo.remote_names();
     let packed = repo.refs.cached_packed_buffer()?;
+    let mailmap = repo.open_mailmap();
 
     // Group branches by identity
     let mut groups: HashMap<BranchIdentity, Vec<GroupBranch>> = HashMap::new();
@@ -207,6 +385,7 @@ fn combine_branches(
                 packed.as_ref().map(|p| &***p),
                 &remotes,
                 &target_branch,
+                &mailmap,
             );
             match res {
                 Ok(branch_entry) => branch_entry,
@@ -231,6 +410,7 @@ fn branch_group_to_branch(
     packed: Option<&gix::refs::packed::Buffer>,
     remotes: &BTreeSet<Cow<'_, BStr>>,
     target: &Target,
+    mailmap: &gix::mailmap::Snapshot,
 ) -> Result<Option<BranchListing>> {
     let (local_branches, remote_branches, mut vbranches) =
         group_branches
@@ -315,7 +495,7 @@ fn branch_group_to_branch(
         (head_commit.time().seconds * 1000) as u128,
         virtual_branch.map_or(0, |x| x.updated_timestamp_ms),
     );
-    let last_commiter = head_commit.author().into();
+    let last_commiter = Author::from_signature_with_mailmap(head_commit.author(), mailmap);
 
     Ok(Some(BranchListing {
         name: identity.to_owned(),
@@ -406,7 +586,7 @@ fn should_list_git_branch(identity: &BranchIdentity) -> bool {
 }
 
 /// A filter that can be applied to the branch listing
-#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct BranchListingFilter {
     /// If the value is true, the listing will only include branches that have local references or virtual branches.
@@ -415,6 +595,80 @@ pub struct BranchListingFilter {
     /// If the value is true, the listing will only include branches that are applied in the workspace.
     /// If the value is false, the listing will only include branches that are not applied in the workspace.
     pub applied: Option<bool>,
+    /// If set, the listing will only include branches whose name matches this glob pattern, like `feature/*`.
+    pub name: Option<String>,
+    /// If set, the listing will only include branches with at least one commit that isn't in the target branch
+    /// and that was authored by someone whose name or email contains this value, ignoring case.
+    /// Authors are resolved through the `.mailmap` of the repository.
+    pub author: Option<String>,
+    /// If set, the listing will only include branches updated at or after this timestamp in milliseconds since the epoch.
+    pub updated_after: Option<u128>,
+    /// If set, the listing will only include branches updated before this timestamp in milliseconds since the epoch.
+    pub updated_before: Option<u128>,
+    /// If the value is true, the listing will only include branches that are integrated into the target branch,
+    /// i.e. merged, rebased or squash-merged into it.
+    /// If the value is false, the listing will only include branches that are not integrated.
+    pub integrated: Option<bool>,
+    /// If the value is true, the listing will only include branches that exist on at least one remote.
+    /// If the value is false, the listing will only include branches that don't exist on any remote.
+    pub has_remote: Option<bool>,
+}
+
+/// The property to sort the branch listing by.
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum BranchListingSortKey {
+    /// Sort by the time of the last update, most recent first.
+    #[default]
+    UpdatedAt,
+    /// Sort by name, alphabetically.
+    Name,
+    /// Sort by the name of the last committer, alphabetically.
+    Author,
+}
+
+/// The order in which to return the branch listing, and which part of it.
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BranchListingOrder {
+    /// The property to sort by.
+    pub sort_by: BranchListingSortKey,
+    /// If true, the natural order of `sort_by` is reversed.
+    pub reverse: bool,
+    /// The amount of branches to skip, after sorting.
+    pub offset: usize,
+    /// The maximum amount of branches to return, or all of them if unset.
+    pub limit: Option<usize>,
+}
+
+impl BranchListingOrder {
+    fn sort(&self, branches: &mut [BranchListing]) {
+        match self.sort_by {
+            BranchListingSortKey::UpdatedAt => {
+                branches.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then(a.name.cmp(&b.name)))
+            }
+            BranchListingSortKey::Name => branches.sort_by(|a, b| a.name.cmp(&b.name)),
+            BranchListingSortKey::Author => branches.sort_by(|a, b| {
+                a.last_commiter
+                    .name
+                    .cmp(&b.last_commiter.name)
+                    .then(a.name.cmp(&b.name))
+            }),
+        }
+        if self.reverse {
+            branches.reverse();
+        }
+    }
+}
+
+/// A page of the branch listing.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchListingPage {
+    /// The branches on this page.
+    pub branches: Vec<BranchListing>,
+    /// The amount of branches that matched the filter, across all pages.
+    pub total: usize,
 }
 
 /// Represents a branch that exists for the repository
@@ -483,15 +737,35 @@ impl From<git2::Signature<'_>> for Author {
 
 impl From<gix::actor::SignatureRef<'_>> for Author {
     fn from(value: gix::actor::SignatureRef<'_>) -> Self {
+        Author::from_name_and_email(value.name, value.email)
+    }
+}
+
+impl Author {
+    /// Create an author from `signature`, with its name and email replaced by the canonical ones in `mailmap`.
+    pub fn from_signature_with_mailmap(
+        signature: gix::actor::SignatureRef<'_>,
+        mailmap: &gix::mailmap::Snapshot,
+    ) -> Self {
+        match mailmap.try_resolve_ref(signature) {
+            Some(resolved) => Author::from_name_and_email(
+                resolved.name.unwrap_or(signature.name),
+                resolved.email.unwrap_or(signature.email),
+            ),
+            None => signature.into(),
+        }
+    }
+
+    fn from_name_and_email(name: &BStr, email: &BStr) -> Self {
         let gravatar_url = {
-            gravatar_url_from_email(&value.email.to_str_lossy())
+            gravatar_url_from_email(&email.to_str_lossy())
                 .map(|url| url.as_ref().into())
                 .ok()
         };
 
         Author {
-            name: Some(value.name.to_owned().into()),
-            email: Some(value.email.to_owned().into()),
+            name: Some(name.to_owned().into()),
+            email: Some(email.to_owned().into()),
             gravatar_url,
         }
     }
@@ -609,6 +883,7 @@ pub fn get_branch_listing_details(
                 move || -> anyhow::Result<()> {
                     let mut repo = repo.to_thread_local();
                     repo.object_cache_size_if_unset(50 * 1024 * 1024);
+                    let mailmap = repo.open_mailmap();
                     let cache = repo.commit_graph_if_enabled()?;
                     let mut graph = repo.revision_graph(cache.as_ref());
                     for (other_branch_commit_id, branch_head) in all_other_branch_commit_ids {
@@ -636,7 +911,10 @@ pub fn get_branch_listing_details(
                                     for commit_info in revwalk {
                                         let commit_info = commit_info?;
                                         let commit = repo.find_commit(commit_info.id)?;
-                                        authors.insert(commit.author()?.into());
+                                        authors.insert(Author::from_signature_with_mailmap(
+                                            commit.author()?,
+                                            &mailmap,
+                                        ));
                                         num_commits += 1;
                                     }
                                     if num_commits > 0 {
@@ -730,7 +1008,7 @@ pub struct BranchListingDetails {
     pub number_of_commits: usize,
     /// A list of authors that have contributes commits to this branch.
     /// In the case of multiple remote tracking branches, or branches whose commits are evaluated,
-    /// it takes the full list of unique authors, without applying a mailmap.
+    /// it takes the full list of unique authors, as resolved through the `.mailmap` of the repository.
     pub authors: Vec<Author>,
     /// The branch may or may not have a virtual branch associated with it.
     pub stack: Option<StackReference>,
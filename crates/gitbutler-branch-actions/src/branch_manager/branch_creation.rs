@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use super::BranchManager;
 use crate::r#virtual as vbranch;
 use crate::{hunk::VirtualBranchHunk, integration::update_workspace_commit, VirtualBranchesExt};
@@ -16,7 +18,7 @@ use gitbutler_reference::{Refname, RemoteRefname};
 use gitbutler_repo::rebase::gitbutler_merge_commits;
 use gitbutler_repo::RepositoryExt as _;
 use gitbutler_repo_actions::RepoActionsExt;
-use gitbutler_stack::{BranchOwnershipClaims, Stack, StackId};
+use gitbutler_stack::{BranchOwnershipClaims, Stack, StackBranch, StackId};
 use gitbutler_time::time::now_since_unix_epoch_ms;
 use gitbutler_workspace::branch_trees::{update_uncommited_changes_with_tree, WorkspaceState};
 #[allow(deprecated)]
@@ -267,6 +269,72 @@ impl BranchManager<'_> {
             Err(err) => Err(err).context("failed to apply"),
         }
     }
+
+    /// Like [`Self::create_virtual_branch_from_branch()`], but also add the local branches that the local branch
+    /// `target` is stacked on top of to the new stack, each as its own series.
+    ///
+    /// The branches are found by following the first parent of `target` down to the target branch,
+    /// see [`but_workspace::branch::linear_branch_chain()`] for details.
+    pub fn create_virtual_branch_from_branch_chain(
+        &self,
+        target: &Refname,
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<StackId> {
+        if !matches!(target, Refname::Local(_)) {
+            bail!("branch {target} must be a local branch to import the branches below it");
+        }
+        let repo = self.ctx.gix_repo()?;
+        let chain = {
+            let meta = self.ctx.meta()?;
+            let mut reference = repo.find_reference(&target.to_string())?;
+            let tip = reference.peel_to_id_in_place()?;
+            let graph = but_graph::Graph::from_commit_traversal(
+                tip,
+                reference.name().to_owned(),
+                &meta,
+                meta.graph_options(),
+            )?;
+            but_workspace::branch::linear_branch_chain(&graph)?
+        };
+
+        // `target` is already the top-most series, the ones below it are sorted by position automatically.
+        let below = chain.get(1..).unwrap_or_default();
+        let vb_state = self.ctx.project().virtual_branches();
+        let applied_heads: HashSet<String> = vb_state
+            .list_stacks_in_workspace()?
+            .iter()
+            .flat_map(|stack| stack.heads.iter().map(|head| head.name().to_owned()))
+            .collect();
+        if let Some(applied) = below
+            .iter()
+            .map(|ref_name| ref_name.shorten().to_string())
+            .find(|name| applied_heads.contains(name))
+        {
+            bail!("branch {applied} below {target} is already part of a stack in the workspace");
+        }
+
+        let stack_id = self.create_virtual_branch_from_branch(target, None, None, perm)?;
+        let mut stack = vb_state.get_stack(stack_id)?;
+        let added = below.iter().try_for_each(|ref_name| {
+            let head = repo
+                .find_reference(ref_name.as_ref())?
+                .peel_to_id_in_place()?;
+            let series =
+                StackBranch::new(head.detach(), ref_name.shorten().to_string(), None, &repo)?;
+            stack.add_series(self.ctx, series, None)
+        });
+        if let Err(err) = added {
+            // Don't leave a stack behind that only contains some of the branches.
+            if let Err(unapply_err) = self.unapply(stack_id, perm, true, Vec::new()) {
+                tracing::warn!(
+                    ?unapply_err,
+                    "failed to remove partially created stack {stack_id}"
+                );
+            }
+            return Err(err);
+        }
+        Ok(stack_id)
+    }
 }
 
 /// Holding private methods associated to branch creation
@@ -4,8 +4,9 @@ mod actions;
 #[allow(deprecated)]
 pub use actions::{
     amend, can_apply_remote_branch, create_commit, create_virtual_branch,
-    create_virtual_branch_from_branch, delete_local_branch, fetch_from_remotes, find_commit,
-    find_git_branches, get_uncommited_files, insert_blank_commit, integrate_upstream,
+    create_virtual_branch_from_branch, create_virtual_branch_from_branch_chain,
+    delete_local_branch, delete_stale_branches, export_stack_as_branch_chain, fetch_from_remotes,
+    find_commit, find_git_branches, get_uncommited_files, insert_blank_commit, integrate_upstream,
     integrate_upstream_commits, list_commit_files, move_commit, move_commit_file, push_base_branch,
     reorder_stack, resolve_upstream_integration, set_base_branch, set_target_push_remote,
     squash_commits, unapply_stack, undo_commit, update_commit_message, update_stack_order,
@@ -69,8 +70,9 @@ mod hunk;
 pub use hunk::{VirtualBranchHunkRange, VirtualBranchHunkRangeMap};
 
 pub use branch::{
-    get_branch_listing_details, list_branches, Author, BranchListing, BranchListingDetails,
-    BranchListingFilter,
+    get_branch_listing_details, list_branches, list_branches_page, Author, BranchListing,
+    BranchListingDetails, BranchListingFilter, BranchListingOrder, BranchListingPage,
+    BranchListingSortKey,
 };
 
 pub use integration::GITBUTLER_WORKSPACE_COMMIT_TITLE;
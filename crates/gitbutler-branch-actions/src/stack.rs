@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use but_workspace::integrated::Integration;
 use gitbutler_command_context::CommandContext;
 use gitbutler_oplog::entry::{OperationKind, SnapshotDetails};
 use gitbutler_oplog::{OplogExt, SnapshotExt};
@@ -230,7 +231,17 @@ pub(crate) fn branch_integrated(
     if branch.archived {
         return Ok(true);
     }
+    Ok(branch_integration(check_commit, branch, repo, gix_repo)?.is_some())
+}
+
+/// Return how the head of `branch` was integrated into the target branch, or `None` if it wasn't.
+pub(crate) fn branch_integration(
+    check_commit: &mut IsCommitIntegrated,
+    branch: &StackBranch,
+    repo: &git2::Repository,
+    gix_repo: &gix::Repository,
+) -> Result<Option<Integration>> {
     let oid = branch.head_oid(gix_repo)?;
     let branch_head = repo.find_commit(oid.to_git2())?;
-    check_commit.is_integrated(&branch_head)
+    check_commit.integration(&branch_head)
 }
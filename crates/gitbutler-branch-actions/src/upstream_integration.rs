@@ -1,8 +1,9 @@
-use crate::stack::branch_integrated;
+use crate::stack::branch_integration;
 use crate::{r#virtual::IsCommitIntegrated, BranchManagerExt, VirtualBranchesExt as _};
 use anyhow::{anyhow, bail, Context, Result};
 use but_core::Reference;
 use but_rebase::{RebaseOutput, RebaseStep};
+use but_workspace::integrated::Integration;
 use but_workspace::stack_ext::StackExt;
 use gitbutler_command_context::CommandContext;
 use gitbutler_commit::commit_ext::CommitExt as _;
@@ -47,7 +48,8 @@ pub enum TreeStatus {
 #[serde(tag = "type", content = "subject", rename_all = "camelCase")]
 pub enum BranchStatus {
     SaflyUpdatable,
-    Integrated,
+    /// The branch is integrated, with its head commit being contained upstream as described.
+    Integrated(Integration),
     Conflicted {
         /// If the branch can be rebased onto the target without conflicts
         rebasable: bool,
@@ -117,7 +119,7 @@ impl StackStatus {
             && self
                 .branch_statuses
                 .iter()
-                .all(|branch_status| branch_status.status == BranchStatus::Integrated)
+                .all(|branch_status| matches!(branch_status.status, BranchStatus::Integrated(_)))
         {
             return matches!(
                 approach,
@@ -236,6 +238,7 @@ fn get_stack_status(
         repo,
         &mut graph,
         git2_to_gix_object_id(target.sha),
+        new_target_commit_id,
         new_target_tree_id.detach(),
         upstream_commit_oids,
     );
@@ -254,18 +257,19 @@ fn get_stack_status(
 
         // If an integrated branch has been found, there is no need to bother
         // with subsequent branches.
-        if !unintegrated_branch_found
-            && branch_integrated(&mut check_commit, branch, repo, gix_repo)?
-        {
-            branch_statuses.push(NameAndStatus {
-                name: branch.name().to_owned(),
-                status: BranchStatus::Integrated,
-            });
+        if !unintegrated_branch_found {
+            if let Some(integration) =
+                branch_integration(&mut check_commit, branch, repo, gix_repo)?
+            {
+                branch_statuses.push(NameAndStatus {
+                    name: branch.name().to_owned(),
+                    status: BranchStatus::Integrated(integration),
+                });
 
-            continue;
-        } else {
-            unintegrated_branch_found = true;
+                continue;
+            }
         }
+        unintegrated_branch_found = true;
 
         // Rebase the commits and see if any conflict
         // Rebasing is preferable to merging, as not everything that is
@@ -692,6 +696,7 @@ fn compute_resolutions(
                         repo,
                         &mut graph,
                         git2_to_gix_object_id(target.sha),
+                        new_target.id().to_gix(),
                         git2_to_gix_object_id(new_target.tree_id()),
                         upstream_commit_oids,
                     );
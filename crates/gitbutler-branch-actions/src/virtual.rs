@@ -2,7 +2,10 @@ use crate::{hunk::VirtualBranchHunk, status::get_applied_status_cached, VirtualB
 use anyhow::{anyhow, bail, Context, Result};
 use bstr::{BString, ByteSlice};
 use but_rebase::RebaseStep;
-use but_workspace::stack_ext::StackExt;
+use but_workspace::{
+    integrated::{Integration, IntegrationMethod, UpstreamPatchIds},
+    stack_ext::StackExt,
+};
 use gitbutler_branch::dedup;
 use gitbutler_branch::BranchUpdateRequest;
 use gitbutler_cherry_pick::RepositoryExt as _;
@@ -305,9 +308,13 @@ pub(crate) struct IsCommitIntegrated<'repo, 'cache, 'graph> {
     gix_repo: &'repo gix::Repository,
     graph: &'graph mut MergeBaseCommitGraph<'repo, 'cache>,
     target_commit_id: gix::ObjectId,
+    /// The tip of the target branch, which integrates everything that can't be attributed to specific commits.
+    upstream_head_id: gix::ObjectId,
     upstream_tree_id: gix::ObjectId,
     upstream_commits: Vec<git2::Oid>,
-    upstream_change_ids: Vec<String>,
+    /// The change-ids of the upstream commits along with the commit, sorted by change-id.
+    upstream_change_ids: Vec<(String, git2::Oid)>,
+    upstream_patch_ids: UpstreamPatchIds,
 }
 
 impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
@@ -329,10 +336,10 @@ impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
                 .log(remote_head.id(), LogUntil::Commit(target.sha), true)?;
         let upstream_change_ids = upstream_commits
             .iter()
-            .filter_map(|commit| commit.change_id())
+            .filter_map(|commit| Some((commit.change_id()?, commit.id())))
             .sorted()
             .collect();
-        let upstream_commits = upstream_commits
+        let upstream_commits: Vec<_> = upstream_commits
             .iter()
             .map(|commit| commit.id())
             .sorted()
@@ -341,7 +348,11 @@ impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
             gix_repo,
             graph,
             target_commit_id: git2_to_gix_object_id(target.sha),
+            upstream_head_id: remote_head.id().to_gix(),
             upstream_tree_id: git2_to_gix_object_id(upstream_tree_id),
+            upstream_patch_ids: UpstreamPatchIds::new(
+                upstream_commits.iter().map(|id| id.to_gix()).collect(),
+            ),
             upstream_commits,
             upstream_change_ids,
         })
@@ -354,6 +365,7 @@ impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
         repo: &'repo git2::Repository,
         graph: &'graph mut MergeBaseCommitGraph<'repo, 'cache>,
         target_commit_id: gix::ObjectId,
+        upstream_head_id: gix::ObjectId,
         upstream_tree_id: gix::ObjectId,
         mut upstream_commits: Vec<git2::Oid>,
     ) -> Self {
@@ -363,7 +375,7 @@ impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
             .iter()
             .filter_map(|oid| {
                 let commit = repo.find_commit(*oid).ok()?;
-                commit.change_id()
+                Some((commit.change_id()?, *oid))
             })
             .sorted()
             .collect();
@@ -371,7 +383,11 @@ impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
             gix_repo,
             graph,
             target_commit_id,
+            upstream_head_id,
             upstream_tree_id,
+            upstream_patch_ids: UpstreamPatchIds::new(
+                upstream_commits.iter().map(|id| id.to_gix()).collect(),
+            ),
             upstream_commits,
             upstream_change_ids,
         }
@@ -380,24 +396,43 @@ impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
 
 impl IsCommitIntegrated<'_, '_, '_> {
     pub(crate) fn is_integrated(&mut self, commit: &git2::Commit) -> Result<bool> {
+        Ok(self.integration(commit)?.is_some())
+    }
+
+    /// Return how `commit` was integrated into the target branch, or `None` if it wasn't.
+    pub(crate) fn integration(&mut self, commit: &git2::Commit) -> Result<Option<Integration>> {
         if self.target_commit_id == git2_to_gix_object_id(commit.id()) {
             // could not be integrated if heads are the same.
-            return Ok(false);
+            return Ok(None);
         }
 
         if self.upstream_commits.is_empty() {
             // could not be integrated - there is nothing new upstream.
-            return Ok(false);
+            return Ok(None);
         }
 
         if let Some(change_id) = commit.change_id() {
-            if self.upstream_change_ids.binary_search(&change_id).is_ok() {
-                return Ok(true);
+            let start = self
+                .upstream_change_ids
+                .partition_point(|(upstream_change_id, _)| *upstream_change_id < change_id);
+            let upstream_commits: Vec<_> = self.upstream_change_ids[start..]
+                .iter()
+                .take_while(|(upstream_change_id, _)| *upstream_change_id == change_id)
+                .map(|(_, id)| id.to_gix())
+                .collect();
+            if !upstream_commits.is_empty() {
+                return Ok(Some(Integration::new(
+                    IntegrationMethod::ChangeId,
+                    upstream_commits,
+                )));
             }
         }
 
         if self.upstream_commits.binary_search(&commit.id()).is_ok() {
-            return Ok(true);
+            return Ok(Some(Integration::new(
+                IntegrationMethod::CommitId,
+                vec![commit.id().to_gix()],
+            )));
         }
 
         let merge_base_id = self.gix_repo.merge_base_with_graph(
@@ -408,13 +443,30 @@ impl IsCommitIntegrated<'_, '_, '_> {
         if gix_to_git2_oid(merge_base_id).eq(&commit.id()) {
             // if merge branch is the same as branch head and there are upstream commits
             // then it's integrated
-            return Ok(true);
+            return Ok(Some(Integration::new(
+                IntegrationMethod::Ancestor,
+                vec![self.upstream_head_id],
+            )));
         }
 
         let merge_base_tree_id = self.gix_repo.find_commit(merge_base_id)?.tree_id()?;
         if merge_base_tree_id == self.upstream_tree_id {
             // if merge base is the same as upstream tree, then it's integrated
-            return Ok(true);
+            return Ok(Some(Integration::new(
+                IntegrationMethod::TreeMerge,
+                vec![self.upstream_head_id],
+            )));
+        }
+
+        if let Some(integration) = self.upstream_patch_ids.integration(
+            self.gix_repo,
+            commit.id().to_gix(),
+            commit.parent_ids().next().map(|id| id.to_gix()),
+            merge_base_id,
+            merge_base_tree_id.detach(),
+            commit.tree_id().to_gix(),
+        )? {
+            return Ok(Some(integration));
         }
 
         // try to merge our tree into the upstream tree
@@ -431,14 +483,15 @@ impl IsCommitIntegrated<'_, '_, '_> {
             .context("failed to merge trees")?;
 
         if merge_output.has_unresolved_conflicts(conflict_kind) {
-            return Ok(false);
+            return Ok(None);
         }
 
         let merge_tree_id = merge_output.tree.write()?.detach();
 
         // if the merge_tree is the same as the new_target_tree and there are no files (uncommitted changes)
         // then the vbranch is fully merged
-        Ok(merge_tree_id == self.upstream_tree_id)
+        Ok((merge_tree_id == self.upstream_tree_id)
+            .then(|| Integration::new(IntegrationMethod::TreeMerge, vec![self.upstream_head_id])))
     }
 }
 
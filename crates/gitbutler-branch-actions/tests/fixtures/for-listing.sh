@@ -82,3 +82,49 @@ git clone remote one-branch-one-commit-other-branch-without-commit
   git checkout -b other-feature main
   $CLI project add --switch-to-workspace "$local_tracking_ref"
 )
+
+git clone remote branches-by-two-authors
+(cd branches-by-two-authors
+  local_tracking_ref="$(git rev-parse --symbolic-full-name @{u})";
+
+  git checkout -b feature/a main
+  echo a > a && git add a
+  tick
+  GIT_AUTHOR_NAME=Alice GIT_AUTHOR_EMAIL=alice@example.com git commit -m "add a"
+
+  git checkout -b feature/b main
+  echo b > b && git add b
+  tick
+  GIT_AUTHOR_NAME=Bob GIT_AUTHOR_EMAIL=bob@example.com git commit -m "add b"
+
+  git checkout -b fix main
+  echo fix > fix && git add fix
+  tick
+  GIT_AUTHOR_NAME=Alice GIT_AUTHOR_EMAIL=alice@example.com git commit -m "start fix"
+  echo more >> fix && git add fix
+  tick
+  GIT_AUTHOR_NAME=Bob GIT_AUTHOR_EMAIL=bob@example.com git commit -m "finish fix"
+
+  git checkout main
+  $CLI project add --switch-to-workspace "$local_tracking_ref"
+)
+
+git clone remote one-merged-one-unmerged-branch
+(cd one-merged-one-unmerged-branch
+  local_tracking_ref="$(git rev-parse --symbolic-full-name @{u})";
+
+  git checkout -b merged main
+  echo merged > merged && git add merged
+  tick
+  git commit -m "merged change"
+  # Pretend the branch was merged upstream.
+  git update-ref "$local_tracking_ref" merged
+
+  git checkout -b unmerged main
+  echo unmerged > unmerged && git add unmerged
+  tick
+  git commit -m "unmerged change"
+
+  git checkout main
+  $CLI project add --switch-to-workspace "$local_tracking_ref"
+)
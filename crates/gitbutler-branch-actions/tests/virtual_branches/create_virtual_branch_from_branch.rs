@@ -159,6 +159,120 @@ fn no_conflicts() {
     assert_eq!(stacks[0].1.branch_details[0].commits[0].message, "first");
 }
 
+#[test]
+fn from_branch_chain() {
+    let Test { repo, ctx, .. } = &Test::default();
+
+    {
+        // create local branches, each on top of the previous one
+        for name in ["feat-1", "feat-2", "feat-3"] {
+            let branch_name: LocalRefname = format!("refs/heads/{name}").parse().unwrap();
+            repo.checkout(&branch_name);
+            fs::write(repo.path().join("file.txt"), name).unwrap();
+            repo.commit_all(name);
+        }
+        repo.checkout(&"refs/heads/master".parse().unwrap());
+    }
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse().unwrap(),
+        false,
+        ctx.project().exclusive_worktree_access().write_permission(),
+    )
+    .unwrap();
+
+    let branch_id = gitbutler_branch_actions::create_virtual_branch_from_branch_chain(
+        ctx,
+        &"refs/heads/feat-3".parse().unwrap(),
+    )
+    .unwrap();
+
+    let stacks = stack_details(ctx);
+    assert_eq!(stacks.len(), 1, "all branches end up in the same stack");
+    assert_eq!(stacks[0].0, branch_id);
+    let branches = &stacks[0].1.branch_details;
+    assert_eq!(
+        branches
+            .iter()
+            .map(|b| b.name.to_string())
+            .collect::<Vec<_>>(),
+        ["feat-3", "feat-2", "feat-1"]
+    );
+    for branch in branches {
+        assert_eq!(branch.commits.len(), 1);
+        assert_eq!(branch.commits[0].message, branch.name);
+    }
+
+    gitbutler_branch_actions::export_stack_as_branch_chain(ctx, branch_id).unwrap();
+    let repo = ctx.gix_repo().unwrap();
+    let config = repo.config_snapshot();
+    for (name, upstream) in [
+        ("feat-3", "refs/heads/feat-2"),
+        ("feat-2", "refs/heads/feat-1"),
+        ("feat-1", "refs/heads/master"),
+    ] {
+        assert_eq!(
+            config
+                .string(format!("branch.{name}.merge").as_str())
+                .map(|v| v.to_string())
+                .as_deref(),
+            Some(upstream)
+        );
+    }
+    assert_eq!(
+        config
+            .string("branch.feat-1.remote")
+            .map(|v| v.to_string())
+            .as_deref(),
+        Some("origin"),
+        "the lowest branch tracks the target branch"
+    );
+}
+
+#[test]
+fn from_branch_chain_with_applied_branch() {
+    let Test { repo, ctx, .. } = &Test::default();
+
+    {
+        // create local branches, each on top of the previous one
+        for name in ["feat-1", "feat-2"] {
+            let branch_name: LocalRefname = format!("refs/heads/{name}").parse().unwrap();
+            repo.checkout(&branch_name);
+            fs::write(repo.path().join("file.txt"), name).unwrap();
+            repo.commit_all(name);
+        }
+        repo.checkout(&"refs/heads/master".parse().unwrap());
+    }
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse().unwrap(),
+        false,
+        ctx.project().exclusive_worktree_access().write_permission(),
+    )
+    .unwrap();
+
+    let branch_id = gitbutler_branch_actions::create_virtual_branch_from_branch(
+        ctx,
+        &"refs/heads/feat-1".parse().unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    let err = gitbutler_branch_actions::create_virtual_branch_from_branch_chain(
+        ctx,
+        &"refs/heads/feat-2".parse().unwrap(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("feat-1"));
+
+    let stacks = stack_details(ctx);
+    assert_eq!(stacks.len(), 1, "no partial stack is left behind");
+    assert_eq!(stacks[0].0, branch_id);
+}
+
 #[test]
 fn conflicts_with_uncommited() {
     let Test { repo, ctx, .. } = &Test::default();
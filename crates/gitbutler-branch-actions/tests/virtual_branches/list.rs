@@ -1,5 +1,8 @@
 use anyhow::Result;
-use gitbutler_branch_actions::BranchListingFilter;
+use gitbutler_branch::BranchIdentity;
+use gitbutler_branch_actions::{
+    list_branches_page, BranchListingFilter, BranchListingOrder, BranchListingSortKey,
+};
 
 #[test]
 fn one_vbranch_in_workspace() -> Result<()> {
@@ -51,6 +54,7 @@ fn two_vbranches_in_workspace_one_commit() -> Result<()> {
         Some(BranchListingFilter {
             local: Some(true),
             applied: Some(true),
+            ..Default::default()
         }),
     )?;
     assert_eq!(list.len(), 1, "only one of these is applied");
@@ -71,6 +75,7 @@ fn two_vbranches_in_workspace_one_commit() -> Result<()> {
         Some(BranchListingFilter {
             local: Some(true),
             applied: Some(false),
+            ..Default::default()
         }),
     )?;
     assert_eq!(list.len(), 1, "only one of these is *not* applied");
@@ -89,6 +94,238 @@ fn two_vbranches_in_workspace_one_commit() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn filter_by_name_pattern_and_remote() -> Result<()> {
+    init_env();
+    let ctx = project_ctx("two-vbranches-in-workspace-one-applied")?;
+    let list = list_branches(
+        &ctx,
+        Some(BranchListingFilter {
+            name: Some("oth*".into()),
+            ..Default::default()
+        }),
+    )?;
+    assert_eq!(list.len(), 1, "only one name matches the pattern");
+    assert_equal(
+        &list[0],
+        ExpectedBranchListing {
+            identity: "other".into(),
+            virtual_branch_given_name: Some("other"),
+            virtual_branch_in_workspace: true,
+            has_local: true,
+            ..Default::default()
+        },
+        "the glob is matched against the whole identity",
+    );
+
+    let list = list_branches(
+        &ctx,
+        Some(BranchListingFilter {
+            has_remote: Some(true),
+            ..Default::default()
+        }),
+    )?;
+    assert_eq!(list.len(), 0, "none of the virtual branches was pushed");
+    Ok(())
+}
+
+#[test]
+fn sort_and_paginate() -> Result<()> {
+    init_env();
+    let ctx = project_ctx("branches-by-two-authors")?;
+    let page = list_branches_page(&ctx, None, BranchListingOrder::default())?;
+    assert_eq!(
+        names(&page.branches),
+        ["fix", "feature/b", "feature/a"],
+        "most recently updated first by default"
+    );
+    assert_eq!(page.total, 3);
+
+    let by_name = BranchListingOrder {
+        sort_by: BranchListingSortKey::Name,
+        ..Default::default()
+    };
+    let page = list_branches_page(&ctx, None, by_name)?;
+    assert_eq!(names(&page.branches), ["feature/a", "feature/b", "fix"]);
+
+    let page = list_branches_page(
+        &ctx,
+        None,
+        BranchListingOrder {
+            reverse: true,
+            ..by_name
+        },
+    )?;
+    assert_eq!(names(&page.branches), ["fix", "feature/b", "feature/a"]);
+
+    let page = list_branches_page(
+        &ctx,
+        None,
+        BranchListingOrder {
+            sort_by: BranchListingSortKey::Author,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(
+        names(&page.branches),
+        ["feature/a", "feature/b", "fix"],
+        "Alice before Bob, and branches of the same author by name"
+    );
+
+    let page = list_branches_page(
+        &ctx,
+        None,
+        BranchListingOrder {
+            offset: 1,
+            limit: Some(1),
+            ..by_name
+        },
+    )?;
+    assert_eq!(names(&page.branches), ["feature/b"]);
+    assert_eq!(page.total, 3, "the total counts all pages");
+
+    let page = list_branches_page(
+        &ctx,
+        Some(BranchListingFilter {
+            name: Some("feature/*".into()),
+            ..Default::default()
+        }),
+        BranchListingOrder {
+            offset: 5,
+            ..by_name
+        },
+    )?;
+    assert!(page.branches.is_empty(), "pages past the end are empty");
+    assert_eq!(page.total, 2, "the total counts what matched the filter");
+    Ok(())
+}
+
+#[test]
+fn filter_by_author_checks_all_commits_of_the_branch() -> Result<()> {
+    init_env();
+    let ctx = project_ctx("branches-by-two-authors")?;
+    let list = list_branches(
+        &ctx,
+        Some(BranchListingFilter {
+            author: Some("alice".into()),
+            ..Default::default()
+        }),
+    )?;
+    assert_eq!(
+        names(&list),
+        ["feature/a", "fix"],
+        "'fix' has a commit by Alice, even though its head commit is by Bob"
+    );
+
+    let list = list_branches(
+        &ctx,
+        Some(BranchListingFilter {
+            author: Some("BOB@EXAMPLE".into()),
+            ..Default::default()
+        }),
+    )?;
+    assert_eq!(
+        names(&list),
+        ["feature/b", "fix"],
+        "emails match too, ignoring case"
+    );
+    Ok(())
+}
+
+#[test]
+fn filter_by_update_time_and_remote() -> Result<()> {
+    init_env();
+    let ctx = project_ctx("branches-by-two-authors")?;
+    let all = list_branches(&ctx, None)?;
+    let feature_b_updated_at = all
+        .iter()
+        .find(|branch| branch.name == BranchIdentity::from("feature/b"))
+        .expect("present")
+        .updated_at;
+
+    let list = list_branches(
+        &ctx,
+        Some(BranchListingFilter {
+            updated_after: Some(feature_b_updated_at),
+            ..Default::default()
+        }),
+    )?;
+    assert_eq!(names(&list), ["feature/b", "fix"], "the bound is inclusive");
+
+    let list = list_branches(
+        &ctx,
+        Some(BranchListingFilter {
+            updated_before: Some(feature_b_updated_at),
+            ..Default::default()
+        }),
+    )?;
+    assert_eq!(names(&list), ["feature/a"], "the bound is exclusive");
+
+    let list = list_branches(
+        &ctx,
+        Some(BranchListingFilter {
+            has_remote: Some(false),
+            ..Default::default()
+        }),
+    )?;
+    assert_eq!(
+        names(&list),
+        ["feature/a", "feature/b", "fix"],
+        "none of them was pushed"
+    );
+    Ok(())
+}
+
+#[test]
+fn filter_by_integration() -> Result<()> {
+    init_env();
+    let ctx = project_ctx("one-merged-one-unmerged-branch")?;
+    let list = list_branches(
+        &ctx,
+        Some(BranchListingFilter {
+            integrated: Some(true),
+            ..Default::default()
+        }),
+    )?;
+    assert_eq!(names(&list), ["merged"]);
+
+    let list = list_branches(
+        &ctx,
+        Some(BranchListingFilter {
+            integrated: Some(false),
+            ..Default::default()
+        }),
+    )?;
+    assert_eq!(names(&list), ["unmerged"]);
+    Ok(())
+}
+
+#[test]
+fn delete_stale_branches_only_deletes_integrated_branches() -> Result<()> {
+    init_env();
+    let (ctx, _tmp) = gitbutler_testsupport::writable::fixture(
+        "for-listing.sh",
+        "one-merged-one-unmerged-branch",
+    )?;
+    let deleted = gitbutler_branch_actions::delete_stale_branches(&ctx)?;
+    assert_eq!(deleted, [BranchIdentity::from("merged")]);
+
+    let repo = ctx.repo();
+    assert!(repo.find_branch("merged", git2::BranchType::Local).is_err());
+    assert!(repo
+        .find_branch("unmerged", git2::BranchType::Local)
+        .is_ok());
+    assert!(
+        repo.find_branch("main", git2::BranchType::Local).is_ok(),
+        "the local target branch is integrated, but isn't listed and thus not stale"
+    );
+    assert_eq!(names(&list_branches(&ctx, None)?), ["unmerged"]);
+
+    let deleted = gitbutler_branch_actions::delete_stale_branches(&ctx)?;
+    assert!(deleted.is_empty(), "there is nothing left to delete");
+    Ok(())
+}
+
 #[test]
 fn one_feature_branch_and_one_vbranch_in_workspace_one_commit() -> Result<()> {
     init_env();
@@ -229,5 +466,12 @@ mod util {
         branches.sort_by(|a, b| a.name.cmp(&b.name));
         Ok(branches)
     }
+
+    pub fn names(branches: &[BranchListing]) -> Vec<String> {
+        branches
+            .iter()
+            .map(|branch| branch.name.as_bstr().to_string())
+            .collect()
+    }
 }
-pub use util::{assert_equal, init_env, list_branches, project_ctx, ExpectedBranchListing};
+pub use util::{assert_equal, init_env, list_branches, names, project_ctx, ExpectedBranchListing};
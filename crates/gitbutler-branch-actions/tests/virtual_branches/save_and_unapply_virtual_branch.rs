@@ -37,6 +37,7 @@ fn unapply_with_data() {
         hunk_header: assignments[0].hunk_header,
         path_bytes: assignments[0].path_bytes.clone(),
         stack_id: Some(stacks[0].0),
+        line_ranges: None,
     };
     but_hunk_assignment::assign(ctx, vec![req], None).unwrap();
     let (assignments, _assignments_error) =
@@ -1,4 +1,5 @@
-use bstr::BStr;
+use bstr::{BStr, ByteSlice};
+use but_core::commit::{ForeignChangeId, JJ_CHANGE_ID_FIELD};
 
 use crate::commit_headers::HasCommitHeaders;
 
@@ -8,7 +9,13 @@ use crate::commit_headers::HasCommitHeaders;
 pub trait CommitExt {
     /// Obtain the commit-message as bytes, but without assuming any encoding.
     fn message_bstr(&self) -> &BStr;
+    /// Return our own identity of this commit across rewrites.
+    ///
+    /// If the commit doesn't have one yet, it's derived from the [foreign change-id](Self::foreign_change_id()),
+    /// just like it will be when the commit is rewritten.
     fn change_id(&self) -> Option<String>;
+    /// Return the change-id written by Jujutsu or Gerrit, if there is one.
+    fn foreign_change_id(&self) -> Option<ForeignChangeId>;
     fn is_signed(&self) -> bool;
     fn is_conflicted(&self) -> bool;
 }
@@ -19,7 +26,20 @@ impl CommitExt for git2::Commit<'_> {
     }
 
     fn change_id(&self) -> Option<String> {
-        self.gitbutler_headers().map(|headers| headers.change_id)
+        self.gitbutler_headers()
+            .map(|headers| headers.change_id)
+            .or_else(|| {
+                self.foreign_change_id()
+                    .map(|id| id.to_change_id().to_string())
+            })
+    }
+
+    fn foreign_change_id(&self) -> Option<ForeignChangeId> {
+        let jj_change_id = self.header_field_bytes(JJ_CHANGE_ID_FIELD).ok();
+        ForeignChangeId::from_parts(
+            jj_change_id.as_deref().map(ByteSlice::as_bstr),
+            self.message_bstr(),
+        )
     }
     fn is_signed(&self) -> bool {
         self.header_field_bytes("gpgsig").is_ok()
@@ -39,7 +59,21 @@ impl CommitExt for gix::Commit<'_> {
     }
 
     fn change_id(&self) -> Option<String> {
-        self.gitbutler_headers().map(|headers| headers.change_id)
+        self.gitbutler_headers()
+            .map(|headers| headers.change_id)
+            .or_else(|| {
+                self.foreign_change_id()
+                    .map(|id| id.to_change_id().to_string())
+            })
+    }
+
+    fn foreign_change_id(&self) -> Option<ForeignChangeId> {
+        self.decode().ok().and_then(|decoded| {
+            ForeignChangeId::from_parts(
+                decoded.extra_headers().find(JJ_CHANGE_ID_FIELD),
+                decoded.message,
+            )
+        })
     }
 
     fn is_signed(&self) -> bool {
@@ -1,4 +1,5 @@
 use bstr::{BStr, BString, ByteSlice};
+use but_core::commit::is_jj_change_id;
 use uuid::Uuid;
 
 /// Header used to determine which version of the headers is in use. This should never be changed
@@ -111,8 +112,11 @@ impl HasCommitHeaders for git2::Commit<'_> {
                 None
             }
         } else {
-            // Parse v1 headers
+            // Parse v1 headers, which use the same field as Jujutsu.
             let change_id = self.header_field_bytes(V1_CHANGE_ID_HEADER).ok()?;
+            if is_jj_change_id(change_id.as_bstr()) {
+                return None;
+            }
             // We can safely assume that the change id should be UTF8
             let change_id = change_id.as_str()?.to_string();
 
@@ -149,8 +153,11 @@ impl HasCommitHeaders for gix::Commit<'_> {
                 None
             }
         } else {
-            // Parse v1 headers
-            let change_id = decoded.extra_headers().find(V1_CHANGE_ID_HEADER)?;
+            // Parse v1 headers, which use the same field as Jujutsu.
+            let change_id = decoded
+                .extra_headers()
+                .find(V1_CHANGE_ID_HEADER)
+                .filter(|id| !is_jj_change_id(id))?;
             let change_id = change_id.to_str().ok()?.to_string();
             let headers = CommitHeadersV1 { change_id };
             Some(headers.into())
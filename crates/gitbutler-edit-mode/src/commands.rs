@@ -8,6 +8,7 @@ use gitbutler_oplog::{
 };
 use gitbutler_reference::ReferenceName;
 
+use crate::resolve::{ConflictStyle, ConflictedPath};
 use crate::ConflictEntryPresence;
 
 pub fn enter_edit_mode(
@@ -81,3 +82,47 @@ pub fn changes_from_initial(ctx: &CommandContext) -> Result<Vec<TreeChange>> {
     let state = crate::changes_from_initial(ctx, guard.read_permission())?;
     Ok(state.into_iter().map(|a| a.into()).collect())
 }
+
+pub fn conflicted_paths(ctx: &CommandContext) -> Result<Vec<ConflictedPath>> {
+    let guard = ctx.project().exclusive_worktree_access();
+
+    ensure_edit_mode(ctx)?;
+
+    crate::resolve::conflicted_paths(ctx, guard.read_permission())
+}
+
+pub fn materialize_conflicts(
+    ctx: &CommandContext,
+    paths: &[String],
+    style: ConflictStyle,
+) -> Result<()> {
+    let mut guard = ctx.project().exclusive_worktree_access();
+
+    ensure_edit_mode(ctx)?;
+
+    crate::resolve::materialize_conflicts(ctx, paths, style, guard.write_permission())
+}
+
+pub fn mark_conflicts_resolved(ctx: &CommandContext, paths: &[String]) -> Result<()> {
+    let mut guard = ctx.project().exclusive_worktree_access();
+
+    ensure_edit_mode(ctx)?;
+
+    crate::resolve::mark_resolved(ctx, paths, guard.write_permission())
+}
+
+pub fn run_mergetool(ctx: &CommandContext, path: &str) -> Result<bool> {
+    let mut guard = ctx.project().exclusive_worktree_access();
+
+    ensure_edit_mode(ctx)?;
+
+    crate::resolve::run_mergetool(ctx, path, guard.write_permission())
+}
+
+pub fn finish_conflict_resolution(ctx: &CommandContext) -> Result<()> {
+    let mut guard = ctx.project().exclusive_worktree_access();
+
+    ensure_edit_mode(ctx).context("Conflicts may only be resolved while in edit mode")?;
+
+    crate::resolve::finish(ctx, guard.write_permission())
+}
@@ -30,6 +30,8 @@ use gitbutler_workspace::branch_trees::{update_uncommited_changes_with_tree, Wor
 use serde::Serialize;
 
 pub mod commands;
+pub mod resolve;
+use resolve::ConflictStyle;
 
 const UNCOMMITTED_CHANGES_REF: &str = "refs/gitbutler/edit-uncommitted-changes";
 
@@ -40,28 +42,8 @@ fn get_commit_index(repository: &git2::Repository, commit: &git2::Commit) -> Res
     let commit_tree = commit.tree().context("Failed to get commit's tree")?;
     // Checkout the commit as unstaged changes
     if commit.is_conflicted() {
-        let base = commit_tree
-            .get_name(".conflict-base-0")
-            .context("Failed to get base")?
-            .id();
-        let ours = commit_tree
-            .get_name(".conflict-side-0")
-            .context("Failed to get base")?
-            .id();
-        let theirs = commit_tree
-            .get_name(".conflict-side-1")
-            .context("Failed to get base")?
-            .id();
-
         let gix_repo = gix_repo_for_merging(repository.path())?;
-        // Merge without favoring a side this time to get a tree containing the actual conflicts.
-        let mut merge_result = gix_repo.merge_trees(
-            git2_to_gix_object_id(base),
-            git2_to_gix_object_id(ours),
-            git2_to_gix_object_id(theirs),
-            gix_repo.default_merge_labels(),
-            gix_repo.tree_merge_options()?,
-        )?;
+        let mut merge_result = merge_conflicted_commit(&gix_repo, commit)?;
         let merged_tree_id = merge_result.tree.write()?;
         let mut index = gix_repo.index_from_tree(&merged_tree_id)?;
         if !merge_result.index_changed_after_applying_conflicts(
@@ -79,6 +61,35 @@ fn get_commit_index(repository: &git2::Repository, commit: &git2::Commit) -> Res
     }
 }
 
+/// Merge the sides of the conflicted `commit` once more, without favoring a side this time,
+/// to get a tree containing the actual conflicts.
+fn merge_conflicted_commit<'repo>(
+    gix_repo: &'repo gix::Repository,
+    commit: &git2::Commit,
+) -> Result<gix::merge::tree::Outcome<'repo>> {
+    let commit_tree = commit.tree().context("Failed to get commit's tree")?;
+    let base = commit_tree
+        .get_name(".conflict-base-0")
+        .context("Failed to get base")?
+        .id();
+    let ours = commit_tree
+        .get_name(".conflict-side-0")
+        .context("Failed to get ours")?
+        .id();
+    let theirs = commit_tree
+        .get_name(".conflict-side-1")
+        .context("Failed to get theirs")?
+        .id();
+
+    Ok(gix_repo.merge_trees(
+        git2_to_gix_object_id(base),
+        git2_to_gix_object_id(ours),
+        git2_to_gix_object_id(theirs),
+        gix_repo.default_merge_labels(),
+        gix_repo.tree_merge_options()?,
+    )?)
+}
+
 /// Returns a commit to be the HEAD of `gitbutler/edit`
 ///
 /// This should a commit who's tree is what the commit getting edited
@@ -144,7 +155,11 @@ fn get_uncommited_changes(ctx: &CommandContext) -> Result<git2::Oid> {
     Ok(uncommited_changes)
 }
 
-fn checkout_edit_branch(ctx: &CommandContext, commit: git2::Commit) -> Result<()> {
+fn checkout_edit_branch(
+    ctx: &CommandContext,
+    commit: git2::Commit,
+    style: ConflictStyle,
+) -> Result<()> {
     let repository = ctx.repo();
 
     // Checkout commits's parent
@@ -166,6 +181,9 @@ fn checkout_edit_branch(ctx: &CommandContext, commit: git2::Commit) -> Result<()
         ),
     )?;
 
+    // Rewrite the conflicted files so their markers use the desired style.
+    resolve::record_conflicts(ctx, &commit, &index, style)?;
+
     Ok(())
 }
 
@@ -212,7 +230,8 @@ pub(crate) fn enter_edit_mode(
 
     commit_uncommited_changes(ctx)?;
     write_edit_mode_metadata(ctx, &edit_mode_metadata).context("Failed to persist metadata")?;
-    checkout_edit_branch(ctx, commit).context("Failed to checkout edit branch")?;
+    let style = ConflictStyle::from_config(ctx.repo());
+    checkout_edit_branch(ctx, commit, style).context("Failed to checkout edit branch")?;
 
     Ok(edit_mode_metadata)
 }
@@ -235,6 +254,7 @@ pub(crate) fn abort_and_return_to_workspace(
         uncommited_changes.as_object(),
         Some(CheckoutBuilder::new().force().remove_untracked(true)),
     )?;
+    resolve::forget_conflicts(ctx)?;
 
     Ok(())
 }
@@ -331,6 +351,7 @@ pub(crate) fn save_and_return_to_workspace(
     let mut index = repository.index()?;
     index.read_tree(&repository.head()?.peel_to_tree()?)?;
     index.write()?;
+    resolve::forget_conflicts(ctx)?;
 
     Ok(())
 }
@@ -0,0 +1,386 @@
+//! Resolution of conflicted commits in edit mode.
+//!
+//! When entering edit mode for a conflicted commit, its conflicting files are written to the worktree with
+//! standard conflict markers. Each of these files can then be edited by hand or with the configured
+//! `merge.tool`, and is marked as resolved when done. Once all files are resolved, the commit can be finalized
+//! which rewrites it without conflicts and returns to the workspace.
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use bstr::ByteSlice;
+use gitbutler_command_context::{gix_repo_for_merging, CommandContext};
+use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_operating_modes::read_edit_mode_metadata;
+use gitbutler_project::access::{WorktreeReadPermission, WorktreeWritePermission};
+use serde::{Deserialize, Serialize};
+
+/// How the conflicting sides of a file are presented in the worktree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictStyle {
+    /// Show our side and their side, which is what Git does by default.
+    #[default]
+    Merge,
+    /// Show our side, their side and the common ancestor in between.
+    Diff3,
+    /// Like [`Diff3`](Self::Diff3), but lines that are the same on both sides are moved out of the conflict.
+    ZDiff3,
+}
+
+impl ConflictStyle {
+    /// Read the style from `merge.conflictStyle` in the configuration of `repo`,
+    /// or use the default if it isn't set or unknown.
+    pub fn from_config(repo: &git2::Repository) -> Self {
+        repo.config()
+            .and_then(|config| config.get_string("merge.conflictStyle"))
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// The name of the style as used in `merge.conflictStyle`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictStyle::Merge => "merge",
+            ConflictStyle::Diff3 => "diff3",
+            ConflictStyle::ZDiff3 => "zdiff3",
+        }
+    }
+}
+
+impl FromStr for ConflictStyle {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Ok(match value {
+            "merge" => ConflictStyle::Merge,
+            "diff3" => ConflictStyle::Diff3,
+            "zdiff3" => ConflictStyle::ZDiff3,
+            _ => bail!("Unknown conflict style '{value}', expected 'merge', 'diff3' or 'zdiff3'"),
+        })
+    }
+}
+
+/// A file of the commit that is edited which was conflicted when entering edit mode.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictedPath {
+    /// The path of the file relative to the worktree.
+    pub path: String,
+    /// Whether the conflict was marked as resolved.
+    pub resolved: bool,
+}
+
+/// The conflicts of the commit that is edited, persisted for as long as edit mode lasts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Conflicts {
+    style: ConflictStyle,
+    paths: Vec<ConflictedPath>,
+}
+
+fn conflicts_path(ctx: &CommandContext) -> PathBuf {
+    ctx.project().gb_dir().join("edit_mode_conflicts.toml")
+}
+
+fn read_conflicts(ctx: &CommandContext) -> Result<Conflicts> {
+    let path = conflicts_path(ctx);
+    if !path.exists() {
+        return Ok(Conflicts::default());
+    }
+    let conflicts = std::fs::read_to_string(path).context("Failed to read conflicts")?;
+    toml::from_str(&conflicts).context("Failed to parse conflicts")
+}
+
+fn write_conflicts(ctx: &CommandContext, conflicts: &Conflicts) -> Result<()> {
+    let conflicts = toml::to_string(conflicts).context("Failed to serialize conflicts")?;
+    gitbutler_fs::write(conflicts_path(ctx), conflicts).context("Failed to write conflicts")?;
+    Ok(())
+}
+
+/// Remember the conflicts of `index`, the index of the edited `commit`, and rewrite the conflicted files
+/// in the worktree so their markers use `style`.
+pub(crate) fn record_conflicts(
+    ctx: &CommandContext,
+    commit: &git2::Commit,
+    index: &git2::Index,
+    style: ConflictStyle,
+) -> Result<()> {
+    let mut paths = Vec::new();
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let Some(entry) = conflict
+            .our
+            .as_ref()
+            .or(conflict.their.as_ref())
+            .or(conflict.ancestor.as_ref())
+        else {
+            continue;
+        };
+        paths.push(ConflictedPath {
+            path: entry.path.to_str_lossy().into_owned(),
+            resolved: false,
+        });
+    }
+
+    let conflicts = Conflicts { style, paths };
+    if commit.is_conflicted() {
+        let paths: Vec<_> = conflicts.paths.iter().map(|c| c.path.clone()).collect();
+        write_markers(ctx, commit, &paths, style)?;
+    }
+    write_conflicts(ctx, &conflicts)
+}
+
+/// Forget about the conflicts of the edited commit, as edit mode is left.
+pub(crate) fn forget_conflicts(ctx: &CommandContext) -> Result<()> {
+    let path = conflicts_path(ctx);
+    if path.exists() {
+        std::fs::remove_file(path).context("Failed to remove conflicts")?;
+    }
+    Ok(())
+}
+
+/// Write the files at `paths` of the conflicted `commit` to the worktree, with conflict markers in `style`.
+fn write_markers(
+    ctx: &CommandContext,
+    commit: &git2::Commit,
+    paths: &[String],
+    style: ConflictStyle,
+) -> Result<()> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    let workdir = ctx
+        .repo()
+        .workdir()
+        .context("Need a worktree to resolve conflicts")?;
+
+    let mut gix_repo = gix_repo_for_merging(ctx.repo().path())?;
+    gix_repo
+        .config_snapshot_mut()
+        .set_value(&gix::config::tree::Merge::CONFLICT_STYLE, style.as_str())?;
+    let mut merge_result = crate::merge_conflicted_commit(&gix_repo, commit)?;
+    let merged_tree = gix_repo.find_tree(merge_result.tree.write()?)?;
+
+    for path in paths {
+        // Files without an entry, like those deleted on one side, were already checked out as well as they can be.
+        let Some(entry) = merged_tree.lookup_entry_by_path(path)? else {
+            continue;
+        };
+        if !entry.mode().is_blob() {
+            continue;
+        }
+        let blob = entry.object()?;
+        std::fs::write(workdir.join(path), &blob.data)
+            .with_context(|| format!("Failed to write conflicted file '{path}'"))?;
+    }
+    Ok(())
+}
+
+/// Return `true` if `data` still contains the markers of a conflict.
+fn has_conflict_markers(data: &[u8]) -> bool {
+    data.lines().any(|line| line.starts_with(b"<<<<<<<"))
+        && data.lines().any(|line| line.starts_with(b">>>>>>>"))
+}
+
+/// Return the files of the edited commit that were conflicted when entering edit mode, along with their resolution state.
+pub(crate) fn conflicted_paths(
+    ctx: &CommandContext,
+    _perm: &WorktreeReadPermission,
+) -> Result<Vec<ConflictedPath>> {
+    Ok(read_conflicts(ctx)?.paths)
+}
+
+/// Write the conflicted files at `paths` to the worktree once more, with conflict markers in `style`,
+/// and mark them as unresolved. All unresolved files are written if `paths` is empty.
+///
+/// This discards all edits made to these files.
+pub(crate) fn materialize_conflicts(
+    ctx: &CommandContext,
+    paths: &[String],
+    style: ConflictStyle,
+    _perm: &mut WorktreeWritePermission,
+) -> Result<()> {
+    let metadata = read_edit_mode_metadata(ctx).context("Failed to read metadata")?;
+    let commit = ctx.repo().find_commit(metadata.commit_oid)?;
+    let mut conflicts = read_conflicts(ctx)?;
+
+    if let Some(unknown) = paths
+        .iter()
+        .find(|path| !conflicts.paths.iter().any(|c| &c.path == *path))
+    {
+        bail!("'{unknown}' isn't conflicted");
+    }
+    let selected: Vec<_> = conflicts
+        .paths
+        .iter()
+        .filter(|c| {
+            if paths.is_empty() {
+                !c.resolved
+            } else {
+                paths.contains(&c.path)
+            }
+        })
+        .map(|c| c.path.clone())
+        .collect();
+
+    write_markers(ctx, &commit, &selected, style)?;
+    for conflict in &mut conflicts.paths {
+        if selected.contains(&conflict.path) {
+            conflict.resolved = false;
+        }
+    }
+    conflicts.style = style;
+    write_conflicts(ctx, &conflicts)
+}
+
+/// Mark the conflicted files at `paths` as resolved, which fails if any of them still contains conflict markers.
+pub(crate) fn mark_resolved(
+    ctx: &CommandContext,
+    paths: &[String],
+    _perm: &mut WorktreeWritePermission,
+) -> Result<()> {
+    let workdir = ctx
+        .repo()
+        .workdir()
+        .context("Need a worktree to resolve conflicts")?;
+    let mut conflicts = read_conflicts(ctx)?;
+    for path in paths {
+        let Some(conflict) = conflicts.paths.iter_mut().find(|c| &c.path == path) else {
+            bail!("'{path}' isn't conflicted");
+        };
+        // A deleted file is a valid resolution.
+        if let Ok(data) = std::fs::read(workdir.join(path)) {
+            if has_conflict_markers(&data) {
+                bail!("'{path}' still contains conflict markers");
+            }
+        }
+        conflict.resolved = true;
+    }
+    write_conflicts(ctx, &conflicts)
+}
+
+/// Run the merge tool configured with `merge.tool` and `mergetool.<tool>.cmd` on the conflicted file at `path`,
+/// and mark it as resolved if the tool succeeded. Return `true` if the file was resolved.
+///
+/// Just like `git mergetool`, the command is run by the shell in the root of the worktree, with `$BASE`, `$LOCAL`
+/// and `$REMOTE` pointing to temporary files with the respective sides of the conflict, and `$MERGED` to the file
+/// to write the resolution to. Unless `mergetool.<tool>.trustExitCode` is set, the file counts as resolved if the tool
+/// changed it, regardless of its exit code.
+pub(crate) fn run_mergetool(
+    ctx: &CommandContext,
+    path: &str,
+    perm: &mut WorktreeWritePermission,
+) -> Result<bool> {
+    let metadata = read_edit_mode_metadata(ctx).context("Failed to read metadata")?;
+    let conflicts = read_conflicts(ctx)?;
+    if !conflicts.paths.iter().any(|c| c.path == path) {
+        bail!("'{path}' isn't conflicted");
+    }
+
+    let repository = ctx.repo();
+    let config = repository.config()?;
+    let tool = config
+        .get_string("merge.tool")
+        .context("No merge tool configured, set one with `git config merge.tool <tool>`")?;
+    let cmd = config
+        .get_string(&format!("mergetool.{tool}.cmd"))
+        .with_context(|| {
+            format!("Merge tool '{tool}' needs a command in 'mergetool.{tool}.cmd'")
+        })?;
+    let trust_exit_code = config
+        .get_bool(&format!("mergetool.{tool}.trustExitCode"))
+        .unwrap_or(false);
+    let workdir = repository
+        .workdir()
+        .context("Need a worktree to resolve conflicts")?;
+
+    let commit = repository.find_commit(metadata.commit_oid)?;
+    let index = crate::get_commit_index(repository, &commit)?;
+    let conflict = index
+        .conflicts()?
+        .filter_map(Result::ok)
+        .find(|conflict| {
+            [&conflict.ancestor, &conflict.our, &conflict.their]
+                .into_iter()
+                .flatten()
+                .any(|entry| entry.path == path.as_bytes())
+        })
+        .with_context(|| format!("Couldn't find the sides of the conflict in '{path}'"))?;
+
+    let merged = workdir.join(path);
+    let mut sides = Vec::new();
+    for (name, entry) in [
+        ("BASE", &conflict.ancestor),
+        ("LOCAL", &conflict.our),
+        ("REMOTE", &conflict.their),
+    ] {
+        let data = match entry {
+            Some(entry) => repository.find_blob(entry.id)?.content().to_owned(),
+            None => Vec::new(),
+        };
+        let side = side_path(&merged, name);
+        std::fs::write(&side, data)?;
+        sides.push((name, side));
+    }
+
+    let before = std::fs::read(&merged).ok();
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(&cmd).current_dir(workdir);
+    command.env("MERGED", path);
+    for (name, side) in &sides {
+        command.env(name, side.strip_prefix(workdir).unwrap_or(side.as_path()));
+    }
+    let status = command.status();
+    for (_, side) in &sides {
+        std::fs::remove_file(side).ok();
+    }
+    let status = status.with_context(|| format!("Failed to run merge tool '{tool}'"))?;
+
+    let after = std::fs::read(&merged).ok();
+    let changed = if trust_exit_code {
+        status.success()
+    } else {
+        after != before
+    };
+    let resolved = changed && !after.as_deref().is_some_and(has_conflict_markers);
+    if resolved {
+        mark_resolved(ctx, &[path.to_owned()], perm)?;
+    }
+    Ok(resolved)
+}
+
+/// Return the path of the temporary file holding the side `name` of the conflicted file at `merged`,
+/// named like `file_BASE_1234.ext` as `git mergetool` does.
+fn side_path(merged: &Path, name: &str) -> PathBuf {
+    let stem = merged
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let mut file_name = format!("{stem}_{name}_{}", std::process::id());
+    if let Some(extension) = merged.extension() {
+        file_name.push('.');
+        file_name.push_str(&extension.to_string_lossy());
+    }
+    merged.with_file_name(file_name)
+}
+
+/// Rewrite the edited commit with the resolutions and return to the workspace,
+/// which fails if not all conflicted files were marked as resolved.
+pub(crate) fn finish(ctx: &CommandContext, perm: &mut WorktreeWritePermission) -> Result<()> {
+    let unresolved: Vec<_> = read_conflicts(ctx)?
+        .paths
+        .into_iter()
+        .filter(|c| !c.resolved)
+        .map(|c| c.path)
+        .collect();
+    if !unresolved.is_empty() {
+        bail!(
+            "Not all conflicts are resolved yet: {}",
+            unresolved.join(", ")
+        );
+    }
+    crate::save_and_return_to_workspace(ctx, perm)
+}
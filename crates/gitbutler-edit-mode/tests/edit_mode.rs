@@ -1,7 +1,11 @@
 use anyhow::Result;
 use git2::build::CheckoutBuilder;
 use gitbutler_command_context::CommandContext;
-use gitbutler_edit_mode::commands::{enter_edit_mode, save_and_return_to_workspace};
+use gitbutler_edit_mode::commands::{
+    conflicted_paths, enter_edit_mode, finish_conflict_resolution, mark_conflicts_resolved,
+    save_and_return_to_workspace,
+};
+use gitbutler_operating_modes::{operating_mode, OperatingMode};
 use tempfile::TempDir;
 
 fn command_ctx(folder: &str) -> Result<(CommandContext, TempDir)> {
@@ -62,3 +66,207 @@ fn conficted_entries_get_written_when_leaving_edit_mode() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn finishing_resolution_of_unconflicted_commit_returns_to_workspace() -> Result<()> {
+    let (ctx, _tempdir) = command_ctx("conficted_entries_get_written_when_leaving_edit_mode")?;
+    let repository = ctx.repo();
+
+    let foobar = repository.head()?.peel_to_commit()?.parent(0)?;
+
+    enter_edit_mode(&ctx, foobar.id(), "refs/gitbutler/branchy".into())?;
+    assert_eq!(conflicted_paths(&ctx)?, vec![]);
+
+    let err = mark_conflicts_resolved(&ctx, &["file".into()]).unwrap_err();
+    assert_eq!(err.to_string(), "'file' isn't conflicted");
+
+    finish_conflict_resolution(&ctx)?;
+    assert!(matches!(operating_mode(&ctx), OperatingMode::OpenWorkspace));
+    assert!(
+        conflicted_paths(&ctx).is_err(),
+        "conflicts can only be listed in edit mode"
+    );
+
+    Ok(())
+}
+
+mod conflict_resolution {
+    use anyhow::Result;
+    use gitbutler_command_context::CommandContext;
+    use gitbutler_commit::commit_ext::CommitExt;
+    use gitbutler_edit_mode::commands::{
+        conflicted_paths, enter_edit_mode, finish_conflict_resolution, mark_conflicts_resolved,
+        materialize_conflicts, run_mergetool,
+    };
+    use gitbutler_edit_mode::resolve::{ConflictStyle, ConflictedPath};
+    use gitbutler_operating_modes::{operating_mode, OperatingMode};
+    use tempfile::TempDir;
+
+    // Fixture:
+    // * xxx (HEAD -> gitbutler/workspace) GitButler Workspace Commit
+    // * xxx local (conflicted)
+    // * xxx (origin/main, origin/HEAD) upstream
+    // * 7950f06 (main) init
+    // Where "local" and "upstream" both change `file`.
+    fn conflicted_commit_in_edit_mode(
+        configure: impl FnOnce(&git2::Repository) -> Result<()>,
+    ) -> Result<(CommandContext, TempDir)> {
+        let (ctx, tempdir) = super::command_ctx("conflicted_commit")?;
+        configure(ctx.repo())?;
+        let local = ctx.repo().head()?.peel_to_commit()?.parent(0)?;
+        assert!(
+            local.is_conflicted(),
+            "the fixture needs a conflicted commit"
+        );
+        enter_edit_mode(&ctx, local.id(), "refs/gitbutler/branchy".into())?;
+        Ok((ctx, tempdir))
+    }
+
+    fn read_file(ctx: &CommandContext) -> Result<String> {
+        Ok(std::fs::read_to_string(
+            ctx.repo().workdir().unwrap().join("file"),
+        )?)
+    }
+
+    fn write_file(ctx: &CommandContext, content: &str) -> Result<()> {
+        Ok(std::fs::write(
+            ctx.repo().workdir().unwrap().join("file"),
+            content,
+        )?)
+    }
+
+    fn file(resolved: bool) -> Vec<ConflictedPath> {
+        vec![ConflictedPath {
+            path: "file".into(),
+            resolved,
+        }]
+    }
+
+    #[test]
+    fn markers_use_merge_style_by_default() -> Result<()> {
+        let (ctx, _tempdir) = conflicted_commit_in_edit_mode(|_| Ok(()))?;
+
+        assert_eq!(conflicted_paths(&ctx)?, file(false));
+        let content = read_file(&ctx)?;
+        assert!(content.contains("<<<<<<<") && content.contains(">>>>>>>"));
+        assert!(content.contains("local") && content.contains("upstream"));
+        assert!(
+            !content.contains("|||||||"),
+            "like Git, the ancestor isn't shown by default"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn markers_use_configured_style() -> Result<()> {
+        let (ctx, _tempdir) = conflicted_commit_in_edit_mode(|repo| {
+            repo.config()?.set_str("merge.conflictStyle", "diff3")?;
+            Ok(())
+        })?;
+
+        let content = read_file(&ctx)?;
+        assert!(content.contains("|||||||"), "the ancestor is shown");
+        assert!(content.lines().any(|line| line == "a"), "with its content");
+        Ok(())
+    }
+
+    #[test]
+    fn materializing_conflicts_rewrites_markers_and_unresolves() -> Result<()> {
+        let (ctx, _tempdir) = conflicted_commit_in_edit_mode(|_| Ok(()))?;
+
+        write_file(&ctx, "resolved\n")?;
+        mark_conflicts_resolved(&ctx, &["file".into()])?;
+        assert_eq!(conflicted_paths(&ctx)?, file(true));
+
+        materialize_conflicts(&ctx, &[], ConflictStyle::Diff3)?;
+        assert_eq!(
+            conflicted_paths(&ctx)?,
+            file(true),
+            "only unresolved files are written if no paths are given"
+        );
+        assert_eq!(read_file(&ctx)?, "resolved\n");
+
+        materialize_conflicts(&ctx, &["file".into()], ConflictStyle::Diff3)?;
+        assert_eq!(conflicted_paths(&ctx)?, file(false));
+        assert!(read_file(&ctx)?.contains("|||||||"));
+
+        let err = materialize_conflicts(&ctx, &["other".into()], ConflictStyle::Merge).unwrap_err();
+        assert_eq!(err.to_string(), "'other' isn't conflicted");
+        Ok(())
+    }
+
+    #[test]
+    fn files_with_markers_cannot_be_marked_as_resolved() -> Result<()> {
+        let (ctx, _tempdir) = conflicted_commit_in_edit_mode(|_| Ok(()))?;
+
+        let err = mark_conflicts_resolved(&ctx, &["file".into()]).unwrap_err();
+        assert_eq!(err.to_string(), "'file' still contains conflict markers");
+        assert_eq!(conflicted_paths(&ctx)?, file(false));
+
+        write_file(&ctx, "resolved\n")?;
+        mark_conflicts_resolved(&ctx, &["file".into()])?;
+        assert_eq!(conflicted_paths(&ctx)?, file(true));
+        Ok(())
+    }
+
+    #[test]
+    fn mergetool_resolves_if_it_changed_the_file() -> Result<()> {
+        let (ctx, _tempdir) = conflicted_commit_in_edit_mode(|repo| {
+            let mut config = repo.config()?;
+            config.set_str("merge.tool", "fake")?;
+            config.set_str("mergetool.fake.cmd", "true")?;
+            Ok(())
+        })?;
+
+        assert!(
+            !run_mergetool(&ctx, "file")?,
+            "the tool didn't change the file"
+        );
+        assert_eq!(conflicted_paths(&ctx)?, file(false));
+
+        ctx.repo().config()?.set_str(
+            "mergetool.fake.cmd",
+            r#"cat "$BASE" "$LOCAL" "$REMOTE" > "$MERGED""#,
+        )?;
+        assert!(run_mergetool(&ctx, "file")?);
+        assert_eq!(conflicted_paths(&ctx)?, file(true));
+        let content = read_file(&ctx)?;
+        assert!(content.starts_with("a\n"), "the base comes first");
+        assert!(content.contains("local") && content.contains("upstream"));
+
+        let workdir = ctx.repo().workdir().unwrap();
+        let leftover = std::fs::read_dir(workdir)?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("file_"))
+            .count();
+        assert_eq!(leftover, 0, "the files with the sides are removed");
+        Ok(())
+    }
+
+    #[test]
+    fn finishing_requires_all_conflicts_to_be_resolved() -> Result<()> {
+        let (ctx, _tempdir) = conflicted_commit_in_edit_mode(|_| Ok(()))?;
+
+        let err = finish_conflict_resolution(&ctx).unwrap_err();
+        assert_eq!(err.to_string(), "Not all conflicts are resolved yet: file");
+        assert!(matches!(operating_mode(&ctx), OperatingMode::Edit(_)));
+
+        write_file(&ctx, "resolved\n")?;
+        mark_conflicts_resolved(&ctx, &["file".into()])?;
+        finish_conflict_resolution(&ctx)?;
+        assert!(matches!(operating_mode(&ctx), OperatingMode::OpenWorkspace));
+
+        let local = ctx.repo().head()?.peel_to_commit()?.parent(0)?;
+        assert!(
+            !local.is_conflicted(),
+            "the commit was rewritten with the resolution"
+        );
+        let blob = local
+            .tree()?
+            .get_name("file")
+            .unwrap()
+            .to_object(ctx.repo())?;
+        assert_eq!(blob.as_blob().unwrap().content(), b"resolved\n");
+        Ok(())
+    }
+}
@@ -37,4 +37,25 @@ git clone repo conficted_entries_get_written_when_leaving_edit_mode
   echo b > file
   $CLI branches create --set-default branchy
   $CLI branches commit  branchy --message foobar
-)
\ No newline at end of file
+)
+# Setup:
+# * xxx (HEAD -> gitbutler/workspace) GitButler Workspace Commit
+# * xxx local (conflicted)
+# * xxx (origin/main, origin/HEAD) upstream
+# * 7950f06 (main) init
+# Where "local" was rebased onto "upstream" which changed the same line of `file`.
+git clone repo conflicted_commit
+(cd repo
+  echo upstream > file
+  git add . && git commit -m "upstream"
+)
+(cd conflicted_commit
+  git config user.name "Author"
+  git config user.email "author@example.com"
+  $CLI project add --switch-to-workspace "$(git rev-parse --symbolic-full-name origin/main)"
+  echo local > file
+  $CLI branches create --set-default branchy
+  $CLI branches commit branchy --message local
+  git fetch origin
+  $CLI integrate-upstream rebase
+)
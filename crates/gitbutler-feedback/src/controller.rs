@@ -1,9 +1,10 @@
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use gitbutler_command_context::CommandContext;
 use gitbutler_project::ProjectId;
 
-use crate::zipper::Zipper;
+use crate::{diagnostics, zipper::Zipper};
 
 pub struct Archival {
     pub cache_dir: PathBuf,
@@ -14,6 +15,16 @@ impl Archival {
     fn zipper(&self) -> Zipper {
         Zipper::new(self.cache_dir.clone())
     }
+
+    /// Return the path to a new archive in the cache directory whose name starts with `prefix`.
+    fn new_archive_path(&self, prefix: &str) -> Result<PathBuf> {
+        let archives_dir = self.cache_dir.join("archives");
+        std::fs::create_dir_all(&archives_dir).context("failed to create cache dir")?;
+        let seconds_since_epoch = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        Ok(archives_dir.join(format!("{prefix}-{seconds_since_epoch}.zip")))
+    }
 }
 
 impl Archival {
@@ -22,7 +33,24 @@ impl Archival {
         self.zipper().zip(project.path)
     }
 
+    /// Create an archive of all logs with secrets removed from them, see [`diagnostics::write_logs()`].
+    /// Return the path to the newly created archive.
     pub fn logs_archive(&self) -> Result<PathBuf> {
-        self.zipper().zip(&self.logs_dir)
+        let archive_path = self.new_archive_path("logs")?;
+        diagnostics::write_logs(&self.logs_dir, &archive_path)?;
+        Ok(archive_path)
+    }
+
+    /// Create a diagnostic bundle for the project of `ctx` which, unlike [`Self::archive()`], only contains
+    /// the contents of worktree files listed in `options`, and which has secrets removed from logs and configuration.
+    /// Return the path to the newly created archive.
+    pub fn diagnostics_archive(
+        &self,
+        ctx: &mut CommandContext,
+        options: &diagnostics::Options,
+    ) -> Result<PathBuf> {
+        let archive_path = self.new_archive_path(&format!("diagnostics-{}", ctx.project().id))?;
+        diagnostics::write_bundle(ctx, &self.logs_dir, options, &archive_path)?;
+        Ok(archive_path)
     }
 }
@@ -0,0 +1,335 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{self, Component},
+};
+
+use anyhow::{Context, Result};
+use gitbutler_command_context::CommandContext;
+use gitbutler_oplog::OplogExt;
+use serde::Serialize;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+mod redact;
+pub use redact::{redact, redact_git_config, REDACTED};
+
+/// The amount of oplog snapshots to list in the bundle.
+const MAX_SNAPSHOTS: usize = 100;
+
+/// Options to control what goes into a diagnostic bundle, in addition to what's always included.
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    /// Paths of files relative to the root of the worktree whose contents should be included *as is*,
+    /// without redaction.
+    pub include_paths: Vec<path::PathBuf>,
+}
+
+/// A description of everything in a diagnostic bundle, stored as `manifest.json` along with it.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    /// The files in the bundle.
+    pub entries: Vec<ManifestEntry>,
+    /// What couldn't be included, along with the reason.
+    pub omitted: Vec<Omission>,
+}
+
+/// A file in a diagnostic bundle.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    /// The path of the file within the bundle.
+    pub path: String,
+    /// What the file contains.
+    pub description: String,
+    /// If `true`, secrets like tokens, email addresses and credentials were removed from the file.
+    pub redacted: bool,
+}
+
+/// Something that should have been part of a diagnostic bundle, but couldn't be obtained.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Omission {
+    /// What couldn't be included.
+    pub description: String,
+    /// Why it couldn't be included.
+    pub reason: String,
+}
+
+/// A snapshot of the oplog, without file names or messages.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotInfo {
+    id: String,
+    created_at: i64,
+    operation: Option<String>,
+    lines_added: usize,
+    lines_removed: usize,
+    files_changed: usize,
+}
+
+/// Write a zip archive to `archive_path` with information about the project of `ctx` and the logs in `logs_dir`
+/// which is useful for diagnosing problems, but avoids the contents of files unless they are listed in `options`.
+///
+/// The archive contains a `manifest.json` file to describe its contents, see [`Manifest`].
+/// Things that can't be obtained are recorded as [omissions](Manifest::omitted), so only failing to write
+/// the archive is an error.
+pub fn write_bundle(
+    ctx: &mut CommandContext,
+    logs_dir: &path::Path,
+    options: &Options,
+    archive_path: &path::Path,
+) -> Result<Manifest> {
+    let mut bundle = Bundle::new(archive_path)?;
+
+    let gb_dir = ctx.project().gb_dir();
+    bundle.add_with(
+        "virtual_branches.toml",
+        "The state of all virtual branches and the target branch",
+        true,
+        || {
+            Ok(redact(&fs::read_to_string(
+                gb_dir.join("virtual_branches.toml"),
+            )?))
+        },
+    )?;
+    bundle.add_with(
+        "database.json",
+        "The schema of all database tables and the number of their rows",
+        false,
+        || {
+            Ok(serde_json::to_string_pretty(
+                &ctx.db()?.diagnostics().tables()?,
+            )?)
+        },
+    )?;
+    bundle.add_with(
+        "oplog.json",
+        "The most recent oplog snapshots, without messages and file names",
+        false,
+        || snapshots(ctx),
+    )?;
+
+    match graph(ctx) {
+        Ok(graph) => {
+            bundle.add(
+                "graph-statistics.txt",
+                "Statistics of the commit-graph as seen from HEAD",
+                false,
+                &format!("{:#?}", graph.statistics()),
+            )?;
+            bundle.add(
+                "graph.dot",
+                "The commit-graph as seen from HEAD as Graphviz dot file, without messages",
+                false,
+                &graph.dot_graph(),
+            )?;
+        }
+        Err(err) => bundle.omit("The commit-graph as seen from HEAD", err),
+    }
+
+    let git_dir = ctx.repo().path().to_owned();
+    bundle.add_with(
+        "git-config",
+        "The configuration of the repository, without credentials",
+        true,
+        || {
+            Ok(redact_git_config(&fs::read_to_string(
+                git_dir.join("config"),
+            )?))
+        },
+    )?;
+
+    bundle.add_logs(logs_dir)?;
+
+    let worktree_dir = ctx.project().path.clone();
+    for rela_path in &options.include_paths {
+        let Some(bundle_path) = bundle_path_for_included_file(rela_path) else {
+            bundle.omit(
+                &format!("The contents of '{}'", rela_path.display()),
+                anyhow::anyhow!("Only paths within the worktree can be included"),
+            );
+            continue;
+        };
+        match read_included_file(&worktree_dir, rela_path) {
+            Ok(contents) => bundle.add_bytes(
+                &bundle_path,
+                "The contents of a file in the worktree that was explicitly included",
+                false,
+                &contents,
+            )?,
+            Err(err) => bundle.omit(&format!("The contents of '{}'", rela_path.display()), err),
+        }
+    }
+
+    bundle.finish()
+}
+
+/// Write a zip archive to `archive_path` with the logs in `logs_dir`, with secrets removed from them
+/// just like in the bundle written by [`write_bundle()`].
+///
+/// The archive contains a `manifest.json` file to describe its contents, see [`Manifest`].
+pub fn write_logs(logs_dir: &path::Path, archive_path: &path::Path) -> Result<Manifest> {
+    let mut bundle = Bundle::new(archive_path)?;
+    bundle.add_logs(logs_dir)?;
+    bundle.finish()
+}
+
+/// Return the path within the bundle for the file at `rela_path` in the worktree, or `None` if `rela_path`
+/// could point outside the worktree.
+fn bundle_path_for_included_file(rela_path: &path::Path) -> Option<String> {
+    let mut out = String::from("files");
+    for component in rela_path.components() {
+        let Component::Normal(component) = component else {
+            return None;
+        };
+        out.push('/');
+        out.push_str(component.to_str()?);
+    }
+    (out.len() > "files".len()).then_some(out)
+}
+
+/// Read the file at `rela_path` in `worktree_dir`, but fail if it resolves to a location outside of it,
+/// which can happen if the path goes through symlinks.
+fn read_included_file(worktree_dir: &path::Path, rela_path: &path::Path) -> Result<Vec<u8>> {
+    let worktree_dir = fs::canonicalize(worktree_dir)?;
+    let path = fs::canonicalize(worktree_dir.join(rela_path))?;
+    if !path.starts_with(&worktree_dir) {
+        anyhow::bail!("Only paths within the worktree can be included");
+    }
+    Ok(fs::read(path)?)
+}
+
+fn log_files(logs_dir: &path::Path) -> Result<Vec<path::PathBuf>> {
+    let mut out = Vec::new();
+    let entries = match fs::read_dir(logs_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(out),
+        Err(err) => return Err(err.into()),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file() {
+            out.push(path);
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+fn snapshots(ctx: &CommandContext) -> Result<String> {
+    let snapshots: Vec<_> = ctx
+        .list_snapshots(MAX_SNAPSHOTS, None, Vec::new())?
+        .into_iter()
+        .map(|snapshot| SnapshotInfo {
+            id: snapshot.commit_id.to_string(),
+            created_at: snapshot.created_at.seconds(),
+            operation: snapshot
+                .details
+                .map(|details| format!("{:?}", details.operation)),
+            lines_added: snapshot.lines_added,
+            lines_removed: snapshot.lines_removed,
+            files_changed: snapshot.files_changed.len(),
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&snapshots)?)
+}
+
+fn graph(ctx: &CommandContext) -> Result<but_graph::Graph> {
+    let repo = ctx.gix_repo()?;
+    let meta = ctx.meta()?;
+    but_graph::Graph::from_head(&repo, &meta, meta.graph_options())
+}
+
+struct Bundle {
+    zip: ZipWriter<fs::File>,
+    manifest: Manifest,
+}
+
+impl Bundle {
+    fn new(archive_path: &path::Path) -> Result<Self> {
+        Ok(Bundle {
+            zip: ZipWriter::new(fs::File::create(archive_path)?),
+            manifest: Manifest::default(),
+        })
+    }
+
+    /// Add all log files in `logs_dir` with secrets removed, or record their omission if they can't be read.
+    fn add_logs(&mut self, logs_dir: &path::Path) -> Result<()> {
+        for log in log_files(logs_dir)? {
+            let Some(file_name) = log.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            self.add_with(
+                &format!("logs/{file_name}"),
+                "An application log file",
+                true,
+                || Ok(redact(&String::from_utf8_lossy(&fs::read(&log)?))),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Add the result of `contents()` at `path`, or record its omission if it fails.
+    fn add_with(
+        &mut self,
+        path: &str,
+        description: &str,
+        redacted: bool,
+        contents: impl FnOnce() -> Result<String>,
+    ) -> Result<()> {
+        match contents() {
+            Ok(contents) => self.add(path, description, redacted, &contents),
+            Err(err) => {
+                self.omit(description, err);
+                Ok(())
+            }
+        }
+    }
+
+    fn add(&mut self, path: &str, description: &str, redacted: bool, contents: &str) -> Result<()> {
+        self.add_bytes(path, description, redacted, contents.as_bytes())
+    }
+
+    fn add_bytes(
+        &mut self,
+        path: &str,
+        description: &str,
+        redacted: bool,
+        contents: &[u8],
+    ) -> Result<()> {
+        self.zip.start_file(path, file_options())?;
+        self.zip.write_all(contents)?;
+        self.manifest.entries.push(ManifestEntry {
+            path: path.to_owned(),
+            description: description.to_owned(),
+            redacted,
+        });
+        Ok(())
+    }
+
+    fn omit(&mut self, description: &str, reason: anyhow::Error) {
+        self.manifest.omitted.push(Omission {
+            description: description.to_owned(),
+            reason: redact(&format!("{reason:#}")),
+        });
+    }
+
+    fn finish(mut self) -> Result<Manifest> {
+        self.zip.start_file("manifest.json", file_options())?;
+        serde_json::to_writer_pretty(&mut self.zip, &self.manifest)?;
+        self.zip
+            .finish()
+            .context("failed to finish writing the archive")?;
+        Ok(self.manifest)
+    }
+}
+
+fn file_options() -> SimpleFileOptions {
+    SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Bzip2)
+        .unix_permissions(0o644)
+}
+
+#[cfg(test)]
+mod tests;
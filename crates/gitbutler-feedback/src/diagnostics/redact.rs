@@ -0,0 +1,57 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// What replaces the secrets that were found.
+pub const REDACTED: &str = "<redacted>";
+
+static URL_USERINFO: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?P<scheme>[A-Za-z][A-Za-z0-9+.-]*://)[^/\s@]+@").expect("valid regex")
+});
+static WELL_KNOWN_TOKENS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"\b(?:gh[pousr]_[A-Za-z0-9]{20,}|github_pat_[A-Za-z0-9_]{20,}|glpat-[A-Za-z0-9_-]{20,}",
+        r"|xox[abprs]-[A-Za-z0-9-]{10,}|sk-[A-Za-z0-9_-]{20,}|AKIA[0-9A-Z]{16})\b"
+    ))
+    .expect("valid regex")
+});
+static AUTHORIZATION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(?P<kind>bearer|basic)\s+[\w.~+/=-]{8,}").expect("valid regex")
+});
+static SECRET_ASSIGNMENTS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r#"(?i)(?P<key>[\w.-]*(?:token|password|passwd|secret|api[_-]?key)[\w.-]*"?)"#,
+        r#"(?P<sep>[ \t]*[:=][ \t]*"?)[^\s"',;&]+"#
+    ))
+    .expect("valid regex")
+});
+static EMAILS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}")
+        .expect("valid regex")
+});
+static GIT_CONFIG_SECRET_VALUES: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"(?im)^(?P<key>[ \t]*[A-Za-z0-9-]*(?:password|token|secret|extraheader|cookie)",
+        r"[A-Za-z0-9-]*[ \t]*=).*$"
+    ))
+    .expect("valid regex")
+});
+
+/// Return `text` with the userinfo of URLs, well-known access tokens, authorization headers,
+/// values assigned to keys that look like they hold secrets, and email addresses replaced.
+///
+/// This is a best-effort approach to make logs and configuration shareable.
+pub fn redact(text: &str) -> String {
+    let text = URL_USERINFO.replace_all(text, format!("${{scheme}}{REDACTED}@"));
+    let text = WELL_KNOWN_TOKENS.replace_all(&text, REDACTED);
+    let text = AUTHORIZATION.replace_all(&text, format!("${{kind}} {REDACTED}"));
+    let text = SECRET_ASSIGNMENTS.replace_all(&text, format!("${{key}}${{sep}}{REDACTED}"));
+    EMAILS.replace_all(&text, REDACTED).into_owned()
+}
+
+/// Like [`redact()`], but also replace the values of all keys in the Git configuration file `config`
+/// that may hold credentials, like `http.extraHeader`.
+pub fn redact_git_config(config: &str) -> String {
+    let config = GIT_CONFIG_SECRET_VALUES.replace_all(config, format!("${{key}} {REDACTED}"));
+    redact(&config)
+}
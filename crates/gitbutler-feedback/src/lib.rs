@@ -1,3 +1,5 @@
 mod controller;
 pub use controller::Archival;
+/// Diagnostic bundles that are safe to share, as opposed to archives of the whole project.
+pub mod diagnostics;
 mod zipper;
@@ -7,6 +7,7 @@ use gitbutler_project::ProjectId;
 pub enum InternalEvent {
     // From file monitor
     GitFilesChange(ProjectId, Vec<PathBuf>),
+    /// Paths in the worktree changed. If there are no paths, any path may have changed.
     ProjectFilesChange(ProjectId, Vec<PathBuf>),
     // Triggered on change in the `.git/gitbutler` directory
     GitButlerOplogChange(ProjectId),
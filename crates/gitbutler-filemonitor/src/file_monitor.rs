@@ -1,13 +1,18 @@
 use anyhow::{anyhow, Context, Result};
 use gitbutler_notify_debouncer::{new_debouncer, Debouncer, NoCache};
-use gitbutler_project::ProjectId;
+use gitbutler_project::{FileMonitorBackend, ProjectId};
 use notify::{RecommendedWatcher, Watcher};
 use std::ffi::OsStr;
-use std::{collections::HashSet, path::Path, time::Duration};
+use std::ops::ControlFlow;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use tokio::task;
 use tracing::Level;
 
-use crate::events::InternalEvent;
+use crate::{events::InternalEvent, query};
 
 /// We will collect notifications for up to this amount of time at a very
 /// maximum before releasing them. This duration will be hit if e.g. a build
@@ -33,11 +38,46 @@ struct RunError {
     source: anyhow::Error,
 }
 
+/// A handle to the running file monitor, which stops monitoring once it's dropped.
+pub struct FileMonitor {
+    inner: Inner,
+}
+
+enum Inner {
+    /// Everything is watched with `notify`.
+    Notify(Debouncer<RecommendedWatcher, NoCache>),
+    /// The worktree is queried from a [`ChangeSource`](crate::query::ChangeSource), while only the interesting
+    /// directories of the git-dir are watched with `notify`.
+    Query {
+        git_dir_watcher: Debouncer<RecommendedWatcher, NoCache>,
+        flush: std::sync::mpsc::Sender<()>,
+    },
+}
+
+impl FileMonitor {
+    /// Send all pending events right away, instead of waiting for the tick-rate.
+    pub fn flush_nonblocking(&self) {
+        match &self.inner {
+            Inner::Notify(debouncer) => debouncer.flush_nonblocking(),
+            Inner::Query {
+                git_dir_watcher,
+                flush,
+            } => {
+                git_dir_watcher.flush_nonblocking();
+                flush.send(()).ok();
+            }
+        }
+    }
+}
+
 /// Listen to interesting filesystem events of files in `path` that are not `.gitignore`d,
 /// turn them into [`Events`](Event) which classifies it, and associates it with `project_id`.
 /// These are sent through the passed `out` channel, to indicate either **Git** repository changes
 /// or **ProjectWorktree** changes
 ///
+/// `backend` determines how changes in the worktree are detected. If it's not [`FileMonitorBackend::Notify`]
+/// but the chosen backend isn't available, we fall back to `notify`.
+///
 /// ### Why is this not an iterator?
 ///
 /// The internal `notify_rx` could be an iterator, which performs all transformations and returns them as item.
@@ -51,21 +91,9 @@ struct RunError {
 pub fn spawn(
     project_id: ProjectId,
     worktree_path: &std::path::Path,
+    backend: FileMonitorBackend,
     out: tokio::sync::mpsc::UnboundedSender<InternalEvent>,
-) -> Result<Debouncer<RecommendedWatcher, NoCache>> {
-    let (notify_tx, notify_rx) = std::sync::mpsc::channel();
-    let mut debouncer = new_debouncer(
-        DEBOUNCE_TIMEOUT,
-        Some(TICK_RATE),
-        Some(FLUSH_AFTER_EMPTY),
-        notify_tx,
-    )
-    .context("failed to create debouncer")?;
-
-    let policy = backoff::ExponentialBackoffBuilder::new()
-        .with_max_elapsed_time(Some(std::time::Duration::from_secs(30)))
-        .build();
-
+) -> Result<FileMonitor> {
     let worktree_path = gix::path::realpath(worktree_path)?;
     let git_dir = gix::open_opts(&worktree_path, gix::open::Options::isolated())
         .context(format!(
@@ -74,6 +102,45 @@ pub fn spawn(
         ))?
         .path()
         .to_owned();
+
+    if backend != FileMonitorBackend::Notify {
+        match query::change_source(backend, &worktree_path, &git_dir) {
+            Ok((source, token)) => {
+                // Only a few files in the git-dir are interesting, so there is no need to watch it recursively.
+                let git_dir_paths = [
+                    git_dir.clone(),
+                    git_dir.join("logs"),
+                    git_dir.join("gitbutler"),
+                ];
+                let git_dir_watcher = watch_with_notify(
+                    project_id,
+                    &worktree_path,
+                    &git_dir,
+                    git_dir_paths
+                        .iter()
+                        .filter(|path| path.is_dir())
+                        .map(|path| (path.as_path(), notify::RecursiveMode::NonRecursive)),
+                    out.clone(),
+                )?;
+                let flush = query::spawn(project_id, worktree_path, git_dir, source, token, out)?;
+                return Ok(FileMonitor {
+                    inner: Inner::Query {
+                        git_dir_watcher,
+                        flush,
+                    },
+                });
+            }
+            Err(err) => {
+                tracing::warn!(
+                    %project_id,
+                    ?backend,
+                    ?err,
+                    "file monitor backend unavailable - falling back to watching all files"
+                );
+            }
+        }
+    }
+
     let extra_git_dir_to_watch = {
         let mut enclosing_worktree_dir = git_dir.clone();
         enclosing_worktree_dir.pop();
@@ -83,21 +150,47 @@ pub fn spawn(
             None
         }
     };
+    let debouncer = watch_with_notify(
+        project_id,
+        &worktree_path,
+        &git_dir,
+        std::iter::once(worktree_path.as_path())
+            .chain(extra_git_dir_to_watch)
+            .map(|path| (path, notify::RecursiveMode::Recursive)),
+        out,
+    )?;
+    Ok(FileMonitor {
+        inner: Inner::Notify(debouncer),
+    })
+}
+
+/// Watch all `paths_to_watch` with `notify` and send the resulting events to `out`.
+fn watch_with_notify<'a>(
+    project_id: ProjectId,
+    worktree_path: &Path,
+    git_dir: &Path,
+    paths_to_watch: impl IntoIterator<Item = (&'a Path, notify::RecursiveMode)>,
+    out: tokio::sync::mpsc::UnboundedSender<InternalEvent>,
+) -> Result<Debouncer<RecommendedWatcher, NoCache>> {
+    let (notify_tx, notify_rx) = std::sync::mpsc::channel();
+    let mut debouncer = new_debouncer(
+        DEBOUNCE_TIMEOUT,
+        Some(TICK_RATE),
+        Some(FLUSH_AFTER_EMPTY),
+        notify_tx,
+    )
+    .context("failed to create debouncer")?;
+
+    let policy = backoff::ExponentialBackoffBuilder::new()
+        .with_max_elapsed_time(Some(std::time::Duration::from_secs(30)))
+        .build();
 
     // Start the watcher, but retry if there are transient errors.
+    let paths_to_watch: Vec<_> = paths_to_watch.into_iter().collect();
     backoff::retry(policy, || {
-        debouncer
-            .watcher()
-            .watch(&worktree_path, notify::RecursiveMode::Recursive)
-            .and_then(|()| {
-                if let Some(git_dir) = extra_git_dir_to_watch {
-                    debouncer
-                        .watcher()
-                        .watch(git_dir, notify::RecursiveMode::Recursive)
-                } else {
-                    Ok(())
-                }
-            })
+        paths_to_watch
+            .iter()
+            .try_for_each(|(path, mode)| debouncer.watcher().watch(path, *mode))
             .map_err(|err| match err.kind {
                 notify::ErrorKind::PathNotFound => backoff::Error::permanent(RunError::from(
                     anyhow!("{} not found", worktree_path.display()),
@@ -111,134 +204,165 @@ pub fn spawn(
     .context("failed to start watcher")?;
 
     let worktree_path = worktree_path.to_owned();
+    let git_dir = git_dir.to_owned();
     task::spawn_blocking(move || {
         let _runtime = tracing::span!(Level::INFO, "file monitor", %project_id ).entered();
         tracing::debug!(%project_id, "file watcher started");
 
-        'outer: for result in notify_rx {
-            let stats = tracing::span!(
-                Level::INFO,
-                "handle debounced events",
-                ignored = tracing::field::Empty,
-                project = tracing::field::Empty,
-                project_dedup = tracing::field::Empty,
-                git = tracing::field::Empty,
-                git_dedup = tracing::field::Empty,
-                git_noop = tracing::field::Empty,
-                fs_events = tracing::field::Empty,
-            )
-            .entered();
-            let (mut ignored, mut git_noop) = (0, 0);
+        for result in notify_rx {
             match result {
                 Err(err) => {
                     tracing::error!(?err, "ignored file watcher error");
                 }
                 Ok(events) => {
                     let num_events = events.len();
-                    let mut classified_file_paths: Vec<_> = events
+                    let file_paths = events
                         .into_iter()
                         .filter(|event| is_interesting_kind(event.kind))
-                        .flat_map(|event| event.event.paths)
-                        .map(|file| {
-                            let kind = classify_file(&git_dir, &file);
-                            (file, kind)
-                        })
-                        .collect();
-                    if classified_file_paths
-                        .iter()
-                        .any(|(_, kind)| *kind == FileKind::Project)
+                        .flat_map(|event| event.event.paths);
+                    if send_changed_paths(
+                        project_id,
+                        &worktree_path,
+                        &git_dir,
+                        file_paths,
+                        num_events,
+                        &out,
+                    )
+                    .is_break()
                     {
-                        if let Ok(repo) = gix::open(&worktree_path) {
-                            if let Ok(index) = repo.index_or_empty() {
-                                if let Ok(mut excludes) = repo.excludes(
-                                    &index,
-                                    None,
-                                    gix::worktree::stack::state::ignore::Source::WorktreeThenIdMappingIfNotSkipped,
-                                ) {
-                                    for (file_path, kind) in classified_file_paths.iter_mut() {
-                                        if let Ok(relative_path) = file_path.strip_prefix(&worktree_path) {
-                                            let is_excluded = excludes
-                                                .at_path(relative_path, None)
-                                                .map(|platform| platform.is_excluded())
-                                                .unwrap_or(false);
-                                            let is_untracked =
-                                                || index.entry_by_path(&gix::path::to_unix_separators_on_windows(gix::path::into_bstr(relative_path))).is_none();
-                                            if is_excluded && is_untracked() {
-                                                *kind = FileKind::ProjectIgnored
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    let mut oplog_changed = false;
-                    let (mut stripped_git_paths, mut worktree_relative_paths) =
-                        (HashSet::new(), HashSet::new());
-                    for (file_path, kind) in classified_file_paths {
-                        match kind {
-                            FileKind::ProjectIgnored => ignored += 1,
-                            FileKind::GitUninteresting => git_noop += 1,
-                            FileKind::GitButlerOplog => {
-                                oplog_changed = true;
-                            }
-                            FileKind::Project | FileKind::Git => match file_path
-                                .strip_prefix(&worktree_path)
-                            {
-                                Ok(relative_file_path) => {
-                                    if relative_file_path.as_os_str().is_empty() {
-                                        continue;
-                                    }
-                                    if let Ok(stripped) = relative_file_path.strip_prefix(".git") {
-                                        stripped_git_paths.insert(stripped.to_owned());
-                                    } else {
-                                        worktree_relative_paths
-                                            .insert(relative_file_path.to_owned());
-                                    };
-                                }
-                                Err(err) => {
-                                    tracing::error!(%project_id, ?err, "failed to strip prefix");
-                                }
-                            },
-                        }
+                        break;
                     }
+                }
+            }
+        }
+    });
+    Ok(debouncer)
+}
 
-                    stats.record("fs_events", num_events);
-                    stats.record("ignored", ignored);
-                    stats.record("git_noop", git_noop);
-                    stats.record("git", stripped_git_paths.len());
-                    stats.record("project", worktree_relative_paths.len());
-
-                    if !stripped_git_paths.is_empty() {
-                        let paths_dedup: Vec<_> = stripped_git_paths.into_iter().collect();
-                        stats.record("git_dedup", paths_dedup.len());
-                        let event = InternalEvent::GitFilesChange(project_id, paths_dedup);
-                        if out.send(event).is_err() {
-                            tracing::info!("channel closed - stopping file watcher");
-                            break 'outer;
-                        }
-                    }
-                    if !worktree_relative_paths.is_empty() {
-                        let paths_dedup: Vec<_> = worktree_relative_paths.into_iter().collect();
-                        stats.record("project_dedup", paths_dedup.len());
-                        let event = InternalEvent::ProjectFilesChange(project_id, paths_dedup);
-                        if out.send(event).is_err() {
-                            tracing::info!("channel closed - stopping file watcher");
-                            break 'outer;
-                        }
-                    }
-                    if oplog_changed {
-                        let event = InternalEvent::GitButlerOplogChange(project_id);
-                        if out.send(event).is_err() {
-                            tracing::info!("channel closed - stopping file watcher");
-                            break 'outer;
+/// Classify all absolute `file_paths` and send them as events to `out`, skipping those that are ignored.
+/// `num_events` is the amount of raw events that led to `file_paths`, for statistics.
+///
+/// Return [`ControlFlow::Break`] if `out` was closed, and monitoring should stop.
+pub(crate) fn send_changed_paths(
+    project_id: ProjectId,
+    worktree_path: &Path,
+    git_dir: &Path,
+    file_paths: impl IntoIterator<Item = PathBuf>,
+    num_events: usize,
+    out: &tokio::sync::mpsc::UnboundedSender<InternalEvent>,
+) -> ControlFlow<()> {
+    let stats = tracing::span!(
+        Level::INFO,
+        "handle debounced events",
+        ignored = tracing::field::Empty,
+        project = tracing::field::Empty,
+        project_dedup = tracing::field::Empty,
+        git = tracing::field::Empty,
+        git_dedup = tracing::field::Empty,
+        git_noop = tracing::field::Empty,
+        fs_events = tracing::field::Empty,
+    )
+    .entered();
+    let (mut ignored, mut git_noop) = (0, 0);
+    let mut classified_file_paths: Vec<_> = file_paths
+        .into_iter()
+        .map(|file| {
+            let kind = classify_file(git_dir, &file);
+            (file, kind)
+        })
+        .collect();
+    if classified_file_paths
+        .iter()
+        .any(|(_, kind)| *kind == FileKind::Project)
+    {
+        if let Ok(repo) = gix::open(worktree_path) {
+            if let Ok(index) = repo.index_or_empty() {
+                if let Ok(mut excludes) = repo.excludes(
+                    &index,
+                    None,
+                    gix::worktree::stack::state::ignore::Source::WorktreeThenIdMappingIfNotSkipped,
+                ) {
+                    for (file_path, kind) in classified_file_paths.iter_mut() {
+                        if let Ok(relative_path) = file_path.strip_prefix(worktree_path) {
+                            let is_excluded = excludes
+                                .at_path(relative_path, None)
+                                .map(|platform| platform.is_excluded())
+                                .unwrap_or(false);
+                            let is_untracked = || {
+                                index
+                                    .entry_by_path(&gix::path::to_unix_separators_on_windows(
+                                        gix::path::into_bstr(relative_path),
+                                    ))
+                                    .is_none()
+                            };
+                            if is_excluded && is_untracked() {
+                                *kind = FileKind::ProjectIgnored
+                            }
                         }
                     }
                 }
             }
         }
-    });
-    Ok(debouncer)
+    }
+    let mut oplog_changed = false;
+    let (mut stripped_git_paths, mut worktree_relative_paths) = (HashSet::new(), HashSet::new());
+    for (file_path, kind) in classified_file_paths {
+        match kind {
+            FileKind::ProjectIgnored => ignored += 1,
+            FileKind::GitUninteresting => git_noop += 1,
+            FileKind::GitButlerOplog => {
+                oplog_changed = true;
+            }
+            FileKind::Project | FileKind::Git => match file_path.strip_prefix(worktree_path) {
+                Ok(relative_file_path) => {
+                    if relative_file_path.as_os_str().is_empty() {
+                        continue;
+                    }
+                    if let Ok(stripped) = relative_file_path.strip_prefix(".git") {
+                        stripped_git_paths.insert(stripped.to_owned());
+                    } else {
+                        worktree_relative_paths.insert(relative_file_path.to_owned());
+                    };
+                }
+                Err(err) => {
+                    tracing::error!(%project_id, ?err, "failed to strip prefix");
+                }
+            },
+        }
+    }
+
+    stats.record("fs_events", num_events);
+    stats.record("ignored", ignored);
+    stats.record("git_noop", git_noop);
+    stats.record("git", stripped_git_paths.len());
+    stats.record("project", worktree_relative_paths.len());
+
+    if !stripped_git_paths.is_empty() {
+        let paths_dedup: Vec<_> = stripped_git_paths.into_iter().collect();
+        stats.record("git_dedup", paths_dedup.len());
+        let event = InternalEvent::GitFilesChange(project_id, paths_dedup);
+        if out.send(event).is_err() {
+            tracing::info!("channel closed - stopping file watcher");
+            return ControlFlow::Break(());
+        }
+    }
+    if !worktree_relative_paths.is_empty() {
+        let paths_dedup: Vec<_> = worktree_relative_paths.into_iter().collect();
+        stats.record("project_dedup", paths_dedup.len());
+        let event = InternalEvent::ProjectFilesChange(project_id, paths_dedup);
+        if out.send(event).is_err() {
+            tracing::info!("channel closed - stopping file watcher");
+            return ControlFlow::Break(());
+        }
+    }
+    if oplog_changed {
+        let event = InternalEvent::GitButlerOplogChange(project_id);
+        if out.send(event).is_err() {
+            tracing::info!("channel closed - stopping file watcher");
+            return ControlFlow::Break(());
+        }
+    }
+    ControlFlow::Continue(())
 }
 
 #[cfg(target_family = "unix")]
@@ -6,4 +6,5 @@ mod events;
 
 pub use events::InternalEvent;
 mod file_monitor;
-pub use file_monitor::spawn;
+pub use file_monitor::{spawn, FileMonitor};
+mod query;
@@ -0,0 +1,312 @@
+//! Query the filesystem monitor configured in `core.fsmonitor`, which is either the builtin
+//! `git fsmonitor--daemon` or a hook that speaks version 2 of the fsmonitor protocol.
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context, Result};
+use bstr::ByteSlice;
+
+use super::{ChangeSource, Changes};
+
+/// The token which makes the builtin daemon respond with its current token only.
+const BUILTIN_INITIAL_TOKEN: &str = "builtin:fake";
+
+pub(crate) struct GitFsmonitor {
+    worktree_path: PathBuf,
+    kind: Kind,
+}
+
+enum Kind {
+    /// The builtin daemon, listening on a socket in the git-dir.
+    Builtin { git_dir: PathBuf },
+    /// A hook executable at the given path.
+    Hook(PathBuf),
+}
+
+impl GitFsmonitor {
+    /// Use whatever `core.fsmonitor` in the repository at `worktree_path` is configured to.
+    pub fn new(worktree_path: &Path, git_dir: &Path) -> Result<Self> {
+        let repo = gix::open(worktree_path)?;
+        let config = repo.config_snapshot();
+        let kind = match config.boolean("core.fsmonitor") {
+            Some(true) => Kind::Builtin {
+                git_dir: git_dir.to_owned(),
+            },
+            Some(false) => bail!("core.fsmonitor is disabled"),
+            None => {
+                let hook = config
+                    .trusted_path("core.fsmonitor")
+                    .transpose()?
+                    .context("core.fsmonitor isn't configured")?;
+                if config
+                    .integer("core.fsmonitorHookVersion")
+                    .is_some_and(|version| version != 2)
+                {
+                    bail!("only version 2 of the fsmonitor hook protocol is supported");
+                }
+                Kind::Hook(hook.into_owned())
+            }
+        };
+        Ok(GitFsmonitor {
+            worktree_path: worktree_path.to_owned(),
+            kind,
+        })
+    }
+}
+
+impl ChangeSource for GitFsmonitor {
+    fn changes_since(&mut self, token: Option<&str>) -> Result<Changes> {
+        let response = match &self.kind {
+            Kind::Builtin { git_dir } => {
+                let token = token.unwrap_or(BUILTIN_INITIAL_TOKEN);
+                match builtin::query(git_dir, token) {
+                    Ok(response) => response,
+                    Err(_) => {
+                        let status = Command::new(gix::path::env::exe_invocation())
+                            .args(["fsmonitor--daemon", "start"])
+                            .current_dir(&self.worktree_path)
+                            .status()
+                            .context("failed to launch git to start the fsmonitor daemon")?;
+                        if !status.success() {
+                            bail!("git fsmonitor--daemon start failed with {status}");
+                        }
+                        builtin::query(git_dir, token)?
+                    }
+                }
+            }
+            Kind::Hook(hook) => {
+                // Hooks know about timestamps, which is what version 1 of the protocol used as tokens.
+                let token = match token {
+                    Some(token) => token.to_owned(),
+                    None => std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)?
+                        .as_nanos()
+                        .to_string(),
+                };
+                let output = Command::new(hook)
+                    .args(["2", &token])
+                    .current_dir(&self.worktree_path)
+                    .output()
+                    .with_context(|| {
+                        format!("failed to run fsmonitor hook at {}", hook.display())
+                    })?;
+                if !output.status.success() {
+                    bail!(
+                        "fsmonitor hook failed with {}: {}",
+                        output.status,
+                        output.stderr.to_str_lossy().trim()
+                    );
+                }
+                output.stdout
+            }
+        };
+        parse_response(&response, token.is_none())
+    }
+}
+
+/// Parse the NUL-separated `response`, a token followed by the changed paths.
+/// A single `/` as path is a trivial response, which means that anything may have changed.
+fn parse_response(response: &[u8], is_initial: bool) -> Result<Changes> {
+    let mut fields = response.split(|b| *b == 0);
+    let token = fields
+        .next()
+        .filter(|token| !token.is_empty())
+        .context("fsmonitor responded without a token")?
+        .to_str()
+        .context("fsmonitor token isn't valid UTF-8")?
+        .to_owned();
+    let mut paths = Vec::new();
+    for path in fields.filter(|path| !path.is_empty()) {
+        if path == b"/" {
+            return Ok(Changes { token, paths: None });
+        }
+        paths.push(gix::path::from_byte_slice(path).into_owned());
+    }
+    Ok(Changes {
+        token,
+        paths: (!is_initial).then_some(paths),
+    })
+}
+
+#[cfg(unix)]
+mod builtin {
+    use std::{
+        io::{Read, Write},
+        os::unix::net::UnixStream,
+        path::Path,
+    };
+
+    use anyhow::{bail, Context, Result};
+
+    /// Send `token` to the daemon listening in `git_dir` and return its response.
+    pub fn query(git_dir: &Path, token: &str) -> Result<Vec<u8>> {
+        let mut stream = UnixStream::connect(git_dir.join("fsmonitor--daemon.ipc"))
+            .context("fsmonitor daemon isn't running")?;
+        write_packet(&mut stream, token.as_bytes())?;
+        stream.write_all(b"0000")?;
+
+        let mut response = Vec::new();
+        loop {
+            let mut len = [0; 4];
+            stream.read_exact(&mut len)?;
+            let len = usize::from_str_radix(std::str::from_utf8(&len)?, 16)
+                .context("invalid packet line length")?;
+            match len {
+                0 => break,
+                1..=3 => bail!("invalid packet line length {len}"),
+                _ => {
+                    let start = response.len();
+                    response.resize(start + len - 4, 0);
+                    stream.read_exact(&mut response[start..])?;
+                }
+            }
+        }
+        Ok(response)
+    }
+
+    fn write_packet(out: &mut impl Write, data: &[u8]) -> Result<()> {
+        write!(out, "{:04x}", data.len() + 4)?;
+        out.write_all(data)?;
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+mod builtin {
+    use std::path::Path;
+
+    use anyhow::{bail, Result};
+
+    pub fn query(_git_dir: &Path, _token: &str) -> Result<Vec<u8>> {
+        bail!("the builtin fsmonitor daemon is only supported on Unix")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_changed_paths() {
+        assert_eq!(
+            parse_response(b"token-2\0src/lib.rs\0README.md\0", false).unwrap(),
+            Changes {
+                token: "token-2".into(),
+                paths: Some(vec!["src/lib.rs".into(), "README.md".into()]),
+            }
+        );
+        assert_eq!(
+            parse_response(b"token-2\0", false).unwrap(),
+            Changes {
+                token: "token-2".into(),
+                paths: Some(Vec::new()),
+            },
+            "nothing changed"
+        );
+    }
+
+    #[test]
+    fn parse_trivial_response() {
+        assert_eq!(
+            parse_response(b"token-2\0src/lib.rs\0/\0", false).unwrap(),
+            Changes {
+                token: "token-2".into(),
+                paths: None,
+            },
+            "a single slash means that anything may have changed"
+        );
+    }
+
+    #[test]
+    fn parse_initial_response() {
+        assert_eq!(
+            parse_response(b"token-1\0src/lib.rs\0", true).unwrap(),
+            Changes {
+                token: "token-1".into(),
+                paths: None,
+            },
+            "the initial query is only about obtaining a token"
+        );
+    }
+
+    #[test]
+    fn parse_invalid_responses() {
+        let err = parse_response(b"", false).unwrap_err();
+        assert_eq!(err.to_string(), "fsmonitor responded without a token");
+        let err = parse_response(b"\0src/lib.rs\0", false).unwrap_err();
+        assert_eq!(err.to_string(), "fsmonitor responded without a token");
+        let err = parse_response(b"\xff\0", false).unwrap_err();
+        assert_eq!(err.to_string(), "fsmonitor token isn't valid UTF-8");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hook_protocol() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir()?;
+        let worktree = tmp.path();
+        let repo = gix::init(worktree)?;
+        let hook = worktree.join("fsmonitor-hook");
+        std::fs::write(
+            &hook,
+            "#!/bin/sh\necho \"$@\" >>args\nprintf 'next-token\\000src/lib.rs\\000'\n",
+        )?;
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755))?;
+        let config_path = repo.git_dir().join("config");
+        let mut config = std::fs::read_to_string(&config_path)?;
+        config.push_str(&format!(
+            "[core]\n\tfsmonitor = {}\n\tfsmonitorHookVersion = 2\n",
+            hook.display()
+        ));
+        std::fs::write(&config_path, config)?;
+
+        let mut monitor = GitFsmonitor::new(worktree, repo.git_dir())?;
+        let initial = monitor.changes_since(None)?;
+        assert_eq!(initial.token, "next-token");
+        assert_eq!(
+            initial.paths, None,
+            "the initial query only obtains a token"
+        );
+
+        let changes = monitor.changes_since(Some("previous-token"))?;
+        assert_eq!(
+            changes,
+            Changes {
+                token: "next-token".into(),
+                paths: Some(vec!["src/lib.rs".into()]),
+            }
+        );
+
+        let args = std::fs::read_to_string(worktree.join("args"))?;
+        let args: Vec<_> = args.lines().collect();
+        assert_eq!(args.len(), 2);
+        assert!(
+            args[0].starts_with("2 "),
+            "the protocol version is passed first, then a timestamp as token: {args:?}"
+        );
+        assert_eq!(args[1], "2 previous-token");
+        Ok(())
+    }
+
+    #[test]
+    fn version_1_hooks_are_rejected() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let repo = gix::init(tmp.path())?;
+        let config_path = repo.git_dir().join("config");
+        let mut config = std::fs::read_to_string(&config_path)?;
+        config.push_str("[core]\n\tfsmonitor = /bin/hook\n\tfsmonitorHookVersion = 1\n");
+        std::fs::write(&config_path, config)?;
+
+        let err = GitFsmonitor::new(tmp.path(), repo.git_dir())
+            .err()
+            .expect("only version 2 is supported");
+        assert_eq!(
+            err.to_string(),
+            "only version 2 of the fsmonitor hook protocol is supported"
+        );
+        Ok(())
+    }
+}
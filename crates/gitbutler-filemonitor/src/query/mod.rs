@@ -0,0 +1,118 @@
+//! Obtain worktree changes by asking a filesystem monitor that keeps track of them for us, instead of
+//! watching the entire worktree ourselves.
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::RecvTimeoutError,
+    time::Duration,
+};
+
+use anyhow::Result;
+use gitbutler_project::{FileMonitorBackend, ProjectId};
+use tracing::Level;
+
+use crate::{events::InternalEvent, file_monitor::send_changed_paths};
+
+mod fsmonitor;
+mod watchman;
+
+/// How often the change source is asked for changes, unless a flush is requested earlier.
+const QUERY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The result of a query for changes.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Changes {
+    /// The token to pass to the next query to receive only the changes after this one.
+    pub token: String,
+    /// The worktree-relative paths that changed since the previous token, or `None` if these aren't known
+    /// and any path may have changed, for instance because the monitor was restarted.
+    pub paths: Option<Vec<PathBuf>>,
+}
+
+/// Something that knows which files changed in a worktree.
+pub(crate) trait ChangeSource: Send {
+    /// Return all changes since `token`, or since the beginning of time if `None`.
+    fn changes_since(&mut self, token: Option<&str>) -> Result<Changes>;
+}
+
+/// Return a change source for `backend` along with the token to query changes from now on,
+/// or an error if it's unavailable.
+pub(crate) fn change_source(
+    backend: FileMonitorBackend,
+    worktree_path: &Path,
+    git_dir: &Path,
+) -> Result<(Box<dyn ChangeSource>, String)> {
+    let mut source: Box<dyn ChangeSource> = match backend {
+        FileMonitorBackend::Notify => {
+            anyhow::bail!("notify isn't a source that can be queried")
+        }
+        FileMonitorBackend::Watchman => Box::new(watchman::Watchman::new(worktree_path)?),
+        FileMonitorBackend::GitFsmonitor => {
+            Box::new(fsmonitor::GitFsmonitor::new(worktree_path, git_dir)?)
+        }
+    };
+    // This also makes sure it actually works before we rely on it.
+    let token = source.changes_since(None)?.token;
+    Ok((source, token))
+}
+
+/// Query `source` for changes in `worktree_path` after `token` periodically and send them to `out` until
+/// it's closed, or until the returned sender is dropped. Send to it to query right away.
+pub(crate) fn spawn(
+    project_id: ProjectId,
+    worktree_path: PathBuf,
+    git_dir: PathBuf,
+    mut source: Box<dyn ChangeSource>,
+    mut token: String,
+    out: tokio::sync::mpsc::UnboundedSender<InternalEvent>,
+) -> Result<std::sync::mpsc::Sender<()>> {
+    let (flush_tx, flush_rx) = std::sync::mpsc::channel();
+    std::thread::Builder::new()
+        .name("gitbutler-filemonitor-query".into())
+        .spawn(move || {
+            let _runtime = tracing::span!(Level::INFO, "file monitor query", %project_id).entered();
+            tracing::debug!(%project_id, "file monitor query started");
+            loop {
+                match flush_rx.recv_timeout(QUERY_INTERVAL) {
+                    Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                let changes = match source.changes_since(Some(&token)) {
+                    Ok(changes) => changes,
+                    Err(err) => {
+                        tracing::warn!(%project_id, ?err, "failed to query file monitor for changes");
+                        continue;
+                    }
+                };
+                token = changes.token;
+                match changes.paths {
+                    Some(paths) => {
+                        let num_paths = paths.len();
+                        let paths = paths.into_iter().map(|path| worktree_path.join(path));
+                        if send_changed_paths(
+                            project_id,
+                            &worktree_path,
+                            &git_dir,
+                            paths,
+                            num_paths,
+                            &out,
+                        )
+                        .is_break()
+                        {
+                            break;
+                        }
+                    }
+                    None => {
+                        // An empty list of paths means that anything may have changed.
+                        if out
+                            .send(InternalEvent::ProjectFilesChange(project_id, Vec::new()))
+                            .is_err()
+                        {
+                            tracing::info!("channel closed - stopping file monitor query");
+                            break;
+                        }
+                    }
+                }
+            }
+        })?;
+    Ok(flush_tx)
+}
@@ -0,0 +1,496 @@
+//! Query [Watchman](https://facebook.github.io/watchman) through its command-line interface.
+//!
+//! A single `watchman` process is kept alive with a subscription to the worktree, which sends the changed
+//! files as they happen. Queries then only collect what was received since.
+use std::{
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdout, Command, Stdio},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+
+use super::{ChangeSource, Changes};
+
+/// The name of our subscription, unique per `watchman` process.
+const SUBSCRIPTION_NAME: &str = "gitbutler";
+/// How long to wait for watchman to confirm a subscription.
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(crate) struct Watchman {
+    /// The root of the watch, which may be above the worktree.
+    watch_root: PathBuf,
+    /// The path to the worktree relative to `watch_root`, if it isn't the root itself.
+    relative_root: Option<PathBuf>,
+    /// The running subscription, or `None` if it has yet to be started or was lost.
+    subscription: Option<Subscription>,
+}
+
+impl Watchman {
+    /// Start watching `worktree_path`, or reuse an existing watch that contains it.
+    pub fn new(worktree_path: &Path) -> Result<Self> {
+        let response = run(json!(["watch-project", worktree_path]))?;
+        let watch_root = response
+            .get("watch")
+            .and_then(Value::as_str)
+            .context("watchman didn't respond with the watched root")?
+            .into();
+        let relative_root = response
+            .get("relative_path")
+            .and_then(Value::as_str)
+            .map(PathBuf::from);
+        Ok(Watchman {
+            watch_root,
+            relative_root,
+            subscription: None,
+        })
+    }
+}
+
+impl ChangeSource for Watchman {
+    fn changes_since(&mut self, token: Option<&str>) -> Result<Changes> {
+        let Some(subscription) = &mut self.subscription else {
+            // Subscribing with a previous clock makes watchman send what we missed in the meantime,
+            // or tell us that it was restarted and that anything could have changed.
+            let (subscription, clock) =
+                Subscription::start(&self.watch_root, self.relative_root.as_deref(), token)?;
+            self.subscription = Some(subscription);
+            return Ok(Changes {
+                token: clock,
+                paths: token.map(|_| Vec::new()),
+            });
+        };
+
+        let token = token.unwrap_or_default().to_owned();
+        collect_changes(
+            &subscription.responses,
+            token,
+            &mut subscription.awaits_initial_response,
+        )
+        .inspect_err(|_| self.subscription = None)
+    }
+}
+
+/// Combine all `responses` received so far into the changes after `token`.
+///
+/// If `awaits_initial_response` is set, the first changes are the ones watchman sends right after subscribing
+/// without a clock. These are ignored even though they claim to be from a fresh instance, as they are
+/// only about what happened before we started to watch.
+fn collect_changes(
+    responses: &Receiver<Result<Response>>,
+    mut token: String,
+    awaits_initial_response: &mut bool,
+) -> Result<Changes> {
+    let mut paths = Some(Vec::new());
+    loop {
+        match responses.try_recv() {
+            Ok(Ok(Response::Changes(changes))) => {
+                token = changes.token;
+                if std::mem::take(awaits_initial_response) {
+                    continue;
+                }
+                match (&mut paths, changes.paths) {
+                    (Some(paths), Some(changed)) => paths.extend(changed),
+                    _ => paths = None,
+                }
+            }
+            Ok(Ok(Response::Subscribed { .. } | Response::Ignored)) => {}
+            Ok(Err(err)) => return Err(err),
+            Err(TryRecvError::Empty) => break,
+            Err(TryRecvError::Disconnected) => {
+                bail!("watchman stopped unexpectedly, subscribing again on next query")
+            }
+        }
+    }
+    Ok(Changes { token, paths })
+}
+
+/// A `watchman` process that keeps sending the changes of a subscription.
+struct Subscription {
+    child: Child,
+    responses: Receiver<Result<Response>>,
+    /// If `true`, the subscription was started without a clock and the initial response is yet to be received.
+    awaits_initial_response: bool,
+}
+
+impl Subscription {
+    /// Subscribe to changes of `watch_root`, limited to `relative_root`, after the `since` clock or from now on.
+    /// Return the subscription along with the clock as of which changes will be sent.
+    fn start(
+        watch_root: &Path,
+        relative_root: Option<&Path>,
+        since: Option<&str>,
+    ) -> Result<(Self, String)> {
+        let mut query = json!({
+            "fields": ["name"],
+            "empty_on_fresh_instance": true,
+        });
+        if let Some(since) = since {
+            query["since"] = json!(since);
+        }
+        if let Some(relative_root) = relative_root {
+            query["relative_root"] = json!(relative_root);
+        }
+
+        let mut child = Command::new("watchman")
+            .args([
+                "--no-pretty",
+                "--server-encoding=json",
+                "--persistent",
+                "-j",
+            ])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("failed to launch watchman - is it installed?")?;
+        // `-j` reads the command until the end of input, and `--persistent` keeps the process
+        // around to receive what the subscription sends afterwards.
+        let mut stdin = child.stdin.take().context("stdin is piped")?;
+        writeln!(
+            stdin,
+            "{}",
+            json!(["subscribe", watch_root, SUBSCRIPTION_NAME, query])
+        )?;
+        drop(stdin);
+
+        let stdout = child.stdout.take().context("stdout is piped")?;
+        let (tx, responses) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("gitbutler-filemonitor-watchman".into())
+            .spawn(move || read_responses(stdout, tx))?;
+        let subscription = Subscription {
+            child,
+            responses,
+            awaits_initial_response: since.is_none(),
+        };
+        match subscription.responses.recv_timeout(SUBSCRIBE_TIMEOUT) {
+            Ok(Ok(Response::Subscribed { clock })) => Ok((subscription, clock)),
+            Ok(Ok(_)) => bail!("watchman didn't confirm the subscription"),
+            Ok(Err(err)) => Err(err),
+            Err(_) => bail!("watchman didn't confirm the subscription in time"),
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// Send each line `watchman` writes to `stdout` as response to `out`, until either of them is closed.
+fn read_responses(stdout: ChildStdout, out: mpsc::Sender<Result<Response>>) {
+    for line in BufReader::new(stdout).lines() {
+        let response = line
+            .map_err(anyhow::Error::from)
+            .and_then(|line| parse_subscription_response(line.as_bytes()));
+        if out.send(response).is_err() {
+            break;
+        }
+    }
+}
+
+/// What watchman sends over a subscription.
+#[derive(Debug, PartialEq)]
+enum Response {
+    /// The subscription was established, and changes will be sent as of `clock`.
+    Subscribed { clock: String },
+    /// Files changed.
+    Changes(Changes),
+    /// Something we don't care about, like notifications about states being entered or left.
+    Ignored,
+}
+
+/// Parse `line` as sent over a subscription.
+fn parse_subscription_response(line: &[u8]) -> Result<Response> {
+    let response = parse_response(line)?;
+    if response.get("subscribe").is_some() {
+        return Ok(Response::Subscribed {
+            clock: clock(&response)?,
+        });
+    }
+    if response.get("subscription").is_none()
+        || response.get("state-enter").is_some()
+        || response.get("state-leave").is_some()
+    {
+        return Ok(Response::Ignored);
+    }
+    if response
+        .get("canceled")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+    {
+        bail!("watchman canceled the subscription");
+    }
+
+    let token = clock(&response)?;
+    if response
+        .get("is_fresh_instance")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+    {
+        return Ok(Response::Changes(Changes { token, paths: None }));
+    }
+    let paths = response
+        .get("files")
+        .and_then(Value::as_array)
+        .context("watchman didn't respond with files")?
+        .iter()
+        .filter_map(Value::as_str)
+        .map(PathBuf::from)
+        .collect();
+    Ok(Response::Changes(Changes {
+        token,
+        paths: Some(paths),
+    }))
+}
+
+fn clock(response: &Value) -> Result<String> {
+    Ok(response
+        .get("clock")
+        .and_then(Value::as_str)
+        .context("watchman didn't respond with a clock")?
+        .to_owned())
+}
+
+/// Run the watchman `command` and return its response, or fail if it was an error.
+fn run(command: Value) -> Result<Value> {
+    let mut child = Command::new("watchman")
+        .args(["--no-pretty", "-j"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to launch watchman - is it installed?")?;
+    child
+        .stdin
+        .take()
+        .context("stdin is piped")?
+        .write_all(command.to_string().as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "watchman failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse_response(&output.stdout)
+}
+
+/// Parse a single JSON `response` of watchman, or fail if it is an error.
+fn parse_response(response: &[u8]) -> Result<Value> {
+    let response: Value =
+        serde_json::from_slice(response).context("could not parse watchman response")?;
+    if let Some(err) = response.get("error").and_then(Value::as_str) {
+        bail!("watchman: {err}");
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_response_fails_on_errors() {
+        let err = parse_response(
+            br#"{"version":"2024.01.01.00","error":"unable to resolve root /nope"}"#,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "watchman: unable to resolve root /nope");
+
+        let err = parse_response(b"not json").unwrap_err();
+        assert_eq!(err.to_string(), "could not parse watchman response");
+
+        let response =
+            parse_response(br#"{"version":"2024.01.01.00","watch":"/repo","watcher":"inotify"}"#)
+                .unwrap();
+        assert_eq!(response["watch"], "/repo");
+    }
+
+    #[test]
+    fn subscription_confirmation() {
+        assert_eq!(
+            parse_subscription_response(
+                br#"{"version":"2024.01.01.00","subscribe":"gitbutler","clock":"c:1:2:3:4"}"#
+            )
+            .unwrap(),
+            Response::Subscribed {
+                clock: "c:1:2:3:4".into()
+            }
+        );
+    }
+
+    #[test]
+    fn subscription_changes() {
+        assert_eq!(
+            parse_subscription_response(
+                br#"{"version":"2024.01.01.00","clock":"c:1:2:3:5","files":["src/lib.rs","README.md"],"is_fresh_instance":false,"root":"/repo","subscription":"gitbutler","unilateral":true,"since":"c:1:2:3:4"}"#
+            )
+            .unwrap(),
+            Response::Changes(Changes {
+                token: "c:1:2:3:5".into(),
+                paths: Some(vec!["src/lib.rs".into(), "README.md".into()]),
+            })
+        );
+    }
+
+    #[test]
+    fn fresh_instances_may_have_changed_anything() {
+        assert_eq!(
+            parse_subscription_response(
+                br#"{"version":"2024.01.01.00","clock":"c:9:2:3:1","files":[],"is_fresh_instance":true,"root":"/repo","subscription":"gitbutler","unilateral":true}"#
+            )
+            .unwrap(),
+            Response::Changes(Changes {
+                token: "c:9:2:3:1".into(),
+                paths: None,
+            })
+        );
+    }
+
+    #[test]
+    fn state_changes_are_ignored() {
+        for state in ["state-enter", "state-leave"] {
+            let response = format!(
+                r#"{{"version":"2024.01.01.00","clock":"c:1:2:3:6","{state}":"hg.update","root":"/repo","subscription":"gitbutler","unilateral":true}}"#
+            );
+            assert_eq!(
+                parse_subscription_response(response.as_bytes()).unwrap(),
+                Response::Ignored
+            );
+        }
+    }
+
+    #[test]
+    fn canceled_subscriptions_are_errors() {
+        let err = parse_subscription_response(
+            br#"{"version":"2024.01.01.00","root":"/repo","subscription":"gitbutler","unilateral":true,"canceled":true}"#,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "watchman canceled the subscription");
+    }
+
+    fn changes(token: &str, paths: Option<&[&str]>) -> Result<Response> {
+        Ok(Response::Changes(Changes {
+            token: token.into(),
+            paths: paths.map(|paths| paths.iter().map(PathBuf::from).collect()),
+        }))
+    }
+
+    #[test]
+    fn initial_fresh_instance_is_ignored() {
+        let (tx, rx) = mpsc::channel();
+        tx.send(Ok(Response::Subscribed {
+            clock: "c:1".into(),
+        }))
+        .unwrap();
+        tx.send(changes("c:2", None)).unwrap();
+        tx.send(changes("c:3", Some(&["a"]))).unwrap();
+
+        let mut awaits_initial_response = true;
+        assert_eq!(
+            collect_changes(&rx, "c:1".into(), &mut awaits_initial_response).unwrap(),
+            Changes {
+                token: "c:3".into(),
+                paths: Some(vec!["a".into()]),
+            },
+            "the fresh instance right after subscribing doesn't mean that anything changed"
+        );
+        assert!(!awaits_initial_response);
+
+        tx.send(changes("c:4", None)).unwrap();
+        tx.send(changes("c:5", Some(&["b"]))).unwrap();
+        assert_eq!(
+            collect_changes(&rx, "c:3".into(), &mut awaits_initial_response).unwrap(),
+            Changes {
+                token: "c:5".into(),
+                paths: None,
+            },
+            "later fresh instances mean that watchman was restarted and we may have missed changes"
+        );
+    }
+
+    #[test]
+    fn collected_changes_are_combined() {
+        let (tx, rx) = mpsc::channel();
+        let mut awaits_initial_response = false;
+        assert_eq!(
+            collect_changes(&rx, "c:1".into(), &mut awaits_initial_response).unwrap(),
+            Changes {
+                token: "c:1".into(),
+                paths: Some(Vec::new()),
+            },
+            "without responses, the token stays the same"
+        );
+
+        tx.send(changes("c:2", Some(&["a", "b"]))).unwrap();
+        tx.send(Ok(Response::Ignored)).unwrap();
+        tx.send(changes("c:3", Some(&["c"]))).unwrap();
+        assert_eq!(
+            collect_changes(&rx, "c:1".into(), &mut awaits_initial_response).unwrap(),
+            Changes {
+                token: "c:3".into(),
+                paths: Some(vec!["a".into(), "b".into(), "c".into()]),
+            }
+        );
+
+        drop(tx);
+        let err = collect_changes(&rx, "c:3".into(), &mut awaits_initial_response).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "watchman stopped unexpectedly, subscribing again on next query"
+        );
+    }
+
+    /// This needs `watchman` to be installed, and passes trivially otherwise.
+    #[test]
+    fn changes_are_received_from_watchman() -> Result<()> {
+        if Command::new("watchman").arg("version").output().is_err() {
+            return Ok(());
+        }
+        let tmp = tempfile::tempdir()?;
+        let worktree = tmp.path().canonicalize()?;
+        // Make watchman consider this directory a project root.
+        std::fs::create_dir(worktree.join(".git"))?;
+
+        let mut watchman = Watchman::new(&worktree)?;
+        let initial = watchman.changes_since(None)?;
+        std::fs::write(worktree.join("file"), "content")?;
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        let mut token = initial.token;
+        let mut seen = Vec::new();
+        while !seen.contains(&PathBuf::from("file")) {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "watchman didn't report the change in time, saw {seen:?}"
+            );
+            std::thread::sleep(Duration::from_millis(50));
+            let changes = watchman.changes_since(Some(&token))?;
+            token = changes.token;
+            seen.extend(
+                changes
+                    .paths
+                    .expect("the initial subscription doesn't cause a full refresh"),
+            );
+        }
+        run(json!(["watch-del", worktree])).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn missing_fields_are_errors() {
+        let err = parse_subscription_response(
+            br#"{"version":"2024.01.01.00","clock":"c:1:2:3:5","subscription":"gitbutler","unilateral":true}"#,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "watchman didn't respond with files");
+    }
+}
@@ -1,3 +1,6 @@
+use std::{collections::BTreeMap, future::Future, str::FromStr};
+
+use gitbutler_url::Url;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -9,3 +12,206 @@ pub enum ForgeName {
     Bitbucket,
     Azure,
 }
+
+impl FromStr for ForgeName {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "github" => ForgeName::GitHub,
+            "gitlab" => ForgeName::GitLab,
+            "bitbucket" => ForgeName::Bitbucket,
+            "azure" => ForgeName::Azure,
+            _ => anyhow::bail!("Unknown forge '{s}'"),
+        })
+    }
+}
+
+impl ForgeName {
+    /// Determine the forge that hosts the repository at `url`, or `None` if it's unknown.
+    ///
+    /// `forge_hosts` maps the hosts of self-hosted forges to forge names like `gitlab`, and is consulted first.
+    /// Unknown forge names in `forge_hosts` are ignored.
+    pub fn from_url(url: &Url, forge_hosts: &BTreeMap<String, String>) -> Option<ForgeName> {
+        let self_hosted = forge_hosts
+            .iter()
+            .filter(|(host, _)| url.is_host(host))
+            .find_map(|(_, name)| name.parse().ok());
+        self_hosted.or_else(|| {
+            if url.is_github() {
+                Some(ForgeName::GitHub)
+            } else if url.is_gitlab() {
+                Some(ForgeName::GitLab)
+            } else if url.is_bitbucket() {
+                Some(ForgeName::Bitbucket)
+            } else if url.is_azure() {
+                Some(ForgeName::Azure)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// The symbol that precedes the number of a pull request when referring to it in text.
+    pub fn pull_request_symbol(&self) -> &'static str {
+        match self {
+            ForgeName::GitHub | ForgeName::Bitbucket => "#",
+            ForgeName::GitLab | ForgeName::Azure => "!",
+        }
+    }
+}
+
+/// The state of a pull request.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum PullRequestState {
+    Open,
+    Closed,
+    Merged,
+}
+
+/// A pull request as known to the forge.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequest {
+    /// The number identifying the pull request within its repository.
+    pub number: usize,
+    pub title: String,
+    pub body: Option<String>,
+    /// The name of the branch with the changes.
+    pub head: String,
+    /// The commit at the tip of the `head` branch.
+    pub head_sha: String,
+    /// The name of the branch the changes should be merged into.
+    pub base: String,
+    /// The URL at which humans can see the pull request.
+    pub url: String,
+    pub state: PullRequestState,
+    pub draft: bool,
+}
+
+/// What's needed to open a new pull request.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePullRequest {
+    pub title: String,
+    pub body: String,
+    /// The name of the branch with the changes.
+    pub head: String,
+    /// The name of the branch the changes should be merged into.
+    pub base: String,
+    pub draft: bool,
+}
+
+/// Changes to an existing pull request, where `None` leaves the respective value unchanged.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePullRequest {
+    pub title: Option<String>,
+    pub body: Option<String>,
+    pub base: Option<String>,
+}
+
+/// The progress of a check, like a CI job, that runs against a pull request.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Queued,
+    InProgress,
+    Completed,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    /// How a completed check ended, like `success` or `failure`, as reported by the forge.
+    pub conclusion: Option<String>,
+}
+
+/// The verdict of a review.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum ReviewState {
+    Approved,
+    ChangesRequested,
+    Commented,
+    Dismissed,
+    Pending,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Review {
+    /// The login of the reviewer.
+    pub author: String,
+    pub state: ReviewState,
+}
+
+/// A pull request along with the checks that ran against its head and the reviews it received.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestStatus {
+    pub pull_request: PullRequest,
+    pub checks: Vec<Check>,
+    pub reviews: Vec<Review>,
+}
+
+/// A client for the pull requests of a single repository on a forge.
+pub trait Forge {
+    /// The kind of forge this client talks to.
+    fn name(&self) -> ForgeName;
+
+    /// Open a new pull request as described by `request`.
+    fn create_pull_request(
+        &self,
+        request: CreatePullRequest,
+    ) -> impl Future<Output = anyhow::Result<PullRequest>> + Send;
+
+    /// Apply `update` to the pull request with `number`.
+    fn update_pull_request(
+        &self,
+        number: usize,
+        update: UpdatePullRequest,
+    ) -> impl Future<Output = anyhow::Result<PullRequest>> + Send;
+
+    /// Close the pull request with `number` without merging it.
+    fn close_pull_request(
+        &self,
+        number: usize,
+    ) -> impl Future<Output = anyhow::Result<PullRequest>> + Send;
+
+    /// Return the pull request with `number`.
+    fn pull_request(
+        &self,
+        number: usize,
+    ) -> impl Future<Output = anyhow::Result<PullRequest>> + Send;
+
+    /// Return the open pull request for the branch named `head`, if there is one.
+    fn find_open_pull_request(
+        &self,
+        head: &str,
+    ) -> impl Future<Output = anyhow::Result<Option<PullRequest>>> + Send;
+
+    /// Return the pull request with `number` along with its checks and reviews.
+    fn pull_request_status(
+        &self,
+        number: usize,
+    ) -> impl Future<Output = anyhow::Result<PullRequestStatus>> + Send;
+
+    /// Make the pull request with `number` target the branch named `base`.
+    fn set_base(
+        &self,
+        number: usize,
+        base: &str,
+    ) -> impl Future<Output = anyhow::Result<PullRequest>> + Send {
+        self.update_pull_request(
+            number,
+            UpdatePullRequest {
+                base: Some(base.to_owned()),
+                ..Default::default()
+            },
+        )
+    }
+}
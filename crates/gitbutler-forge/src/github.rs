@@ -0,0 +1,302 @@
+//! A [`Forge`] implementation for the GitHub REST API.
+use anyhow::{bail, Context, Result};
+use gitbutler_url::Url;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::forge::{
+    Check, CheckStatus, CreatePullRequest, Forge, ForgeName, PullRequest, PullRequestState,
+    PullRequestStatus, Review, ReviewState, UpdatePullRequest,
+};
+
+/// The API of github.com, which is used unless another one is configured.
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// A client for the pull requests of a single GitHub repository.
+#[derive(Debug, Clone)]
+pub struct GitHub {
+    client: reqwest::Client,
+    api_url: String,
+    owner: String,
+    repo: String,
+    token: String,
+}
+
+impl GitHub {
+    /// Create a client for the repository `owner/repo` on github.com that authenticates with `token`.
+    pub fn new(
+        owner: impl Into<String>,
+        repo: impl Into<String>,
+        token: impl Into<String>,
+    ) -> Self {
+        GitHub {
+            client: reqwest::Client::new(),
+            api_url: DEFAULT_API_URL.to_owned(),
+            owner: owner.into(),
+            repo: repo.into(),
+            token: token.into(),
+        }
+    }
+
+    /// Create a client for the repository `owner/repo` that authenticates with the GitHub token of `user`.
+    pub fn from_user(
+        user: &gitbutler_user::User,
+        owner: impl Into<String>,
+        repo: impl Into<String>,
+    ) -> Result<Self> {
+        let token = user
+            .github_access_token()?
+            .context("The user isn't authenticated with GitHub")?;
+        Ok(Self::new(owner, repo, token.0))
+    }
+
+    /// Create a client for the repository at `url` that authenticates with the GitHub token of `user`.
+    ///
+    /// Repositories that aren't on github.com are assumed to be on a GitHub Enterprise server at the same host.
+    pub fn from_remote_url(user: &gitbutler_user::User, url: &Url) -> Result<Self> {
+        let (owner, repo) = owner_and_repo(url).with_context(|| {
+            format!("Couldn't find the owner and name of the repository at {url}")
+        })?;
+        let forge = Self::from_user(user, owner, repo)?;
+        Ok(match url.host.as_deref() {
+            Some(host) if !url.is_github() => forge.with_api_url(format!("https://{host}/api/v3")),
+            _ => forge,
+        })
+    }
+
+    /// Use the API at `api_url` instead of the one of github.com, like the one of a GitHub Enterprise server.
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into().trim_end_matches('/').to_owned();
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/repos/{}/{}/{path}", self.api_url, self.owner, self.repo)
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        let response = request
+            .bearer_auth(&self.token)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header(reqwest::header::USER_AGENT, "GitButler")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let message = serde_json::from_str::<ApiError>(&body)
+                .map(|error| error.message)
+                .unwrap_or(body);
+            bail!("GitHub responded with {status}: {message}");
+        }
+        Ok(response.json().await?)
+    }
+}
+
+/// Return the owner and the name of the repository at `url`, like `gitbutlerapp` and `gitbutler`.
+pub fn owner_and_repo(url: &Url) -> Option<(String, String)> {
+    let path = url.path.to_string();
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let (owner, repo) = path.split_once('/')?;
+    (!owner.is_empty() && !repo.is_empty() && !repo.contains('/'))
+        .then(|| (owner.to_owned(), repo.to_owned()))
+}
+
+impl Forge for GitHub {
+    fn name(&self) -> ForgeName {
+        ForgeName::GitHub
+    }
+
+    async fn create_pull_request(&self, request: CreatePullRequest) -> Result<PullRequest> {
+        let pr: ApiPullRequest = self
+            .send(self.client.post(self.url("pulls")).json(&request))
+            .await?;
+        Ok(pr.into())
+    }
+
+    async fn update_pull_request(
+        &self,
+        number: usize,
+        update: UpdatePullRequest,
+    ) -> Result<PullRequest> {
+        let pr: ApiPullRequest = self
+            .send(
+                self.client
+                    .patch(self.url(&format!("pulls/{number}")))
+                    .json(&ApiUpdate {
+                        title: update.title,
+                        body: update.body,
+                        base: update.base,
+                        state: None,
+                    }),
+            )
+            .await?;
+        Ok(pr.into())
+    }
+
+    async fn close_pull_request(&self, number: usize) -> Result<PullRequest> {
+        let pr: ApiPullRequest = self
+            .send(
+                self.client
+                    .patch(self.url(&format!("pulls/{number}")))
+                    .json(&ApiUpdate {
+                        state: Some("closed"),
+                        ..Default::default()
+                    }),
+            )
+            .await?;
+        Ok(pr.into())
+    }
+
+    async fn pull_request(&self, number: usize) -> Result<PullRequest> {
+        let pr: ApiPullRequest = self
+            .send(self.client.get(self.url(&format!("pulls/{number}"))))
+            .await?;
+        Ok(pr.into())
+    }
+
+    async fn find_open_pull_request(&self, head: &str) -> Result<Option<PullRequest>> {
+        let prs: Vec<ApiPullRequest> = self
+            .send(self.client.get(self.url("pulls")).query(&[
+                ("head", format!("{}:{head}", self.owner).as_str()),
+                ("state", "open"),
+            ]))
+            .await?;
+        Ok(prs.into_iter().next().map(Into::into))
+    }
+
+    async fn pull_request_status(&self, number: usize) -> Result<PullRequestStatus> {
+        let pull_request = self.pull_request(number).await?;
+        let checks: ApiCheckRuns = self
+            .send(
+                self.client
+                    .get(self.url(&format!("commits/{}/check-runs", pull_request.head_sha))),
+            )
+            .await?;
+        let reviews: Vec<ApiReview> = self
+            .send(
+                self.client
+                    .get(self.url(&format!("pulls/{number}/reviews"))),
+            )
+            .await?;
+        Ok(PullRequestStatus {
+            pull_request,
+            checks: checks.check_runs.into_iter().map(Into::into).collect(),
+            reviews: reviews.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+#[derive(Serialize, Default)]
+struct ApiUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<&'static str>,
+}
+
+#[derive(Deserialize)]
+struct ApiRef {
+    #[serde(rename = "ref")]
+    name: String,
+    sha: String,
+}
+
+#[derive(Deserialize)]
+struct ApiPullRequest {
+    number: usize,
+    title: String,
+    body: Option<String>,
+    head: ApiRef,
+    base: ApiRef,
+    html_url: String,
+    state: String,
+    merged_at: Option<String>,
+    #[serde(default)]
+    draft: bool,
+}
+
+impl From<ApiPullRequest> for PullRequest {
+    fn from(pr: ApiPullRequest) -> Self {
+        let state = if pr.merged_at.is_some() {
+            PullRequestState::Merged
+        } else if pr.state == "open" {
+            PullRequestState::Open
+        } else {
+            PullRequestState::Closed
+        };
+        PullRequest {
+            number: pr.number,
+            title: pr.title,
+            body: pr.body,
+            head: pr.head.name,
+            head_sha: pr.head.sha,
+            base: pr.base.name,
+            url: pr.html_url,
+            state,
+            draft: pr.draft,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiCheckRuns {
+    check_runs: Vec<ApiCheckRun>,
+}
+
+#[derive(Deserialize)]
+struct ApiCheckRun {
+    name: String,
+    status: String,
+    conclusion: Option<String>,
+}
+
+impl From<ApiCheckRun> for Check {
+    fn from(check: ApiCheckRun) -> Self {
+        Check {
+            name: check.name,
+            status: match check.status.as_str() {
+                "completed" => CheckStatus::Completed,
+                "in_progress" => CheckStatus::InProgress,
+                _ => CheckStatus::Queued,
+            },
+            conclusion: check.conclusion,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiUser {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct ApiReview {
+    user: Option<ApiUser>,
+    state: String,
+}
+
+impl From<ApiReview> for Review {
+    fn from(review: ApiReview) -> Self {
+        Review {
+            author: review.user.map(|user| user.login).unwrap_or_default(),
+            state: match review.state.as_str() {
+                "APPROVED" => ReviewState::Approved,
+                "CHANGES_REQUESTED" => ReviewState::ChangesRequested,
+                "DISMISSED" => ReviewState::Dismissed,
+                "PENDING" => ReviewState::Pending,
+                _ => ReviewState::Commented,
+            },
+        }
+    }
+}
@@ -1,2 +1,4 @@
 pub mod forge;
+pub mod github;
 pub mod review;
+pub mod stack;
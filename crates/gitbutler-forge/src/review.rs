@@ -90,13 +90,22 @@ pub fn get_review_template_functions(forge_name: &ForgeName) -> ReviewTemplateFu
             is_review_template: is_review_template_bitbucket,
             get_root: get_bitbucket_directory_path,
             is_valid_review_template_path: is_valid_review_template_path_bitbucket,
-            supported_template_directories: &[SupportedTemplateDirectory::ForgeRoot],
+            supported_template_directories: &[
+                SupportedTemplateDirectory::ForgeRoot,
+                SupportedTemplateDirectory::ProjectRoot,
+            ],
         },
         ForgeName::Azure => ReviewTemplateFunctions {
             is_review_template: is_review_template_azure,
             get_root: get_azure_directory_path,
             is_valid_review_template_path: is_valid_review_template_path_azure,
-            supported_template_directories: &[SupportedTemplateDirectory::ForgeRoot],
+            supported_template_directories: &[
+                SupportedTemplateDirectory::ForgeRoot,
+                SupportedTemplateDirectory::ProjectRoot,
+                SupportedTemplateDirectory::Custom(".vsts"),
+                SupportedTemplateDirectory::Custom("docs"),
+                SupportedTemplateDirectory::Custom("pull_request_template"),
+            ],
         },
     }
 }
@@ -126,48 +135,75 @@ fn is_valid_review_template_path_github(path: &path::Path) -> bool {
 }
 
 fn get_gitlab_directory_path(root_path: &path::Path) -> path::PathBuf {
-    // TODO: implement
-    root_path.to_path_buf()
+    let mut path = root_path.to_path_buf();
+    path.push(".gitlab");
+    path
 }
 
-fn is_review_template_gitlab(_path_str: &str) -> bool {
-    // TODO: implement
-    false
+/// GitLab only offers the Markdown files directly inside `.gitlab/merge_request_templates`.
+fn is_review_template_gitlab(path_str: &str) -> bool {
+    let normalized_path = path_str.replace('\\', "/");
+    normalized_path
+        .strip_prefix(".gitlab/merge_request_templates/")
+        .is_some_and(|name| !name.contains('/') && name.ends_with(".md"))
 }
 
-fn is_valid_review_template_path_gitlab(_path: &path::Path) -> bool {
-    // TODO: implement
-    false
+fn is_valid_review_template_path_gitlab(path: &path::Path) -> bool {
+    is_review_template_gitlab(path.to_str().unwrap_or_default())
 }
 
 fn get_bitbucket_directory_path(root_path: &path::Path) -> path::PathBuf {
-    // TODO: implement
-    root_path.to_path_buf()
+    let mut path = root_path.to_path_buf();
+    path.push(".bitbucket");
+    path
 }
 
-fn is_review_template_bitbucket(_path_str: &str) -> bool {
-    // TODO: implement
-    false
+/// Bitbucket has no built-in templates, so we follow the GitHub naming conventions,
+/// either in the project root or in `.bitbucket`, where `.bitbucket/pull_request_templates` may hold several.
+fn is_review_template_bitbucket(path_str: &str) -> bool {
+    let normalized_path = path_str.replace('\\', "/");
+    let (in_forge_root, path) = match normalized_path.strip_prefix(".bitbucket/") {
+        Some(path) => (true, path),
+        None => (false, normalized_path.as_str()),
+    };
+    path.eq_ignore_ascii_case("pull_request_template.md")
+        || in_forge_root
+            && path
+                .strip_prefix("pull_request_templates/")
+                .or_else(|| path.strip_prefix("PULL_REQUEST_TEMPLATES/"))
+                .is_some_and(|name| !name.contains('/') && name.ends_with(".md"))
 }
 
-fn is_valid_review_template_path_bitbucket(_path: &path::Path) -> bool {
-    // TODO: implement
-    false
+fn is_valid_review_template_path_bitbucket(path: &path::Path) -> bool {
+    is_review_template_bitbucket(path.to_str().unwrap_or_default())
 }
 
 fn get_azure_directory_path(root_path: &path::Path) -> path::PathBuf {
-    // TODO: implement
-    root_path.to_path_buf()
+    let mut path = root_path.to_path_buf();
+    path.push(".azuredevops");
+    path
 }
 
-fn is_review_template_azure(_path_str: &str) -> bool {
-    // TODO: implement
-    false
+/// Azure DevOps looks for `pull_request_template.md` or `.txt` in `.azuredevops`, `.vsts`, `docs` or the project root,
+/// and for additional and branch-specific templates in a `pull_request_template` directory next to it.
+/// Names are matched case-insensitively.
+fn is_review_template_azure(path_str: &str) -> bool {
+    let normalized_path = path_str.replace('\\', "/").to_lowercase();
+    let is_template = |path: &str| {
+        let is_text = path.ends_with(".md") || path.ends_with(".txt");
+        path == "pull_request_template.md"
+            || path == "pull_request_template.txt"
+            || path.starts_with("pull_request_template/") && is_text
+    };
+    [".azuredevops/", ".vsts/", "docs/"]
+        .iter()
+        .filter_map(|dir| normalized_path.strip_prefix(dir))
+        .chain(Some(normalized_path.as_str()))
+        .any(is_template)
 }
 
-fn is_valid_review_template_path_azure(_path: &path::Path) -> bool {
-    // TODO: implement
-    false
+fn is_valid_review_template_path_azure(path: &path::Path) -> bool {
+    is_review_template_azure(path.to_str().unwrap_or_default())
 }
 
 #[cfg(test)]
@@ -234,4 +270,64 @@ mod tests {
             invalid_review_template_path,
         ));
     }
+
+    #[test]
+    fn test_is_valid_review_template_path_gitlab() {
+        assert!(is_valid_review_template_path_gitlab(Path::new(
+            ".gitlab/merge_request_templates/Default.md"
+        )));
+        assert!(is_valid_review_template_path_gitlab(Path::new(
+            ".gitlab\\merge_request_templates\\Bug.md"
+        )));
+        assert!(!is_valid_review_template_path_gitlab(Path::new(
+            ".gitlab/merge_request_templates/nested/Bug.md"
+        )));
+        assert!(!is_valid_review_template_path_gitlab(Path::new(
+            ".gitlab/issue_templates/Bug.md"
+        )));
+        assert!(!is_valid_review_template_path_gitlab(Path::new(
+            "merge_request_templates/Default.md"
+        )));
+    }
+
+    #[test]
+    fn test_is_valid_review_template_path_bitbucket() {
+        assert!(is_valid_review_template_path_bitbucket(Path::new(
+            "PULL_REQUEST_TEMPLATE.md"
+        )));
+        assert!(is_valid_review_template_path_bitbucket(Path::new(
+            ".bitbucket/pull_request_template.md"
+        )));
+        assert!(is_valid_review_template_path_bitbucket(Path::new(
+            ".bitbucket/pull_request_templates/feature.md"
+        )));
+        assert!(!is_valid_review_template_path_bitbucket(Path::new(
+            "pull_request_templates/feature.md"
+        )));
+        assert!(!is_valid_review_template_path_bitbucket(Path::new(
+            "README.md"
+        )));
+    }
+
+    #[test]
+    fn test_is_valid_review_template_path_azure() {
+        assert!(is_valid_review_template_path_azure(Path::new(
+            ".azuredevops/pull_request_template.md"
+        )));
+        assert!(is_valid_review_template_path_azure(Path::new(
+            ".vsts\\PULL_REQUEST_TEMPLATE.txt"
+        )));
+        assert!(is_valid_review_template_path_azure(Path::new(
+            "docs/pull_request_template/branches/main.md"
+        )));
+        assert!(is_valid_review_template_path_azure(Path::new(
+            "pull_request_template/feature.md"
+        )));
+        assert!(!is_valid_review_template_path_azure(Path::new(
+            "src/pull_request_template.md"
+        )));
+        assert!(!is_valid_review_template_path_azure(Path::new(
+            ".azuredevops/pull_request_template/image.png"
+        )));
+    }
 }
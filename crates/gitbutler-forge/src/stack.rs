@@ -0,0 +1,126 @@
+//! Pull requests for stacked branches, with one pull request per branch that targets the branch below it.
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::forge::{
+    CreatePullRequest, Forge, ForgeName, PullRequest, PullRequestState, UpdatePullRequest,
+};
+
+/// Marks the beginning of the stack navigation in the body of a pull request.
+pub const FOOTER_BOUNDARY_TOP: &str = "<!-- GitButler Footer Boundary Top -->";
+/// Marks the end of the stack navigation in the body of a pull request.
+pub const FOOTER_BOUNDARY_BOTTOM: &str = "<!-- GitButler Footer Boundary Bottom -->";
+
+/// A branch in a stack that should have a pull request.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StackedBranch {
+    pub name: String,
+    /// The title of the pull request if one is created.
+    pub title: String,
+    /// The body of the pull request if one is created.
+    pub body: String,
+    /// The number of the pull request that was previously created for the branch, if any.
+    pub pr_number: Option<usize>,
+}
+
+/// Create or retarget one pull request per branch in `branches`, and link them to each other.
+///
+/// `branches` are ordered from the bottom of the stack to its top. The bottom branch targets `target_branch`,
+/// and each other branch targets the branch below it. Pull requests are reused if they are known by number
+/// and still open, or if there is another open one for the branch. Otherwise, a new one is created,
+/// which is a draft if `draft` is `true`.
+/// If there is more than one branch, a navigation footer listing all pull requests of the stack is
+/// added to, or replaced in, the body of each pull request.
+///
+/// Return the pull requests in the order of `branches`, whose numbers should be stored with the branches.
+pub async fn sync_stack_pull_requests(
+    forge: &impl Forge,
+    target_branch: &str,
+    branches: &[StackedBranch],
+    draft: bool,
+) -> Result<Vec<PullRequest>> {
+    let mut prs: Vec<PullRequest> = Vec::with_capacity(branches.len());
+    for (index, branch) in branches.iter().enumerate() {
+        let base = index
+            .checked_sub(1)
+            .map_or(target_branch, |below| branches[below].name.as_str());
+        let known = match branch.pr_number {
+            Some(number) => Some(forge.pull_request(number).await?),
+            None => None,
+        };
+        // Closed and merged pull requests can't be reused, so the branch needs an open or a new one.
+        let existing = match known.filter(|pr| pr.state == PullRequestState::Open) {
+            Some(pr) => Some(pr),
+            None => forge.find_open_pull_request(&branch.name).await?,
+        };
+        let pr = match existing {
+            Some(pr) if pr.base == base => pr,
+            Some(pr) => forge.set_base(pr.number, base).await?,
+            None => {
+                forge
+                    .create_pull_request(CreatePullRequest {
+                        title: branch.title.clone(),
+                        body: branch.body.clone(),
+                        head: branch.name.clone(),
+                        base: base.to_owned(),
+                        draft,
+                    })
+                    .await?
+            }
+        };
+        prs.push(pr);
+    }
+
+    if prs.len() < 2 {
+        return Ok(prs);
+    }
+    // The footer lists the top of the stack first.
+    let pr_numbers: Vec<usize> = prs.iter().rev().map(|pr| pr.number).collect();
+    for pr in &mut prs {
+        let footer = stack_footer(forge.name(), &pr_numbers, pr.number);
+        let body = update_body(pr.body.as_deref().unwrap_or_default(), &footer);
+        if pr.body.as_deref() != Some(body.as_str()) {
+            *pr = forge
+                .update_pull_request(
+                    pr.number,
+                    UpdatePullRequest {
+                        body: Some(body),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+        }
+    }
+    Ok(prs)
+}
+
+/// Generate the navigation footer for the pull request with `current` among `pr_numbers`,
+/// which are ordered from the top of the stack to its bottom.
+pub fn stack_footer(forge: ForgeName, pr_numbers: &[usize], current: usize) -> String {
+    let len = pr_numbers.len();
+    let nth = len - pr_numbers.iter().position(|n| *n == current).unwrap_or(0);
+    let symbol = forge.pull_request_symbol();
+    let mut footer = format!(
+        "{FOOTER_BOUNDARY_TOP}\n---\nThis is **part {nth} of {len} in a stack** made with GitButler:\n"
+    );
+    for (index, number) in pr_numbers.iter().enumerate() {
+        let marker = if *number == current { "👈 " } else { "" };
+        footer.push_str(&format!(
+            "- <kbd>&nbsp;{}&nbsp;</kbd> {symbol}{number} {marker}\n",
+            len - index
+        ));
+    }
+    footer.push_str(FOOTER_BOUNDARY_BOTTOM);
+    footer
+}
+
+/// Return `body` with its existing stack footer replaced by `footer`, or with `footer` appended if it had none.
+pub fn update_body(body: &str, footer: &str) -> String {
+    let head = body.split(FOOTER_BOUNDARY_TOP).next().unwrap_or_default();
+    let tail = body
+        .split_once(FOOTER_BOUNDARY_BOTTOM)
+        .map(|(_, tail)| tail)
+        .unwrap_or_default();
+    format!("{}\n\n{footer}\n\n{}", head.trim(), tail.trim())
+}
@@ -0,0 +1,534 @@
+mod mock;
+
+mod detect {
+    use std::collections::BTreeMap;
+
+    use gitbutler_forge::{forge::ForgeName, github::owner_and_repo};
+    use gitbutler_url::Url;
+
+    fn detect(url: &str, forge_hosts: &[(&str, &str)]) -> Option<ForgeName> {
+        let url: Url = url.parse().unwrap();
+        let forge_hosts: BTreeMap<_, _> = forge_hosts
+            .iter()
+            .map(|(host, name)| (host.to_string(), name.to_string()))
+            .collect();
+        ForgeName::from_url(&url, &forge_hosts)
+    }
+
+    #[test]
+    fn public_forges() {
+        assert_eq!(
+            detect("git@github.com:gitbutlerapp/gitbutler.git", &[]),
+            Some(ForgeName::GitHub)
+        );
+        assert_eq!(
+            detect("https://gitlab.com/gitbutler/test.git", &[]),
+            Some(ForgeName::GitLab)
+        );
+        assert_eq!(
+            detect("https://bitbucket.org/gitbutler/test.git", &[]),
+            Some(ForgeName::Bitbucket)
+        );
+        assert_eq!(
+            detect("https://dev.azure.com/org/project/_git/repo", &[]),
+            Some(ForgeName::Azure)
+        );
+        assert_eq!(detect("https://git.example.com/test.git", &[]), None);
+    }
+
+    #[test]
+    fn self_hosted_forges_take_precedence() {
+        let hosts = [
+            ("git.example.com", "gitlab"),
+            ("gitlab.example.com", "github"),
+        ];
+        assert_eq!(
+            detect("git@git.example.com:gitbutler/test.git", &hosts),
+            Some(ForgeName::GitLab)
+        );
+        assert_eq!(
+            detect("https://gitlab.example.com/gitbutler/test.git", &hosts),
+            Some(ForgeName::GitHub)
+        );
+    }
+
+    #[test]
+    fn github_owner_and_repo() {
+        let parse = |url: &str| owner_and_repo(&url.parse().unwrap());
+        let expected = Some(("gitbutlerapp".to_owned(), "gitbutler".to_owned()));
+        assert_eq!(parse("git@github.com:gitbutlerapp/gitbutler.git"), expected);
+        assert_eq!(parse("https://github.com/gitbutlerapp/gitbutler"), expected);
+        assert_eq!(
+            parse("ssh://git@github.example.com:2222/gitbutlerapp/gitbutler.git/"),
+            expected
+        );
+        assert_eq!(parse("https://github.com/gitbutlerapp"), None);
+        assert_eq!(parse("https://gitlab.com/group/subgroup/repo.git"), None);
+    }
+
+    #[test]
+    fn unknown_forge_names_are_ignored() {
+        assert_eq!(
+            detect(
+                "https://gitlab.example.com/test.git",
+                &[("gitlab.example.com", "gitea")]
+            ),
+            Some(ForgeName::GitLab)
+        );
+    }
+}
+
+mod github {
+    use gitbutler_forge::{
+        forge::{
+            CheckStatus, CreatePullRequest, Forge, PullRequestState, ReviewState, UpdatePullRequest,
+        },
+        github::GitHub,
+    };
+    use serde_json::json;
+
+    use crate::mock::{github_pr, Server};
+
+    fn forge(server: &Server) -> GitHub {
+        GitHub::new("owner", "repo", "secret").with_api_url(&server.url)
+    }
+
+    #[tokio::test]
+    async fn create_pull_request() -> anyhow::Result<()> {
+        let server = Server::start(|_| (201, github_pr(7, "feature", "main", "body")));
+        let pr = forge(&server)
+            .create_pull_request(CreatePullRequest {
+                title: "title".into(),
+                body: "body".into(),
+                head: "feature".into(),
+                base: "main".into(),
+                draft: true,
+            })
+            .await?;
+        assert_eq!(pr.number, 7);
+        assert_eq!(pr.head, "feature");
+        assert_eq!(pr.base, "main");
+        assert_eq!(pr.state, PullRequestState::Open);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/repos/owner/repo/pulls");
+        assert_eq!(request.header("authorization"), Some("Bearer secret"));
+        assert_eq!(
+            request.header("accept"),
+            Some("application/vnd.github+json")
+        );
+        assert_eq!(
+            request.body,
+            Some(json!({
+                "title": "title",
+                "body": "body",
+                "head": "feature",
+                "base": "main",
+                "draft": true,
+            }))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_only_sends_changed_fields() -> anyhow::Result<()> {
+        let server = Server::start(|_| (200, github_pr(7, "feature", "develop", "")));
+        let pr = forge(&server).set_base(7, "develop").await?;
+        assert_eq!(pr.base, "develop");
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "PATCH");
+        assert_eq!(requests[0].path, "/repos/owner/repo/pulls/7");
+        assert_eq!(requests[0].body, Some(json!({ "base": "develop" })));
+
+        forge(&server)
+            .update_pull_request(
+                7,
+                UpdatePullRequest {
+                    title: Some("new title".into()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert_eq!(
+            server.requests()[1].body,
+            Some(json!({ "title": "new title" }))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn close_pull_request() -> anyhow::Result<()> {
+        let server = Server::start(|_| {
+            let mut pr = github_pr(7, "feature", "main", "");
+            pr["state"] = "closed".into();
+            (200, pr)
+        });
+        let pr = forge(&server).close_pull_request(7).await?;
+        assert_eq!(pr.state, PullRequestState::Closed);
+        assert_eq!(
+            server.requests()[0].body,
+            Some(json!({ "state": "closed" }))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn merged_pull_requests_are_closed_with_merge_time() -> anyhow::Result<()> {
+        let server = Server::start(|_| {
+            let mut pr = github_pr(7, "feature", "main", "");
+            pr["state"] = "closed".into();
+            pr["merged_at"] = "2025-07-28T09:15:30Z".into();
+            (200, pr)
+        });
+        let pr = forge(&server).pull_request(7).await?;
+        assert_eq!(pr.state, PullRequestState::Merged);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn find_open_pull_request_by_head() -> anyhow::Result<()> {
+        let server = Server::start(|request| {
+            if request.path.contains("head=owner%3Afeature") {
+                (200, json!([github_pr(7, "feature", "main", "")]))
+            } else {
+                (200, json!([]))
+            }
+        });
+        let pr = forge(&server).find_open_pull_request("feature").await?;
+        assert_eq!(pr.map(|pr| pr.number), Some(7));
+        assert_eq!(forge(&server).find_open_pull_request("other").await?, None);
+        assert!(server.requests()[0].path.contains("state=open"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pull_request_status_includes_checks_and_reviews() -> anyhow::Result<()> {
+        let server = Server::start(|request| match request.path.as_str() {
+            "/repos/owner/repo/pulls/7" => (200, github_pr(7, "feature", "main", "")),
+            "/repos/owner/repo/commits/0000000000000000000000000000000000000007/check-runs" => (
+                200,
+                json!({
+                    "total_count": 2,
+                    "check_runs": [
+                        { "name": "test", "status": "completed", "conclusion": "failure" },
+                        { "name": "lint", "status": "in_progress", "conclusion": null },
+                    ]
+                }),
+            ),
+            "/repos/owner/repo/pulls/7/reviews" => (
+                200,
+                json!([
+                    { "user": { "login": "reviewer" }, "state": "APPROVED" },
+                    { "user": null, "state": "COMMENTED" },
+                ]),
+            ),
+            _ => (404, json!({ "message": "Not Found" })),
+        });
+        let status = forge(&server).pull_request_status(7).await?;
+        assert_eq!(status.pull_request.number, 7);
+        assert_eq!(
+            status
+                .checks
+                .iter()
+                .map(|check| (
+                    check.name.as_str(),
+                    check.status,
+                    check.conclusion.as_deref()
+                ))
+                .collect::<Vec<_>>(),
+            [
+                ("test", CheckStatus::Completed, Some("failure")),
+                ("lint", CheckStatus::InProgress, None),
+            ]
+        );
+        assert_eq!(
+            status
+                .reviews
+                .iter()
+                .map(|review| (review.author.as_str(), review.state))
+                .collect::<Vec<_>>(),
+            [
+                ("reviewer", ReviewState::Approved),
+                ("", ReviewState::Commented)
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn errors_contain_the_message_of_the_api() {
+        let server = Server::start(|_| (422, json!({ "message": "Validation Failed" })));
+        let err = forge(&server).pull_request(7).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "GitHub responded with 422 Unprocessable Entity: Validation Failed"
+        );
+    }
+}
+
+mod stack {
+    use std::{
+        collections::BTreeMap,
+        sync::{Arc, Mutex},
+    };
+
+    use gitbutler_forge::{
+        forge::ForgeName,
+        github::GitHub,
+        stack::{stack_footer, sync_stack_pull_requests, update_body, StackedBranch},
+    };
+    use serde_json::{json, Value};
+
+    use crate::mock::{github_pr, Server};
+
+    /// A server that keeps track of pull requests like GitHub does, starting with `prs`.
+    fn github_server(prs: impl IntoIterator<Item = Value>) -> Server {
+        let prs: Arc<Mutex<BTreeMap<usize, Value>>> = Arc::new(Mutex::new(
+            prs.into_iter()
+                .map(|pr| (pr["number"].as_u64().unwrap() as usize, pr))
+                .collect(),
+        ));
+        Server::start(move |request| {
+            let mut prs = prs.lock().unwrap();
+            let path = request.path.trim_start_matches("/repos/owner/repo/pulls");
+            match (request.method.as_str(), path) {
+                ("POST", "") => {
+                    let body = request.body.as_ref().unwrap();
+                    let number = prs.last_key_value().map_or(0, |(number, _)| *number) + 1;
+                    let pr = github_pr(
+                        number,
+                        body["head"].as_str().unwrap(),
+                        body["base"].as_str().unwrap(),
+                        body["body"].as_str().unwrap(),
+                    );
+                    prs.insert(number, pr.clone());
+                    (201, pr)
+                }
+                ("GET", query) if query.starts_with('?') => {
+                    let open: Vec<_> = prs
+                        .values()
+                        .filter(|pr| {
+                            let head =
+                                format!("head=owner%3A{}&", pr["head"]["ref"].as_str().unwrap());
+                            query.contains(&head) && pr["state"] == "open"
+                        })
+                        .cloned()
+                        .collect();
+                    (200, json!(open))
+                }
+                (method, number) => {
+                    let number: usize = number.trim_start_matches('/').parse().unwrap();
+                    let Some(pr) = prs.get_mut(&number) else {
+                        return (404, json!({ "message": "Not Found" }));
+                    };
+                    if method == "PATCH" {
+                        let update = request.body.as_ref().unwrap();
+                        if let Some(base) = update.get("base") {
+                            pr["base"]["ref"] = base.clone();
+                        }
+                        if let Some(body) = update.get("body") {
+                            pr["body"] = body.clone();
+                        }
+                    }
+                    (200, pr.clone())
+                }
+            }
+        })
+    }
+
+    fn branch(name: &str, pr_number: Option<usize>) -> StackedBranch {
+        StackedBranch {
+            name: name.into(),
+            title: format!("Title of {name}"),
+            body: format!("Body of {name}"),
+            pr_number,
+        }
+    }
+
+    fn forge(server: &Server) -> GitHub {
+        GitHub::new("owner", "repo", "secret").with_api_url(&server.url)
+    }
+
+    #[tokio::test]
+    async fn creates_one_pull_request_per_branch_targeting_the_branch_below() -> anyhow::Result<()>
+    {
+        let server = github_server([]);
+        let prs = sync_stack_pull_requests(
+            &forge(&server),
+            "main",
+            &[branch("bottom", None), branch("top", None)],
+            false,
+        )
+        .await?;
+
+        assert_eq!(
+            prs.iter()
+                .map(|pr| (pr.number, pr.head.as_str(), pr.base.as_str()))
+                .collect::<Vec<_>>(),
+            [(1, "bottom", "main"), (2, "top", "bottom")]
+        );
+        assert_eq!(
+            prs[0].body.as_deref(),
+            Some(concat!(
+                "Body of bottom\n\n",
+                "<!-- GitButler Footer Boundary Top -->\n",
+                "---\n",
+                "This is **part 1 of 2 in a stack** made with GitButler:\n",
+                "- <kbd>&nbsp;2&nbsp;</kbd> #2 \n",
+                "- <kbd>&nbsp;1&nbsp;</kbd> #1 👈 \n",
+                "<!-- GitButler Footer Boundary Bottom -->\n\n",
+            ))
+        );
+        assert_eq!(
+            prs[1].body.as_deref(),
+            Some(concat!(
+                "Body of top\n\n",
+                "<!-- GitButler Footer Boundary Top -->\n",
+                "---\n",
+                "This is **part 2 of 2 in a stack** made with GitButler:\n",
+                "- <kbd>&nbsp;2&nbsp;</kbd> #2 👈 \n",
+                "- <kbd>&nbsp;1&nbsp;</kbd> #1 \n",
+                "<!-- GitButler Footer Boundary Bottom -->\n\n",
+            ))
+        );
+
+        let requests = server.requests();
+        assert_eq!(
+            requests
+                .iter()
+                .filter(|request| request.method == "POST")
+                .count(),
+            2
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn existing_pull_requests_are_retargeted() -> anyhow::Result<()> {
+        // The top branch was previously targeting `main` directly, and the bottom one already has a PR.
+        let server = github_server([
+            github_pr(1, "top", "main", "Body"),
+            github_pr(2, "bottom", "main", "Body"),
+        ]);
+        let prs = sync_stack_pull_requests(
+            &forge(&server),
+            "main",
+            &[branch("bottom", None), branch("top", Some(1))],
+            false,
+        )
+        .await?;
+
+        assert_eq!(
+            prs.iter()
+                .map(|pr| (pr.number, pr.head.as_str(), pr.base.as_str()))
+                .collect::<Vec<_>>(),
+            [(2, "bottom", "main"), (1, "top", "bottom")]
+        );
+        let requests = server.requests();
+        assert!(requests.iter().all(|request| request.method != "POST"));
+        assert!(requests.iter().any(|request| {
+            request.method == "PATCH"
+                && request.path.ends_with("/pulls/1")
+                && request.body == Some(json!({ "base": "bottom" }))
+        }));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn closed_or_merged_pull_requests_are_replaced() -> anyhow::Result<()> {
+        let mut closed = github_pr(1, "bottom", "main", "Body");
+        closed["state"] = "closed".into();
+        let mut merged = github_pr(2, "top", "bottom", "Body");
+        merged["state"] = "closed".into();
+        merged["merged_at"] = "2025-07-28T09:15:30Z".into();
+        let server = github_server([closed, merged]);
+        let prs = sync_stack_pull_requests(
+            &forge(&server),
+            "main",
+            &[branch("bottom", Some(1)), branch("top", Some(2))],
+            false,
+        )
+        .await?;
+
+        assert_eq!(
+            prs.iter()
+                .map(|pr| (pr.number, pr.head.as_str(), pr.base.as_str()))
+                .collect::<Vec<_>>(),
+            [(3, "bottom", "main"), (4, "top", "bottom")]
+        );
+        let requests = server.requests();
+        assert!(
+            requests
+                .iter()
+                .all(|request| !request.path.ends_with("/pulls/1") || request.method == "GET"),
+            "closed pull requests are left alone"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn open_pull_requests_are_preferred_over_closed_ones() -> anyhow::Result<()> {
+        let mut closed = github_pr(1, "only", "main", "Body");
+        closed["state"] = "closed".into();
+        let server = github_server([closed, github_pr(2, "only", "main", "Body")]);
+        let prs =
+            sync_stack_pull_requests(&forge(&server), "main", &[branch("only", Some(1))], false)
+                .await?;
+
+        assert_eq!(prs[0].number, 2);
+        assert!(server
+            .requests()
+            .iter()
+            .all(|request| request.method == "GET"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn synced_stacks_are_not_updated_again() -> anyhow::Result<()> {
+        let server = github_server([]);
+        let branches = [branch("bottom", None), branch("top", None)];
+        let prs = sync_stack_pull_requests(&forge(&server), "main", &branches, false).await?;
+        let requests_after_first_sync = server.requests().len();
+
+        let branches: Vec<_> = branches
+            .into_iter()
+            .zip(&prs)
+            .map(|(branch, pr)| StackedBranch {
+                pr_number: Some(pr.number),
+                ..branch
+            })
+            .collect();
+        let resynced = sync_stack_pull_requests(&forge(&server), "main", &branches, false).await?;
+        assert_eq!(resynced, prs);
+        assert!(server.requests()[requests_after_first_sync..]
+            .iter()
+            .all(|request| request.method == "GET"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn single_branches_get_no_footer() -> anyhow::Result<()> {
+        let server = github_server([]);
+        let prs = sync_stack_pull_requests(&forge(&server), "main", &[branch("only", None)], false)
+            .await?;
+        assert_eq!(prs[0].body.as_deref(), Some("Body of only"));
+        Ok(())
+    }
+
+    #[test]
+    fn footer_is_replaced_in_place() {
+        let old_footer = stack_footer(ForgeName::GitLab, &[3, 2], 2);
+        let body = format!("Description\n\n{old_footer}\n\nMore text");
+        let new_footer = stack_footer(ForgeName::GitLab, &[4, 3, 2], 2);
+        assert_eq!(
+            update_body(&body, &new_footer),
+            format!("Description\n\n{new_footer}\n\nMore text")
+        );
+        assert!(new_footer.contains("This is **part 1 of 3 in a stack**"));
+        assert!(new_footer.contains("- <kbd>&nbsp;3&nbsp;</kbd> !4 \n"));
+    }
+}
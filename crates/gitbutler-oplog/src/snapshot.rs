@@ -54,6 +54,12 @@ pub trait SnapshotExt {
         branch_name: String,
         perm: &mut WorktreeWritePermission,
     ) -> anyhow::Result<()>;
+    fn snapshot_stale_branches_deletion(
+        &self,
+        snapshot_tree: git2::Oid,
+        result: Result<&[String], &anyhow::Error>,
+        perm: &mut WorktreeWritePermission,
+    ) -> anyhow::Result<()>;
     fn snapshot_branch_update(
         &self,
         snapshot_tree: git2::Oid,
@@ -175,6 +181,27 @@ impl SnapshotExt for CommandContext {
         self.create_snapshot(details, perm)?;
         Ok(())
     }
+    fn snapshot_stale_branches_deletion(
+        &self,
+        snapshot_tree: git2::Oid,
+        result: Result<&[String], &anyhow::Error>,
+        perm: &mut WorktreeWritePermission,
+    ) -> anyhow::Result<()> {
+        let trailers = match result {
+            Ok([]) => return Ok(()),
+            Ok(branch_names) => branch_names
+                .iter()
+                .map(|name| Trailer {
+                    key: "name".to_string(),
+                    value: name.to_owned(),
+                })
+                .collect(),
+            Err(err) => result_trailer(Err(err), "name".to_string()),
+        };
+        let details = SnapshotDetails::new(OperationKind::DeleteBranch).with_trailers(trailers);
+        self.commit_snapshot(snapshot_tree, details, perm)?;
+        Ok(())
+    }
     fn snapshot_branch_update(
         &self,
         snapshot_tree: git2::Oid,
@@ -7,7 +7,9 @@ mod storage;
 use std::path::Path;
 
 use controller::Controller;
-pub use project::{ApiProject, AuthKey, CodePushState, FetchResult, Project, ProjectId};
+pub use project::{
+    ApiProject, AuthKey, CodePushState, FetchResult, FileMonitorBackend, Project, ProjectId,
+};
 pub use storage::UpdateRequest;
 
 /// A utility to be used from applications to optimize `git2` configuration.
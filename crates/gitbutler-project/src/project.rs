@@ -3,6 +3,7 @@ use gitbutler_id::id::Id;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::{
+    collections::BTreeMap,
     path::{self, PathBuf},
     time,
 };
@@ -22,6 +23,20 @@ pub enum AuthKey {
     SystemExecutable,
 }
 
+/// The way changes to files in the worktree of a project are detected.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FileMonitorBackend {
+    /// Recursively watch the worktree and the git-dir for changes.
+    #[default]
+    Notify,
+    /// Query a running Watchman service for paths that changed since the last query.
+    Watchman,
+    /// Query Git's `core.fsmonitor` for paths that changed since the last query, which can be
+    /// its built-in daemon or a hook.
+    GitFsmonitor,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ApiProject {
     pub name: String,
@@ -100,6 +115,18 @@ pub struct Project {
     pub snapshot_lines_threshold: Option<usize>,
     #[serde(default)]
     pub forge_override: Option<String>,
+    /// Hosts of self-hosted forges, mapped to the name of the forge they run, like `gitlab`.
+    ///
+    /// These take precedence over the hosts of the public forges when detecting the forge of a remote.
+    #[serde(default)]
+    pub forge_hosts: BTreeMap<String, String>,
+    /// How to learn about changes to files in the worktree. Falls back to `Notify` if the chosen backend isn't available.
+    #[serde(default)]
+    pub file_monitor_backend: FileMonitorBackend,
+    /// If `true`, branch metadata like descriptions and pull request numbers is shared with others through the
+    /// `refs/gitbutler/meta` reference, which is pushed and fetched along with branches.
+    #[serde(default)]
+    pub share_metadata: bool,
 }
 
 /// Instantiation
@@ -1,9 +1,11 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::{ApiProject, AuthKey, CodePushState, FetchResult, Project, ProjectId};
+use crate::{
+    ApiProject, AuthKey, CodePushState, FetchResult, FileMonitorBackend, Project, ProjectId,
+};
 
 const PROJECTS_FILE: &str = "projects.json";
 
@@ -32,6 +34,9 @@ pub struct UpdateRequest {
     pub forge_override: Option<String>,
     #[serde(default = "default_false")]
     pub unset_forge_override: bool,
+    pub forge_hosts: Option<BTreeMap<String, String>>,
+    pub file_monitor_backend: Option<FileMonitorBackend>,
+    pub share_metadata: Option<bool>,
 }
 
 fn default_false() -> bool {
@@ -114,6 +119,10 @@ impl Storage {
             project.forge_override = None;
         }
 
+        if let Some(forge_hosts) = &update_request.forge_hosts {
+            project.forge_hosts = forge_hosts.clone();
+        }
+
         if let Some(preferred_key) = &update_request.preferred_key {
             project.preferred_key = preferred_key.clone();
         }
@@ -144,6 +153,14 @@ impl Storage {
             project.snapshot_lines_threshold = Some(snapshot_lines_threshold);
         }
 
+        if let Some(file_monitor_backend) = update_request.file_monitor_backend {
+            project.file_monitor_backend = file_monitor_backend;
+        }
+
+        if let Some(share_metadata) = update_request.share_metadata {
+            project.share_metadata = share_metadata;
+        }
+
         self.inner
             .write(PROJECTS_FILE, &serde_json::to_string_pretty(&projects)?)?;
 
@@ -5,6 +5,8 @@ use gitbutler_stack::StackId;
 use serde::Serialize;
 use tokio::sync::{oneshot, Mutex};
 
+mod terminal;
+
 static mut GLOBAL_ASKPASS_BROKER: Option<AskpassBroker> = None;
 
 /// Initialize the global askpass broker.
@@ -19,6 +21,19 @@ pub unsafe fn init(submit_prompt: impl Fn(PromptEvent<Context>) + Send + Sync +
     GLOBAL_ASKPASS_BROKER.replace(AskpassBroker::init(submit_prompt));
 }
 
+/// Initialize the global askpass broker to answer prompts on the terminal, for use in CLI processes.
+///
+/// Prompts are answered by the program in `GIT_ASKPASS` or `SSH_ASKPASS` if set, and are otherwise
+/// asked on the controlling terminal, hiding the input of passwords and passphrases.
+/// If there is no terminal, prompts remain unanswered so the operation fails with an authorization error.
+///
+/// # Safety
+/// The same rules as for [`init`] apply.
+#[allow(static_mut_refs)]
+pub unsafe fn init_terminal() {
+    GLOBAL_ASKPASS_BROKER.replace(AskpassBroker::terminal());
+}
+
 /// Get the global askpass broker.
 ///
 /// # Panics
@@ -47,8 +62,18 @@ pub enum Context {
 
 #[derive(Clone)]
 pub struct AskpassBroker {
-    pending_requests: Arc<Mutex<HashMap<Id<AskpassRequest>, AskpassRequest>>>,
-    submit_prompt_event: Arc<dyn Fn(PromptEvent<Context>) + Send + Sync>,
+    inner: Inner,
+}
+
+#[derive(Clone)]
+enum Inner {
+    /// Prompts are submitted as events, and answered with [`AskpassBroker::handle_response()`].
+    Events {
+        pending_requests: Arc<Mutex<HashMap<Id<AskpassRequest>, AskpassRequest>>>,
+        submit_prompt_event: Arc<dyn Fn(PromptEvent<Context>) + Send + Sync>,
+    },
+    /// Prompts are answered right away on the terminal.
+    Terminal,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -61,26 +86,55 @@ pub struct PromptEvent<C: Serialize + Clone> {
 impl AskpassBroker {
     pub fn init(submit_prompt: impl Fn(PromptEvent<Context>) + Send + Sync + 'static) -> Self {
         Self {
-            pending_requests: Arc::new(Mutex::new(HashMap::new())),
-            submit_prompt_event: Arc::new(submit_prompt),
+            inner: Inner::Events {
+                pending_requests: Arc::new(Mutex::new(HashMap::new())),
+                submit_prompt_event: Arc::new(submit_prompt),
+            },
+        }
+    }
+
+    /// Create a broker which answers prompts on the terminal, see [`init_terminal()`].
+    pub fn terminal() -> Self {
+        Self {
+            inner: Inner::Terminal,
         }
     }
 
     pub async fn submit_prompt(&self, prompt: String, context: Context) -> Option<String> {
-        let (sender, receiver) = oneshot::channel();
-        let id = Id::generate();
-        let request = AskpassRequest { sender };
-        self.pending_requests.lock().await.insert(id, request);
-        (self.submit_prompt_event)(PromptEvent {
-            id,
-            prompt,
-            context,
-        });
-        receiver.await.unwrap()
+        match &self.inner {
+            Inner::Events {
+                pending_requests,
+                submit_prompt_event,
+            } => {
+                let (sender, receiver) = oneshot::channel();
+                let id = Id::generate();
+                let request = AskpassRequest { sender };
+                pending_requests.lock().await.insert(id, request);
+                submit_prompt_event(PromptEvent {
+                    id,
+                    prompt,
+                    context,
+                });
+                receiver.await.unwrap()
+            }
+            Inner::Terminal => {
+                tokio::task::spawn_blocking(move || terminal::ask(&prompt, &context))
+                    .await
+                    .ok()
+                    .flatten()
+            }
+        }
     }
 
     pub async fn handle_response(&self, id: Id<AskpassRequest>, response: Option<String>) {
-        let mut pending_requests = self.pending_requests.lock().await;
+        let Inner::Events {
+            pending_requests, ..
+        } = &self.inner
+        else {
+            log::warn!("received response for askpass request {id}, but prompts are answered on the terminal");
+            return;
+        };
+        let mut pending_requests = pending_requests.lock().await;
         if let Some(request) = pending_requests.remove(&id) {
             let _ = request.sender.send(response);
         } else {
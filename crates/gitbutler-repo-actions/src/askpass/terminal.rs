@@ -0,0 +1,59 @@
+//! Answer prompts on the controlling terminal, for processes that have no user interface to forward them to.
+use gix::prompt::{Mode, Options};
+
+use super::Context;
+
+/// Ask for the answer to `prompt`, issued for `context`, by running the program in `GIT_ASKPASS` or
+/// `SSH_ASKPASS`, or by reading it from the terminal otherwise.
+/// Only usernames are echoed, everything else, like passwords and passphrases, is read with hidden input.
+///
+/// Return `None` if no answer could be obtained, for instance because no terminal is attached.
+pub(super) fn ask(prompt: &str, context: &Context) -> Option<String> {
+    let mode = if is_username_prompt(prompt) {
+        Mode::Visible
+    } else {
+        Mode::Hidden
+    };
+    let options = Options {
+        askpass: None,
+        mode,
+    }
+    .apply_environment(true, true, true);
+    match gix::prompt::ask(prompt, &options) {
+        Ok(answer) => Some(answer),
+        Err(err) => {
+            tracing::warn!(
+                ?err,
+                ?context,
+                "Could not prompt for credentials on the terminal"
+            );
+            None
+        }
+    }
+}
+
+/// Return `true` if `prompt` is the one Git issues for usernames, like `Username for 'https://host': `.
+/// Anything else may ask for a secret, even if it mentions a username, like `Password for 'https://username@host': `.
+fn is_username_prompt(prompt: &str) -> bool {
+    prompt.starts_with("Username for ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_username_prompt;
+
+    #[test]
+    fn only_username_prompts_are_echoed() {
+        assert!(is_username_prompt("Username for 'https://github.com': "));
+        for prompt in [
+            "Password for 'https://github.com': ",
+            "Password for 'https://username@github.com': ",
+            "Password for 'https://my-username@example.com': ",
+            "git@github.com's password: ",
+            "Enter passphrase for key '/home/username/.ssh/id_ed25519': ",
+            "Enter username and password: ",
+        ] {
+            assert!(!is_username_prompt(prompt), "{prompt:?} must not be echoed");
+        }
+    }
+}
@@ -1,13 +1,14 @@
 use std::str::FromStr;
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use but_graph::RefsMetadata;
 use gitbutler_command_context::CommandContext;
 use gitbutler_commit::commit_headers::CommitHeadersV2;
 use gitbutler_error::error::Code;
-use gitbutler_oxidize::{ObjectIdExt, RepoExt};
+use gitbutler_oxidize::{ObjectIdExt, OidExt, RepoExt};
 use gitbutler_project::AuthKey;
 use gitbutler_reference::{Refname, RemoteRefname};
-use gitbutler_stack::{Stack, StackId};
+use gitbutler_stack::{Stack, StackId, VirtualBranchesHandle};
 
 use crate::askpass;
 use gitbutler_repo::{
@@ -162,6 +163,7 @@ impl RepoActionsExt for CommandContext {
         refspec: Option<String>,
         askpass_broker: Option<Option<StackId>>,
     ) -> Result<()> {
+        ensure_lfs_objects_exist_locally(self, head, branch.remote())?;
         let refspec = refspec.unwrap_or_else(|| {
             if with_force {
                 format!("+{}:refs/heads/{}", head, branch.branch())
@@ -169,154 +171,274 @@ impl RepoActionsExt for CommandContext {
                 format!("{}:refs/heads/{}", head, branch.branch())
             }
         });
+        push_refspec(self, branch.remote(), refspec, with_force, askpass_broker)?;
+        tracing::info!(
+            project_id = %self.project().id,
+            remote = %branch.remote(),
+            %head,
+            branch = branch.branch(),
+            "pushed git branch"
+        );
 
-        // NOTE(qix-): This is a nasty hack, however the codebase isn't structured
-        // NOTE(qix-): in a way that allows us to really incorporate new backends
-        // NOTE(qix-): without a lot of work. This is a temporary measure to
-        // NOTE(qix-): work around a time-sensitive change that was necessary
-        // NOTE(qix-): without having to refactor a large portion of the codebase.
-        if self.project().preferred_key == AuthKey::SystemExecutable {
-            let path = self.project().worktree_path();
-            let remote = branch.remote().to_string();
-            return std::thread::spawn(move || {
-                tokio::runtime::Runtime::new()
-                    .unwrap()
-                    .block_on(gitbutler_git::push(
-                        path,
-                        gitbutler_git::tokio::TokioExecutor,
-                        &remote,
-                        gitbutler_git::RefSpec::parse(refspec).unwrap(),
-                        with_force,
-                        handle_git_prompt_push,
-                        askpass_broker,
-                    ))
-            })
-            .join()
-            .unwrap()
-            .map_err(Into::into);
+        if self.project().share_metadata {
+            if let Err(err) = push_shared_metadata(self, branch.remote(), askpass_broker) {
+                tracing::warn!(project_id = %self.project().id, ?err, "failed to push shared metadata");
+            }
+        }
+        Ok(())
+    }
+
+    fn fetch(&self, remote_name: &str, askpass: Option<String>) -> Result<()> {
+        let refspec = format!("+refs/heads/*:refs/remotes/{}/*", remote_name);
+        fetch_refspec(self, remote_name, refspec, askpass.clone())?;
+
+        if self.project().share_metadata {
+            if let Err(err) = fetch_shared_metadata(self, remote_name, askpass) {
+                tracing::warn!(project_id = %self.project().id, ?err, "failed to fetch shared metadata");
+            }
         }
+        Ok(())
+    }
+}
 
-        let auth_flows = credentials::help(self, branch.remote())?;
-        for (mut remote, callbacks) in auth_flows {
-            let mut update_refs_error: Option<git2::Error> = None;
-            for callback in callbacks {
-                let mut cbs: git2::RemoteCallbacks = callback.into();
-                if self.project().omit_certificate_check.unwrap_or(false) {
-                    cbs.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
+/// Push `refspec` to the remote named `remote_name`.
+fn push_refspec(
+    ctx: &CommandContext,
+    remote_name: &str,
+    refspec: String,
+    with_force: bool,
+    askpass_broker: Option<Option<StackId>>,
+) -> Result<()> {
+    // NOTE(qix-): This is a nasty hack, however the codebase isn't structured
+    // NOTE(qix-): in a way that allows us to really incorporate new backends
+    // NOTE(qix-): without a lot of work. This is a temporary measure to
+    // NOTE(qix-): work around a time-sensitive change that was necessary
+    // NOTE(qix-): without having to refactor a large portion of the codebase.
+    if ctx.project().preferred_key == AuthKey::SystemExecutable {
+        let path = ctx.project().worktree_path();
+        let remote = remote_name.to_string();
+        return std::thread::spawn(move || {
+            tokio::runtime::Runtime::new()
+                .unwrap()
+                .block_on(gitbutler_git::push(
+                    path,
+                    gitbutler_git::tokio::TokioExecutor,
+                    &remote,
+                    gitbutler_git::RefSpec::parse(refspec).unwrap(),
+                    with_force,
+                    handle_git_prompt_push,
+                    askpass_broker,
+                ))
+        })
+        .join()
+        .unwrap()
+        .map_err(Into::into);
+    }
+
+    let auth_flows = credentials::help(ctx, remote_name)?;
+    for (mut remote, callbacks) in auth_flows {
+        let mut update_refs_error: Option<git2::Error> = None;
+        for callback in callbacks {
+            let mut cbs: git2::RemoteCallbacks = callback.into();
+            if ctx.project().omit_certificate_check.unwrap_or(false) {
+                cbs.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
+            }
+            cbs.push_update_reference(|_reference: &str, status: Option<&str>| {
+                if let Some(status) = status {
+                    update_refs_error = Some(git2::Error::from_str(status));
+                    return Err(git2::Error::from_str(status));
+                };
+                Ok(())
+            });
+
+            let push_result = remote.push(
+                &[refspec.as_str()],
+                Some(&mut git2::PushOptions::new().remote_callbacks(cbs)),
+            );
+            match push_result {
+                Ok(()) => {
+                    tracing::info!(project_id = %ctx.project().id, remote = %remote_name, %refspec, "git pushed");
+                    return Ok(());
                 }
-                cbs.push_update_reference(|_reference: &str, status: Option<&str>| {
-                    if let Some(status) = status {
-                        update_refs_error = Some(git2::Error::from_str(status));
-                        return Err(git2::Error::from_str(status));
-                    };
-                    Ok(())
-                });
-
-                let push_result = remote.push(
-                    &[refspec.as_str()],
-                    Some(&mut git2::PushOptions::new().remote_callbacks(cbs)),
-                );
-                match push_result {
-                    Ok(()) => {
-                        tracing::info!(
-                            project_id = %self.project().id,
-                            remote = %branch.remote(),
-                            %head,
-                            branch = branch.branch(),
-                            "pushed git branch"
-                        );
-                        return Ok(());
+                Err(err) => match err.class() {
+                    git2::ErrorClass::Net | git2::ErrorClass::Http => {
+                        tracing::warn!(project_id = %ctx.project().id, ?err, "push failed due to network");
+                        continue;
                     }
-                    Err(err) => match err.class() {
-                        git2::ErrorClass::Net | git2::ErrorClass::Http => {
-                            tracing::warn!(project_id = %self.project().id, ?err, "push failed due to network");
+                    _ => match err.code() {
+                        git2::ErrorCode::Auth => {
+                            tracing::warn!(project_id = %ctx.project().id, ?err, "push failed due to auth");
                             continue;
                         }
-                        _ => match err.code() {
-                            git2::ErrorCode::Auth => {
-                                tracing::warn!(project_id = %self.project().id, ?err, "push failed due to auth");
-                                continue;
+                        _ => {
+                            if let Some(update_refs_err) = update_refs_error {
+                                return Err(update_refs_err).context(err);
                             }
-                            _ => {
-                                if let Some(update_refs_err) = update_refs_error {
-                                    return Err(update_refs_err).context(err);
-                                }
-                                return Err(err.into());
-                            }
-                        },
+                            return Err(err.into());
+                        }
                     },
-                }
+                },
             }
         }
-
-        Err(anyhow!("authentication failed").context(Code::ProjectGitAuth))
     }
 
-    fn fetch(&self, remote_name: &str, askpass: Option<String>) -> Result<()> {
-        let refspec = format!("+refs/heads/*:refs/remotes/{}/*", remote_name);
+    Err(anyhow!("authentication failed").context(Code::ProjectGitAuth))
+}
 
-        // NOTE(qix-): This is a nasty hack, however the codebase isn't structured
-        // NOTE(qix-): in a way that allows us to really incorporate new backends
-        // NOTE(qix-): without a lot of work. This is a temporary measure to
-        // NOTE(qix-): work around a time-sensitive change that was necessary
-        // NOTE(qix-): without having to refactor a large portion of the codebase.
-        if self.project().preferred_key == AuthKey::SystemExecutable {
-            let path = self.project().worktree_path();
-            let remote = remote_name.to_string();
-            return std::thread::spawn(move || {
-                tokio::runtime::Runtime::new()
-                    .unwrap()
-                    .block_on(gitbutler_git::fetch(
-                        path,
-                        gitbutler_git::tokio::TokioExecutor,
-                        &remote,
-                        gitbutler_git::RefSpec::parse(refspec).unwrap(),
-                        handle_git_prompt_fetch,
-                        askpass,
-                    ))
-            })
-            .join()
-            .unwrap()
-            .map_err(Into::into);
-        }
+/// Fetch `refspec` from the remote named `remote_name`.
+fn fetch_refspec(
+    ctx: &CommandContext,
+    remote_name: &str,
+    refspec: String,
+    askpass: Option<String>,
+) -> Result<()> {
+    // NOTE(qix-): This is a nasty hack, however the codebase isn't structured
+    // NOTE(qix-): in a way that allows us to really incorporate new backends
+    // NOTE(qix-): without a lot of work. This is a temporary measure to
+    // NOTE(qix-): work around a time-sensitive change that was necessary
+    // NOTE(qix-): without having to refactor a large portion of the codebase.
+    if ctx.project().preferred_key == AuthKey::SystemExecutable {
+        let path = ctx.project().worktree_path();
+        let remote = remote_name.to_string();
+        return std::thread::spawn(move || {
+            tokio::runtime::Runtime::new()
+                .unwrap()
+                .block_on(gitbutler_git::fetch(
+                    path,
+                    gitbutler_git::tokio::TokioExecutor,
+                    &remote,
+                    gitbutler_git::RefSpec::parse(refspec).unwrap(),
+                    handle_git_prompt_fetch,
+                    askpass,
+                ))
+        })
+        .join()
+        .unwrap()
+        .map_err(Into::into);
+    }
 
-        let auth_flows = credentials::help(self, remote_name)?;
-        for (mut remote, callbacks) in auth_flows {
-            for callback in callbacks {
-                let mut fetch_opts = git2::FetchOptions::new();
-                let mut cbs: git2::RemoteCallbacks = callback.into();
-                if self.project().omit_certificate_check.unwrap_or(false) {
-                    cbs.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
-                }
-                fetch_opts.remote_callbacks(cbs);
-                fetch_opts.prune(git2::FetchPrune::On);
+    let auth_flows = credentials::help(ctx, remote_name)?;
+    for (mut remote, callbacks) in auth_flows {
+        for callback in callbacks {
+            let mut fetch_opts = git2::FetchOptions::new();
+            let mut cbs: git2::RemoteCallbacks = callback.into();
+            if ctx.project().omit_certificate_check.unwrap_or(false) {
+                cbs.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
+            }
+            fetch_opts.remote_callbacks(cbs);
+            fetch_opts.prune(git2::FetchPrune::On);
 
-                match remote.fetch(&[&refspec], Some(&mut fetch_opts), None) {
-                    Ok(()) => {
-                        tracing::info!(project_id = %self.project().id, %refspec, "git fetched");
-                        return Ok(());
+            match remote.fetch(&[&refspec], Some(&mut fetch_opts), None) {
+                Ok(()) => {
+                    tracing::info!(project_id = %ctx.project().id, %refspec, "git fetched");
+                    return Ok(());
+                }
+                Err(err) => match err.class() {
+                    git2::ErrorClass::Net | git2::ErrorClass::Http => {
+                        tracing::warn!(project_id = %ctx.project().id, ?err, "fetch failed due to network");
+                        continue;
                     }
-                    Err(err) => match err.class() {
-                        git2::ErrorClass::Net | git2::ErrorClass::Http => {
-                            tracing::warn!(project_id = %self.project().id, ?err, "fetch failed due to network");
+                    _ => match err.code() {
+                        git2::ErrorCode::Auth => {
+                            tracing::warn!(project_id = %ctx.project().id, ?err, "fetch failed due to auth");
                             continue;
                         }
-                        _ => match err.code() {
-                            git2::ErrorCode::Auth => {
-                                tracing::warn!(project_id = %self.project().id, ?err, "fetch failed due to auth");
-                                continue;
-                            }
-                            _ => {
-                                return Err(err.into());
-                            }
-                        },
+                        _ => {
+                            return Err(err.into());
+                        }
                     },
-                }
+                },
             }
         }
+    }
 
-        Err(anyhow!("authentication failed")).context(Code::ProjectGitAuth)
+    Err(anyhow!("authentication failed")).context(Code::ProjectGitAuth)
+}
+
+/// Share the local branch metadata with others by pushing it to the remote named `remote`,
+/// see [`Project::share_metadata`](gitbutler_project::Project::share_metadata).
+fn push_shared_metadata(
+    ctx: &CommandContext,
+    remote: &str,
+    askpass_broker: Option<Option<StackId>>,
+) -> Result<()> {
+    let mut shared = RefsMetadata::from_repo(ctx.gix_repo()?)?;
+    shared.import(&ctx.meta()?)?;
+    push_refspec(
+        ctx,
+        remote,
+        RefsMetadata::push_refspec(),
+        false,
+        askpass_broker,
+    )
+}
+
+/// Fetch the branch metadata others shared on the remote named `remote_name`, and merge it with the local one,
+/// see [`Project::share_metadata`](gitbutler_project::Project::share_metadata).
+///
+/// Local changes take part in the merge, and only metadata of branches that exist locally is updated.
+/// This takes exclusive worktree access while changing the local metadata.
+fn fetch_shared_metadata(
+    ctx: &CommandContext,
+    remote_name: &str,
+    askpass: Option<String>,
+) -> Result<()> {
+    fetch_refspec(
+        ctx,
+        remote_name,
+        RefsMetadata::fetch_refspec(remote_name),
+        askpass,
+    )?;
+    let _guard = ctx.project().exclusive_worktree_access();
+    let mut shared = RefsMetadata::from_repo(ctx.gix_repo()?)?;
+    let mut local = ctx.meta()?;
+    shared.import(&local)?;
+    shared.merge_remote(remote_name)?;
+    shared.update_known_branches(&mut local)?;
+    Ok(())
+}
+
+/// Fail if the commits to push, those reachable from `head` but not from any remote tracking branch of `remote`
+/// or the target branch, refer to LFS objects that don't exist locally, as these couldn't be uploaded by the LFS pre-push hook.
+///
+/// The target branch bounds the commits to check even if nothing was fetched from `remote` yet, as its
+/// LFS objects were uploaded already.
+/// Nothing is checked if LFS isn't configured, as then there is no pre-push hook to upload anything either.
+fn ensure_lfs_objects_exist_locally(
+    ctx: &CommandContext,
+    head: git2::Oid,
+    remote: &str,
+) -> Result<()> {
+    let repo = ctx.gix_repo()?;
+    let config = repo.config_snapshot();
+    if config.string("filter.lfs.process").is_none() && config.string("filter.lfs.clean").is_none()
+    {
+        return Ok(());
+    }
+    let mut pushed_tips: Vec<_> = repo
+        .references()?
+        .prefixed(format!("refs/remotes/{remote}/").as_str())?
+        .filter_map(Result::ok)
+        .filter_map(|r| r.try_id().map(|id| id.detach()))
+        .collect();
+    if let Some(target) =
+        VirtualBranchesHandle::new(ctx.project().gb_dir()).maybe_get_default_target()?
+    {
+        pushed_tips.push(target.sha.to_gix());
+    }
+    let missing = but_core::lfs::missing_objects(&repo, Some(head.to_gix()), pushed_tips)?;
+    if missing.is_empty() {
+        return Ok(());
     }
+    let objects = missing
+        .iter()
+        .map(|(path, pointer)| format!("{path} ({oid})", oid = pointer.oid))
+        .collect::<Vec<_>>()
+        .join("\n");
+    bail!(
+        "Refusing to push as these LFS objects are missing locally and can't be uploaded:\n{objects}\n\n\
+         Run `git lfs fetch --all` to obtain them if they exist on the LFS server."
+    )
 }
 
 async fn handle_git_prompt_push(
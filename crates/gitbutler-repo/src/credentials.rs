@@ -1,4 +1,9 @@
-use std::{path::PathBuf, str::FromStr, vec};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    vec,
+};
 
 use anyhow::Context;
 use gitbutler_command_context::CommandContext;
@@ -7,6 +12,9 @@ use gitbutler_url::{ConvertError, Scheme, Url};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SshCredential {
+    /// Use the keys held by the SSH agent listening on `SSH_AUTH_SOCK`, which is what
+    /// hardware-backed keys and password managers provide.
+    SshAgent,
     Keyfile {
         key_path: PathBuf,
         passphrase: Option<String>,
@@ -31,6 +39,24 @@ impl From<Credential> for git2::RemoteCallbacks<'_> {
         let mut remote_callbacks = git2::RemoteCallbacks::new();
         match value {
             Credential::Noop => {}
+            Credential::Ssh(SshCredential::SshAgent) => {
+                // libgit2 asks again if the agent didn't have a suitable key, so only answer once
+                // to fall through to the next credential instead of looping forever.
+                let asked = AtomicBool::new(false);
+                remote_callbacks.credentials(move |url, username_from_url, allowed_types| {
+                    if !allowed_types.contains(git2::CredentialType::SSH_KEY)
+                        || asked.swap(true, Ordering::Relaxed)
+                    {
+                        return Err(git2::Error::new(
+                            git2::ErrorCode::Auth,
+                            git2::ErrorClass::Ssh,
+                            "ssh agent has no key accepted by the remote",
+                        ));
+                    }
+                    tracing::info!("authenticating with {url} using ssh agent");
+                    git2::Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"))
+                });
+            }
             Credential::Ssh(SshCredential::Keyfile {
                 key_path,
                 passphrase,
@@ -80,9 +106,25 @@ pub enum HelpError {
     Other(#[from] anyhow::Error),
 }
 
+/// Return the remotes to try along with the credentials to try with each of them, in order.
+///
+/// An SSH agent advertised by `SSH_AUTH_SOCK` is tried before the preferred private key.
 pub fn help<'a>(
     ctx: &'a CommandContext,
     remote_name: &str,
+) -> Result<Vec<(git2::Remote<'a>, Vec<Credential>)>, HelpError> {
+    help_with_ssh_agent(ctx, remote_name, ssh_agent::socket_from_env().as_deref())
+}
+
+/// Like [`help()`], but consider the SSH agent listening on `ssh_agent_socket` instead of the
+/// one in the environment, or none at all if it's `None`.
+///
+/// Note that libgit2 always connects to the agent in `SSH_AUTH_SOCK` when authenticating, so the
+/// socket is only used to check if the agent holds keys.
+pub fn help_with_ssh_agent<'a>(
+    ctx: &'a CommandContext,
+    remote_name: &str,
+    ssh_agent_socket: Option<&Path>,
 ) -> Result<Vec<(git2::Remote<'a>, Vec<Credential>)>, HelpError> {
     let remote = ctx.repo().find_remote(remote_name)?;
     let remote_url = Url::from_str(remote.url().ok_or(HelpError::NoUrlSet)?)
@@ -102,13 +144,15 @@ pub fn help<'a>(
                 ctx.repo().remote_anonymous(&ssh_url.to_string())
             }?;
 
-            Ok(vec![(
-                ssh_remote,
-                vec![Credential::Ssh(SshCredential::Keyfile {
-                    key_path: private_key_path.clone(),
-                    passphrase: None,
-                })],
-            )])
+            let mut flow = vec![];
+            if ssh_agent_socket.is_some_and(ssh_agent::has_identities) {
+                flow.push(Credential::Ssh(SshCredential::SshAgent));
+            }
+            flow.push(Credential::Ssh(SshCredential::Keyfile {
+                key_path: private_key_path.clone(),
+                passphrase: None,
+            }));
+            Ok(vec![(ssh_remote, flow)])
         }
         AuthKey::GitCredentialsHelper => {
             let https_remote = if remote_url.scheme == Scheme::Https {
@@ -144,3 +188,65 @@ fn https_flow(ctx: &CommandContext, remote_url: &Url) -> Result<Vec<HttpsCredent
 
     Ok(flow)
 }
+
+pub mod ssh_agent {
+    //! Learn about the SSH agent without authenticating against a remote.
+    use std::path::{Path, PathBuf};
+
+    /// Return the path to the socket of the SSH agent as advertised in `SSH_AUTH_SOCK`.
+    pub fn socket_from_env() -> Option<PathBuf> {
+        std::env::var_os("SSH_AUTH_SOCK")
+            .filter(|socket| !socket.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Return `true` if the agent listening on `socket` holds at least one key.
+    /// Failing to talk to the agent is logged and counts as it having no keys.
+    pub fn has_identities(socket: &Path) -> bool {
+        match count_identities(socket) {
+            Ok(count) => count > 0,
+            Err(err) => {
+                tracing::warn!(?err, socket = %socket.display(), "could not query ssh agent");
+                false
+            }
+        }
+    }
+
+    /// Ask the agent listening on `socket` for the amount of keys it holds.
+    #[cfg(unix)]
+    pub fn count_identities(socket: &Path) -> std::io::Result<u32> {
+        use std::{
+            io::{Error, ErrorKind, Read, Write},
+            os::unix::net::UnixStream,
+            time::Duration,
+        };
+
+        /// `SSH2_AGENTC_REQUEST_IDENTITIES` as per the agent protocol.
+        const REQUEST_IDENTITIES: u8 = 11;
+        /// `SSH2_AGENT_IDENTITIES_ANSWER` as per the agent protocol.
+        const IDENTITIES_ANSWER: u8 = 12;
+
+        let mut stream = UnixStream::connect(socket)?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        stream.write_all(&[0, 0, 0, 1, REQUEST_IDENTITIES])?;
+
+        let mut header = [0; 9];
+        stream.read_exact(&mut header[..5])?;
+        if header[4] != IDENTITIES_ANSWER {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unexpected ssh agent response of type {}", header[4]),
+            ));
+        }
+        stream.read_exact(&mut header[5..])?;
+        Ok(u32::from_be_bytes([
+            header[5], header[6], header[7], header[8],
+        ]))
+    }
+
+    /// Windows agents don't listen on a socket, so assume the agent holds keys if it's advertised.
+    #[cfg(not(unix))]
+    pub fn count_identities(_socket: &Path) -> std::io::Result<u32> {
+        Ok(1)
+    }
+}
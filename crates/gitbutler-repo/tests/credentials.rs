@@ -1,9 +1,12 @@
-use std::{path::PathBuf, str};
+use std::{
+    path::{Path, PathBuf},
+    str,
+};
 
 use but_settings::AppSettings;
 use gitbutler_command_context::CommandContext;
 use gitbutler_project as projects;
-use gitbutler_repo::credentials::{help, Credential, SshCredential};
+use gitbutler_repo::credentials::{help_with_ssh_agent, Credential, SshCredential};
 use gitbutler_testsupport::{temp_dir, test_repository};
 use gitbutler_user as users;
 
@@ -12,6 +15,7 @@ struct TestCase<'a> {
     remote_url: &'a str,
     with_github_login: bool,
     preferred_key: projects::AuthKey,
+    ssh_agent_socket: Option<&'a Path>,
 }
 
 impl TestCase<'_> {
@@ -37,7 +41,7 @@ impl TestCase<'_> {
         };
         let ctx = CommandContext::open(&project, AppSettings::default()).unwrap();
 
-        let flow = help(&ctx, "origin").unwrap();
+        let flow = help_with_ssh_agent(&ctx, "origin", self.ssh_agent_socket).unwrap();
         flow.into_iter()
             .map(|(remote, credentials)| (remote.url().as_ref().unwrap().to_string(), credentials))
             .collect::<Vec<_>>()
@@ -58,6 +62,7 @@ mod not_github {
                 preferred_key: projects::AuthKey::Local {
                     private_key_path: PathBuf::from("/tmp/id_rsa"),
                 },
+                ..Default::default()
             };
             let flow = test_case.run();
             assert_eq!(flow.len(), 1);
@@ -82,6 +87,7 @@ mod not_github {
                 preferred_key: projects::AuthKey::Local {
                     private_key_path: PathBuf::from("/tmp/id_rsa"),
                 },
+                ..Default::default()
             };
             let flow = test_case.run();
             assert_eq!(flow.len(), 1);
@@ -117,6 +123,7 @@ mod github {
                     preferred_key: projects::AuthKey::Local {
                         private_key_path: PathBuf::from("/tmp/id_rsa"),
                     },
+                    ..Default::default()
                 };
                 let flow = test_case.run();
                 assert_eq!(flow.len(), 1);
@@ -141,6 +148,7 @@ mod github {
                     preferred_key: projects::AuthKey::Local {
                         private_key_path: PathBuf::from("/tmp/id_rsa"),
                     },
+                    ..Default::default()
                 };
                 let flow = test_case.run();
                 assert_eq!(flow.len(), 1);
@@ -159,3 +167,132 @@ mod github {
         }
     }
 }
+
+#[cfg(unix)]
+mod ssh_agent {
+    use std::process::{Child, Command};
+
+    use gitbutler_repo::credentials::ssh_agent;
+
+    use super::*;
+
+    /// An `ssh-agent` listening on a socket in a temporary directory, killed on drop.
+    struct Agent {
+        child: Child,
+        dir: tempfile::TempDir,
+    }
+
+    impl Agent {
+        fn spawn() -> Self {
+            let dir = temp_dir();
+            let child = Command::new("ssh-agent")
+                .arg("-D")
+                .arg("-a")
+                .arg(dir.path().join("agent.sock"))
+                .stdout(std::process::Stdio::null())
+                .spawn()
+                .expect("ssh-agent is installed");
+            let agent = Agent { child, dir };
+            for _ in 0..100 {
+                if agent.socket().exists() {
+                    return agent;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            panic!("ssh-agent didn't create its socket in time");
+        }
+
+        fn socket(&self) -> PathBuf {
+            self.dir.path().join("agent.sock")
+        }
+
+        fn add_key(&self) {
+            let key = self.dir.path().join("id_ed25519");
+            let status = Command::new("ssh-keygen")
+                .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+                .arg(&key)
+                .status()
+                .unwrap();
+            assert!(status.success());
+            let status = Command::new("ssh-add")
+                .arg("-q")
+                .arg(&key)
+                .env("SSH_AUTH_SOCK", self.socket())
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+    }
+
+    impl Drop for Agent {
+        fn drop(&mut self) {
+            self.child.kill().ok();
+            self.child.wait().ok();
+        }
+    }
+
+    #[test]
+    fn agent_is_tried_before_key_file_once_it_holds_keys() {
+        let agent = Agent::spawn();
+        let socket = agent.socket();
+        assert_eq!(ssh_agent::count_identities(&socket).unwrap(), 0);
+
+        let test_case = TestCase {
+            remote_url: "https://github.com/gitbutlerapp/gitbutler.git",
+            preferred_key: projects::AuthKey::Local {
+                private_key_path: PathBuf::from("/tmp/id_rsa"),
+            },
+            ssh_agent_socket: Some(&socket),
+            ..Default::default()
+        };
+        let key_file = Credential::Ssh(SshCredential::Keyfile {
+            key_path: PathBuf::from("/tmp/id_rsa"),
+            passphrase: None,
+        });
+        let flow = test_case.run();
+        assert_eq!(flow.len(), 1);
+        assert_eq!(
+            flow[0].1,
+            vec![key_file.clone()],
+            "an agent without keys isn't worth asking"
+        );
+
+        agent.add_key();
+        assert_eq!(ssh_agent::count_identities(&socket).unwrap(), 1);
+
+        let flow = test_case.run();
+        assert_eq!(flow.len(), 1);
+        assert_eq!(
+            flow[0].0,
+            "git@github.com:gitbutlerapp/gitbutler.git".to_string(),
+        );
+        assert_eq!(
+            flow[0].1,
+            vec![Credential::Ssh(SshCredential::SshAgent), key_file]
+        );
+    }
+
+    #[test]
+    fn unreachable_agent_is_skipped() {
+        let dir = temp_dir();
+        let socket = dir.path().join("missing.sock");
+        assert!(ssh_agent::count_identities(&socket).is_err());
+
+        let flow = TestCase {
+            remote_url: "git@github.com:gitbutlerapp/gitbutler.git",
+            preferred_key: projects::AuthKey::Local {
+                private_key_path: PathBuf::from("/tmp/id_rsa"),
+            },
+            ssh_agent_socket: Some(&socket),
+            ..Default::default()
+        }
+        .run();
+        assert_eq!(
+            flow[0].1,
+            vec![Credential::Ssh(SshCredential::Keyfile {
+                key_path: PathBuf::from("/tmp/id_rsa"),
+                passphrase: None,
+            })]
+        );
+    }
+}
@@ -1,3 +1,7 @@
+use std::sync::Arc;
+
+use crate::approval::TauriApprover;
+use crate::emit::TauriEmitter;
 use crate::error::Error;
 use but_action::OpenAiProvider;
 use but_core::ui::TreeChange;
@@ -67,7 +71,7 @@ pub fn auto_commit(
     let ctx = &mut CommandContext::open(&project, settings.get()?.clone())?;
     let openai = OpenAiProvider::with(Some(but_action::CredentialsKind::GitButlerProxied));
     match openai {
-        Some(openai) => but_action::auto_commit(&app_handle, ctx, &openai, changes).map_err(|e| Error::from(anyhow::anyhow!(e))),
+        Some(openai) => but_action::auto_commit(&TauriEmitter(app_handle), ctx, &openai, changes).map_err(|e| Error::from(anyhow::anyhow!(e))),
         None => {
             Err(Error::from(anyhow::anyhow!(
                 "No valid credentials found for AI provider. Please configure your GitButler account credentials."
@@ -90,7 +94,7 @@ pub fn auto_branch_changes(
     let ctx = &mut CommandContext::open(&project, settings.get()?.clone())?;
     let openai = OpenAiProvider::with(Some(but_action::CredentialsKind::GitButlerProxied));
     match openai {
-        Some(openai) => but_action::branch_changes(&app_handle, ctx, &openai, changes).map_err(|e| Error::from(anyhow::anyhow!(e))),
+        Some(openai) => but_action::branch_changes(&TauriEmitter(app_handle), ctx, &openai, changes).map_err(|e| Error::from(anyhow::anyhow!(e))),
         None => {
             Err(Error::from(anyhow::anyhow!(
                 "No valid credentials found for AI provider. Please configure your GitButler account credentials."
@@ -113,7 +117,7 @@ pub fn absorb(
     let ctx = &mut CommandContext::open(&project, settings.get()?.clone())?;
     let openai = OpenAiProvider::with(Some(but_action::CredentialsKind::GitButlerProxied));
     match openai {
-        Some(openai) => but_action::absorb(&app_handle, ctx, &openai, changes).map_err(|e| Error::from(anyhow::anyhow!(e))),
+        Some(openai) => but_action::absorb(&TauriEmitter(app_handle), ctx, &openai, changes).map_err(|e| Error::from(anyhow::anyhow!(e))),
         None => {
             Err(Error::from(anyhow::anyhow!(
                 "No valid credentials found for AI provider. Please configure your GitButler account credentials."
@@ -136,7 +140,14 @@ pub fn freestyle(
     let ctx = &mut CommandContext::open(&project, settings.get()?.clone())?;
     let openai = OpenAiProvider::with(Some(but_action::CredentialsKind::GitButlerProxied));
     match openai {
-        Some(openai) => but_action::freestyle(project_id, message_id, &app_handle, ctx, &openai, chat_messages, model).map_err(|e| Error::from(anyhow::anyhow!(e))),
+        Some(openai) => {
+            let approver = TauriApprover {
+                app_handle: app_handle.clone(),
+                project_id,
+                message_id: message_id.clone(),
+            };
+            but_action::freestyle(project_id, message_id, Arc::new(TauriEmitter(app_handle)), &approver, ctx, &openai, chat_messages, model).map_err(|e| Error::from(anyhow::anyhow!(e)))
+        }
         None => {
             Err(Error::from(anyhow::anyhow!(
                 "No valid credentials found for AI provider. Please configure your GitButler account credentials."
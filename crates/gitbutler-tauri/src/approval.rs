@@ -0,0 +1,82 @@
+//! Let users approve the tool calls of agents which need approval according to the policy of the project.
+use std::{
+    collections::HashMap,
+    sync::{mpsc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Context;
+use but_tools::policy::Approver;
+use gitbutler_project::ProjectId;
+use tauri::{Emitter, Manager};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::error::Error;
+
+/// How long to wait for the user to respond before the tool call is denied.
+const TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// The approvals which are waiting for the user to respond, by the id of their request.
+#[derive(Default)]
+pub struct ToolApprovals {
+    pending: Mutex<HashMap<Uuid, mpsc::Sender<bool>>>,
+}
+
+/// An [`Approver`] which asks the user in the frontend, and waits for their response.
+pub struct TauriApprover {
+    pub app_handle: tauri::AppHandle,
+    pub project_id: ProjectId,
+    /// The message which made the agent call the tool.
+    pub message_id: String,
+}
+
+impl Approver for TauriApprover {
+    fn approve(&self, name: &str, parameters: &serde_json::Value) -> bool {
+        let approvals = self.app_handle.state::<ToolApprovals>();
+        let request_id = Uuid::new_v4();
+        let (tx, rx) = mpsc::channel();
+        let Ok(mut pending) = approvals.pending.lock() else {
+            return false;
+        };
+        pending.insert(request_id, tx);
+        drop(pending);
+
+        let event = format!("project://{}/tool-approval", self.project_id);
+        let payload = serde_json::json!({
+            "requestId": request_id,
+            "messageId": self.message_id,
+            "name": name,
+            "parameters": parameters.to_string(),
+        });
+        let approved = match self.app_handle.emit(&event, payload) {
+            Ok(()) => rx.recv_timeout(TIMEOUT).unwrap_or(false),
+            Err(err) => {
+                tracing::warn!("Failed to ask for tool approval: {err}");
+                false
+            }
+        };
+        if let Ok(mut pending) = approvals.pending.lock() {
+            pending.remove(&request_id);
+        }
+        approved
+    }
+}
+
+#[tauri::command(async)]
+#[instrument(skip(approvals), err(Debug))]
+pub fn respond_to_tool_approval(
+    approvals: tauri::State<'_, ToolApprovals>,
+    request_id: Uuid,
+    approved: bool,
+) -> Result<(), Error> {
+    let response = approvals
+        .pending
+        .lock()
+        .map_err(|e| anyhow::anyhow!("{e}"))?
+        .remove(&request_id)
+        .context("The tool call isn't waiting for approval anymore")?;
+    // The agent may have given up waiting in the meantime.
+    response.send(approved).ok();
+    Ok(())
+}
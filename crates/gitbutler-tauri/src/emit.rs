@@ -0,0 +1,47 @@
+//! Emit the events of [`but_tools`] to the frontend.
+use but_tools::emit::{EmitStackUpdate, EmitTokenEvent, EmitToolCall, ToolCall};
+use but_workspace::StackId;
+use gitbutler_project::ProjectId;
+use tauri::Emitter;
+
+/// An [emitter](but_tools::emit::Emitter) that sends all events to the frontend.
+#[derive(Clone)]
+pub struct TauriEmitter(pub tauri::AppHandle);
+
+impl EmitStackUpdate for TauriEmitter {
+    fn emit_stack_update(&self, project_id: ProjectId, stack_id: StackId) {
+        let name = format!("project://{}/stack_details_update", project_id);
+        let payload = serde_json::json!({ "stackId": stack_id });
+        self.0
+            .emit(&name, payload)
+            .expect("Failed to emit stack details update");
+    }
+}
+
+impl EmitToolCall for TauriEmitter {
+    fn emit_tool_call(&self, project_id: ProjectId, message_id: String, tool_call: ToolCall) {
+        let name = format!("project://{}/tool-call", project_id);
+        let payload = serde_json::json!({
+            "messageId": message_id,
+            "name": tool_call.name,
+            "parameters": tool_call.parameters,
+            "result": tool_call.result,
+        });
+        self.0
+            .emit(&name, payload)
+            .expect("Failed to emit tool call event");
+    }
+}
+
+impl EmitTokenEvent for TauriEmitter {
+    fn emit_token_event(&self, token: &str, project_id: ProjectId, message_id: String) {
+        let name = format!("project://{}/token-updates", project_id);
+        let payload = serde_json::json!({
+            "messageId": message_id,
+            "token": token,
+        });
+        self.0
+            .emit(&name, payload)
+            .expect("Failed to emit token event");
+    }
+}
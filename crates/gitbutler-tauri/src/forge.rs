@@ -1,15 +1,22 @@
 pub mod commands {
     use std::path::Path;
 
-    use anyhow::Context;
+    use anyhow::{bail, Context};
+    use but_settings::AppSettingsWithDiskSync;
+    use gitbutler_command_context::CommandContext;
     use gitbutler_forge::{
-        forge::ForgeName,
+        forge::{ForgeName, PullRequest},
+        github::GitHub,
         review::{
             available_review_templates, get_review_template_functions, ReviewTemplateFunctions,
         },
+        stack::StackedBranch,
     };
     use gitbutler_project::ProjectId;
     use gitbutler_repo::RepoCommands;
+    use gitbutler_stack::{StackId, VirtualBranchesHandle};
+    use gitbutler_url::Url;
+    use tauri::State;
     use tracing::instrument;
 
     use crate::error::Error;
@@ -47,4 +54,77 @@ pub mod commands {
             .content
             .context("PR template was not valid UTF-8")?)
     }
+
+    /// Create or update the pull requests of all unarchived branches of the stack with `stack_id`
+    /// on the forge of the target remote, and remember their numbers with the branches.
+    ///
+    /// New pull requests use the branch name as title and the branch description as body.
+    /// Only GitHub is supported for now, with GitHub Enterprise servers configured as `forge_hosts` of the project.
+    #[tauri::command(async)]
+    #[instrument(skip(settings, users), err(Debug))]
+    pub async fn sync_stack_pull_requests(
+        settings: State<'_, AppSettingsWithDiskSync>,
+        users: State<'_, gitbutler_user::Controller>,
+        project_id: ProjectId,
+        stack_id: StackId,
+        draft: bool,
+    ) -> Result<Vec<PullRequest>, Error> {
+        let project = gitbutler_project::get_validated(project_id)?;
+        let user = users
+            .get_user()?
+            .context("Creating pull requests requires a signed in user")?;
+        let state = VirtualBranchesHandle::new(project.gb_dir());
+        let target = state.get_default_target()?;
+        let forge = github_for_remote(&project, &user, &target.remote_url)?;
+
+        let branches: Vec<StackedBranch> = state
+            .get_stack(stack_id)?
+            .branches()
+            .into_iter()
+            .filter(|branch| !branch.archived)
+            .map(|branch| StackedBranch {
+                title: branch.name.clone(),
+                body: branch.description.unwrap_or_default(),
+                pr_number: branch.pr_number,
+                name: branch.name,
+            })
+            .collect();
+
+        let prs = gitbutler_forge::stack::sync_stack_pull_requests(
+            &forge,
+            target.branch.branch(),
+            &branches,
+            draft,
+        )
+        .await?;
+
+        let ctx = CommandContext::open(&project, settings.get()?.clone())?;
+        for (branch, pr) in branches.iter().zip(&prs) {
+            if branch.pr_number != Some(pr.number) {
+                gitbutler_branch_actions::stack::update_branch_pr_number(
+                    &ctx,
+                    stack_id,
+                    branch.name.clone(),
+                    Some(pr.number),
+                )?;
+            }
+        }
+        Ok(prs)
+    }
+
+    /// Return a client for the GitHub repository at `remote_url`, which must be hosted on GitHub.
+    fn github_for_remote(
+        project: &gitbutler_project::Project,
+        user: &gitbutler_user::User,
+        remote_url: &str,
+    ) -> anyhow::Result<GitHub> {
+        let url: Url = remote_url
+            .parse()
+            .with_context(|| format!("Invalid URL of the target remote: {remote_url}"))?;
+        match ForgeName::from_url(&url, &project.forge_hosts) {
+            Some(ForgeName::GitHub) => GitHub::from_remote_url(user, &url),
+            Some(forge) => bail!("Stacked pull requests aren't supported on {forge:?} yet"),
+            None => bail!("Couldn't determine the forge of {remote_url}"),
+        }
+    }
 }
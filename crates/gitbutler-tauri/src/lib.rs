@@ -23,9 +23,11 @@ pub use window::state::event::ChangeForFrontend;
 pub use window::state::WindowState;
 
 pub mod action;
+pub mod approval;
 pub mod askpass;
 pub mod cli;
 pub mod config;
+pub mod emit;
 pub mod error;
 pub mod forge;
 pub mod github;
@@ -15,8 +15,8 @@ use but_settings::AppSettingsWithDiskSync;
 use gitbutler_tauri::csp::csp_with_extras;
 use gitbutler_tauri::settings::SettingsStore;
 use gitbutler_tauri::{
-    action, askpass, cli, commands, config, diff, env, forge, github, logs, menu, modes, open,
-    projects, remotes, repo, rules, secret, settings, stack, undo, users, virtual_branches,
+    action, approval, askpass, cli, commands, config, diff, env, forge, github, logs, menu, modes,
+    open, projects, remotes, repo, rules, secret, settings, stack, undo, users, virtual_branches,
     workspace, zip, App, WindowState,
 };
 use tauri::Emitter;
@@ -141,6 +141,7 @@ fn main() {
                         logs_dir: app_log_dir,
                     });
                     app_handle.manage(app);
+                    app_handle.manage(approval::ToolApprovals::default());
 
                     tauri_app.on_menu_event(move |_handle, event| {
                         menu::handle_event(&window.clone(), &event)
@@ -187,6 +188,7 @@ fn main() {
                     commands::git_index_size,
                     zip::commands::get_logs_archive_path,
                     zip::commands::get_project_archive_path,
+                    zip::commands::get_diagnostics_archive_path,
                     users::commands::set_user,
                     users::commands::delete_user,
                     users::commands::get_user,
@@ -228,6 +230,8 @@ fn main() {
                     virtual_branches::commands::update_commit_message,
                     virtual_branches::commands::find_git_branches,
                     virtual_branches::commands::list_branches,
+                    virtual_branches::commands::list_branches_page,
+                    virtual_branches::commands::delete_stale_branches,
                     virtual_branches::commands::get_branch_listing_details,
                     virtual_branches::commands::squash_commits,
                     virtual_branches::commands::fetch_from_remotes,
@@ -268,6 +272,7 @@ fn main() {
                     open::show_in_finder,
                     forge::commands::pr_templates,
                     forge::commands::pr_template,
+                    forge::commands::sync_stack_pull_requests,
                     settings::get_app_settings,
                     settings::update_onboarding_complete,
                     settings::update_telemetry,
@@ -280,12 +285,15 @@ fn main() {
                     action::auto_branch_changes,
                     action::absorb,
                     action::freestyle,
+                    approval::respond_to_tool_approval,
                     cli::install_cli,
                     cli::cli_path,
                     rules::create_workspace_rule,
                     rules::delete_workspace_rule,
                     rules::update_workspace_rule,
                     rules::list_workspace_rules,
+                    rules::get_agent_policy,
+                    rules::set_agent_policy,
                     workspace::stacks,
                     workspace::stack_details,
                     workspace::branch_details,
@@ -4,6 +4,7 @@ use but_rules::{
     WorkspaceRule,
 };
 use but_settings::AppSettingsWithDiskSync;
+use but_tools::policy::{policy, set_policy, Policy};
 use gitbutler_command_context::CommandContext;
 use gitbutler_project::ProjectId;
 use tauri::State;
@@ -63,3 +64,30 @@ pub fn list_workspace_rules(
     )?;
     list_rules(ctx).map_err(Into::into)
 }
+
+#[tauri::command(async)]
+#[instrument(skip(settings), err(Debug))]
+pub fn get_agent_policy(
+    settings: State<'_, AppSettingsWithDiskSync>,
+    project_id: ProjectId,
+) -> Result<Policy, Error> {
+    let ctx = &mut CommandContext::open(
+        &gitbutler_project::get(project_id)?,
+        settings.get()?.clone(),
+    )?;
+    policy(ctx).map_err(Into::into)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(settings), err(Debug))]
+pub fn set_agent_policy(
+    settings: State<'_, AppSettingsWithDiskSync>,
+    project_id: ProjectId,
+    policy: Policy,
+) -> Result<Policy, Error> {
+    let ctx = &mut CommandContext::open(
+        &gitbutler_project::get(project_id)?,
+        settings.get()?.clone(),
+    )?;
+    set_policy(ctx, policy).map_err(Into::into)
+}
@@ -5,15 +5,15 @@ pub mod commands {
     use but_settings::AppSettingsWithDiskSync;
     use but_workspace::ui::StackEntryNoOpt;
     use but_workspace::DiffSpec;
-    use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
+    use gitbutler_branch::{BranchCreateRequest, BranchIdentity, BranchUpdateRequest};
     use gitbutler_branch_actions::branch_upstream_integration::IntegrationStrategy;
     use gitbutler_branch_actions::upstream_integration::{
         BaseBranchResolution, BaseBranchResolutionApproach, IntegrationOutcome, Resolution,
         StackStatuses,
     };
     use gitbutler_branch_actions::{
-        BaseBranch, BranchListing, BranchListingDetails, BranchListingFilter, RemoteBranchData,
-        RemoteBranchFile, RemoteCommit, StackOrder,
+        BaseBranch, BranchListing, BranchListingDetails, BranchListingFilter, BranchListingOrder,
+        BranchListingPage, RemoteBranchData, RemoteBranchFile, RemoteCommit, StackOrder,
     };
     use gitbutler_command_context::CommandContext;
     use gitbutler_oxidize::ObjectIdExt;
@@ -341,6 +341,33 @@ pub mod commands {
         Ok(branches)
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(settings), err(Debug))]
+    pub fn list_branches_page(
+        settings: State<'_, AppSettingsWithDiskSync>,
+        project_id: ProjectId,
+        filter: Option<BranchListingFilter>,
+        order: Option<BranchListingOrder>,
+    ) -> Result<BranchListingPage, Error> {
+        let project = gitbutler_project::get(project_id)?;
+        let ctx = CommandContext::open(&project, settings.get()?.clone())?;
+        let page =
+            gitbutler_branch_actions::list_branches_page(&ctx, filter, order.unwrap_or_default())?;
+        Ok(page)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(settings), err(Debug))]
+    pub fn delete_stale_branches(
+        settings: State<'_, AppSettingsWithDiskSync>,
+        project_id: ProjectId,
+    ) -> Result<Vec<BranchIdentity>, Error> {
+        let project = gitbutler_project::get(project_id)?;
+        let ctx = CommandContext::open(&project, settings.get()?.clone())?;
+        let deleted = gitbutler_branch_actions::delete_stale_branches(&ctx)?;
+        Ok(deleted)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(settings), err(Debug))]
     pub fn get_branch_listing_details(
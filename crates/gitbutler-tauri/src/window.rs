@@ -96,6 +96,41 @@ pub(crate) mod state {
                         }),
                         project_id,
                     },
+                    ItemKind::Rules => ChangeForFrontend {
+                        name: format!("project://{}/db-updates", project_id),
+                        payload: serde_json::json!({
+                            "kind": "workspace-rules"
+                        }),
+                        project_id,
+                    },
+                    ItemKind::ClaudeSessions => ChangeForFrontend {
+                        name: format!("project://{}/db-updates", project_id),
+                        payload: serde_json::json!({
+                            "kind": "claude-code-sessions"
+                        }),
+                        project_id,
+                    },
+                    ItemKind::FileWriteLocks => ChangeForFrontend {
+                        name: format!("project://{}/db-updates", project_id),
+                        payload: serde_json::json!({
+                            "kind": "file-write-locks"
+                        }),
+                        project_id,
+                    },
+                    ItemKind::AgentPolicies => ChangeForFrontend {
+                        name: format!("project://{}/db-updates", project_id),
+                        payload: serde_json::json!({
+                            "kind": "agent-policies"
+                        }),
+                        project_id,
+                    },
+                    ItemKind::RefMetadata => ChangeForFrontend {
+                        name: format!("project://{}/db-updates", project_id),
+                        payload: serde_json::json!({
+                            "kind": "ref-metadata"
+                        }),
+                        project_id,
+                    },
                     _ => {
                         tracing::warn!("Unhandled ItemKind in ChangeForFrontend: {:?}", item);
                         ChangeForFrontend {
@@ -213,6 +248,7 @@ pub(crate) mod state {
                 handler,
                 worktree_dir,
                 project_id,
+                project.file_monitor_backend,
                 app_settings,
             )?;
 
@@ -205,6 +205,7 @@ pub fn amend_commit_from_worktree_changes(
     worktree_changes: Vec<but_workspace::DiffSpec>,
 ) -> Result<commit_engine::ui::CreateCommitOutcome, Error> {
     let project = gitbutler_project::get(project_id)?;
+    let ctx = CommandContext::open(&project, settings.get()?.clone())?;
     let mut guard = project.exclusive_worktree_access();
     let repo = but_core::open_repo_for_merging(project.worktree_path())?;
     let outcome = commit_engine::create_commit_and_update_refs_with_project(
@@ -224,6 +225,16 @@ pub fn amend_commit_from_worktree_changes(
     if !outcome.rejected_specs.is_empty() {
         tracing::warn!(?outcome.rejected_specs, "Failed to commit at least one hunk");
     }
+    let shared = but_workspace::rebase_stacks_and_branches_sharing_amended_commit(
+        &ctx,
+        stack_id,
+        commit_id.into(),
+        &outcome,
+    )?;
+    if !shared.updated_references.is_empty() {
+        let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+        update_workspace_commit(&vb_state, &ctx)?;
+    }
     Ok(outcome.into())
 }
 
@@ -263,6 +274,15 @@ pub fn discard_worktree_changes(
 #[serde(rename_all = "camelCase")]
 pub struct UIMoveChangesResult {
     replaced_commits: Vec<(String, String)>,
+    updated_references: Vec<UIUpdatedReference>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UIUpdatedReference {
+    name: String,
+    old_commit_id: String,
+    new_commit_id: String,
 }
 
 impl From<MoveChangesResult> for UIMoveChangesResult {
@@ -273,6 +293,15 @@ impl From<MoveChangesResult> for UIMoveChangesResult {
                 .into_iter()
                 .map(|(x, y)| (x.to_hex().to_string(), y.to_hex().to_string()))
                 .collect(),
+            updated_references: value
+                .updated_references
+                .into_iter()
+                .map(|r| UIUpdatedReference {
+                    name: r.reference.to_string(),
+                    old_commit_id: r.old_commit_id.to_hex().to_string(),
+                    new_commit_id: r.new_commit_id.to_hex().to_string(),
+                })
+                .collect(),
         }
     }
 }
@@ -468,6 +497,7 @@ pub fn uncommit_changes(
                 hunk_header: a.hunk_header,
                 path_bytes: a.path_bytes,
                 stack_id: Some(stack_id),
+                line_ranges: a.line_ranges,
             })
             .collect::<Vec<_>>();
 
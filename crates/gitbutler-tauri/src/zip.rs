@@ -3,8 +3,11 @@ pub mod commands {
     use std::path::PathBuf;
 
     use anyhow::Context;
+    use but_settings::AppSettingsWithDiskSync;
+    use gitbutler_command_context::CommandContext;
     use gitbutler_error::{error, error::Code};
-    use gitbutler_feedback::Archival;
+    use gitbutler_feedback::{diagnostics, Archival};
+    use gitbutler_project::ProjectId;
     use tauri::State;
     use tracing::instrument;
 
@@ -28,4 +31,19 @@ pub mod commands {
     pub fn get_logs_archive_path(archival: State<'_, Archival>) -> Result<PathBuf, Error> {
         archival.logs_archive().map_err(Into::into)
     }
+
+    #[tauri::command(async)]
+    #[instrument(skip(archival, settings), err(Debug))]
+    pub fn get_diagnostics_archive_path(
+        archival: State<'_, Archival>,
+        settings: State<'_, AppSettingsWithDiskSync>,
+        project_id: ProjectId,
+        include_paths: Vec<PathBuf>,
+    ) -> Result<PathBuf, Error> {
+        let project = gitbutler_project::get(project_id)?;
+        let mut ctx = CommandContext::open(&project, settings.get()?.clone())?;
+        archival
+            .diagnostics_archive(&mut ctx, &diagnostics::Options { include_paths })
+            .map_err(Into::into)
+    }
 }
@@ -33,6 +33,43 @@ impl Url {
             .as_ref()
             .is_some_and(|host| host.contains("github.com"))
     }
+
+    /// Return `true` for gitlab.com, and for self-hosted instances that follow the `gitlab.<domain>` convention.
+    pub fn is_gitlab(&self) -> bool {
+        self.host.as_deref().is_some_and(|host| {
+            let host = host.to_ascii_lowercase();
+            host == "gitlab.com" || host.starts_with("gitlab.")
+        })
+    }
+
+    pub fn is_bitbucket(&self) -> bool {
+        self.host
+            .as_ref()
+            .is_some_and(|host| host.contains("bitbucket.org"))
+    }
+
+    /// Return `true` for Azure DevOps, including the legacy `<organization>.visualstudio.com` hosts.
+    pub fn is_azure(&self) -> bool {
+        self.host.as_deref().is_some_and(|host| {
+            let host = host.to_ascii_lowercase();
+            host.ends_with("dev.azure.com") || host.ends_with(".visualstudio.com")
+        })
+    }
+
+    /// Return `true` if this URL points to `host`, which may include a port like `git.example.com:8443`.
+    ///
+    /// Host names are compared case-insensitively.
+    pub fn is_host(&self, host: &str) -> bool {
+        let Some(own_host) = self.host.as_deref() else {
+            return false;
+        };
+        match host.rsplit_once(':') {
+            Some((name, port)) if port.parse::<u16>().is_ok() => {
+                own_host.eq_ignore_ascii_case(name) && self.port == port.parse().ok()
+            }
+            _ => own_host.eq_ignore_ascii_case(host),
+        }
+    }
 }
 
 impl std::fmt::Display for Url {
@@ -89,3 +126,87 @@ impl FromStr for Url {
         parse::parse(s.as_bytes().into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forge_detection() {
+        for (input, github, gitlab, bitbucket, azure) in [
+            (
+                "git@github.com:gitbutlerapp/gitbutler.git",
+                true,
+                false,
+                false,
+                false,
+            ),
+            (
+                "https://gitlab.com/gitbutler/test.git",
+                false,
+                true,
+                false,
+                false,
+            ),
+            (
+                "git@gitlab.example.com:gitbutler/test.git",
+                false,
+                true,
+                false,
+                false,
+            ),
+            (
+                "https://bitbucket.org/gitbutler-nikita/test.git",
+                false,
+                false,
+                true,
+                false,
+            ),
+            (
+                "https://org@dev.azure.com/org/project/_git/repo",
+                false,
+                false,
+                false,
+                true,
+            ),
+            (
+                "git@ssh.dev.azure.com:v3/org/project/repo",
+                false,
+                false,
+                false,
+                true,
+            ),
+            (
+                "https://org.visualstudio.com/project/_git/repo",
+                false,
+                false,
+                false,
+                true,
+            ),
+            (
+                "https://git.example.com/gitbutler/test.git",
+                false,
+                false,
+                false,
+                false,
+            ),
+        ] {
+            let url: Url = input.parse().unwrap();
+            assert_eq!(url.is_github(), github, "{input}");
+            assert_eq!(url.is_gitlab(), gitlab, "{input}");
+            assert_eq!(url.is_bitbucket(), bitbucket, "{input}");
+            assert_eq!(url.is_azure(), azure, "{input}");
+        }
+    }
+
+    #[test]
+    fn is_host_with_and_without_port() {
+        let url: Url = "https://Git.Example.com:8443/gitbutler/test.git"
+            .parse()
+            .unwrap();
+        assert!(url.is_host("git.example.com:8443"));
+        assert!(url.is_host("git.example.com"));
+        assert!(!url.is_host("git.example.com:443"));
+        assert!(!url.is_host("example.com"));
+    }
+}
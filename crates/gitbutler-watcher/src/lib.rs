@@ -6,7 +6,7 @@ use std::path::Path;
 
 use anyhow::Result;
 use but_settings::AppSettingsWithDiskSync;
-use gitbutler_project::ProjectId;
+use gitbutler_project::{FileMonitorBackend, ProjectId};
 pub use handler::Handler;
 use tokio::{
     sync::mpsc::{unbounded_channel, UnboundedSender},
@@ -62,17 +62,24 @@ impl WatcherHandle {
 /// up if they take longer to process than the 100ms window between them, causing high-CPU and possibly
 /// high-memory. However, the likelihood for this is much lower than it was before the architecture
 /// was changed to what it is now, which should be much less wasteful.
+///
+/// Filesystem events are obtained from `file_monitor_backend`, see [`gitbutler_filemonitor::spawn()`].
 pub fn watch_in_background(
     handler: handler::Handler,
     worktree_path: impl AsRef<Path>,
     project_id: ProjectId,
+    file_monitor_backend: FileMonitorBackend,
     app_settings: AppSettingsWithDiskSync,
 ) -> Result<WatcherHandle, anyhow::Error> {
     let (events_out, mut events_in) = unbounded_channel();
     let (flush_tx, mut flush_rx) = unbounded_channel();
 
-    let debounce =
-        gitbutler_filemonitor::spawn(project_id, worktree_path.as_ref(), events_out.clone())?;
+    let debounce = gitbutler_filemonitor::spawn(
+        project_id,
+        worktree_path.as_ref(),
+        file_monitor_backend,
+        events_out.clone(),
+    )?;
 
     let cancellation_token = CancellationToken::new();
     let handle = WatcherHandle {